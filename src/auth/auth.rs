@@ -1,4 +1,5 @@
-use std::error::Error;
+use std::collections::BTreeMap;
+
 use serde::{Serialize, Deserialize};
 use sha2::{digest::generic_array::GenericArray, Digest, Sha256};
 use base64::Engine;
@@ -7,28 +8,168 @@ use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation}
 use chrono::{Utc, Duration};
 use bcrypt::{hash as crypt_hash, DEFAULT_COST};
 
+use crate::auth::builder::AuthServiceBuilder;
 use crate::auth::claims::Claims;
+use crate::auth::error::{AuthError, DecryptError};
+use crate::auth::jwt::{bearer_claims, verification_secret, ExpiryPolicy, JwtMiddleware};
+
+/// Length of the hex length prefix plus the hex CRC32 in framed ciphertexts
+const FRAME_HEADER_LEN: usize = 16;
+
+/// Environment variable holding the JWT signing secret
+pub const JWT_SECRET_ENV: &str = "JWT_SECRET";
+/// Environment variable holding the encryption key
+pub const ENCRYPTION_KEY_ENV: &str = "ENCRYPTION_KEY";
+/// Optional environment variable listing retired encryption keys, comma-separated
+pub const ENCRYPTION_PREVIOUS_KEYS_ENV: &str = "ENCRYPTION_PREVIOUS_KEYS";
+/// Shortest secret or encryption key accepted by `from_env`
+pub const MIN_KEY_LEN: usize = 32;
+
+fn read_key(var: &str) -> Result<String, AuthError> {
+    let key = std::env::var(var).map_err(|_| AuthError::MissingEnv(var.to_string()))?;
+    check_key_len(var, &key)?;
+    Ok(key)
+}
+
+pub(crate) fn check_key_len(var: &str, key: &str) -> Result<(), AuthError> {
+    if key.len() < MIN_KEY_LEN {
+        return Err(AuthError::Key(format!("{} must be at least {} characters long", var, MIN_KEY_LEN)));
+    }
+    Ok(())
+}
+
+/// Reads the comma-separated keys of an optional variable, each at least `MIN_KEY_LEN` characters long
+fn read_key_list(var: &str) -> Result<Vec<String>, AuthError> {
+    let Ok(list) = std::env::var(var) else {
+        return Ok(Vec::new());
+    };
+    let label = format!("every key in {}", var);
+    list.split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(|key| check_key_len(&label, key).map(|_| key.to_string()))
+        .collect()
+}
 
 fn derive_key_from_string(key_str: &str) -> [u8; 32] {
     let hasher = Sha256::new_with_prefix(key_str.as_bytes());
     hasher.finalize().into()
 }
 
+/// Base64 of a random nonce followed by `input` encrypted under `encryption_key` with AES-256-GCM
+fn encrypt_with(encryption_key: &str, input: &str) -> Result<String, AuthError> {
+    let key_bytes = derive_key_from_string(encryption_key);
+    let key = GenericArray::from_slice(&key_bytes);
+    let cipher = Aes256Gcm::new(key);
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let cipher_text = cipher.encrypt(&nonce, input.as_bytes())
+        .map_err(|e| AuthError::Encryption(e.to_string()))?;
+
+    let mut encrypted_data = nonce.to_vec();
+    encrypted_data.extend_from_slice(&cipher_text);
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(encrypted_data))
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct AuthService {
     secret_key: String,
     encryption_key: String,
+    /// Retired encryption keys, only used to decrypt data encrypted before a rotation
+    #[serde(default)]
+    previous_encryption_keys: Vec<String>,
+    #[serde(default)]
+    expiry_policy: ExpiryPolicy,
+    /// `kid` header of the tokens this service signs
+    #[serde(default)]
+    key_id: Option<String>,
+    /// Secrets of the tokens signed elsewhere under a `kid`, by key id
+    #[serde(default)]
+    verification_keys: BTreeMap<String, String>,
 }
 impl AuthService {
 
+    /// Shortcut of `builder()` for the two required keys, taken as they are without the checks of `build`
     pub fn new(secret_key: String, encryption_key: String) -> Self {
         Self {
             secret_key,
             encryption_key,
+            previous_encryption_keys: Vec::new(),
+            expiry_policy: ExpiryPolicy::default(),
+            key_id: None,
+            verification_keys: BTreeMap::new(),
         }
     }
 
-    pub fn hash(input: &str) -> Result<String, Box<dyn Error>> {
+    /// Configures a service setting by setting, validated by `AuthServiceBuilder::build`
+    pub fn builder() -> AuthServiceBuilder {
+        AuthServiceBuilder::new()
+    }
+
+    /// Keeps retired encryption keys around so `decrypt` still accepts data encrypted under them,
+    /// while `encrypt` always uses the current key. Keys are tried in the given order
+    pub fn with_previous_encryption_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.previous_encryption_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Chooses whether tokens without `exp` are rejected (the default) or accepted as never expiring
+    pub fn with_expiry_policy(mut self, expiry_policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = expiry_policy;
+        self
+    }
+
+    /// Signs tokens with a `kid` header naming the secret, so verifiers holding several keys (e.g. a
+    /// published JWKS) pick the right one. This service verifies tokens with that `kid` with its own secret
+    pub fn with_key_id(mut self, kid: impl Into<String>) -> Self {
+        self.key_id = Some(kid.into());
+        self
+    }
+
+    /// Also accepts the tokens signed with `secret_key` under the `kid` header `kid`, e.g. by another
+    /// service or with a secret being rotated out. Tokens without a `kid`, or with one no key was added
+    /// for, are verified with this service's own secret
+    pub fn with_verification_key(mut self, kid: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        self.verification_keys.insert(kid.into(), secret_key.into());
+        self
+    }
+
+    /// Reads the keys from `JWT_SECRET` and `ENCRYPTION_KEY`, both at least `MIN_KEY_LEN` characters long,
+    /// and the retired encryption keys from the optional `ENCRYPTION_PREVIOUS_KEYS`
+    pub fn from_env() -> Result<Self, AuthError> {
+        Self::builder()
+            .secret_key(read_key(JWT_SECRET_ENV)?)
+            .encryption_key(read_key(ENCRYPTION_KEY_ENV)?)
+            .previous_encryption_keys(read_key_list(ENCRYPTION_PREVIOUS_KEYS_ENV)?)
+            .build()
+    }
+
+    /// Middleware validating tokens signed by this service
+    pub fn jwt_middleware(&self) -> JwtMiddleware {
+        self.verification_keys.iter().fold(
+            JwtMiddleware::new(self.secret_key.clone()).with_expiry_policy(self.expiry_policy),
+            |middleware, (kid, secret_key)| middleware.with_key(kid.clone(), secret_key.clone()),
+        )
+    }
+
+    /// Claims of an `Authorization: Bearer <token>` header signed by this service
+    #[cfg_attr(not(feature = "axum"), allow(dead_code))]
+    pub(crate) fn bearer_claims(&self, header: Option<&[u8]>) -> Result<Claims, &'static str> {
+        bearer_claims(header, &self.secret_key, &self.verification_keys, &self.expiry_policy.validation())
+    }
+
+    /// Key verifying `token`, picked by its `kid` header
+    fn decoding_key(&self, token: &str) -> DecodingKey {
+        DecodingKey::from_secret(verification_secret(token, &self.secret_key, &self.verification_keys).as_bytes())
+    }
+
+    pub fn hash(input: &str) -> Result<String, AuthError> {
         let mut hasher = Sha256::new_with_prefix(input.as_bytes());
         hasher.update(input.as_bytes());
         let result = hasher.finalize();
@@ -54,11 +195,15 @@ impl AuthService {
             sub: email,
             exp: expiration,
             iat: Utc::now().timestamp() as usize,
-            data: data,
+            data,
         };
 
+        let header = Header {
+            kid: self.key_id.clone(),
+            ..Header::default()
+        };
         encode(
-            &Header::default(),
+            &header,
             &claims,
             &EncodingKey::from_secret(self.secret_key.as_bytes()),
         ).unwrap()
@@ -68,25 +213,46 @@ impl AuthService {
     where
         T: for<'de> Deserialize<'de> + Clone, 
     {
-        let validation = Validation::default();
+        let validation = self.expiry_policy.validation();
         let result = decode::<Claims<T>>(
             token,
-            &DecodingKey::from_secret(self.secret_key.as_bytes()),
+            &self.decoding_key(token),
             &validation,
         );
         result.is_ok()
     }
 
+    /// Reads the claims of `token` WITHOUT checking its signature or expiry, to inspect untrusted tokens
+    /// while debugging. Anyone can forge a token this accepts, so never base an authentication or
+    /// authorization decision on what it returns: `verify_token` is the check for that
+    pub fn decode_insecure<T>(token: &str) -> Result<Claims<T>, AuthError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let mut validation = Validation::default();
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+        validation.validate_nbf = false;
+        validation.validate_aud = false;
+        validation.required_spec_claims.clear();
+        let data = decode::<Claims<T>>(token, &DecodingKey::from_secret(&[]), &validation)?;
+        Ok(data.claims)
+    }
+
     pub fn is_token_expired<T>(&self, token: &str) -> bool 
     where
         T: for<'de> Deserialize<'de> + Clone,
     {
-        let validation = Validation::default();
+        let validation = self.expiry_policy.validation();
         if let Ok(data) = decode::<Claims<T>>(
             token,
-            &DecodingKey::from_secret(self.secret_key.as_bytes()),
+            &self.decoding_key(token),
             &validation,
         ) {
+            // Only decodable without `exp` under `ExpiryPolicy::AllowMissing`, where it never expires
+            if data.claims.exp == 0 {
+                return false;
+            }
             let now = Utc::now().timestamp() as usize;
             data.claims.exp < now
         } else {
@@ -94,43 +260,287 @@ impl AuthService {
         }
     }
 
-    pub fn encrypt(&self, input: &str) -> Result<String, Box<dyn Error>> {
+    pub fn encrypt(&self, input: &str) -> Result<String, AuthError> {
+        encrypt_with(&self.encryption_key, input)
+    }
+
+    /// Decrypts `ciphertext` (under the current or a previous key) and encrypts it again under `new_key`,
+    /// for migrating stored data before switching the service to `new_key`. Nothing is encrypted when
+    /// decryption fails, and `new_key` must be at least `MIN_KEY_LEN` characters long
+    pub fn reencrypt(&self, ciphertext: &str, new_key: &str) -> Result<String, AuthError> {
+        check_key_len("the new encryption key", new_key)?;
+        let plaintext = self.decrypt(ciphertext)?;
+        encrypt_with(new_key, &plaintext)
+    }
+
+    /// Decrypts the output of `encrypt`, trying the current key first and then the previous ones
+    pub fn decrypt(&self, input: &str) -> Result<String, AuthError> {
 
-        let key_bytes = derive_key_from_string(&self.encryption_key);
-        let key = GenericArray::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
+        let encrypted_data = base64::engine::general_purpose::STANDARD.decode(input)?;
+
+        if encrypted_data.len() < 12 {
+            return Err(DecryptError::TooShort.into());
+        }
+        
+        let (nonce_bytes, cipher_text) = encrypted_data.split_at(12);
+        let nonce = Nonce::from_slice(nonce_bytes);
+
+        // GCM authentication only succeeds under the key the data was encrypted with
+        let keys = std::iter::once(&self.encryption_key).chain(&self.previous_encryption_keys);
+        for encryption_key in keys {
+            let key_bytes = derive_key_from_string(encryption_key);
+            let cipher = Aes256Gcm::new(GenericArray::from_slice(&key_bytes));
+            if let Ok(plaintext) = cipher.decrypt(nonce, cipher_text) {
+                return Ok(String::from_utf8(plaintext)?);
+            }
+        }
+
+        Err(DecryptError::AuthFailed.into())
+    }
+
+    /// Like `encrypt`, but prefixes the base64 payload with its length and a CRC32 of it
+    /// (both as 8 hex digits) so `decrypt_framed` can tell truncation from corruption
+    pub fn encrypt_framed(&self, input: &str) -> Result<String, AuthError> {
+        let payload = self.encrypt(input)?;
+        let checksum = crc32fast::hash(payload.as_bytes());
+        Ok(format!("{:08x}{:08x}{}", payload.len(), checksum, payload))
+    }
+
+    /// Decrypts the output of `encrypt_framed`, verifying its length and checksum first
+    pub fn decrypt_framed(&self, input: &str) -> Result<String, AuthError> {
+        if input.len() < FRAME_HEADER_LEN || !input.is_char_boundary(FRAME_HEADER_LEN) {
+            return Err(DecryptError::TooShort.into());
+        }
 
+        let (header, payload) = input.split_at(FRAME_HEADER_LEN);
+        let (length, checksum) = header.split_at(8);
+        let length = usize::from_str_radix(length, 16)
+            .map_err(|_| DecryptError::CorruptEncoding("invalid length prefix".to_string()))?;
+        let checksum = u32::from_str_radix(checksum, 16)
+            .map_err(|_| DecryptError::CorruptEncoding("invalid checksum".to_string()))?;
+
+        if payload.len() < length {
+            return Err(DecryptError::TooShort.into());
+        }
+        if payload.len() > length {
+            return Err(DecryptError::CorruptEncoding("payload longer than its length prefix".to_string()).into());
+        }
+        if crc32fast::hash(payload.as_bytes()) != checksum {
+            return Err(DecryptError::CorruptEncoding("checksum mismatch".to_string()).into());
+        }
+
+        self.decrypt(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "a-jwt-secret-of-at-least-32-characters";
+    const KEY: &str = "an-encryption-key-of-at-least-32-chars";
+
+    fn service() -> AuthService {
+        AuthService::new(SECRET.to_string(), KEY.to_string())
+    }
+
+    /// HS256 token of `claims` signed with `secret`, under the `kid` header `kid`
+    fn sign(claims: &serde_json::Value, secret: &str, kid: Option<&str>) -> String {
+        let header = Header { kid: kid.map(str::to_string), ..Header::default() };
+        encode(&header, claims, &EncodingKey::from_secret(secret.as_bytes())).unwrap()
+    }
+
+    fn in_minutes(minutes: i64) -> i64 {
+        (Utc::now() + Duration::minutes(minutes)).timestamp()
+    }
+
+    #[test]
+    fn decode_insecure_reads_tokens_this_service_rejects() {
+        let claims = serde_json::json!({
+            "sub": "ada@example.com",
+            "iat": 0,
+            "exp": in_minutes(5),
+            "aud": "billing",
+            "data": { "role": "admin" },
+        });
+        let token = sign(&claims, "an-unrelated-secret-of-32-characters!", None);
+
+        let decoded = AuthService::decode_insecure::<serde_json::Value>(&token).unwrap();
+        assert_eq!(decoded.sub, "ada@example.com");
+        assert_eq!(decoded.data["role"], "admin");
+        assert!(!service().verify_token::<serde_json::Value>(&token));
+    }
+
+    /// `encrypt_framed` of bytes that aren't UTF-8, which `encrypt` can't produce
+    fn framed_bytes(key: &str, bytes: &[u8]) -> String {
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(&derive_key_from_string(key)));
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut data = nonce.to_vec();
+        data.extend(cipher.encrypt(&nonce, bytes).unwrap());
+        let payload = base64::engine::general_purpose::STANDARD.encode(data);
+        format!("{:08x}{:08x}{}", payload.len(), crc32fast::hash(payload.as_bytes()), payload)
+    }
+
+    fn decrypt_error(result: Result<String, AuthError>) -> DecryptError {
+        match result {
+            Err(AuthError::Decryption(error)) => error,
+            other => panic!("expected a decryption error, got {:?}", other),
+        }
+    }
 
-        let cipher_text = cipher.encrypt(&nonce, input.as_bytes())
-            .map_err(|e| format!("Encryption failed: {}", e))?;
+    #[test]
+    fn framed_round_trip() {
+        let service = service();
+        let framed = service.encrypt_framed("hello, world").unwrap();
+        assert_eq!(service.decrypt_framed(&framed).unwrap(), "hello, world");
+    }
 
-        let mut encrypted_data = nonce.to_vec();
-        encrypted_data.extend_from_slice(&cipher_text);
+    #[test]
+    fn framed_truncation_is_too_short() {
+        let service = service();
+        let framed = service.encrypt_framed("hello, world").unwrap();
+        assert_eq!(decrypt_error(service.decrypt_framed(&framed[..framed.len() - 4])), DecryptError::TooShort);
+        assert_eq!(decrypt_error(service.decrypt_framed(&framed[..10])), DecryptError::TooShort);
+    }
 
-        Ok(base64::engine::general_purpose::STANDARD.encode(encrypted_data))
+    #[test]
+    fn framed_corruption_fails_the_checksum() {
+        let service = service();
+        let framed = service.encrypt_framed("hello, world").unwrap();
+        let last = framed.chars().last().unwrap();
+        let corrupted = format!("{}{}", &framed[..framed.len() - 1], if last == 'A' { 'B' } else { 'A' });
+        assert_eq!(
+            decrypt_error(service.decrypt_framed(&corrupted)),
+            DecryptError::CorruptEncoding("checksum mismatch".to_string())
+        );
     }
 
-    pub fn decrypt(&self, input: &str) -> Result<String, Box<dyn Error>> {
+    #[test]
+    fn framed_data_of_another_key_fails_authentication() {
+        let framed = service().encrypt_framed("hello, world").unwrap();
+        let other = AuthService::new(SECRET.to_string(), "another-encryption-key-of-32-chars!".to_string());
+        assert_eq!(decrypt_error(other.decrypt_framed(&framed)), DecryptError::AuthFailed);
+    }
 
-        let key_bytes = derive_key_from_string(&self.encryption_key);
-        let key = GenericArray::from_slice(&key_bytes);
-        let cipher = Aes256Gcm::new(key);
+    #[test]
+    fn framed_bytes_that_are_not_utf8_are_rejected() {
+        let framed = framed_bytes(KEY, &[0xff, 0xfe, 0xfd]);
+        assert!(matches!(decrypt_error(service().decrypt_framed(&framed)), DecryptError::InvalidUtf8(_)));
+    }
 
-        let encrypted_data = base64::engine::general_purpose::STANDARD.decode(input)
-            .map_err(|e| format!("Base64 decode failed: {}", e))?;
+    #[test]
+    fn decrypts_data_encrypted_under_a_previous_key() {
+        const OLD_KEY: &str = "the-encryption-key-before-rotation!";
+        let old = AuthService::new(SECRET.to_string(), OLD_KEY.to_string());
+        let ciphertext = old.encrypt("hello").unwrap();
 
-        if encrypted_data.len() < 12 {
-            return Err("Invalid encrypted data: too short".into());
+        let rotated = AuthService::new(SECRET.to_string(), KEY.to_string()).with_previous_encryption_keys([OLD_KEY]);
+        assert_eq!(rotated.decrypt(&ciphertext).unwrap(), "hello");
+        // New data is encrypted under the current key only
+        assert!(old.decrypt(&rotated.encrypt("hello").unwrap()).is_err());
+        assert_eq!(decrypt_error(service().decrypt(&ciphertext)), DecryptError::AuthFailed);
+    }
+
+    #[test]
+    fn reencrypts_data_of_a_rotated_key_under_the_new_one() {
+        const OLD_KEY: &str = "the-encryption-key-before-rotation!";
+        const NEW_KEY: &str = "the-encryption-key-after-rotation!!";
+        let ciphertext = AuthService::new(SECRET.to_string(), OLD_KEY.to_string()).encrypt("hello").unwrap();
+        let migrating = AuthService::new(SECRET.to_string(), KEY.to_string()).with_previous_encryption_keys([OLD_KEY]);
+
+        let reencrypted = migrating.reencrypt(&ciphertext, NEW_KEY).unwrap();
+        let rotated = AuthService::new(SECRET.to_string(), NEW_KEY.to_string());
+        assert_eq!(rotated.decrypt(&reencrypted).unwrap(), "hello");
+        assert_eq!(decrypt_error(migrating.decrypt(&reencrypted)), DecryptError::AuthFailed);
+
+        assert!(matches!(migrating.reencrypt(&ciphertext, "short"), Err(AuthError::Key(_))));
+        assert_eq!(decrypt_error(rotated.reencrypt(&ciphertext, NEW_KEY)), DecryptError::AuthFailed);
+    }
+
+    #[test]
+    fn signs_with_the_key_id_and_verifies_by_kid() {
+        const PARTNER: &str = "the-partner-service-jwt-secret-32ch";
+        let service = service().with_key_id("2024-01").with_verification_key("partner", PARTNER);
+        let token = service.generate_token("ada@example.com".to_string(), (), 5);
+        assert_eq!(jsonwebtoken::decode_header(&token).unwrap().kid.as_deref(), Some("2024-01"));
+        assert!(service.verify_token::<()>(&token));
+
+        let claims = serde_json::json!({ "sub": "bob", "iat": 0, "exp": in_minutes(5), "data": null });
+        assert!(service.verify_token::<()>(&sign(&claims, PARTNER, Some("partner"))));
+        // The partner's secret only counts under its kid
+        assert!(!service.verify_token::<()>(&sign(&claims, PARTNER, None)));
+        assert!(!service.verify_token::<()>(&sign(&claims, SECRET, Some("partner"))));
+    }
+
+    #[test]
+    fn verifies_an_unknown_kid_with_its_own_secret() {
+        let service = service().with_verification_key("partner", "the-partner-service-jwt-secret-32ch");
+        let claims = serde_json::json!({ "sub": "bob", "iat": 0, "exp": in_minutes(5), "data": null });
+        assert!(service.verify_token::<()>(&sign(&claims, SECRET, Some("unknown"))));
+        assert!(!service.verify_token::<()>(&sign(&claims, "a-secret-nobody-registered-32-chars", Some("unknown"))));
+    }
+
+    #[test]
+    fn expiry_policy_decides_on_tokens_without_exp() {
+        let without_exp = sign(&serde_json::json!({ "sub": "ada", "iat": 0, "data": null }), SECRET, None);
+        let expired = sign(&serde_json::json!({ "sub": "ada", "iat": 0, "exp": in_minutes(-10), "data": null }), SECRET, None);
+
+        let strict = service();
+        assert!(!strict.verify_token::<()>(&without_exp));
+        assert!(strict.is_token_expired::<()>(&without_exp));
+
+        let lenient = service().with_expiry_policy(ExpiryPolicy::AllowMissing);
+        assert!(lenient.verify_token::<()>(&without_exp));
+        assert!(!lenient.is_token_expired::<()>(&without_exp));
+        // Tokens with an `exp` are still checked
+        assert!(!lenient.verify_token::<()>(&expired));
+        assert!(lenient.is_token_expired::<()>(&expired));
+    }
+
+    /// Serializes the tests setting the process-wide variables `from_env` reads
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// `from_env` under `vars`, with the other variables it reads unset
+    fn from_env_with(vars: &[(&str, &str)]) -> Result<AuthService, AuthError> {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // SAFETY: the lock keeps the other tests of this module from touching the environment meanwhile
+        unsafe {
+            for var in [JWT_SECRET_ENV, ENCRYPTION_KEY_ENV, ENCRYPTION_PREVIOUS_KEYS_ENV] {
+                std::env::remove_var(var);
+            }
+            for (var, value) in vars {
+                std::env::set_var(var, value);
+            }
         }
-        
-        let (nonce_bytes, cipher_text) = encrypted_data.split_at(12);
-        let nonce = Nonce::from_slice(nonce_bytes);
-        
-        let plaintext = cipher.decrypt(nonce, cipher_text)
-            .map_err(|e| format!("Decryption failed: {}", e))?;
-        
-        String::from_utf8(plaintext)
-            .map_err(|e| format!("Invalid UTF-8: {}", e).into())
+        let service = AuthService::from_env();
+        unsafe {
+            for (var, _) in vars {
+                std::env::remove_var(var);
+            }
+        }
+        service
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn from_env_requires_a_jwt_secret() {
+        let error = from_env_with(&[(ENCRYPTION_KEY_ENV, KEY)]).err().unwrap();
+        assert_eq!(error, AuthError::MissingEnv(JWT_SECRET_ENV.to_string()));
+        assert_eq!(error.to_string(), "Missing environment variable: JWT_SECRET");
+    }
+
+    #[test]
+    fn from_env_rejects_a_short_encryption_key() {
+        let error = from_env_with(&[(JWT_SECRET_ENV, SECRET), (ENCRYPTION_KEY_ENV, "too-short")]).err().unwrap();
+        assert_eq!(error, AuthError::Key("ENCRYPTION_KEY must be at least 32 characters long".to_string()));
+    }
+
+    #[test]
+    fn from_env_builds_a_working_service() {
+        let configured = from_env_with(&[(JWT_SECRET_ENV, SECRET), (ENCRYPTION_KEY_ENV, KEY)]).unwrap();
+        let token = configured.generate_token("ada@example.com".to_string(), (), 5);
+        assert!(configured.verify_token::<()>(&token));
+        // Signed with the secret of the environment
+        assert!(service().verify_token::<()>(&token));
+        assert_eq!(configured.decrypt(&configured.encrypt("hello").unwrap()).unwrap(), "hello");
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header::AUTHORIZATION, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::auth::auth::AuthService;
+
+/// Axum counterpart of `JwtMiddleware`, for `axum::middleware::from_fn_with_state`:
+/// verifies the Bearer token with the service's secret and expiry policy, then inserts its
+/// `Claims` into the request extensions so handlers can take them as `Extension<Claims>`.
+/// Rejected requests get a 401 with the same messages as the actix middleware
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use axum::{middleware, routing::get, Extension, Router};
+/// use cargo_mold::auth::axum::require_jwt;
+/// use cargo_mold::auth::{AuthError, AuthService, Claims};
+///
+/// async fn me(Extension(claims): Extension<Claims>) -> String {
+///     claims.sub
+/// }
+///
+/// # fn main() -> Result<(), AuthError> {
+/// let auth = Arc::new(AuthService::from_env()?);
+/// let private: Router = Router::new()
+///     .route("/me", get(me))
+///     .route_layer(middleware::from_fn_with_state(auth, require_jwt));
+/// # Ok(())
+/// # }
+/// ```
+pub async fn require_jwt(State(auth): State<Arc<AuthService>>, mut req: Request, next: Next) -> Response {
+    let header = req.headers().get(AUTHORIZATION).map(|header| header.as_bytes());
+    match auth.bearer_claims(header) {
+        Ok(claims) => {
+            req.extensions_mut().insert(claims);
+            next.run(req).await
+        }
+        Err(message) => (StatusCode::UNAUTHORIZED, message).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::routing::get;
+    use axum::{middleware, Extension, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::auth::claims::Claims;
+
+    fn service() -> Arc<AuthService> {
+        Arc::new(AuthService::new(
+            "a-jwt-secret-of-at-least-32-characters".to_string(),
+            "an-encryption-key-of-at-least-32-chars".to_string(),
+        ))
+    }
+
+    /// Status and body of `GET /me` behind `require_jwt`, answering with the subject of the token
+    async fn get_me(auth: Arc<AuthService>, authorization: Option<String>) -> (StatusCode, String) {
+        let app = Router::new()
+            .route("/me", get(|Extension(claims): Extension<Claims>| async move { claims.sub }))
+            .route_layer(middleware::from_fn_with_state(auth, require_jwt));
+        let mut request = axum::http::Request::builder().uri("/me");
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn hands_the_claims_of_a_valid_token_to_the_handler() {
+        let auth = service();
+        let token = auth.generate_token("ada@example.com".to_string(), (), 5);
+        let (status, body) = get_me(auth, Some(format!("Bearer {}", token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "ada@example.com");
+    }
+
+    #[tokio::test]
+    async fn rejects_a_request_without_a_token() {
+        let (status, body) = get_me(service(), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "Authorization header missing");
+    }
+
+    #[tokio::test]
+    async fn rejects_an_expired_token() {
+        let auth = service();
+        let token = auth.generate_token("ada@example.com".to_string(), (), -10);
+        let (status, body) = get_me(auth, Some(format!("Bearer {}", token))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, "Token expired");
+    }
+}
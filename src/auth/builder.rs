@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use crate::auth::auth::{check_key_len, AuthService};
+use crate::auth::error::AuthError;
+use crate::auth::jwt::ExpiryPolicy;
+
+/// Fluent configuration of an `AuthService`, checked as a whole by `build`.
+///
+/// ```
+/// use cargo_mold::auth::{AuthService, ExpiryPolicy};
+///
+/// let secret = "a-jwt-secret-of-at-least-32-characters";
+/// let key = "an-encryption-key-of-at-least-32-chars";
+///
+/// // Only the two keys are required
+/// let service = AuthService::builder().secret_key(secret).encryption_key(key).build().unwrap();
+/// let token = service.generate_token("ada@example.com".to_string(), (), 5);
+/// assert!(service.verify_token::<()>(&token));
+///
+/// let service = AuthService::builder()
+///     .secret_key(secret)
+///     .encryption_key(key)
+///     .previous_encryption_keys(["a-retired-encryption-key-of-32-chars"])
+///     .expiry_policy(ExpiryPolicy::AllowMissing)
+///     .key_id("2024-01")
+///     .verification_key("partner", "the-partner-service-jwt-secret-32ch")
+///     .build()
+///     .unwrap();
+/// assert_eq!(service.decrypt(&service.encrypt("hello").unwrap()).unwrap(), "hello");
+///
+/// assert!(AuthService::builder().secret_key(secret).build().is_err());
+/// assert!(AuthService::builder().secret_key("short").encryption_key(key).build().is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AuthServiceBuilder {
+    secret_key: Option<String>,
+    encryption_key: Option<String>,
+    previous_encryption_keys: Vec<String>,
+    expiry_policy: ExpiryPolicy,
+    key_id: Option<String>,
+    verification_keys: BTreeMap<String, String>,
+}
+
+impl AuthServiceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Secret signing and verifying the tokens, required
+    pub fn secret_key(mut self, secret_key: impl Into<String>) -> Self {
+        self.secret_key = Some(secret_key.into());
+        self
+    }
+
+    /// Key of `encrypt` and `decrypt`, required
+    pub fn encryption_key(mut self, encryption_key: impl Into<String>) -> Self {
+        self.encryption_key = Some(encryption_key.into());
+        self
+    }
+
+    /// Retired encryption keys `decrypt` falls back to, tried in the given order
+    pub fn previous_encryption_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.previous_encryption_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether tokens without `exp` are rejected (the default) or accepted as never expiring
+    pub fn expiry_policy(mut self, expiry_policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = expiry_policy;
+        self
+    }
+
+    /// `kid` header of the tokens the service signs
+    pub fn key_id(mut self, kid: impl Into<String>) -> Self {
+        self.key_id = Some(kid.into());
+        self
+    }
+
+    /// Also accepts the tokens signed with `secret_key` under the `kid` header `kid`, can be called repeatedly
+    pub fn verification_key(mut self, kid: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        self.verification_keys.insert(kid.into(), secret_key.into());
+        self
+    }
+
+    /// The configured service, failing when the secret or the encryption key is missing, or when
+    /// any key is shorter than `MIN_KEY_LEN` characters
+    pub fn build(self) -> Result<AuthService, AuthError> {
+        let missing = |name: &str| AuthError::Key(format!("{} is required, set it with `{}`", name.replace('_', " "), name));
+        let secret_key = self.secret_key.ok_or_else(|| missing("secret_key"))?;
+        let encryption_key = self.encryption_key.ok_or_else(|| missing("encryption_key"))?;
+        check_key_len("the JWT secret", &secret_key)?;
+        check_key_len("the encryption key", &encryption_key)?;
+        for key in &self.previous_encryption_keys {
+            check_key_len("every previous encryption key", key)?;
+        }
+        for (kid, key) in &self.verification_keys {
+            check_key_len(&format!("the verification key '{}'", kid), key)?;
+        }
+        if self.key_id.as_deref().is_some_and(str::is_empty) {
+            return Err(AuthError::Key("the key id can't be empty".to_string()));
+        }
+
+        let service = AuthService::new(secret_key, encryption_key)
+            .with_previous_encryption_keys(self.previous_encryption_keys)
+            .with_expiry_policy(self.expiry_policy);
+        let service = match self.key_id {
+            Some(kid) => service.with_key_id(kid),
+            None => service,
+        };
+        Ok(self.verification_keys.into_iter()
+            .fold(service, |service, (kid, secret_key)| service.with_verification_key(kid, secret_key)))
+    }
+}
@@ -6,7 +6,9 @@ pub struct Claims<T = serde_json::Value> {
     pub sub: String,
     /// Issued at (timestamp)
     pub iat: usize,
-    /// Expiration time (timestamp) 
+    /// Expiration time (timestamp), 0 when a token without `exp` was accepted
+    /// under `ExpiryPolicy::AllowMissing`
+    #[serde(default)]
     pub exp: usize,
     /// Custom claims data
     pub data: T,
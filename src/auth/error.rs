@@ -0,0 +1,134 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use thiserror::Error;
+
+/// Why a ciphertext could not be turned back into plaintext
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DecryptError {
+    /// The input is shorter than its framing or than a nonce, usually a truncation in transit
+    #[error("Invalid encrypted data: too short")]
+    TooShort,
+    /// The framing, checksum or base64 encoding is damaged
+    #[error("Invalid encrypted data: corrupt encoding ({0})")]
+    CorruptEncoding(String),
+    /// The data is well-formed but was tampered with or encrypted under another key
+    #[error("Decryption failed: authentication tag mismatch")]
+    AuthFailed,
+    /// The decrypted bytes are not valid UTF-8
+    #[error("Invalid UTF-8: {0}")]
+    InvalidUtf8(String),
+}
+
+/// Errors returned by `AuthService` operations
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("Encryption failed: {0}")]
+    Encryption(String),
+    #[error(transparent)]
+    Decryption(#[from] DecryptError),
+    #[error("Encoding failed: {0}")]
+    Encoding(String),
+    #[error("Invalid key: {0}")]
+    Key(String),
+    #[error("Missing environment variable: {0}")]
+    MissingEnv(String),
+}
+
+impl From<base64::DecodeError> for AuthError {
+    fn from(e: base64::DecodeError) -> Self {
+        AuthError::Decryption(DecryptError::CorruptEncoding(format!("base64 decode failed: {}", e)))
+    }
+}
+
+impl From<std::string::FromUtf8Error> for AuthError {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        AuthError::Decryption(DecryptError::InvalidUtf8(e.to_string()))
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AuthError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        AuthError::Encoding(e.to_string())
+    }
+}
+
+/// Ciphertexts that can't be decrypted come from the client and answer 400. The other errors are
+/// misconfigurations answering 500, without their details
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Decryption(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let message = if status.is_server_error() {
+            status.canonical_reason().unwrap_or_default().to_string()
+        } else {
+            self.to_string()
+        };
+        HttpResponse::build(status).body(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::MessageBody;
+    use base64::Engine;
+
+    use super::*;
+
+    /// Status and body `error` answers with
+    fn response(error: &AuthError) -> (StatusCode, String) {
+        let response = error.error_response();
+        let body = response.into_body().try_into_bytes().unwrap();
+        (error.status_code(), String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn decrypt_errors_are_bad_requests() {
+        let cases = [
+            (DecryptError::TooShort, "Invalid encrypted data: too short"),
+            (
+                DecryptError::CorruptEncoding("checksum mismatch".to_string()),
+                "Invalid encrypted data: corrupt encoding (checksum mismatch)",
+            ),
+            (DecryptError::AuthFailed, "Decryption failed: authentication tag mismatch"),
+            (DecryptError::InvalidUtf8("invalid utf-8 sequence".to_string()), "Invalid UTF-8: invalid utf-8 sequence"),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+            let error = AuthError::from(error);
+            assert_eq!(error.to_string(), message);
+            assert_eq!(response(&error), (StatusCode::BAD_REQUEST, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn other_errors_are_server_errors_without_details() {
+        let cases = [
+            (AuthError::Encryption("aead::Error".to_string()), "Encryption failed: aead::Error"),
+            (AuthError::Encoding("InvalidKeyFormat".to_string()), "Encoding failed: InvalidKeyFormat"),
+            (AuthError::Key("JWT_SECRET must be at least 32 characters long".to_string()), "Invalid key: JWT_SECRET must be at least 32 characters long"),
+            (AuthError::MissingEnv("ENCRYPTION_KEY".to_string()), "Missing environment variable: ENCRYPTION_KEY"),
+        ];
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+            assert_eq!(response(&error), (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error".to_string()));
+        }
+    }
+
+    #[test]
+    fn converts_library_errors_to_their_variant() {
+        let base64 = base64::engine::general_purpose::STANDARD.decode("not base64!").unwrap_err();
+        assert!(matches!(AuthError::from(base64), AuthError::Decryption(DecryptError::CorruptEncoding(_))));
+
+        let utf8 = String::from_utf8(vec![0xff]).unwrap_err();
+        assert!(matches!(AuthError::from(utf8), AuthError::Decryption(DecryptError::InvalidUtf8(_))));
+
+        let jwt = jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidToken);
+        assert_eq!(AuthError::from(jwt), AuthError::Encoding("InvalidToken".to_string()));
+    }
+}
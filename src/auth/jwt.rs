@@ -1,22 +1,87 @@
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use std::{rc::Rc, task::{Context, Poll}};
-use actix_web::Error;
+use std::{collections::BTreeMap, rc::Rc, task::{Context, Poll}};
+use actix_web::{Error, HttpMessage};
 use actix_service::{Service, Transform};
 use futures::{future::{ok, LocalBoxFuture, Ready}};
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
 
 use crate::auth::claims::Claims;
 
+/// Whether tokens must carry an `exp` claim
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExpiryPolicy {
+    /// Reject tokens without `exp`
+    #[default]
+    Strict,
+    /// Accept tokens without `exp` as never expiring, tokens with one are still checked.
+    /// A leaked token without `exp` stays valid until the secret is rotated,
+    /// only use this to accept legacy tokens you can't reissue
+    AllowMissing,
+}
+
+impl ExpiryPolicy {
+    /// HS256 validation enforcing this policy
+    pub fn validation(self) -> Validation {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_exp = true;
+        if self == ExpiryPolicy::AllowMissing {
+            validation.required_spec_claims.remove("exp");
+        }
+        validation
+    }
+}
+
 pub struct JwtMiddleware {
-    secret_key: String
+    secret_key: String,
+    /// Secrets of the tokens signed under a `kid`, by key id
+    keys: BTreeMap<String, String>,
+    expiry_policy: ExpiryPolicy,
+    audiences: Vec<String>,
 }
 
 impl JwtMiddleware{
     pub fn new(secret_key: String) -> Self {
         Self {
-            secret_key
+            secret_key,
+            keys: BTreeMap::new(),
+            expiry_policy: ExpiryPolicy::default(),
+            audiences: Vec::new(),
         }
     }
+
+    /// Verifies the tokens whose `kid` header is `kid` with `secret_key`, for setups signing with several keys.
+    /// Tokens without a `kid`, or with one no key was added for, are verified with the secret given to `new`
+    pub fn with_key(mut self, kid: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        self.keys.insert(kid.into(), secret_key.into());
+        self
+    }
+
+    pub fn with_expiry_policy(mut self, expiry_policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = expiry_policy;
+        self
+    }
+
+    /// Only accepts tokens whose `aud` claim names one of `audiences`; tokens without `aud` are rejected.
+    /// Each instance keeps its own audiences, so scopes wrapped by different instances can require different ones
+    pub fn with_audience<I, A>(mut self, audiences: I) -> Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        self.audiences = audiences.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Validation of this instance: its expiry policy, plus its audiences when it has some
+    fn validation(&self) -> Validation {
+        let mut validation = self.expiry_policy.validation();
+        if !self.audiences.is_empty() {
+            validation.set_audience(&self.audiences);
+            validation.required_spec_claims.insert("aud".to_string());
+        }
+        validation
+    }
 }
 
 impl<S, B> Transform<S, ServiceRequest> for JwtMiddleware
@@ -34,7 +99,9 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(JwtMiddlewareService {
             service: Rc::new(service),
-            secret_key: self.secret_key.clone()
+            secret_key: self.secret_key.clone(),
+            keys: self.keys.clone(),
+            validation: self.validation(),
         })
     }
 }
@@ -42,6 +109,8 @@ where
 pub struct JwtMiddlewareService<S> {
     service: Rc<S>,
     secret_key: String,
+    keys: BTreeMap<String, String>,
+    validation: Validation,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtMiddlewareService<S>
@@ -59,70 +128,124 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = Rc::clone(&self.service);
-        
-        let auth_header = match req.headers().get("Authorization") {
-            Some(header) => header,
-            None => {
-                return Box::pin(async {
-                    Err(actix_web::error::ErrorUnauthorized("Authorization header missing"))
-                })
-            }
-        };
-
-        let auth_str = match auth_header.to_str() {
-            Ok(str) => str,
-            Err(_) => {
-                return Box::pin(async {
-                    Err(actix_web::error::ErrorUnauthorized("Invalid Authorization header encoding"))
-                })
-            }
-        };
+        let header = req.headers().get("Authorization").map(|header| header.as_bytes());
 
-        if !auth_str.starts_with("Bearer ") {
-            return Box::pin(async {
-                Err(actix_web::error::ErrorUnauthorized("Authorization header must start with 'Bearer '"))
-            })
+        match bearer_claims(header, &self.secret_key, &self.keys, &self.validation) {
+            // Later middlewares and handlers read them with `req.extensions().get::<Claims>()`
+            Ok(claims) => {
+                req.extensions_mut().insert(claims);
+                Box::pin(service.call(req))
+            }
+            Err(error_msg) => Box::pin(async move {
+                Err(actix_web::error::ErrorUnauthorized(error_msg))
+            }),
         }
+    }
+}
 
-        let token = &auth_str[7..];
+/// Secret verifying `token`: the one of `keys` named by its `kid` header, or `secret_key` when it has
+/// no `kid` or an unknown one (its signature is still checked, so a wrong `kid` doesn't get it accepted)
+pub(crate) fn verification_secret<'a>(token: &str, secret_key: &'a str, keys: &'a BTreeMap<String, String>) -> &'a str {
+    decode_header(token)
+        .ok()
+        .and_then(|header| header.kid)
+        .and_then(|kid| keys.get(&kid))
+        .map_or(secret_key, String::as_str)
+}
 
-        if token.is_empty() {
-            return Box::pin(async {
-                Err(actix_web::error::ErrorUnauthorized("Empty token"))
-            });
-        }
+/// Validates an `Authorization: Bearer <token>` header, returning its claims or why it was rejected.
+/// Shared by the actix and axum middlewares so both answer with the same messages
+pub(crate) fn bearer_claims(
+    header: Option<&[u8]>,
+    secret_key: &str,
+    keys: &BTreeMap<String, String>,
+    validation: &Validation,
+) -> Result<Claims, &'static str> {
+    let auth_header = header.ok_or("Authorization header missing")?;
+    let auth_str = std::str::from_utf8(auth_header).map_err(|_| "Invalid Authorization header encoding")?;
+    let token = auth_str.strip_prefix("Bearer ").ok_or("Authorization header must start with 'Bearer '")?;
+
+    if token.is_empty() {
+        return Err("Empty token");
+    }
 
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.validate_exp = true;
+    decode::<Claims<serde_json::Value>>( // Concrete type
+        token,
+        &DecodingKey::from_secret(verification_secret(token, secret_key, keys).as_bytes()),
+        validation,
+    )
+    .map(|data| data.claims)
+    .map_err(|err| match err.kind() {
+        jsonwebtoken::errors::ErrorKind::ExpiredSignature => "Token expired",
+        jsonwebtoken::errors::ErrorKind::InvalidToken => "Invalid token",
+        jsonwebtoken::errors::ErrorKind::InvalidSignature => "Invalid token signature",
+        jsonwebtoken::errors::ErrorKind::InvalidEcdsaKey => "Invalid key",
+        jsonwebtoken::errors::ErrorKind::InvalidAlgorithm => "Invalid algorithm",
+        jsonwebtoken::errors::ErrorKind::InvalidIssuer => "Invalid issuer",
+        jsonwebtoken::errors::ErrorKind::InvalidAudience => "Invalid audience",
+        jsonwebtoken::errors::ErrorKind::InvalidSubject => "Invalid subject",
+        jsonwebtoken::errors::ErrorKind::ImmatureSignature => "Token not yet valid",
+        _ => "Invalid token", // Handles malformed_jwt_structure and other cases
+    })
+}
 
-        let token_data = decode::<Claims<serde_json::Value>>( // Concrete type
-                token,
-                &DecodingKey::from_secret(self.secret_key.as_bytes()),
-                &validation
-            );
-
-        match token_data {
-            Ok(_data) => {
-                return Box::pin(service.call(req))
-            },
-            Err(err) => {
-                let error_msg = match err.kind() {
-                    jsonwebtoken::errors::ErrorKind::ExpiredSignature => "Token expired",
-                    jsonwebtoken::errors::ErrorKind::InvalidToken => "Invalid token",
-                    jsonwebtoken::errors::ErrorKind::InvalidSignature => "Invalid token signature",
-                    jsonwebtoken::errors::ErrorKind::InvalidEcdsaKey => "Invalid key",
-                    jsonwebtoken::errors::ErrorKind::InvalidAlgorithm => "Invalid algorithm",
-                    jsonwebtoken::errors::ErrorKind::InvalidIssuer => "Invalid issuer",
-                    jsonwebtoken::errors::ErrorKind::InvalidAudience => "Invalid audience",
-                    jsonwebtoken::errors::ErrorKind::InvalidSubject => "Invalid subject",
-                    jsonwebtoken::errors::ErrorKind::ImmatureSignature => "Token not yet valid",
-                    _ => "Invalid token", // Handles malformed_jwt_structure and other cases
-                };
-
-                Box::pin(async move {
-                    Err(actix_web::error::ErrorUnauthorized(error_msg))
-                })
-            }
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    use super::*;
+
+    const SECRET: &str = "a-jwt-secret-of-at-least-32-characters";
+
+    /// Token for `aud`, or without an `aud` claim
+    fn token(aud: Option<&str>) -> String {
+        let mut claims = serde_json::json!({
+            "sub": "ada@example.com",
+            "iat": 0,
+            "exp": chrono::Utc::now().timestamp() + 300,
+            "data": null,
+        });
+        if let Some(aud) = aud {
+            claims["aud"] = aud.into();
+        }
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn scopes_accept_only_their_own_audience() {
+        let app = test::init_service(
+            App::new()
+                .service(
+                    web::scope("/admin-api")
+                        .wrap(JwtMiddleware::new(SECRET.to_string()).with_audience(["admin"]))
+                        .route("", web::get().to(HttpResponse::Ok)),
+                )
+                .service(
+                    web::scope("/user-api")
+                        .wrap(JwtMiddleware::new(SECRET.to_string()).with_audience(["user"]))
+                        .route("", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        for (uri, aud, expected) in [
+            ("/admin-api", Some("admin"), StatusCode::OK),
+            ("/user-api", Some("admin"), StatusCode::UNAUTHORIZED),
+            ("/user-api", Some("user"), StatusCode::OK),
+            ("/admin-api", Some("user"), StatusCode::UNAUTHORIZED),
+            ("/admin-api", None, StatusCode::UNAUTHORIZED),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", token(aud))))
+                .to_request();
+            let status = match test::try_call_service(&app, req).await {
+                Ok(res) => res.status(),
+                Err(err) => err.as_response_error().status_code(),
+            };
+            assert_eq!(status, expected, "{} with aud {:?}", uri, aud);
         }
     }
 }
@@ -1,7 +1,14 @@
+#[allow(clippy::module_inception)]
 pub mod auth;
+pub mod builder;
 pub mod jwt;
 pub mod claims;
+pub mod error;
+#[cfg(feature = "axum")]
+pub mod axum;
 
 pub use claims::Claims;
-pub use jwt::JwtMiddleware;
-pub use auth::AuthService;
\ No newline at end of file
+pub use error::{AuthError, DecryptError};
+pub use jwt::{ExpiryPolicy, JwtMiddleware};
+pub use auth::AuthService;
+pub use builder::AuthServiceBuilder;
\ No newline at end of file
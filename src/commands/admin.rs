@@ -0,0 +1,255 @@
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::commands::resource::Resource;
+use crate::utils::fields::Field;
+use crate::utils::manifest::Manifest;
+use crate::utils::output::report;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::write_if_changed;
+
+/// Path of the admin pages generated for a resource
+pub fn admin_file_path(ctx: &ProjectContext, resource_name: &str) -> String {
+    ctx.module_file("admin", &format!("{}_admin", resource_name))
+}
+
+/// Requirement added to the project's `[dependencies]` when maud, rendering the admin pages, isn't listed yet
+pub const MAUD_DEPENDENCY: &str = r#"{ version = "0.27", features = ["actix-web"] }"#;
+
+/// Generates the list, create and edit pages of a resource under `/admin/{name}`,
+/// along with the shared layout when it doesn't exist yet, and adds maud to the project's dependencies
+pub async fn generate_admin(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let mut manifest = Manifest::load(ctx).await?;
+    if manifest.add_dependency("maud", MAUD_DEPENDENCY)? {
+        manifest.save().await?;
+        report(format!("📦 Added maud to Cargo.toml: maud = {}", MAUD_DEPENDENCY));
+    }
+
+    fs::create_dir_all(ctx.module_dir("admin")).await?;
+    generate_layout(ctx).await?;
+
+    let resource_name = &resource.name;
+    // Pages are served under the plain resource name, even with a file prefix
+    let path = &resource.path;
+    let pascal_case = &resource.type_name;
+
+    let headers: String = resource.fields.iter()
+        .map(|field| format!("                        th {{ \"{}\" }}\n", field.name))
+        .collect();
+    let cells: String = resource.fields.iter()
+        .map(|field| format!("                            td {{ ({}) }}\n", display(field)))
+        .collect();
+    let inputs: String = resource.fields.iter()
+        .map(|field| input(field, &resource.json_case.field_name(&field.name)))
+        .collect();
+
+    let content = format!(
+        r#"// Server-rendered admin pages for `{pascal_case}`
+use std::collections::BTreeMap;
+use std::sync::{{LazyLock, RwLock}};
+
+use actix_web::{{http::header, web, HttpResponse}};
+use maud::{{html, Markup}};
+
+use crate::admin::layout::layout;
+use crate::models::{resource_name}::{pascal_case};
+
+/// Rows managed by the admin until it is wired to real storage (e.g. a generated repository)
+static STORE: LazyLock<RwLock<BTreeMap<u64, {pascal_case}>>> = LazyLock::new(Default::default);
+
+pub fn {resource_name}_admin_routes(cfg: &mut web::ServiceConfig) {{
+    cfg.service(
+        web::scope("/{path}")
+            .route("", web::get().to(list))
+            .route("", web::post().to(create))
+            .route("/new", web::get().to(new_form))
+            .route("/{{id}}", web::get().to(edit_form))
+            .route("/{{id}}", web::post().to(update))
+    );
+}}
+
+/// Table of every `{pascal_case}`
+pub fn list_view(items: &BTreeMap<u64, {pascal_case}>) -> Markup {{
+    layout(
+        "{pascal_case}",
+        html! {{
+            a href="/admin/{path}/new" {{ "New {pascal_case}" }}
+            table {{
+                thead {{
+                    tr {{
+                        th {{ "id" }}
+{headers}                        th {{}}
+                    }}
+                }}
+                tbody {{
+                    @for (id, item) in items {{
+                        tr {{
+                            td {{ (id) }}
+{cells}                            td {{ a href={{ "/admin/{path}/" (id) }} {{ "Edit" }} }}
+                        }}
+                    }}
+                }}
+            }}
+        }},
+    )
+}}
+
+/// Form creating a new `{pascal_case}` or editing the one at `action`
+pub fn form_view(title: &str, action: &str, item: &{pascal_case}) -> Markup {{
+    layout(
+        title,
+        html! {{
+            form method="post" action=(action) {{
+{inputs}                button type="submit" {{ "Save" }}
+            }}
+            a href="/admin/{path}" {{ "Back" }}
+        }},
+    )
+}}
+
+async fn list() -> Markup {{
+    list_view(&STORE.read().unwrap())
+}}
+
+async fn new_form() -> Markup {{
+    form_view("New {pascal_case}", "/admin/{path}", &{pascal_case}::default())
+}}
+
+async fn create(form: web::Form<{pascal_case}>) -> HttpResponse {{
+    let mut store = STORE.write().unwrap();
+    let id = store.keys().next_back().map_or(1, |last| last + 1);
+    store.insert(id, form.into_inner());
+    redirect_to_list()
+}}
+
+async fn edit_form(path: web::Path<u64>) -> HttpResponse {{
+    let id = path.into_inner();
+    match STORE.read().unwrap().get(&id) {{
+        Some(item) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(form_view("Edit {pascal_case}", &format!("/admin/{path}/{{}}", id), item).into_string()),
+        None => HttpResponse::NotFound().finish(),
+    }}
+}}
+
+async fn update(path: web::Path<u64>, form: web::Form<{pascal_case}>) -> HttpResponse {{
+    match STORE.write().unwrap().get_mut(&path.into_inner()) {{
+        Some(item) => {{
+            *item = form.into_inner();
+            redirect_to_list()
+        }}
+        None => HttpResponse::NotFound().finish(),
+    }}
+}}
+
+fn redirect_to_list() -> HttpResponse {{
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, "/admin/{path}"))
+        .finish()
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+    use actix_web::{{test, App}};
+
+    #[actix_web::test]
+    async fn renders_list_and_new_form() {{
+        let app = test::init_service(App::new().service(web::scope("/admin").configure({resource_name}_admin_routes))).await;
+
+        for uri in ["/admin/{path}", "/admin/{path}/new"] {{
+            let res = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+            assert!(res.status().is_success(), "GET {{}} returned {{}}", uri, res.status());
+            let body = test::read_body(res).await;
+            assert!(std::str::from_utf8(&body).unwrap().contains("{pascal_case}"));
+        }}
+    }}
+}}
+"#
+    );
+
+    write_if_changed(admin_file_path(ctx, resource_name), content).await?;
+
+    ctx.register("admin", &format!("{}_admin", resource_name)).await?;
+    Ok(())
+}
+
+/// Generates the base layout shared by every admin page, keeping any existing (possibly edited) copy
+async fn generate_layout(ctx: &ProjectContext) -> Result<()> {
+    let layout_path = ctx.module_file("admin", "layout");
+    if Path::new(&layout_path).exists() {
+        return Ok(());
+    }
+
+    let content = r#"// Base layout of the admin pages
+use maud::{html, Markup, DOCTYPE};
+
+/// Wraps an admin page body in the shared HTML skeleton
+pub fn layout(title: &str, body: Markup) -> Markup {
+    html! {
+        (DOCTYPE)
+        html lang="en" {
+            head {
+                meta charset="utf-8";
+                title { (title) " · Admin" }
+                style {
+                    "body { font-family: sans-serif; margin: 2rem; } "
+                    "table { border-collapse: collapse; margin: 1rem 0; } "
+                    "th, td { border: 1px solid #ccc; padding: 0.4rem 0.8rem; } "
+                    "label { display: block; margin-bottom: 0.6rem; }"
+                }
+            }
+            body {
+                h1 { (title) }
+                main { (body) }
+            }
+        }
+    }
+}
+"#;
+
+    write_if_changed(&layout_path, content).await?;
+    ctx.register("admin", "layout").await?;
+    Ok(())
+}
+
+/// Maud expression rendering a field of `item` in a table cell
+fn display(field: &Field) -> String {
+    if is_scalar(&field.ty) {
+        format!("item.{}", field.name)
+    } else {
+        format!("format!(\"{{:?}}\", item.{})", field.name)
+    }
+}
+
+/// Form control for a field, prefilled from `item` and submitted under the `key` the model deserializes it from
+fn input(field: &Field, key: &str) -> String {
+    let name = &field.name;
+    let control = match field.ty.as_str() {
+        "bool" => format!(
+            r#"select name="{key}" {{
+                        option value="true" selected[item.{name}] {{ "true" }}
+                        option value="false" selected[!item.{name}] {{ "false" }}
+                    }}"#
+        ),
+        ty if is_scalar(ty) && ty != "String" && ty != "char" => {
+            format!(r#"input type="number" step="any" name="{key}" value=(item.{name});"#)
+        }
+        ty if is_scalar(ty) => format!(r#"input type="text" name="{key}" value=(item.{name});"#),
+        // Forms only carry flat values, richer types need a custom control
+        _ => format!(r#"input type="text" name="{key}" value=(format!("{{:?}}", item.{name})) disabled;"#),
+    };
+    format!(
+        "                label {{\n                    \"{name}\" \" \"\n                    {control}\n                }}\n"
+    )
+}
+
+fn is_scalar(ty: &str) -> bool {
+    matches!(
+        ty,
+        "String" | "bool" | "char" | "f32" | "f64"
+            | "i8" | "i16" | "i32" | "i64" | "i128" | "isize"
+            | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+    )
+}
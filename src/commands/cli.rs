@@ -0,0 +1,123 @@
+use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::utils::manifest::Manifest;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct CliArgs {
+    /// Name of the binary, run with `cargo run --bin <name>`
+    pub name: String,
+    /// Overwrite the binary if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+/// Requirement added to the project's `[dependencies]` when clap isn't listed yet
+const CLAP_DEPENDENCY: &str = r#"{ version = "4", features = ["derive"] }"#;
+
+pub async fn execute(args: CliArgs) -> anyhow::Result<()> {
+    report(format!("⌨️ Generating CLI: {}", args.name));
+
+    let valid_name = args.name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && args.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        anyhow::bail!("❌ Invalid binary name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = format!("{}/bin/{}.rs", ctx.src_dir(), args.name);
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    // `[[bin]]` paths are relative to the manifest, next to the sources directory
+    let src_dir = Path::new(&ctx.config().src_dir).file_name().map_or("src".into(), |dir| dir.to_string_lossy());
+    let mut manifest = Manifest::load(&ctx).await?;
+    let clap_added = manifest.add_dependency("clap", CLAP_DEPENDENCY)?;
+    manifest.add_bin(&args.name, &format!("{}/bin/{}.rs", src_dir, args.name))?;
+    if Path::new(&format!("{}/main.rs", ctx.src_dir())).exists() {
+        manifest.keep_default_run();
+    }
+
+    fs::create_dir_all(format!("{}/bin", ctx.src_dir())).await?;
+    generate_cli(&args.name, &file_path).await?;
+    manifest.save().await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ CLI '{}' created successfully!", args.name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    if clap_added {
+        report(format!("📦 Added clap to Cargo.toml: clap = {}", CLAP_DEPENDENCY));
+    }
+    report(format!("▶️ Try it with `cargo run --bin {} -- --help`", args.name));
+
+    Ok(())
+}
+
+/// Writes a clap skeleton with one example subcommand, printing its help when run without any
+async fn generate_cli(name: &str, file_path: &str) -> Result<()> {
+    let content = format!(
+        r#"// `{name}`: command-line companion of the service, run with `cargo run --bin {name} -- --help`
+use clap::{{CommandFactory, Parser, Subcommand}};
+
+#[derive(Parser)]
+#[command(name = "{name}", version, about = "Command-line companion of the service")]
+struct Cli {{
+    #[command(subcommand)]
+    command: Option<Command>,
+}}
+
+#[derive(Subcommand)]
+enum Command {{
+    /// Prints a greeting; replace with the commands the service needs
+    Hello {{
+        /// Who to greet
+        #[arg(long, default_value = "world")]
+        name: String,
+    }},
+}}
+
+fn main() {{
+    let cli = Cli::parse();
+    match cli.command {{
+        Some(Command::Hello {{ name }}) => println!("Hello, {{}}!", name),
+        None => {{
+            // Without a subcommand, behave like `--help`
+            Cli::command().print_help().expect("failed to print the help");
+        }}
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_valid() {{
+        Cli::command().debug_assert();
+    }}
+
+    #[test]
+    fn hello_defaults_to_the_world() {{
+        let cli = Cli::try_parse_from(["{name}", "hello"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Hello {{ name }}) if name == "world"));
+    }}
+}}
+"#
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
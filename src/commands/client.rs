@@ -0,0 +1,455 @@
+use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::utils::conversions::to_pascal_case;
+use crate::utils::manifest::Manifest;
+use crate::utils::project::{ModulesStyle, ProjectContext};
+use crate::utils::utils::{document_env, fix_generated, write_if_changed};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct ClientArgs {
+    /// Name of the external API, e.g. `payments` for `src/clients/payments.rs`
+    pub name: String,
+    /// Fail calls fast once the API keeps failing, through a shared `clients::circuit_breaker::CircuitBreaker`
+    #[arg(long)]
+    pub circuit_breaker: bool,
+    /// Consecutive failures (errors and 5xx answers) opening the circuit
+    #[arg(long, value_name = "COUNT", default_value_t = 5, requires = "circuit_breaker", value_parser = clap::value_parser!(u32).range(1..))]
+    pub failure_threshold: u32,
+    /// Seconds an open circuit fails calls fast before letting a trial call through
+    #[arg(long, value_name = "SECS", default_value_t = 30, requires = "circuit_breaker")]
+    pub open_secs: u64,
+    /// Overwrite the client if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+/// Requirement added to the project's `[dependencies]` for the HTTP calls, rustls keeping OpenSSL out of the build
+const REQWEST_DEPENDENCY: &str = r#"{ version = "0.12", default-features = false, features = ["json", "rustls-tls"] }"#;
+
+pub async fn execute(args: ClientArgs) -> anyhow::Result<()> {
+    report(format!("🌐 Generating client: {}", args.name));
+
+    let name = args.name.replace('-', "_").to_lowercase();
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name || name == "circuit_breaker" {
+        anyhow::bail!("❌ Invalid client name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = ctx.module_file("clients", &name);
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    let mut manifest = Manifest::load(&ctx).await?;
+    let reqwest_added = manifest.add_dependency("reqwest", REQWEST_DEPENDENCY)?;
+
+    let env_var = format!("{}_API_URL", name.to_uppercase());
+    fs::create_dir_all(ctx.module_dir("clients")).await?;
+    let mut generated = Vec::new();
+    if args.circuit_breaker && generate_circuit_breaker(&ctx).await? {
+        generated.push(ctx.module_location("clients", "circuit_breaker"));
+    }
+    generate_client(&args, &name, &env_var, &file_path).await?;
+    ctx.register("clients", &name).await?;
+    manifest.save().await?;
+    document_env(&ctx, &format!("{} API", to_pascal_case(&name)), &env_var, "https://api.example.com").await?;
+    generated.push(file_path);
+    if args.fix {
+        fix_generated(&ctx, &generated).await?;
+    }
+
+    report(format!("✅ Client '{}' created successfully!", name));
+    report("📝 Generated files:");
+    for file in &generated {
+        report(format!("   - {}", file));
+    }
+    if reqwest_added {
+        report(format!("📦 Added reqwest to Cargo.toml: reqwest = {}", REQWEST_DEPENDENCY));
+    }
+    report(format!(
+        "📣 Call the API with `crate::clients::{}::{}Client::from_env()`, its base URL read from {}",
+        name,
+        to_pascal_case(&name),
+        env_var
+    ));
+
+    Ok(())
+}
+
+/// Writes the reqwest client of the API, going through the circuit breaker with `--circuit-breaker`
+async fn generate_client(args: &ClientArgs, name: &str, env_var: &str, file_path: &str) -> Result<()> {
+    let type_name = format!("{}Client", to_pascal_case(name));
+    let content = if args.circuit_breaker {
+        format!(
+            r#"// `{name}` API client: JSON calls through reqwest, failing fast while the API is down
+// thanks to a circuit breaker shared by every clone of the client
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{{Client, Method, RequestBuilder}};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::clients::circuit_breaker::{{BreakerError, CircuitBreaker}};
+
+/// Environment variable holding the base URL of the API
+pub const BASE_URL_ENV: &str = "{env_var}";
+/// Requests taking longer fail, and count against the circuit
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Consecutive failures opening the circuit
+pub const FAILURE_THRESHOLD: u32 = {threshold};
+/// How long the open circuit fails calls fast before letting a trial call through
+pub const OPEN_FOR: Duration = Duration::from_secs({open_secs});
+
+/// Outcome of a call: the API's error, or `BreakerError::Open` when it wasn't even attempted
+pub type Result<T> = std::result::Result<T, BreakerError<reqwest::Error>>;
+
+#[derive(Clone)]
+pub struct {type_name} {{
+    http: Client,
+    base_url: String,
+    breaker: Arc<CircuitBreaker>,
+}}
+
+impl {type_name} {{
+    pub fn new(base_url: impl Into<String>) -> Self {{
+        Self {{
+            http: Client::builder().timeout(TIMEOUT).build().unwrap_or_default(),
+            base_url: base_url.into(),
+            breaker: Arc::new(CircuitBreaker::new(FAILURE_THRESHOLD, OPEN_FOR)),
+        }}
+    }}
+
+    /// The client of the API at `{env_var}`
+    pub fn from_env() -> std::result::Result<Self, std::env::VarError> {{
+        std::env::var(BASE_URL_ENV).map(Self::new)
+    }}
+
+    /// Trips on other thresholds than `FAILURE_THRESHOLD` and `OPEN_FOR`
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {{
+        self.breaker = Arc::new(breaker);
+        self
+    }}
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {{
+        self.send(self.request(Method::GET, path)).await
+    }}
+
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {{
+        self.send(self.request(Method::POST, path).json(body)).await
+    }}
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {{
+        let url = format!("{{}}/{{}}", self.base_url.trim_end_matches('/'), path.trim_start_matches('/'));
+        self.http.request(method, url)
+    }}
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {{
+        let response = self.breaker.call(|| async move {{
+            let response = request.send().await?;
+            // Only the API being down counts against the circuit, not the requests it refuses
+            if response.status().is_server_error() {{
+                response.error_for_status()
+            }} else {{
+                Ok(response)
+            }}
+        }})
+        .await?;
+        let response = response.error_for_status().map_err(BreakerError::Inner)?;
+        response.json().await.map_err(BreakerError::Inner)
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use serde_json::Value;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn repeated_failures_open_the_circuit_and_calls_fail_fast() {{
+        // Nothing listens on the discard port, so every call fails to connect
+        let client = {type_name}::new("http://127.0.0.1:9").with_circuit_breaker(CircuitBreaker::new(2, Duration::from_secs(60)));
+
+        for _ in 0..2 {{
+            assert!(matches!(client.get::<Value>("/").await, Err(BreakerError::Inner(_))));
+        }}
+        assert!(matches!(client.get::<Value>("/").await, Err(BreakerError::Open)));
+    }}
+
+    #[test]
+    fn joins_the_base_url_and_the_path() {{
+        let client = {type_name}::new("http://localhost:8080/");
+        let request = client.request(Method::GET, "/items/1").build().unwrap();
+        assert_eq!(request.url().as_str(), "http://localhost:8080/items/1");
+    }}
+}}
+"#,
+            threshold = args.failure_threshold,
+            open_secs = args.open_secs,
+        )
+    } else {
+        format!(
+            r#"// `{name}` API client: JSON calls through reqwest
+use std::time::Duration;
+
+use reqwest::{{Client, Method, RequestBuilder}};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Environment variable holding the base URL of the API
+pub const BASE_URL_ENV: &str = "{env_var}";
+/// Requests taking longer fail
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct {type_name} {{
+    http: Client,
+    base_url: String,
+}}
+
+impl {type_name} {{
+    pub fn new(base_url: impl Into<String>) -> Self {{
+        Self {{
+            http: Client::builder().timeout(TIMEOUT).build().unwrap_or_default(),
+            base_url: base_url.into(),
+        }}
+    }}
+
+    /// The client of the API at `{env_var}`
+    pub fn from_env() -> Result<Self, std::env::VarError> {{
+        std::env::var(BASE_URL_ENV).map(Self::new)
+    }}
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> reqwest::Result<T> {{
+        self.send(self.request(Method::GET, path)).await
+    }}
+
+    pub async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> reqwest::Result<T> {{
+        self.send(self.request(Method::POST, path).json(body)).await
+    }}
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {{
+        let url = format!("{{}}/{{}}", self.base_url.trim_end_matches('/'), path.trim_start_matches('/'));
+        self.http.request(method, url)
+    }}
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> reqwest::Result<T> {{
+        request.send().await?.error_for_status()?.json().await
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn joins_the_base_url_and_the_path() {{
+        let client = {type_name}::new("http://localhost:8080/");
+        let request = client.request(Method::GET, "/items/1").build().unwrap();
+        assert_eq!(request.url().as_str(), "http://localhost:8080/items/1");
+    }}
+}}
+"#
+        )
+    };
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
+
+/// Writes `clients/circuit_breaker.rs` unless it exists already. Returns whether it was written
+async fn generate_circuit_breaker(ctx: &ProjectContext) -> Result<bool> {
+    if ctx.has_module("clients", "circuit_breaker") {
+        return Ok(false);
+    }
+    ctx.write_module("clients", "circuit_breaker", CIRCUIT_BREAKER).await?;
+    if ctx.config().modules_style == ModulesStyle::Files {
+        ctx.register("clients", "circuit_breaker").await?;
+    }
+    Ok(true)
+}
+
+/// `clients/circuit_breaker.rs`: the failure-counting breaker shared by the generated clients
+const CIRCUIT_BREAKER: &str = r#"// Circuit breaker of the outbound calls: after `failure_threshold` consecutive failures the circuit
+// opens and calls fail fast with `BreakerError::Open`, until `open_for` has passed and a single trial
+// call is let through (half-open). Its success closes the circuit again, its failure reopens it
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// Calls go through, failures are counted
+    Closed,
+    /// Calls fail fast until `open_for` has passed
+    Open,
+    /// A trial call decides whether the circuit closes or opens again
+    HalfOpen,
+}
+
+#[derive(Debug)]
+pub enum BreakerError<E> {
+    /// The circuit is open, the call wasn't attempted
+    Open,
+    /// The call was attempted and failed
+    Inner(E),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for BreakerError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakerError::Open => f.write_str("circuit open, the call was not attempted"),
+            BreakerError::Inner(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for BreakerError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BreakerError::Open => None,
+            BreakerError::Inner(e) => Some(e),
+        }
+    }
+}
+
+impl<E> From<E> for BreakerError<E> {
+    fn from(e: E) -> Self {
+        BreakerError::Inner(e)
+    }
+}
+
+#[derive(Debug)]
+struct Circuit {
+    state: State,
+    /// Consecutive failures while closed
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    circuit: Mutex<Circuit>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_for: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_for,
+            circuit: Mutex::new(Circuit { state: State::Closed, failures: 0, opened_at: None }),
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.lock().state
+    }
+
+    /// Runs `call` unless the circuit is open, recording whether it succeeded
+    pub async fn call<T, E, F, Fut>(&self, call: F) -> Result<T, BreakerError<E>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.acquire()?;
+        let result = call().await;
+        self.record(result.is_ok());
+        result.map_err(BreakerError::Inner)
+    }
+
+    /// Lets a call through when closed, or as the trial call once `open_for` has passed
+    fn acquire<E>(&self) -> Result<(), BreakerError<E>> {
+        let mut circuit = self.lock();
+        match circuit.state {
+            State::Closed => Ok(()),
+            State::Open if circuit.opened_at.is_some_and(|at| at.elapsed() >= self.open_for) => {
+                circuit.state = State::HalfOpen;
+                Ok(())
+            }
+            // One trial call at a time
+            State::Open | State::HalfOpen => Err(BreakerError::Open),
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut circuit = self.lock();
+        if success {
+            *circuit = Circuit { state: State::Closed, failures: 0, opened_at: None };
+            return;
+        }
+        circuit.failures += 1;
+        if circuit.state == State::HalfOpen || circuit.failures >= self.failure_threshold {
+            circuit.state = State::Open;
+            circuit.opened_at = Some(Instant::now());
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Circuit> {
+        // The state stays consistent even if a holder panicked
+        self.circuit.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn fail(breaker: &CircuitBreaker) -> Result<(), BreakerError<&'static str>> {
+        breaker.call(|| async { Err::<(), _>("down") }).await
+    }
+
+    async fn succeed(breaker: &CircuitBreaker) -> Result<(), BreakerError<&'static str>> {
+        breaker.call(|| async { Ok(()) }).await
+    }
+
+    #[actix_web::test]
+    async fn repeated_failures_open_the_circuit_and_calls_fail_fast() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+        for _ in 0..3 {
+            assert!(matches!(fail(&breaker).await, Err(BreakerError::Inner("down"))));
+        }
+        assert_eq!(breaker.state(), State::Open);
+
+        let mut attempted = false;
+        let result = breaker.call(|| async { attempted = true; Ok::<_, &str>(()) }).await;
+        assert!(matches!(result, Err(BreakerError::Open)));
+        assert!(!attempted);
+    }
+
+    #[actix_web::test]
+    async fn a_success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        assert!(fail(&breaker).await.is_err());
+        assert!(succeed(&breaker).await.is_ok());
+        assert!(fail(&breaker).await.is_err());
+        assert_eq!(breaker.state(), State::Closed);
+    }
+
+    #[actix_web::test]
+    async fn the_trial_call_closes_or_reopens_the_circuit() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        assert!(fail(&breaker).await.is_err());
+        assert_eq!(breaker.state(), State::Open);
+
+        // `open_for` has passed, the trial fails and the circuit opens again
+        assert!(matches!(fail(&breaker).await, Err(BreakerError::Inner(_))));
+        assert_eq!(breaker.state(), State::Open);
+
+        assert!(succeed(&breaker).await.is_ok());
+        assert_eq!(breaker.state(), State::Closed);
+    }
+}
+"#;
@@ -0,0 +1,16 @@
+use clap::{Args, Command};
+use clap_complete::{generate, Shell};
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// Prints the completion script of the whole CLI (`cmd`) for the requested shell to stdout
+pub async fn execute(args: CompletionsArgs, mut cmd: Command) -> anyhow::Result<()> {
+    let bin_name = cmd.get_name().to_string();
+    generate(args.shell, &mut cmd, bin_name, &mut std::io::stdout());
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use clap::Args;
 
+use crate::utils::project::ProjectContext;
+use crate::utils::output::report;
+
 #[derive(Args)]
 pub struct ControllerArgs {
     /// Name of the controller
@@ -7,7 +10,8 @@ pub struct ControllerArgs {
 }
 
 pub async fn execute(args: ControllerArgs) -> anyhow::Result<()> {
-    println!("🎮 Generating controller: {}", args.name);
+    report(format!("🎮 Generating controller: {}", args.name));
+    let _ctx = ProjectContext::load().await?;
     // Your controller generation logic here
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,151 @@
+use clap::Args;
+
+use crate::commands::resource::{field_enum, sample_value, uses_hash_map};
+use crate::utils::conversions::{to_pascal_case, to_snake_case};
+use crate::utils::fields::{is_identifier, parse_fields, Field, FieldKind};
+use crate::utils::project::{ModulesStyle, ProjectContext};
+use crate::utils::utils::fix_generated;
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct DtoArgs {
+    /// Name of the DTO, e.g. `LoginRequest` or `login_request`
+    pub name: String,
+    /// Fields as `name:Type` pairs, parsed like the `--fields` of `g resource`
+    #[arg(long, num_args = 1.., required = true)]
+    pub fields: Vec<String>,
+    /// Make every field an `Option`, left out of the JSON as `None`
+    #[arg(long)]
+    pub optional: bool,
+    /// Overwrite the model if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+pub async fn execute(args: DtoArgs) -> anyhow::Result<()> {
+    report(format!("📦 Generating DTO: {}", args.name));
+
+    let module = to_snake_case(&args.name.replace('-', "_"));
+    if !is_identifier(&module) {
+        anyhow::bail!("❌ Invalid DTO name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+    let type_name = to_pascal_case(&module);
+    let fields = parse_fields(&args.fields, &type_name)?;
+    if args.optional && let Some(field) = fields.iter().find(|field| field.default.is_some()) {
+        anyhow::bail!("❌ --optional fields default to `None`, remove the default of '{}'", field.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = ctx.module_location("models", &module);
+    if ctx.has_module("models", &module) && !args.force {
+        anyhow::bail!("❌ Model '{}' already exists in '{}', use --force to overwrite it", module, file_path);
+    }
+
+    let content = generate_dto(&ctx, &type_name, &fields, args.optional);
+    ctx.write_module("models", &module, &content).await?;
+    if ctx.config().modules_style == ModulesStyle::Files {
+        ctx.register("models", &module).await?;
+    }
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ DTO '{}' created successfully!", type_name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    report(format!("🔗 Use it with `use crate::models::{}::{};`", module, type_name));
+
+    Ok(())
+}
+
+/// Source of the DTO: a serde struct of the fields, without the constructor, traits and
+/// query builder of resource models, and a test of its JSON round trip
+fn generate_dto(ctx: &ProjectContext, type_name: &str, fields: &[Field], optional: bool) -> String {
+    let openapi = ctx.config().openapi;
+    let json_case = ctx.config().json_case;
+    let ty = |field: &Field| if optional { format!("Option<{}>", field.ty) } else { field.ty.clone() };
+
+    let mut imports = String::new();
+    if fields.iter().any(|field| uses_hash_map(&field.ty)) {
+        imports.push_str("use std::collections::HashMap;\n\n");
+    }
+    imports.push_str("use serde::{Deserialize, Serialize};\n");
+
+    let body: String = fields.iter()
+        .map(|field| {
+            // Request bodies may leave out the fields with a default
+            let default = match &field.default {
+                Some(_) => format!("    #[serde(default = \"default_{}\")]\n", field.name),
+                None => String::new(),
+            };
+            format!("{}    pub {}: {},\n", default, field.name, ty(field))
+        })
+        .collect();
+    let schema = if openapi { ", utoipa::ToSchema" } else { "" };
+    let rename = json_case.serde_attribute().map(|attribute| format!("{}\n", attribute)).unwrap_or_default();
+    let mut content = format!(
+        r#"{imports}
+/// `{type_name}` request or response body, not backed by a resource
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq{schema})]
+{rename}pub struct {type_name} {{
+{body}}}
+"#
+    );
+
+    for field in fields.iter().filter(|field| field.default.is_some()) {
+        content.push_str(&format!(
+            "\n/// `{}` of the bodies leaving it out\nfn default_{}() -> {} {{\n    {}\n}}\n",
+            field.name, field.name, field.ty, field.default.as_deref().unwrap_or_default()
+        ));
+    }
+    for field in fields {
+        if let FieldKind::Enum(variants) = &field.kind {
+            content.push_str(&field_enum(type_name, field, variants, openapi));
+        }
+    }
+
+    let values: String = fields.iter()
+        .map(|field| {
+            let value = sample_value(&field.ty);
+            let value = if optional { format!("Some({})", value) } else { value };
+            format!("            {}: {},\n", field.name, value)
+        })
+        .collect();
+    let keys: String = fields.iter()
+        .map(|field| format!("        assert!(json.get(\"{}\").is_some());\n", json_case.field_name(&field.name)))
+        .collect();
+    let missing_test = if optional {
+        format!(
+            r#"
+    #[test]
+    fn missing_fields_are_none() {{
+        let dto: {type_name} = serde_json::from_str("{{}}").unwrap();
+        assert_eq!(dto, {type_name}::default());
+    }}
+"#
+        )
+    } else {
+        String::new()
+    };
+    content.push_str(&format!(
+        r#"
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn round_trips_through_json() {{
+        let dto = {type_name} {{
+{values}        }};
+        let json = serde_json::to_value(&dto).unwrap();
+{keys}        assert_eq!(serde_json::from_value::<{type_name}>(json).unwrap(), dto);
+    }}
+{missing_test}}}
+"#
+    ));
+    content
+}
@@ -0,0 +1,123 @@
+use anyhow::Result;
+use tokio::fs;
+
+use crate::commands::resource::{Method, Resource};
+use crate::utils::manifest::Manifest;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::write_if_changed;
+
+/// Name of the example target generated for a resource
+pub fn example_name(resource_name: &str) -> String {
+    format!("{}_usage", resource_name)
+}
+
+/// Path of the usage example generated for a resource
+pub fn example_file_path(ctx: &ProjectContext, resource_name: &str) -> String {
+    ctx.crate_path(&format!("examples/{}.rs", example_name(resource_name)))
+}
+
+/// Generates `examples/{name}_usage.rs`, calling every route of the resource in-process and printing
+/// the answers. Feature-gated resources get an `[[example]]` requiring their feature
+pub async fn generate_example(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let mut manifest = Manifest::load(ctx).await?;
+    let Some(crate_name) = manifest.lib_name() else {
+        anyhow::bail!("❌ Could not find the package name in Cargo.toml, needed to import the crate from the example");
+    };
+    if let Some(feature) = &resource.feature {
+        manifest.add_example(&example_name(&resource.name), feature)?;
+        manifest.save().await?;
+    }
+
+    let resource_name = &resource.name;
+    let pascal_case = &resource.type_name;
+    // Nested routes are only reachable through the parent's scope
+    let (routes_module, uri) = match &resource.parent {
+        Some(parent) => (resource.module_of(parent), format!("/{}/1/{}", parent, resource.path)),
+        None => (resource_name.clone(), format!("/{}", resource.path)),
+    };
+
+    let mut calls = String::new();
+    let item_routes = [Method::Get, Method::Update, Method::Delete].iter().any(|method| resource.has(*method));
+    if resource.has(Method::Create) {
+        let created = if item_routes { "let created = " } else { "" };
+        calls.push_str(&format!(
+            r#"
+    let req = test::TestRequest::post().uri("{uri}").set_json({pascal_case}::default()).to_request();
+    {created}show("POST {uri}", test::call_service(&app, req).await).await;
+"#
+        ));
+        if item_routes {
+            calls.push_str(
+                r#"    // The item routes are called with the id of the created item, when it has one
+    let id = created.get("id").map_or_else(|| "1".to_string(), |id| id.to_string().trim_matches('"').to_string());
+"#,
+            );
+        }
+    } else if item_routes {
+        calls.push_str("\n    let id = \"1\";\n");
+    }
+    if resource.has(Method::List) {
+        calls.push_str(&format!(
+            r#"
+    let req = test::TestRequest::get().uri("{uri}").to_request();
+    show("GET {uri}", test::call_service(&app, req).await).await;
+"#
+        ));
+    }
+    if item_routes {
+        calls.push_str(&format!("\n    let uri = format!(\"{}/{{}}\", id);\n", uri));
+    }
+    for (method, verb, body) in [(Method::Get, "get", false), (Method::Update, "put", true), (Method::Delete, "delete", false)] {
+        if !resource.has(method) {
+            continue;
+        }
+        let body = if body { format!(".set_json({}::default())", pascal_case) } else { String::new() };
+        calls.push_str(&format!(
+            r#"
+    let req = test::TestRequest::{verb}().uri(&uri){body}.to_request();
+    show(&format!("{method} {{}}", uri), test::call_service(&app, req).await).await;
+"#,
+            method = verb.to_uppercase()
+        ));
+    }
+
+    // The model is only needed for the bodies of the create and update requests
+    let model_import = if resource.has(Method::Create) || resource.has(Method::Update) {
+        format!("use {}::models::{}::{};\n", crate_name, resource_name, pascal_case)
+    } else {
+        String::new()
+    };
+
+    let content = format!(
+        r#"// Usage of the `{resource_name}` resource: builds an `App` serving its routes, calls each of them
+// in-process and prints what they answer. Run it with `cargo run --example {example}`;
+// `cargo build --examples` keeps it compiling along with the handlers
+use actix_web::body::MessageBody;
+use actix_web::dev::ServiceResponse;
+use actix_web::{{test, App}};
+use serde_json::Value;
+
+{model_import}use {crate_name}::routes::{routes_module}_routes::{routes_module}_routes;
+
+#[actix_web::main]
+async fn main() {{
+    // The resource's routes on their own, without the `/api` scope and the middlewares of the server
+    let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+{calls}}}
+
+/// Prints the status and body of a response, returning the body as JSON (`Null` when it isn't)
+async fn show<B: MessageBody>(request: &str, res: ServiceResponse<B>) -> Value {{
+    let status = res.status();
+    let body = test::read_body(res).await;
+    println!("{{}} -> {{}}\n{{}}\n", request, status, String::from_utf8_lossy(&body));
+    serde_json::from_slice(&body).unwrap_or(Value::Null)
+}}
+"#,
+        example = example_name(resource_name)
+    );
+
+    let file_path = example_file_path(ctx, resource_name);
+    fs::create_dir_all(ctx.crate_path("examples")).await?;
+    write_if_changed(&file_path, content).await?;
+    Ok(())
+}
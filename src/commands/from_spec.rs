@@ -0,0 +1,421 @@
+use clap::Args;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::mpsc;
+
+use crate::commands::resource::{
+    check_base_model, generate_resource, print_wiring_warnings, register_routes, resolve_derives, Derive, Layer, Method, NamingArgs,
+    Resource, TzType, TIMESTAMP_FIELDS,
+};
+use crate::utils::fields::{add_foreign_keys, is_identifier, is_type, parse_fields, parse_primary_key, parse_relations, parse_status_machine};
+use crate::utils::project::{HandlerNaming, JsonCase, ProjectContext};
+use crate::utils::utils::{check_project, fix_generated};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct FromSpecArgs {
+    /// YAML or TOML file describing the resources to generate
+    pub spec: PathBuf,
+    /// Overwrite resources that already exist
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+    /// Run `cargo check` afterwards and fail when the project no longer compiles
+    #[arg(long)]
+    pub check: bool,
+    /// Keep running, regenerating the resources whose spec changed every time the file is saved
+    #[arg(long)]
+    pub watch: bool,
+    #[command(flatten)]
+    pub naming: NamingArgs,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    #[serde(default)]
+    resources: Vec<ResourceSpec>,
+}
+
+#[derive(Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResourceSpec {
+    name: String,
+    #[serde(default)]
+    fields: Vec<String>,
+    #[serde(default = "all_methods")]
+    methods: Vec<Method>,
+    #[serde(default)]
+    protected: bool,
+    #[serde(default)]
+    problem_json: bool,
+    #[serde(default)]
+    content_guards: bool,
+    #[serde(default)]
+    route_check: bool,
+    #[serde(default)]
+    etag: bool,
+    #[serde(default)]
+    paginate: bool,
+    #[serde(default)]
+    service: bool,
+    #[serde(default)]
+    store: bool,
+    #[serde(default)]
+    prelude: bool,
+    #[serde(default)]
+    readonly: bool,
+    #[serde(default)]
+    status_machine: Option<String>,
+    #[serde(default)]
+    timestamps: bool,
+    /// Type of the timestamp fields and of their columns, `utc` by default
+    #[serde(default)]
+    tz_type: TzType,
+    /// Module of the shared fields flattened into the model
+    #[serde(default)]
+    base_model: Option<String>,
+    #[serde(default)]
+    validate: bool,
+    /// Comparison traits of the model, every one its fields support by default
+    #[serde(default)]
+    derives: Option<Vec<Derive>>,
+    #[serde(default)]
+    admin: bool,
+    /// Generate `examples/{name}_usage.rs`
+    #[serde(default)]
+    example: bool,
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    relations: Vec<String>,
+    /// Naming convention of the handlers, the project's `handler_naming` by default
+    #[serde(default)]
+    naming: Option<HandlerNaming>,
+    /// Case of the model's JSON field names, the project's `json_case` by default
+    #[serde(default)]
+    json_case: Option<JsonCase>,
+    #[serde(default = "default_id_type")]
+    id_type: String,
+    /// Fields of a composite primary key identifying the items instead of an id, e.g. `[user_id, role_id]`
+    #[serde(default)]
+    primary_key: Vec<String>,
+}
+
+fn all_methods() -> Vec<Method> {
+    Method::ALL.to_vec()
+}
+
+fn default_id_type() -> String {
+    "String".to_string()
+}
+
+/// Quiet time after a change before regenerating, so the bursts of events of a single save
+/// (or several quick saves) only trigger one run
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub async fn execute(args: FromSpecArgs) -> anyhow::Result<()> {
+    report(format!("📋 Generating resources from: {}", args.spec.display()));
+
+    let ctx = ProjectContext::load().await?;
+
+    let specs = read_spec(&args.spec).await?.resources;
+    let resources = build_resources(&ctx, &args.spec, specs.clone(), &args.naming, args.force)?;
+    if resources.is_empty() {
+        report("⚠️ The spec doesn't describe any resource, nothing to generate");
+    } else {
+        generate_resources(&ctx, &args, &resources).await?;
+    }
+
+    if args.watch {
+        watch(&ctx, &args, specs).await?;
+    }
+    Ok(())
+}
+
+/// Generates and wires `resources`, then prints their summary
+async fn generate_resources(ctx: &ProjectContext, args: &FromSpecArgs, resources: &[Resource]) -> Result<()> {
+    let mut warnings = Vec::new();
+    for resource in resources {
+        warnings.extend(generate_resource(ctx, resource).await?);
+        report(format!("   📁 {}", resource.name));
+    }
+
+    // Wire every resource at once so routes.rs is only edited a single time
+    let resource_refs: Vec<&Resource> = resources.iter().collect();
+    warnings.extend(register_routes(ctx, &resource_refs).await?);
+    if args.fix {
+        let files: Vec<String> = resources.iter().flat_map(|resource| resource.files(ctx)).collect();
+        fix_generated(ctx, &files).await?;
+    }
+
+    report(format!("✅ Generated {} resources successfully!", resources.len()));
+    report("📊 Summary:");
+    for resource in resources {
+        let methods: Vec<String> = resource.methods.iter().map(|m| m.to_string()).collect();
+        report(format!(
+            "   - {} ({} fields, methods: {}{})",
+            resource.name,
+            resource.fields.len(),
+            methods.join(", "),
+            if resource.protected { ", protected" } else { "" }
+        ));
+    }
+    print_wiring_warnings(&warnings);
+    if args.check {
+        check_project(ctx).await?;
+    }
+
+    Ok(())
+}
+
+/// Regenerates the resources whose spec changed each time the spec file is saved, until Ctrl-C.
+/// A failing run is reported and the previous spec kept, so fixing the file triggers a new attempt
+async fn watch(ctx: &ProjectContext, args: &FromSpecArgs, mut previous: Vec<ResourceSpec>) -> Result<()> {
+    let spec_path = std::path::absolute(&args.spec)?;
+    let (sender, mut changes) = mpsc::unbounded_channel();
+    let watched = spec_path.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+            && event.paths.iter().any(|path| path == &watched) {
+            let _ = sender.send(());
+        }
+    })?;
+    // Editors often save by replacing the file, which a watch on the file itself would miss
+    let directory = spec_path.parent().unwrap_or(Path::new("."));
+    watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    report(format!("👀 Watching {} for changes, press Ctrl-C to stop", args.spec.display()));
+    let mut ctrl_c = pin!(tokio::signal::ctrl_c());
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            change = changes.recv() => {
+                if change.is_none() {
+                    break;
+                }
+            }
+        }
+        while let Ok(Some(())) = tokio::time::timeout(WATCH_DEBOUNCE, changes.recv()).await {}
+
+        report(format!("🔄 {} changed, regenerating", args.spec.display()));
+        match regenerate(ctx, args, &previous).await {
+            Ok(specs) => previous = specs,
+            Err(e) => report(format!("{:#}", e)),
+        }
+    }
+
+    report("👋 Stopped watching");
+    Ok(())
+}
+
+/// Generates the resources that are new or differ from `previous`, returning the specs read
+async fn regenerate(ctx: &ProjectContext, args: &FromSpecArgs, previous: &[ResourceSpec]) -> Result<Vec<ResourceSpec>> {
+    let specs = read_spec(&args.spec).await?.resources;
+    // Validated as a whole, since resources refer to each other
+    let resources = build_resources(ctx, &args.spec, specs.clone(), &args.naming, true)?;
+    let changed: Vec<Resource> = resources.into_iter()
+        .filter(|resource| {
+            let spec = specs.iter().find(|spec| spec.name == resource.name);
+            !spec.is_some_and(|spec| previous.contains(spec))
+        })
+        .collect();
+
+    let unchanged = specs.len() - changed.len();
+    if changed.is_empty() {
+        report(format!("💤 No resource changed ({} unchanged)", unchanged));
+    } else {
+        generate_resources(ctx, args, &changed).await?;
+        if unchanged > 0 {
+            report(format!("💤 Skipped {} unchanged resources", unchanged));
+        }
+    }
+    Ok(specs)
+}
+
+/// Parses a YAML or TOML spec file
+async fn read_spec(path: &Path) -> Result<Spec> {
+    let content = fs::read_to_string(path).await
+        .map_err(|e| anyhow::anyhow!("❌ Could not read spec '{}': {}", path.display(), e))?;
+
+    let spec: Result<Spec> = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(anyhow::Error::from),
+        Some("yaml") | Some("yml") => serde_yml::from_str(&content).map_err(anyhow::Error::from),
+        _ => anyhow::bail!("❌ Unsupported spec format for '{}', use a .yaml, .yml or .toml file", path.display()),
+    };
+    spec.with_context(|| format!("❌ Invalid spec '{}'", path.display()))
+}
+
+/// Validates the resources of the spec at `path`, reporting every problem found instead of only the first one
+fn build_resources(ctx: &ProjectContext, path: &Path, specs: Vec<ResourceSpec>, naming: &NamingArgs, force: bool) -> Result<Vec<Resource>> {
+    let mut resources = Vec::new();
+    let mut errors = Vec::new();
+    // Relations may point at resources declared later, e.g. `has_many` children
+    let declared: Vec<String> = specs.iter().map(|r| r.name.clone()).collect();
+
+    for resource_spec in specs {
+        let name = resource_spec.name;
+        if !is_identifier(&name) {
+            errors.push(format!("'{}' is not a valid resource name", name));
+        }
+        if resources.iter().any(|r: &Resource| r.name == name) {
+            errors.push(format!("resource '{}' is declared more than once", name));
+        }
+        let mut methods = resource_spec.methods;
+        if methods.is_empty() {
+            errors.push(format!("resource '{}' doesn't declare any method", name));
+        } else if resource_spec.readonly {
+            methods.retain(|method| method.is_read());
+            if methods.is_empty() {
+                errors.push(format!("resource '{}': readonly only generates the list and get methods", name));
+            }
+        }
+        if resource_spec.readonly && (resource_spec.status_machine.is_some() || resource_spec.admin || resource_spec.content_guards || resource_spec.validate) {
+            errors.push(format!("resource '{}': readonly can't be combined with status_machine, admin, content_guards or validate", name));
+        }
+        if let Some(parent) = &resource_spec.parent {
+            let declared_before = resources.iter().any(|r: &Resource| &r.name == parent);
+            if !declared_before && !Layer::Routes.exists(ctx, parent) {
+                errors.push(format!("resource '{}': parent '{}' is neither declared earlier in the spec nor generated", name, parent));
+            }
+            if resource_spec.protected {
+                errors.push(format!("resource '{}': nested resources inherit the protection of their parent", name));
+            }
+        }
+        if resource_spec.service && resource_spec.problem_json {
+            errors.push(format!("resource '{}': service and problem_json both define the error responses, pick one", name));
+        }
+        if resource_spec.etag && resource_spec.paginate {
+            errors.push(format!("resource '{}': etag and paginate both shape the list response, pick one", name));
+        }
+        if resource_spec.service && resource_spec.store {
+            errors.push(format!("resource '{}': service and store both back the handlers, pick one", name));
+        }
+        if resource_spec.content_guards && !methods.contains(&Method::Create) {
+            errors.push(format!("resource '{}': content_guards needs the create method", name));
+        }
+        if !is_type(&resource_spec.id_type) {
+            errors.push(format!("resource '{}': invalid id type '{}'", name, resource_spec.id_type));
+        }
+        let type_name = naming.type_name(&name);
+        let mut fields = match parse_fields(&resource_spec.fields, &type_name) {
+            Ok(fields) => fields,
+            Err(e) => {
+                errors.push(format!("resource '{}': {}", name, e));
+                Vec::new()
+            }
+        };
+        let relations = match parse_relations(&resource_spec.relations) {
+            Ok(relations) => relations,
+            Err(e) => {
+                errors.push(format!("resource '{}': {}", name, e));
+                Vec::new()
+            }
+        };
+        for relation in &relations {
+            if !declared.contains(&relation.resource) && !Layer::Model.exists(ctx, &relation.resource) {
+                errors.push(format!("resource '{}': related '{}' is neither declared in the spec nor generated", name, relation.resource));
+            }
+        }
+        add_foreign_keys(&mut fields, &relations);
+        let status_machine = match resource_spec.status_machine.as_deref().map(parse_status_machine).transpose() {
+            Ok(status_machine) => status_machine,
+            Err(e) => {
+                errors.push(format!("resource '{}': {}", name, e));
+                None
+            }
+        };
+        if status_machine.is_some() && fields.iter().any(|field| field.name == "status") {
+            errors.push(format!("resource '{}': the status machine adds a `status` field, remove it from the fields", name));
+        }
+        if resource_spec.timestamps && fields.iter().any(|field| TIMESTAMP_FIELDS.contains(&field.name.as_str())) {
+            errors.push(format!("resource '{}': timestamps adds the `created_at` and `updated_at` fields, remove them from the fields", name));
+        }
+        if !resource_spec.timestamps && resource_spec.tz_type != TzType::Utc {
+            errors.push(format!("resource '{}': tz_type only applies with timestamps", name));
+        }
+        if let Some(base) = &resource_spec.base_model {
+            if resource_spec.timestamps || resource_spec.readonly {
+                errors.push(format!("resource '{}': base_model provides the timestamps, it can't be combined with timestamps or readonly", name));
+            }
+            if let Err(e) = check_base_model(base, &fields) {
+                errors.push(format!("resource '{}': {}", name, e));
+            }
+        }
+        let primary_key = if resource_spec.primary_key.is_empty() {
+            Vec::new()
+        } else {
+            if resource_spec.service || resource_spec.store || resource_spec.parent.is_some() || resource_spec.admin || resource_spec.example {
+                errors.push(format!("resource '{}': primary_key can't be combined with service, store, parent, admin or example", name));
+            }
+            if resource_spec.id_type != default_id_type() {
+                errors.push(format!("resource '{}': primary_key identifies the items in place of id_type, remove it", name));
+            }
+            match parse_primary_key(&resource_spec.primary_key, &fields) {
+                Ok(primary_key) => primary_key,
+                Err(e) => {
+                    errors.push(format!("resource '{}': {}", name, e));
+                    Vec::new()
+                }
+            }
+        };
+        let derives = match resolve_derives(resource_spec.derives.as_deref(), &fields, resource_spec.base_model.is_some()) {
+            Ok(derives) => derives,
+            Err(e) => {
+                errors.push(format!("resource '{}': {}", name, e));
+                Vec::new()
+            }
+        };
+
+        resources.push(Resource {
+            type_name,
+            path: name.clone(),
+            file_prefix: String::new(),
+            name,
+            fields,
+            methods,
+            protected: resource_spec.protected,
+            problem_json: resource_spec.problem_json,
+            content_guards: resource_spec.content_guards,
+            route_check: resource_spec.route_check,
+            etag: resource_spec.etag,
+            paginate: resource_spec.paginate,
+            service: resource_spec.service,
+            store: resource_spec.store,
+            prelude: resource_spec.prelude,
+            readonly: resource_spec.readonly,
+            status_machine,
+            timestamps: resource_spec.timestamps,
+            tz_type: resource_spec.tz_type,
+            base_model: resource_spec.base_model,
+            validate: resource_spec.validate,
+            derives,
+            admin: resource_spec.admin,
+            example: resource_spec.example,
+            parent: resource_spec.parent,
+            relations,
+            deprecation: None,
+            feature: None,
+            handler_naming: resource_spec.naming.unwrap_or(ctx.config().handler_naming),
+            json_case: resource_spec.json_case.unwrap_or(ctx.config().json_case),
+            id_type: resource_spec.id_type,
+            primary_key,
+            layers: Layer::ALL.to_vec(),
+            force,
+        });
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!("❌ Invalid spec '{}':\n   - {}", path.display(), errors.join("\n   - "));
+    }
+
+    Ok(resources)
+}
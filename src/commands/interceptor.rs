@@ -0,0 +1,242 @@
+use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::commands::resource::{print_wiring_warnings, WiringWarning};
+use crate::utils::conversions::to_pascal_case;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
+use crate::utils::output::report;
+use crate::utils::wiring::insert_wrap;
+
+#[derive(Args)]
+pub struct InterceptorArgs {
+    /// Name of the interceptor, e.g. `envelope` or `timing`
+    pub name: String,
+    /// Wrap the JWT protected `/private-api` scope instead of `/api`
+    #[arg(long)]
+    pub protected: bool,
+    /// Overwrite the interceptor if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+pub async fn execute(args: InterceptorArgs) -> anyhow::Result<()> {
+    report(format!("🪝 Generating interceptor: {}", args.name));
+
+    let name = args.name.replace('-', "_").to_lowercase();
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        anyhow::bail!("❌ Invalid interceptor name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = ctx.module_file("interceptors", &name);
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    let type_name = format!("{}Interceptor", to_pascal_case(&name));
+    fs::create_dir_all(ctx.module_dir("interceptors")).await?;
+    generate_interceptor(&name, &type_name, &file_path).await?;
+    ctx.register("interceptors", &name).await?;
+    let scope = if args.protected { "/private-api" } else { "/api" };
+    let warnings = wrap_scope(&ctx, scope, &name, &type_name).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Interceptor '{}' created successfully!", name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    report(format!(
+        "✏️ Edit `crate::interceptors::{}::transform` to change what the handlers under {} answer",
+        name, scope
+    ));
+    print_wiring_warnings(&warnings);
+
+    Ok(())
+}
+
+/// Wraps the scope of `routes.rs` serving `scope` in the interceptor
+async fn wrap_scope(ctx: &ProjectContext, scope: &str, name: &str, type_name: &str) -> Result<Vec<WiringWarning>> {
+    let routes_file_path = ctx.routes_file();
+    let scope_call = format!("web::scope(\"{}\")", scope);
+    let warning = |missing: &str| WiringWarning {
+        file: routes_file_path.clone(),
+        missing: missing.to_string(),
+        fix: format!("`use crate::interceptors::{};` and `.wrap({}::{})` on `{}`", name, name, type_name, scope_call),
+    };
+
+    if !Path::new(&routes_file_path).exists() {
+        return Ok(vec![warning("the routes file")]);
+    }
+    let mut routes_file = fs::read_to_string(&routes_file_path).await?;
+    if !insert_wrap(&mut routes_file, &scope_call, "interceptors", name, type_name) {
+        return Ok(vec![warning(&format!("`{}`", scope_call))]);
+    }
+    write_if_changed(&routes_file_path, routes_file).await?;
+    Ok(Vec::new())
+}
+
+/// Writes the middleware running the handlers and passing their successful JSON responses through `transform`
+async fn generate_interceptor(name: &str, type_name: &str, file_path: &str) -> Result<()> {
+    let content = format!(
+        r#"// `{name}` interceptor: runs the handlers it wraps, then rewrites their successful JSON
+// responses through `transform`, like a NestJS interceptor. Wrap an `App` or a scope with `{type_name}`
+use std::future::{{ready, Future, Ready}};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::time::{{Duration, Instant}};
+
+use actix_web::body::{{self, BoxBody, MessageBody}};
+use actix_web::dev::{{forward_ready, Service, ServiceRequest, ServiceResponse, Transform}};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::http::header::{{self, HeaderMap, HeaderName, HeaderValue}};
+use actix_web::web::Bytes;
+use actix_web::{{Error, HttpRequest}};
+use serde_json::{{json, Value}};
+
+/// Set by the default `transform`: how long the handler took, in milliseconds
+pub const RESPONSE_TIME_HEADER: &str = "x-response-time";
+
+/// The body sent instead of a successful JSON response's `body`, free to edit `headers` too.
+/// By default it wraps the body in a `{{ "data": ... }}` envelope and reports the handler's timing
+pub fn transform(_req: &HttpRequest, headers: &mut HeaderMap, body: Value, elapsed: Duration) -> Value {{
+    if let Ok(millis) = HeaderValue::from_str(&elapsed.as_millis().to_string()) {{
+        headers.insert(HeaderName::from_static(RESPONSE_TIME_HEADER), millis);
+    }}
+    json!({{ "data": body }})
+}}
+
+/// Passes the successful JSON responses of the handlers it wraps through `transform`.
+/// Errors and other bodies (text, files, event streams...) are left untouched
+pub struct {type_name};
+
+impl<S, B> Transform<S, ServiceRequest> for {type_name}
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = {type_name}Service<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {{
+        ready(Ok({type_name}Service {{
+            service: Rc::new(service),
+        }}))
+    }}
+}}
+
+pub struct {type_name}Service<S> {{
+    service: Rc<S>,
+}}
+
+impl<S, B> Service<ServiceRequest> for {type_name}Service<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {{
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {{
+            let started = Instant::now();
+            let res = service.call(req).await?;
+            let elapsed = started.elapsed();
+            if !res.status().is_success() || !is_json(res.headers()) {{
+                return Ok(res.map_into_boxed_body());
+            }}
+            intercept(res, elapsed).await
+        }})
+    }}
+}}
+
+fn is_json(headers: &HeaderMap) -> bool {{
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"))
+}}
+
+/// Reads the whole body and answers with what `transform` makes of it, or the body as it was
+/// when it isn't valid JSON after all
+async fn intercept<B: MessageBody>(res: ServiceResponse<B>, elapsed: Duration) -> Result<ServiceResponse<BoxBody>, Error> {{
+    let (req, res) = res.into_parts();
+    let (mut head, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(|e| ErrorInternalServerError(Into::<Box<dyn std::error::Error>>::into(e).to_string()))?;
+    let body = match serde_json::from_slice(&bytes) {{
+        Ok(value) => {{
+            let value = transform(&req, head.headers_mut(), value, elapsed);
+            Bytes::from(serde_json::to_vec(&value).map_err(ErrorInternalServerError)?)
+        }}
+        Err(_) => bytes,
+    }};
+    Ok(ServiceResponse::new(req, head.set_body(body).map_into_boxed_body()))
+}}
+
+#[cfg(test)]
+mod tests {{
+    use actix_web::test::{{call_service, init_service, read_body, read_body_json, TestRequest}};
+    use actix_web::{{web, App, HttpResponse}};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn transforms_successful_json_responses() {{
+        let app = init_service(
+            App::new()
+                .wrap({type_name})
+                .route("/", web::get().to(|| async {{ HttpResponse::Ok().json(json!({{ "id": 1 }})) }})),
+        )
+        .await;
+
+        let res = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert!(res.headers().contains_key(RESPONSE_TIME_HEADER));
+        let body: Value = read_body_json(res).await;
+        assert_eq!(body, json!({{ "data": {{ "id": 1 }} }}));
+    }}
+
+    #[actix_web::test]
+    async fn leaves_errors_and_other_bodies_alone() {{
+        let app = init_service(
+            App::new()
+                .wrap({type_name})
+                .route("/missing", web::get().to(|| async {{ HttpResponse::NotFound().json(json!({{ "error": "missing" }})) }}))
+                .route("/text", web::get().to(|| async {{ HttpResponse::Ok().body("plain") }})),
+        )
+        .await;
+
+        let res = call_service(&app, TestRequest::get().uri("/missing").to_request()).await;
+        let body: Value = read_body_json(res).await;
+        assert_eq!(body, json!({{ "error": "missing" }}));
+
+        let res = call_service(&app, TestRequest::get().uri("/text").to_request()).await;
+        assert!(!res.headers().contains_key(RESPONSE_TIME_HEADER));
+        assert_eq!(&read_body(res).await[..], b"plain");
+    }}
+}}
+"#
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use clap::Args;
+use anyhow::Result;
+use tokio::fs;
+
+use crate::utils::fields::{parse_model, Field};
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct MapperArgs {
+    /// Model converted from
+    pub from: String,
+    /// Model converted into
+    pub to: String,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+/// A generated model read back from `src/models`
+struct Model {
+    name: String,
+    type_name: String,
+    fields: Vec<Field>,
+}
+
+pub async fn execute(args: MapperArgs) -> anyhow::Result<()> {
+    report(format!("🔁 Generating mapper: {} -> {}", args.from, args.to));
+
+    let ctx = ProjectContext::load().await?;
+
+    let from = load_model(&ctx, &args.from).await?;
+    let to = load_model(&ctx, &args.to).await?;
+    let module = format!("{}_to_{}", from.name, to.name);
+    let file_path = ctx.module_file("mappers", &module);
+
+    fs::create_dir_all(ctx.module_dir("mappers")).await?;
+    let todos = generate_mapper(&from, &to, &file_path).await?;
+    ctx.register("mappers", &module).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Mapper '{}' created successfully!", module));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    if todos > 0 {
+        report(format!("⚠️ {} fields of {} couldn't be mapped automatically, look for the TODOs", todos, to.type_name));
+    }
+
+    Ok(())
+}
+
+async fn load_model(ctx: &ProjectContext, name: &str) -> Result<Model> {
+    let path = ctx.module_location("models", name);
+    let source = ctx.read_module("models", name).await.map_err(|_| {
+        anyhow::anyhow!("❌ Model '{}' not found, generate it with `cargo mold g resource {}`", path, name)
+    })?;
+    let Some((type_name, fields)) = parse_model(&source) else {
+        anyhow::bail!("❌ Could not find a `pub struct` in '{}'", path);
+    };
+
+    Ok(Model {
+        name: name.to_string(),
+        type_name,
+        fields,
+    })
+}
+
+/// Writes the `From` impl and returns how many target fields were left as TODOs
+async fn generate_mapper(from: &Model, to: &Model, file_path: &str) -> Result<usize> {
+    let mut assignments = String::new();
+    let mut matching = Vec::new();
+    let mut todos = 0;
+
+    for field in &to.fields {
+        match from.fields.iter().find(|f| f.name == field.name) {
+            Some(source) if source.ty == field.ty => {
+                assignments.push_str(&format!("            {0}: value.{0},\n", field.name));
+                matching.push(&field.name);
+            }
+            Some(source) => {
+                assignments.push_str(&format!(
+                    "            // TODO: convert `value.{}` ({}) into {}\n            {}: Default::default(),\n",
+                    source.name, source.ty, field.ty, field.name
+                ));
+                todos += 1;
+            }
+            None => {
+                assignments.push_str(&format!(
+                    "            // TODO: `{}` has no `{}` field\n            {}: Default::default(),\n",
+                    from.type_name, field.name, field.name
+                ));
+                todos += 1;
+            }
+        }
+    }
+
+    let unmapped: Vec<&str> = from.fields.iter()
+        .filter(|f| !to.fields.iter().any(|t| t.name == f.name))
+        .map(|f| f.name.as_str())
+        .collect();
+    let unmapped = if unmapped.is_empty() {
+        String::new()
+    } else {
+        format!("/// Dropped fields: {}\n", unmapped.join(", "))
+    };
+
+    let value = if matching.is_empty() { "_value" } else { "value" };
+    let test_body = if matching.is_empty() {
+        format!("        let _ = {}::from({}::default());\n", to.type_name, from.type_name)
+    } else {
+        let assertions: String = matching.iter()
+            .map(|name| format!("        assert_eq!(converted.{0}, expected.{0});\n", name))
+            .collect();
+        format!(
+            "        let source = {}::default();\n        let expected = source.clone();\n        let converted = {}::from(source);\n{}",
+            from.type_name, to.type_name, assertions
+        )
+    };
+
+    let content = format!(
+        r#"use crate::models::{from_name}::{from_type};
+use crate::models::{to_name}::{to_type};
+
+/// Converts `{from_type}` into `{to_type}` field by field
+{unmapped}impl From<{from_type}> for {to_type} {{
+    fn from({value}: {from_type}) -> Self {{
+        Self {{
+{assignments}        }}
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn matching_fields_convert() {{
+{test_body}    }}
+}}
+"#,
+        from_name = from.name,
+        from_type = from.type_name,
+        to_name = to.name,
+        to_type = to.type_name,
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(todos)
+}
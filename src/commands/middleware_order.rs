@@ -0,0 +1,81 @@
+use clap::Args;
+use std::path::Path;
+use tokio::fs;
+
+use crate::utils::project::{Middleware, ProjectContext};
+use crate::utils::utils::write_if_changed;
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct MiddlewareOrderArgs {}
+
+pub async fn execute(_args: MiddlewareOrderArgs) -> anyhow::Result<()> {
+    report("🧅 Ordering middlewares");
+
+    let ctx = ProjectContext::load().await?;
+    let order = &ctx.config().middleware;
+    Middleware::check_order(order)?;
+    let names: Vec<&str> = order.iter().map(|middleware| middleware.name()).collect();
+    report(format!("🔗 Outermost first: {}", names.join(" → ")));
+
+    // The integration harness builds the app like the server does
+    let files = [ctx.server_file(), ctx.routes_file(), ctx.path("tests/common/mod.rs")];
+    let mut edited = Vec::new();
+    for file in files {
+        if !Path::new(&file).exists() {
+            continue;
+        }
+        let source = fs::read_to_string(&file).await?;
+        let reordered = reorder_wraps(&source, order);
+        if reordered != source {
+            write_if_changed(&file, reordered).await?;
+            edited.push(file);
+        }
+    }
+
+    if edited.is_empty() {
+        report("✅ Middlewares already wrapped in the configured order");
+    } else {
+        report("✅ Middlewares reordered successfully!");
+        report("📝 Edited files:");
+        for file in &edited {
+            report(format!("   - {}", file));
+        }
+    }
+
+    Ok(())
+}
+
+/// `source` with the `.wrap` calls of each method chain swapped around so their middlewares run in
+/// `order`. Only the lines written by `cargo mold new` move, everything else in the chain stays put
+fn reorder_wraps(source: &str, order: &[Middleware]) -> String {
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let mut start = 0;
+    while start < lines.len() {
+        // A chain is a run of lines each holding one `.call(...)`
+        let end = start + lines[start..].iter().take_while(|line| line.trim_start().starts_with('.')).count();
+        if end == start {
+            start += 1;
+            continue;
+        }
+
+        let wraps: Vec<(usize, Middleware)> = (start..end)
+            .filter_map(|i| {
+                let call = lines[i].trim();
+                Middleware::DEFAULT_ORDER.into_iter().find(|m| m.wrap_call() == call).map(|m| (i, m))
+            })
+            .collect();
+        let enabled: Vec<Middleware> = wraps.iter().map(|(_, middleware)| *middleware).collect();
+        for ((i, _), middleware) in wraps.iter().zip(Middleware::wrap_sequence(order, &enabled)) {
+            let indent = &lines[*i][..lines[*i].len() - lines[*i].trim_start().len()];
+            lines[*i] = format!("{}{}", indent, middleware.wrap_call());
+        }
+        start = end;
+    }
+
+    let mut reordered = lines.join("\n");
+    if source.ends_with('\n') {
+        reordered.push('\n');
+    }
+    reordered
+}
@@ -1,5 +1,24 @@
+pub mod admin;
+pub mod cli;
+pub mod client;
+pub mod completions;
 pub mod controller;
+pub mod dto;
+pub mod example;
+pub mod from_schema;
+pub mod from_spec;
+pub mod interceptor;
+pub mod mapper;
+pub mod middleware_order;
 pub mod module;
 pub mod new;
+pub mod policy;
+pub mod rename;
+pub mod repository;
 pub mod resource;
+pub mod schema;
 pub mod service;
+pub mod sse;
+pub mod subscriber;
+pub mod test_factory;
+pub mod validator;
@@ -1,5 +1,8 @@
 use clap::Args;
 
+use crate::utils::project::ProjectContext;
+use crate::utils::output::report;
+
 #[derive(Args)]
 pub struct ModuleArgs {
     /// Name of the module
@@ -7,7 +10,8 @@ pub struct ModuleArgs {
 }
 
 pub async fn execute(args: ModuleArgs) -> anyhow::Result<()> {
-    println!("📦 Generating module: {}", args.name);
+    report(format!("📦 Generating module: {}", args.name));
+    let _ctx = ProjectContext::load().await?;
     // Your module generation logic here
     Ok(())
 }
@@ -2,41 +2,350 @@ use anyhow::Result;
 use clap::Args;
 use std::path::Path;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+
+use crate::templates;
+use crate::utils::manifest::merge_manifests;
+use crate::utils::plan::GenerationPlan;
+use crate::utils::license::{set_license_header, LicenseHeader, LICENSE_HEADER_FILE};
+use crate::utils::project::{HandlerNaming, JsonCase, Middleware, ModuleLayout, ModulesStyle, ProjectConfig};
+use crate::utils::output::report;
+use crate::utils::utils::write_if_changed;
 
 #[derive(Args)]
 pub struct NewArgs {
-    /// Name of the project
+    /// Name of the project, created in a directory of the same name
     pub project_name: String,
+    #[command(flatten)]
+    pub options: ProjectOptions,
+}
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Name of the crate, the current directory's name by default
+    pub name: Option<String>,
+    /// Scaffold into a directory that isn't empty. Its files are kept: the project's dependencies are
+    /// merged into its Cargo.toml, and the other files the project would replace are listed as skipped
+    #[arg(long)]
+    pub force: bool,
+    #[command(flatten)]
+    pub options: ProjectOptions,
+}
+
+/// Access log line of `--access-log`: peer address, request line (method, path and protocol),
+/// status, response size and duration in milliseconds
+const DEFAULT_ACCESS_LOG_FORMAT: &str = r#"%a "%r" %s %b %Dms"#;
+
+/// Features and settings of a generated project, shared by `new` and `init`
+#[derive(Args)]
+pub struct ProjectOptions {
+    /// Tag every request with an `X-Request-Id`, echoed back in the response
+    #[arg(long)]
+    pub request_id: bool,
+    /// Replay the cached response of POST/PUT/PATCH/DELETE requests retried with the same `Idempotency-Key`
+    #[arg(long)]
+    pub idempotency: bool,
+    /// Log every request with `tracing`, and instrument the handlers generated afterwards
+    #[arg(long)]
+    pub tracing: bool,
+    /// Export request traces to this OpenTelemetry collector (OTLP/HTTP, e.g. http://localhost:4318); implies `--tracing`
+    #[arg(long, value_name = "ENDPOINT")]
+    pub otel: Option<String>,
+    /// Set `X-Content-Type-Options`, `X-Frame-Options`, `Strict-Transport-Security` and `Content-Security-Policy`
+    /// on every response, each overridable or disabled from the environment
+    #[arg(long)]
+    pub security_headers: bool,
+    /// Default `Content-Security-Policy` of `--security-headers` (`default-src 'self'; frame-ancestors 'none'`,
+    /// allowing RapiDoc's script with `--with-openapi`)
+    #[arg(long, value_name = "POLICY", requires = "security_headers")]
+    pub csp: Option<String>,
+    /// Write an access log line per request with actix's `Logger` (`--tracing` already logs them, so it's off by default)
+    #[arg(long)]
+    pub access_log: bool,
+    /// Format of the access log lines, in the `Logger` tokens documented in the generated `server.rs`
+    #[arg(long, value_name = "FORMAT", default_value = DEFAULT_ACCESS_LOG_FORMAT, requires = "access_log")]
+    pub access_log_format: String,
+    /// Generate a `Settings<T>` extractor for typed per-route configuration
+    #[arg(long)]
+    pub with_settings: bool,
+    /// Generate an `AppConfig` read from the environment at startup, reporting every missing variable at once
+    #[arg(long)]
+    pub with_config: bool,
+    /// Generate a `build.rs` baking in the git commit and build time, reported by a `/version` route
+    #[arg(long)]
+    pub build_info: bool,
+    /// Serve the OpenAPI document of the annotated handlers at `/openapi.json` and RapiDoc at `/docs`,
+    /// and document the handlers generated afterwards
+    #[arg(long)]
+    pub with_openapi: bool,
+    /// Generate a `tests/common` harness building the configured app for integration tests, with a first test using it
+    /// and tests of the JWT protected routes signing their tokens with `common::auth_header`
+    #[arg(long)]
+    pub with_integration: bool,
+    /// Answer unmatched paths with a JSON 404 and wrong methods with a JSON 405 (`--with-fallback false` keeps actix's bare ones)
+    #[arg(long, value_name = "BOOL", default_value_t = true, action = clap::ArgAction::Set)]
+    pub with_fallback: bool,
+    /// Generate a message catalog read from `locales/*.json`, and localize the errors of handlers generated afterwards
+    #[arg(long)]
+    pub i18n: bool,
+    /// Serve HTTPS (and HTTP/2) with rustls, loading the certificate from `TLS_CERT_PATH`/`TLS_KEY_PATH`
+    #[arg(long)]
+    pub tls: bool,
+    /// Add a `[profile.release]` trading longer release builds for smaller, faster binaries
+    #[arg(long)]
+    pub tuned_profile: bool,
+    /// Accept JWTs without an `exp` claim as never expiring (by default they are rejected)
+    #[arg(long)]
+    pub allow_no_exp: bool,
+    /// Largest request body the server accepts, in bytes; larger ones are answered with a 413
+    #[arg(long, value_name = "BYTES", default_value_t = 262_144, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_body_size: u64,
+    /// Seconds an idle keep-alive connection stays open (0 disables keep-alive)
+    #[arg(long, value_name = "SECS", default_value_t = 5)]
+    pub keep_alive: u64,
+    /// Milliseconds a client has to send the request head before getting a 408 (0 disables the timeout)
+    #[arg(long, value_name = "MILLIS", default_value_t = 5000)]
+    pub client_timeout: u64,
+    /// Whether generated resources get a file per module or `pub mod name { ... }` blocks in their `mod.rs`
+    #[arg(long, value_enum, default_value_t = ModulesStyle::Files)]
+    pub modules_style: ModulesStyle,
+    /// Whether top-level modules declare their files in `models/mod.rs` (legacy) or `models.rs` (modern)
+    #[arg(long, value_enum, value_name = "LAYOUT", default_value_t = ModuleLayout::Legacy)]
+    pub module_layout: ModuleLayout,
+    /// Default naming convention of generated handler functions
+    #[arg(long = "naming", value_enum, value_name = "CONVENTION", default_value_t = HandlerNaming::Rest)]
+    pub handler_naming: HandlerNaming,
+    /// Default case of the JSON field names of generated models
+    #[arg(long, value_enum, value_name = "CASE", default_value_t = JsonCase::Snake)]
+    pub json_case: JsonCase,
+    /// Put the license header of this file atop every generated `.rs` file, `{year}` and `{author}` substituted.
+    /// It is copied to the project's `LICENSE_HEADER` so later `cargo mold g` commands apply it too
+    #[arg(long, value_name = "FILE")]
+    pub license_header: Option<String>,
+    /// Copyright holder substituted for `{author}` in the license header (`git config user.name` by default)
+    #[arg(long, value_name = "NAME", requires = "license_header")]
+    pub license_author: Option<String>,
+}
+
+impl ProjectOptions {
+    /// Whether the project logs through `tracing`, on its own or as part of the OpenTelemetry export
+    fn tracing(&self) -> bool {
+        self.tracing || self.otel.is_some()
+    }
+
+    /// The default `Content-Security-Policy` of `--security-headers`
+    fn content_security_policy(&self) -> String {
+        match &self.csp {
+            Some(policy) => policy.clone(),
+            // `/docs` loads RapiDoc from unpkg
+            None if self.with_openapi => "default-src 'self'; frame-ancestors 'none'; script-src 'self' https://unpkg.com".to_string(),
+            None => "default-src 'self'; frame-ancestors 'none'".to_string(),
+        }
+    }
+}
+
+/// A project being scaffolded: its crate, the directory its files go in and the chosen options
+struct Scaffold {
+    /// Name of the package, the crate being its `-`s replaced with `_`s
+    name: String,
+    /// Directory holding the project, `.` when scaffolding into the current one
+    dir: String,
+    options: ProjectOptions,
+}
+
+impl Scaffold {
+    /// Path of `relative` inside the project
+    fn path(&self, relative: &str) -> String {
+        if self.dir == "." {
+            relative.to_string()
+        } else {
+            format!("{}/{}", self.dir, relative)
+        }
+    }
+
+    /// Path of the file declaring the files of a top-level module, following `--module-layout`
+    fn mod_path(&self, module: &str) -> String {
+        match self.options.module_layout {
+            ModuleLayout::Legacy => self.path(&format!("src/{}/mod.rs", module)),
+            ModuleLayout::Modern => self.path(&format!("src/{}.rs", module)),
+        }
+    }
 }
 
 /// Creates a new Actix Web project with proper structure and boilerplate code
 pub async fn execute(args: NewArgs) -> Result<()> {
-    println!("🚀 Creating new project: {}", args.project_name);
-
-    // Create project structure and generate all necessary files
-    create_project_structure(&args.project_name).await?;
-    generate_cargo_toml(&args.project_name).await?;
-    generate_main_rs(&args.project_name).await?;
-    generate_lib_rs(&args.project_name).await?;
-    generate_route_files(&args.project_name).await?;
-    generate_handler_files(&args.project_name).await?;
-    generate_server_files(&args.project_name).await?;
-    generate_mod_files(&args.project_name).await?;
-    generate_env_example(&args.project_name).await?;
-    generate_cargo_mold_file(&args.project_name).await?;
-
-    println!("✅ Project '{}' created successfully!", args.project_name);
-    println!("📂 Next steps:");
-    println!("   cd {}", args.project_name);
-    println!("   cargo run");
+    report(format!("🚀 Creating new project: {}", args.project_name));
+
+    let project = Scaffold {
+        name: args.project_name.clone(),
+        dir: args.project_name,
+        options: args.options,
+    };
+    scaffold(&project).await?;
+
+    report(format!("✅ Project '{}' created successfully!", project.name));
+    report("📂 Next steps:");
+    report(format!("   cd {}", project.dir));
+    print_run_steps(&project);
 
     Ok(())
 }
 
+/// Scaffolds the project into the current directory, named after it unless a name is given
+pub async fn execute_init(args: InitArgs) -> Result<()> {
+    let name = match args.name {
+        Some(name) => name,
+        None => match existing_package_name().await {
+            Some(name) => name,
+            None => {
+                let current_dir = std::env::current_dir()?;
+                current_dir.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .ok_or_else(|| anyhow::anyhow!("❌ The current directory has no name, pass one: `cargo mold init <name>`"))?
+            }
+        },
+    };
+    let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid_name {
+        anyhow::bail!(
+            "❌ Invalid crate name '{}': use letters, digits, '-' and '_', starting with a letter.\n\
+             Pass another one with `cargo mold init <name>`",
+            name
+        );
+    }
+
+    // A fresh `git init` doesn't count
+    let mut entries = fs::read_dir(".").await?;
+    let mut occupied = false;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_name() != ".git" {
+            occupied = true;
+            break;
+        }
+    }
+    if occupied && !args.force {
+        anyhow::bail!("❌ The current directory isn't empty, use --force to scaffold into it anyway, keeping its files");
+    }
+
+    report(format!("🚀 Initializing project: {}", name));
+    let project = if occupied {
+        scaffold_around_existing_files(name, args.options).await?
+    } else {
+        let project = Scaffold { name, dir: ".".to_string(), options: args.options };
+        scaffold(&project).await?;
+        project
+    };
+
+    report(format!("✅ Project '{}' initialized successfully!", project.name));
+    report("📂 Next steps:");
+    print_run_steps(&project);
+
+    Ok(())
+}
+
+/// `[package] name` of the Cargo.toml in the current directory, if there is one
+async fn existing_package_name() -> Option<String> {
+    let manifest = fs::read_to_string("Cargo.toml").await.ok()?.parse::<toml::Table>().ok()?;
+    manifest.get("package")?.get("name")?.as_str().map(str::to_string)
+}
+
+/// Scaffolds the project into a scratch directory, then copies over the files the current directory
+/// doesn't have. Its Cargo.toml gets the project's dependencies, every other existing file is kept as is
+async fn scaffold_around_existing_files(name: String, options: ProjectOptions) -> Result<Scaffold> {
+    let scratch = std::env::temp_dir().join(format!("cargo-mold-init-{}", std::process::id()));
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch).await?;
+    }
+    let mut project = Scaffold { name, dir: scratch.to_string_lossy().into_owned(), options };
+    let plan = match scaffold(&project).await {
+        Ok(()) => GenerationPlan::between(Path::new("."), &scratch).await,
+        Err(e) => Err(e),
+    };
+    let copied = match plan {
+        Ok(plan) => keep_existing_files(&plan, &scratch).await,
+        Err(e) => Err(e),
+    };
+    fs::remove_dir_all(&scratch).await?;
+    let skipped = copied?;
+
+    if !skipped.is_empty() {
+        report("⏭️ Kept the existing files the project would have replaced, merge them by hand:");
+        for path in &skipped {
+            report(format!("   - {}", path));
+        }
+    }
+    project.dir = ".".to_string();
+    Ok(project)
+}
+
+/// Copies the files `plan` creates from `scratch`, merges the edit of `Cargo.toml` into it and
+/// returns the other edited files, left untouched
+async fn keep_existing_files(plan: &GenerationPlan, scratch: &Path) -> Result<Vec<String>> {
+    for path in &plan.creates {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::copy(scratch.join(path), path).await?;
+    }
+
+    let mut skipped = Vec::new();
+    for edit in &plan.edits {
+        if edit.path == "Cargo.toml" {
+            let existing = fs::read_to_string(&edit.path).await?;
+            let generated = fs::read_to_string(scratch.join(&edit.path)).await?;
+            write_if_changed(&edit.path, merge_manifests(&edit.path, &existing, &generated)?).await?;
+            report("📦 Merged the project's dependencies into the existing Cargo.toml");
+        } else {
+            skipped.push(edit.path.clone());
+        }
+    }
+    Ok(skipped)
+}
+
+/// Writes every file of the project
+async fn scaffold(project: &Scaffold) -> Result<()> {
+    // Read before anything is written, so an unreadable header leaves no half-made project behind
+    let header = match &project.options.license_header {
+        Some(path) => Some(LicenseHeader::load(path, project.options.license_author.as_deref()).await?),
+        None => None,
+    };
+    create_project_structure(project).await?;
+    if let Some(header) = header {
+        write_if_changed(project.path(LICENSE_HEADER_FILE), header.template()).await?;
+        set_license_header(header);
+        generate_license_header_test(project).await?;
+    }
+    generate_cargo_toml(project).await?;
+    generate_main_rs(project).await?;
+    generate_lib_rs(project).await?;
+    generate_route_files(project).await?;
+    generate_handler_files(project).await?;
+    generate_server_files(project).await?;
+    generate_middleware_files(project).await?;
+    generate_telemetry_file(project).await?;
+    generate_settings_file(project).await?;
+    generate_config_file(project).await?;
+    generate_i18n_files(project).await?;
+    generate_openapi_file(project).await?;
+    generate_build_script(project).await?;
+    generate_dev_cert_example(project).await?;
+    generate_integration_harness(project).await?;
+    generate_mod_files(project).await?;
+    generate_env_example(project).await?;
+    generate_cargo_mold_file(project).await
+}
+
+/// The commands getting the project running, once in its directory
+fn print_run_steps(project: &Scaffold) {
+    if project.options.tls {
+        report("   cargo run --example dev_cert   # self-signed certificate for local HTTPS");
+    }
+    report("   cargo run");
+}
+
 /// Creates the directory structure for the Actix Web project
-async fn create_project_structure(project_name: &str) -> Result<()> {
-    let project_path = Path::new(project_name);
+async fn create_project_structure(project: &Scaffold) -> Result<()> {
+    let project_path = Path::new(&project.dir);
     fs::create_dir_all(project_path.join("src/routes")).await?;
     fs::create_dir_all(project_path.join("src/models")).await?;
     fs::create_dir_all(project_path.join("src/handlers")).await?;
@@ -46,8 +355,8 @@ async fn create_project_structure(project_name: &str) -> Result<()> {
 }
 
 /// Generates .env-example file with example variables
-async fn generate_env_example(project_name: &str) -> Result<()> {
-    let content = r#"# Environment Configuration
+async fn generate_env_example(project: &Scaffold) -> Result<()> {
+    let mut content = r#"# Environment Configuration
 # Copy this file to '.env' and update the values with your actual configuration
 
 # ==========================================
@@ -56,30 +365,207 @@ async fn generate_env_example(project_name: &str) -> Result<()> {
 # Secret key for signing JWT tokens
 # Generate a secure random key: openssl rand -base64 64
 JWT_SECRET=your-super-secure-jwt-secret-key-change-this-in-production
-"#;
+# Key used by AuthService to encrypt data (at least 32 characters)
+# Generate a secure random key: openssl rand -base64 48
+ENCRYPTION_KEY=your-super-secure-encryption-key-change-this-in-production
+# When rotating ENCRYPTION_KEY, list the retired keys (comma-separated) so existing data still decrypts
+# ENCRYPTION_PREVIOUS_KEYS=
+"#.to_string();
+
+    if let Some(endpoint) = &project.options.otel {
+        content.push_str(&format!(
+            r#"
+# ==========================================
+# OpenTelemetry
+# ==========================================
+# OTLP/HTTP collector receiving the traces (Jaeger, Tempo, ...)
+OTEL_EXPORTER_OTLP_ENDPOINT={}
+# Log filter, e.g. info or my_app=debug
+RUST_LOG=info
+"#,
+            endpoint
+        ));
+    } else if project.options.tracing {
+        content.push_str(
+            r#"
+# ==========================================
+# Tracing
+# ==========================================
+# Log filter, e.g. info or my_app=debug
+RUST_LOG=info
+"#,
+        );
+    } else if project.options.access_log {
+        content.push_str(
+            r#"
+# ==========================================
+# Access Log
+# ==========================================
+# Log filter, e.g. info or actix_web::middleware::logger=info
+RUST_LOG=info
+"#,
+        );
+    }
 
-    let mut file = fs::File::create(format!("{}/.env-example", project_name)).await?;
-    file.write_all(content.as_bytes()).await?;
+    if project.options.with_config {
+        content.push_str(
+            r#"
+# ==========================================
+# Server
+# ==========================================
+# Address the server listens on, read by AppConfig (defaults to 127.0.0.1:8080)
+APP_HOST=127.0.0.1
+APP_PORT=8080
+"#,
+        );
+    }
+
+    if project.options.with_settings {
+        content.push_str(
+            r#"
+# ==========================================
+# Feature Flags
+# ==========================================
+FEATURE_SIGNUPS_ENABLED=true
+FEATURE_MAX_PAGE_SIZE=100
+"#,
+        );
+    }
+
+    if project.options.i18n {
+        content.push_str(
+            r#"
+# ==========================================
+# Localization
+# ==========================================
+# Directory holding the locales/{lang}.json catalogs (defaults to the crate's locales directory)
+# LOCALES_DIR=locales
+"#,
+        );
+    }
+
+    if project.options.security_headers {
+        content.push_str(
+            r#"
+# ==========================================
+# Security Headers
+# ==========================================
+# Override the header values set on every response, or disable one with `off`
+# SECURITY_CSP=default-src 'self'
+# SECURITY_HSTS=max-age=31536000; includeSubDomains
+# SECURITY_FRAME_OPTIONS=DENY
+# SECURITY_CONTENT_TYPE_OPTIONS=nosniff
+"#,
+        );
+    }
+
+    if project.options.tls {
+        content.push_str(
+            r#"
+# ==========================================
+# TLS
+# ==========================================
+# PEM certificate chain and private key served over HTTPS
+# Create a self-signed pair for local development with: cargo run --example dev_cert
+TLS_CERT_PATH=certs/cert.pem
+TLS_KEY_PATH=certs/key.pem
+"#,
+        );
+    }
+
+    write_if_changed(project.path(".env-example"), content).await?;
     Ok(())
 }
 
-/// Generates .env-example file with example variables
-async fn generate_cargo_mold_file(project_name: &str) -> Result<()> {
-    let content = format!(
-        r#"Future changes will be done into this file
-I'm still thinking what info to write here and how to use it in the future
-"#
-    );
+/// Generates the .cargo-mold file marking the project and holding its settings
+async fn generate_cargo_mold_file(project: &Scaffold) -> Result<()> {
+    let config = ProjectConfig {
+        tracing: project.options.tracing(),
+        i18n: project.options.i18n,
+        openapi: project.options.with_openapi,
+        modules_style: project.options.modules_style,
+        module_layout: project.options.module_layout,
+        handler_naming: project.options.handler_naming,
+        json_case: project.options.json_case,
+        license_header: project.options.license_header.as_ref().map(|_| LICENSE_HEADER_FILE.to_string()),
+        license_author: project.options.license_author.clone(),
+        ..ProjectConfig::default()
+    };
+    let content = config.to_file_content()?;
 
-    let mut file = fs::File::create(format!("{}/.cargo-mold", project_name)).await?;
-    file.write_all(content.as_bytes()).await?;
+    write_if_changed(project.path(".cargo-mold"), content).await?;
     Ok(())
 }
 
 /// Generates the Cargo.toml file with necessary dependencies
-async fn generate_cargo_toml(project_name: &str) -> Result<()> {
-    
-    let mold_version = r#"cargo-mold = 0.2.1"#;
+/// `[profile.release]` of `--tuned-profile`, only affecting `cargo build --release`
+const RELEASE_PROFILE: &str = r#"
+# Smaller, faster release binaries, at the cost of noticeably longer release builds;
+# debug builds and `cargo test` are unaffected
+[profile.release]
+# Optimizes across crates; "thin" gets most of the gains of full LTO in a fraction of its link time
+lto = "thin"
+# One codegen unit per crate optimizes better but compiles serially
+codegen-units = 1
+# Drops symbols and debug info: much smaller binaries, but backtraces only show addresses
+strip = true
+# Aborts instead of unwinding: smaller code, but a panicking handler takes the whole process down
+# instead of only its request, so run the service under a supervisor that restarts it
+panic = "abort"
+"#;
+
+async fn generate_cargo_toml(project: &Scaffold) -> Result<()> {
+    let project_name = &project.name;
+    // The generated code calls APIs of this version, e.g. `AuthService::from_env`
+    let mold_version = format!(r#"cargo-mold = "{}""#, env!("CARGO_PKG_VERSION"));
+    let actix_web = if project.options.tls {
+        r#"actix-web = { version = "4.4", features = ["rustls-0_23"] }"#
+    } else {
+        r#"actix-web = "4.4""#
+    };
+
+    // Dependencies only needed by the optional features
+    let mut extra_dependencies = String::new();
+    let mut dev_dependencies = String::new();
+    if project.options.request_id {
+        extra_dependencies.push_str("uuid = { version = \"1\", features = [\"v4\"] }\n");
+    }
+    if project.options.tracing() {
+        extra_dependencies.push_str(
+            r#"tracing = "0.1"
+tracing-subscriber = { version = "0.3", features = ["env-filter"] }
+tracing-actix-web = "0.7"
+"#,
+        );
+    } else if project.options.access_log {
+        extra_dependencies.push_str("env_logger = \"0.11\"\n");
+    }
+    if project.options.otel.is_some() {
+        extra_dependencies.push_str(
+            r#"opentelemetry = "0.33"
+opentelemetry_sdk = "0.33"
+opentelemetry-otlp = "0.33"
+tracing-opentelemetry = "0.34"
+"#,
+        );
+    }
+    if project.options.with_openapi {
+        extra_dependencies.push_str("utoipa = \"5\"\nutoipa-rapidoc = { version = \"6\", features = [\"actix-web\"] }\n");
+    }
+    if project.options.tls {
+        extra_dependencies.push_str(
+            "rustls = { version = \"0.23\", default-features = false, features = [\"ring\", \"std\", \"tls12\"] }\n",
+        );
+        dev_dependencies.push_str("rcgen = \"0.14\"\n");
+    }
+    if project.options.with_integration {
+        // `init_service` hands out a service of `actix_http::Request`s, which actix-web doesn't re-export
+        dev_dependencies.push_str("actix-http = \"3\"\n");
+    }
+    if !dev_dependencies.is_empty() {
+        dev_dependencies.insert_str(0, "\n[dev-dependencies]\n");
+    }
+    let release_profile = if project.options.tuned_profile { RELEASE_PROFILE } else { "" };
 
     let content = format!(
         r#"[package]
@@ -89,25 +575,24 @@ edition = "2021"
 
 [dependencies]
 {}
-actix-web = "4.4"
+{}
 tokio = {{ version = "1.0", features = ["full"] }}
 serde = {{ version = "1.0", features = ["derive"] }}
 serde_json = "1.0"
-
+{}{}
 [lib]
 name = "{}"
 path = "src/lib.rs"
-"#, 
-        project_name, mold_version, project_name.replace("-", "_")
+{}"#, 
+        project_name, mold_version, actix_web, extra_dependencies, dev_dependencies, project_name.replace("-", "_"), release_profile
     );
 
-    let mut file = fs::File::create(format!("{}/Cargo.toml", project_name)).await?;
-    file.write_all(content.as_bytes()).await?;
+    write_if_changed(project.path("Cargo.toml"), content).await?;
     Ok(())
 }
 
 /// Generates the main.rs file with server initialization
-async fn generate_main_rs(project_name: &str) -> Result<()> {
+async fn generate_main_rs(project: &Scaffold) -> Result<()> {
     let content = format!(
         r#"// Main entry point for the Actix Web application
 use {}::server::server;
@@ -116,40 +601,84 @@ use {}::server::server;
 async fn main() -> std::io::Result<()> {{
     server::run().await
 }}"#,
-        project_name.replace("-", "_")
+        project.name.replace("-", "_")
     );
 
-    let mut file = fs::File::create(format!("{}/src/main.rs", project_name)).await?;
-    file.write_all(content.as_bytes()).await?;
+    write_if_changed(project.path("src/main.rs"), content).await?;
     Ok(())
 }
 
 /// Generates the lib.rs file with module declarations
-async fn generate_lib_rs(project_name: &str) -> Result<()> {
-    let content = r#"// Library crate root module declarations
+async fn generate_lib_rs(project: &Scaffold) -> Result<()> {
+    let mut content = r#"// Library crate root module declarations
 pub mod server;
 pub mod routes;
 pub mod models;
 pub mod utils;
-pub mod handlers;"#;
+pub mod handlers;"#.to_string();
+
+    if project.options.request_id || project.options.idempotency || project.options.tracing() {
+        content.push_str("\npub mod middleware;");
+    }
+    if project.options.otel.is_some() {
+        content.push_str("\npub mod telemetry;");
+    }
+    if project.options.with_settings {
+        content.push_str("\npub mod settings;");
+    }
+    if project.options.with_config {
+        content.push_str("\npub mod config;");
+    }
+    if project.options.i18n {
+        content.push_str("\npub mod i18n;");
+    }
+    if project.options.with_openapi {
+        content.push_str("\npub mod openapi;");
+    }
 
-    let mut file = fs::File::create(format!("{}/src/lib.rs", project_name)).await?;
-    file.write_all(content.as_bytes()).await?;
+    write_if_changed(project.path("src/lib.rs"), content).await?;
     Ok(())
 }
 
 /// Generates route-related files
-async fn generate_route_files(project_name: &str) -> Result<()> {
+async fn generate_route_files(project: &Scaffold) -> Result<()> {
+    // Tokens without `exp` are rejected unless legacy ones must keep working
+    let (auth_import, expiry_policy) = if project.options.allow_no_exp {
+        (
+            "{AuthService, ExpiryPolicy}",
+            "\n        // Legacy tokens without `exp` never expire, rotate JWT_SECRET to revoke them\n        .with_expiry_policy(ExpiryPolicy::AllowMissing)",
+        )
+    } else {
+        ("AuthService", "")
+    };
+
+    // Ops endpoints live outside the `/api` scope
+    let version_route = if project.options.build_info {
+        "\n    cfg.route(\"/version\", web::get().to(handlers::version));"
+    } else {
+        ""
+    };
+
+    // The user context reads the claims, so it runs inside the JWT middleware
+    let (user_context_import, scope_wraps) = if project.options.tracing() {
+        (
+            "use crate::middleware::user_context::UserContextMiddleware;\n",
+            wrap_calls(&[Middleware::Auth, Middleware::UserContext]),
+        )
+    } else {
+        ("", wrap_calls(&[Middleware::Auth]))
+    };
+
     // routes/routes.rs
     let routes_file = r#"// Route configuration module
 // Defines all public API routes and their handlers
 use actix_web::web;
-use cargo_mold::auth::JwtMiddleware;
+use cargo_mold::auth::{{auth_import}};
 
 use crate::handlers::handlers;
-
+{{user_context_import}}
 /// Configures all public routes for the application
-pub fn public_routes(cfg: &mut web::ServiceConfig) {
+pub fn public_routes(cfg: &mut web::ServiceConfig) {{{version_route}}
     cfg.service(
         web::scope("/api")
             .route("/hello", web::get().to(handlers::hello))
@@ -159,100 +688,403 @@ pub fn public_routes(cfg: &mut web::ServiceConfig) {
 /// Configures all private routes for the application
 pub fn private_routes(cfg: &mut web::ServiceConfig) {
 
-    let jwt_secret = std::env::var("JWT_SECRET")
-        .expect("JWT_SECRET must be set in environment");
-    let jwt_middleware = JwtMiddleware::new(jwt_secret);
+    let auth_service = AuthService::from_env()
+        .unwrap_or_else(|e| panic!("Invalid auth configuration: {}", e)){{expiry_policy}};
 
     cfg.service(
-        web::scope("/private-api")
-            .wrap(jwt_middleware)
+        web::scope("/private-api"){{scope_wraps}}
             .route("/", web::get().to(handlers::hello))
     );
-}"#;
+}"#
+    .replace("{{version_route}}", version_route)
+    .replace("{{auth_import}}", auth_import)
+    .replace("{{expiry_policy}}", expiry_policy)
+    .replace("{{user_context_import}}", user_context_import)
+    .replace("{{scope_wraps}}", &scope_wraps);
 
-    let mut file = fs::File::create(format!("{}/src/routes/routes.rs", project_name)).await?;
-    file.write_all(routes_file.as_bytes()).await?;
+    write_if_changed(project.path("src/routes/routes.rs"), routes_file).await?;
 
     Ok(())
 }
 
 /// Generates handler files with example handlers
-async fn generate_handler_files(project_name: &str) -> Result<()> {
+async fn generate_handler_files(project: &Scaffold) -> Result<()> {
+    // Documented under its public route, the one `ApiDoc` serves
+    let openapi_path = if project.options.with_openapi {
+        "\n#[utoipa::path(get, path = \"/api/hello\", responses((status = 200, description = \"A greeting\", body = String)))]"
+    } else {
+        ""
+    };
+
     // handlers/handlers.rs
     let handlers_file = r#"// Request handlers for the Actix Web application
 use actix_web::{HttpResponse, Responder};
 
-/// Simple hello world endpoint
+/// Simple hello world endpoint{{openapi_path}}
 pub async fn hello() -> impl Responder {
     HttpResponse::Ok().body("Hello, World! from Actix Web")
-}"#;
-    let mut file = fs::File::create(format!("{}/src/handlers/handlers.rs", project_name)).await?;
-    file.write_all(handlers_file.as_bytes()).await?;
+}"#
+    .replace("{{openapi_path}}", openapi_path);
+    let handlers_file = if project.options.build_info {
+        handlers_file + templates::VERSION_HANDLER
+    } else {
+        handlers_file
+    };
+    write_if_changed(project.path("src/handlers/handlers.rs"), handlers_file).await?;
+
+    if project.options.with_fallback {
+        write_if_changed(project.path("src/handlers/fallback.rs"), templates::FALLBACK_HANDLERS).await?;
+    }
 
     Ok(())
 }
 
+/// Middlewares and app data of the whole application, shared by the server and the integration test harness
+struct AppSetup {
+    imports: String,
+    /// Statements building what the wraps share between workers
+    startup: String,
+    /// `.wrap`/`.app_data`/`.configure`/`.default_service` calls on `App::new()`, the last one wrapped runs first
+    wraps: String,
+}
+
+/// The `AppSetup` of the enabled features, importing the project's modules through `crate_path`.
+/// The `TracingLogger` and access log are only wrapped with `logger`, the test harness runs without them
+fn app_setup(project: &Scaffold, crate_path: &str, logger: bool) -> AppSetup {
+    let mut imports = String::new();
+    let mut startup = String::new();
+    let mut enabled = Vec::new();
+    let mut wraps = String::new();
+    if project.options.with_fallback {
+        // Innermost, so the other middlewares see the JSON 405
+        imports.push_str(&format!("use {}::handlers::fallback;\n", crate_path));
+        wraps.push_str("\n            .wrap(fallback::method_not_allowed())");
+    }
+    if logger && project.options.tracing() {
+        imports.push_str("use tracing_actix_web::TracingLogger;\n");
+        enabled.push(Middleware::Tracing);
+    }
+    if logger && project.options.access_log {
+        imports.push_str("use actix_web::middleware::Logger;\n");
+        enabled.push(Middleware::AccessLog);
+    }
+    if project.options.security_headers {
+        // Read once, the environment can't change under the workers
+        imports.push_str(&format!("use {}::middleware::security_headers::SecurityHeaders;\n", crate_path));
+        startup.push_str("    let security_headers = SecurityHeaders::from_env();\n");
+        enabled.push(Middleware::SecurityHeaders);
+    }
+    if project.options.request_id {
+        imports.push_str(&format!("use {}::middleware::request_id::RequestIdMiddleware;\n", crate_path));
+        enabled.push(Middleware::RequestId);
+    }
+    if project.options.idempotency {
+        // Built once so every worker replays from the same store
+        imports.push_str(&format!("use {}::middleware::idempotency::IdempotencyMiddleware;\n", crate_path));
+        startup.push_str("    let idempotency = IdempotencyMiddleware::default();\n");
+        enabled.push(Middleware::Idempotency);
+    }
+    wraps.push_str(&wrap_calls(&enabled));
+
+    if project.options.with_openapi {
+        imports.push_str(&format!("use {}::openapi::openapi_routes;\n", crate_path));
+        wraps.push_str("\n            .configure(openapi_routes)");
+    }
+    if project.options.with_settings {
+        imports.push_str(&format!("use {}::settings::FeatureFlags;\n", crate_path));
+        wraps.push_str("\n            .app_data(feature_flags.clone())");
+        startup.push_str("    let feature_flags = web::Data::new(FeatureFlags::from_env());\n");
+    }
+    if project.options.with_fallback {
+        wraps.push_str("\n            .default_service(web::to(fallback::not_found))");
+    }
+    AppSetup { imports, startup, wraps }
+}
+
+/// The `.wrap` calls of `enabled`, one per line, written so they run in the default middleware order
+fn wrap_calls(enabled: &[Middleware]) -> String {
+    Middleware::wrap_sequence(&Middleware::DEFAULT_ORDER, enabled)
+        .into_iter()
+        .map(|middleware| format!("\n            {}", middleware.wrap_call()))
+        .collect()
+}
+
 /// Generates server configuration files
-async fn generate_server_files(project_name: &str) -> Result<()> {
-    // server/server.rs
-    let server_file = r#"// Server configuration and startup
-use actix_web::{App, HttpServer};
-use crate::routes;
+async fn generate_server_files(project: &Scaffold) -> Result<()> {
+    let AppSetup { mut imports, mut startup, wraps } = app_setup(project, "crate", true);
 
-/// Starts the HTTP server and begins listening for requests
-pub async fn run() -> std::io::Result<()> {
-    println!("🚀 Starting Actix Web server on http://127.0.0.1:8080");
-    
-    HttpServer::new(|| {
+    // Statements around the server, e.g. to install telemetry and flush it on shutdown
+    let mut shutdown = String::new();
+    if project.options.otel.is_some() {
+        imports.push_str("use crate::telemetry;\n");
+        startup.push_str("    let tracer_provider = telemetry::init_telemetry();\n");
+        shutdown.push_str("    let _ = tracer_provider.shutdown();\n");
+    } else if project.options.tracing {
+        imports.push_str("use tracing_subscriber::EnvFilter;\n");
+        startup.push_str(
+            "    tracing_subscriber::fmt()\n        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(\"info\")))\n        .init();\n",
+        );
+    } else if project.options.access_log {
+        // `Logger` writes through `log`, which tracing's subscriber picks up when it is installed
+        startup.push_str("    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(\"info\")).init();\n");
+    }
+
+    // Items appended after `run`, and how the server listens
+    let mut helpers = String::new();
+    let (address, address_args) = if project.options.with_config {
+        imports.push_str("use crate::config::AppConfig;\n");
+        startup.insert_str(0, "    let config = AppConfig::from_env().unwrap_or_else(|e| panic!(\"{}\", e));\n");
+        ("(config.host.as_str(), config.port)", "{}:{}\", config.host, config.port")
+    } else {
+        ("\"127.0.0.1:8080\"", "127.0.0.1:8080\"")
+    };
+    let mut bind = format!(".bind({})?", address);
+    let mut scheme = "http";
+    if project.options.tls {
+        imports.push_str("use std::sync::Arc;\nuse rustls::pki_types::pem::PemObject;\nuse rustls::pki_types::{CertificateDer, PrivateKeyDer};\n");
+        startup.push_str("    let tls_config = load_rustls_config()?;\n");
+        bind = format!(".bind_rustls_0_23({}, tls_config)?", address);
+        scheme = "https";
+        helpers.push_str(templates::TLS_CONFIG);
+    }
+    // Constants following the server's limits
+    let access_log_format = if project.options.access_log {
+        format!("{}const ACCESS_LOG_FORMAT: &str = {:?};\n", templates::ACCESS_LOG_TOKENS, project.options.access_log_format)
+    } else {
+        String::new()
+    };
+    let mut tests = templates::MIDDLEWARE_ORDER_TEST.to_string();
+    if project.options.access_log {
+        let end = tests.trim_end().len() - 1;
+        tests.insert_str(end, templates::ACCESS_LOG_TEST);
+    }
+    helpers.push_str(&tests);
+
+    let server = format!(
+        r#"HttpServer::new(move || {{{{
         App::new()
+            .app_data(web::PayloadConfig::new(MAX_BODY_SIZE))
+            .app_data(web::JsonConfig::default().limit(MAX_BODY_SIZE))
+            .app_data(web::FormConfig::default().limit(MAX_BODY_SIZE)){{}}
             .configure(routes::routes::public_routes)
-    })
-    .bind("127.0.0.1:8080")?
+    }}}})
+    .keep_alive(KEEP_ALIVE)
+    .client_request_timeout(CLIENT_REQUEST_TIMEOUT)
+    {}
     .run()
-    .await
-}"#;
+    .await"#,
+        bind
+    );
+    let server = if shutdown.is_empty() {
+        format!("    {}", server)
+    } else {
+        format!("    let result = {};\n\n{}\n    result", server, shutdown)
+    };
+    let server = server.replace("{{", "{").replace("}}", "}").replacen("{}", &wraps, 1);
+
+    // server/server.rs
+    let server_file = format!(
+        r#"// Server configuration and startup
+use std::time::Duration;
+
+use actix_web::{{web, App, HttpServer}};
+use crate::routes;
+{}
+/// Largest request body accepted, in bytes, by the `Bytes`/`String`, `Json` and `Form` extractors;
+/// larger ones are answered with a 413. A `JsonConfig` registered on a scope replaces this limit there
+pub const MAX_BODY_SIZE: usize = {};
+/// How long an idle keep-alive connection stays open before it is closed
+const KEEP_ALIVE: Duration = Duration::from_secs({});
+/// How long a client has to send the request head before getting a 408, so slow clients
+/// (e.g. slowloris attacks) can't hold connections open
+const CLIENT_REQUEST_TIMEOUT: Duration = Duration::from_millis({});
+{}
+/// Starts the HTTP server and begins listening for requests
+pub async fn run() -> std::io::Result<()> {{
+{}    println!("🚀 Starting Actix Web server on {}://{});
+    
+{}
+}}
+{}"#,
+        imports,
+        project.options.max_body_size,
+        project.options.keep_alive,
+        project.options.client_timeout,
+        access_log_format,
+        startup,
+        scheme,
+        address_args,
+        server,
+        helpers
+    );
+
+    write_if_changed(project.path("src/server/server.rs"), server_file).await?;
+
+    Ok(())
+}
+
+/// Generates the OpenTelemetry setup when `--otel` is given
+async fn generate_telemetry_file(project: &Scaffold) -> Result<()> {
+    if let Some(endpoint) = &project.options.otel {
+        let content = templates::TELEMETRY.replace("{{otel_endpoint}}", endpoint);
+        write_if_changed(project.path("src/telemetry.rs"), content).await?;
+    }
+    Ok(())
+}
+
+/// Generates the `Settings<T>` extractor when `--with-settings` is given
+async fn generate_settings_file(project: &Scaffold) -> Result<()> {
+    if project.options.with_settings {
+        write_if_changed(project.path("src/settings.rs"), templates::SETTINGS).await?;
+    }
+    Ok(())
+}
+
+/// Generates the `AppConfig` loaded by the server when `--with-config` is given
+async fn generate_config_file(project: &Scaffold) -> Result<()> {
+    if project.options.with_config {
+        write_if_changed(project.path("src/config.rs"), templates::APP_CONFIG).await?;
+    }
+    Ok(())
+}
+
+/// Generates the message catalog and its English and Spanish locales when `--i18n` is given
+async fn generate_i18n_files(project: &Scaffold) -> Result<()> {
+    if project.options.i18n {
+        write_if_changed(project.path("src/i18n.rs"), templates::I18N).await?;
+
+        fs::create_dir_all(project.path("locales")).await?;
+        for (lang, messages) in [("en", templates::LOCALE_EN), ("es", templates::LOCALE_ES)] {
+            write_if_changed(project.path(&format!("locales/{}.json", lang)), messages).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Generates the `ApiDoc` served at `/openapi.json` when `--with-openapi` is given
+async fn generate_openapi_file(project: &Scaffold) -> Result<()> {
+    if project.options.with_openapi {
+        write_if_changed(project.path("src/openapi.rs"), templates::OPENAPI).await?;
+    }
+    Ok(())
+}
+
+/// Generates the `build.rs` feeding the `/version` handler when `--build-info` is given
+async fn generate_build_script(project: &Scaffold) -> Result<()> {
+    if project.options.build_info {
+        write_if_changed(project.path("build.rs"), templates::BUILD_RS).await?;
+    }
+    Ok(())
+}
+
+/// Generates the `tests/common` harness, a first integration test and the `/private-api` ones when `--with-integration` is given
+async fn generate_integration_harness(project: &Scaffold) -> Result<()> {
+    if !project.options.with_integration {
+        return Ok(());
+    }
+
+    let crate_name = project.name.replace("-", "_");
+    let AppSetup { imports, startup, wraps } = app_setup(project, &crate_name, false);
+    let harness = templates::INTEGRATION_HARNESS
+        .replace("{{crate_name}}", &crate_name)
+        .replace("{{imports}}", &imports)
+        .replace("{{startup}}", &startup)
+        .replace("{{wraps}}", &wraps);
+
+    fs::create_dir_all(project.path("tests/common")).await?;
+    write_if_changed(project.path("tests/common/mod.rs"), harness).await?;
+    write_if_changed(project.path("tests/api.rs"), templates::INTEGRATION_API_TEST).await?;
+    write_if_changed(project.path("tests/private_api.rs"), templates::INTEGRATION_PRIVATE_API_TEST).await?;
+    Ok(())
+}
+
+/// Generates `tests/license_header.rs`, checking every source file starts with the license header
+async fn generate_license_header_test(project: &Scaffold) -> Result<()> {
+    fs::create_dir_all(project.path("tests")).await?;
+    write_if_changed(project.path("tests/license_header.rs"), templates::LICENSE_HEADER_TEST).await?;
+    Ok(())
+}
+
+/// Generates `examples/dev_cert.rs` writing a self-signed certificate when `--tls` is given
+async fn generate_dev_cert_example(project: &Scaffold) -> Result<()> {
+    if project.options.tls {
+        fs::create_dir_all(project.path("examples")).await?;
+        write_if_changed(project.path("examples/dev_cert.rs"), templates::DEV_CERT_EXAMPLE).await?;
+    }
+    Ok(())
+}
+
+/// Generates the optional middleware files
+async fn generate_middleware_files(project: &Scaffold) -> Result<()> {
+    let mut middleware_mod = String::from("// Application middlewares");
+
+    if project.options.request_id {
+        fs::create_dir_all(project.path("src/middleware")).await?;
+        write_if_changed(project.path("src/middleware/request_id.rs"), templates::REQUEST_ID_MIDDLEWARE).await?;
+        middleware_mod.push_str("\npub mod request_id;");
+    }
+
+    if project.options.security_headers {
+        fs::create_dir_all(project.path("src/middleware")).await?;
+        let content = templates::SECURITY_HEADERS_MIDDLEWARE
+            .replace("{{content_security_policy}}", &project.options.content_security_policy().replace('"', "\\\""));
+        write_if_changed(project.path("src/middleware/security_headers.rs"), content).await?;
+        middleware_mod.push_str("\npub mod security_headers;");
+    }
+
+    if project.options.idempotency {
+        fs::create_dir_all(project.path("src/middleware")).await?;
+        write_if_changed(project.path("src/middleware/idempotency.rs"), templates::IDEMPOTENCY_MIDDLEWARE).await?;
+        middleware_mod.push_str("\npub mod idempotency;");
+    }
+
+    // Every project authenticates `/private-api`, so the user context only depends on tracing
+    if project.options.tracing() {
+        fs::create_dir_all(project.path("src/middleware")).await?;
+        write_if_changed(project.path("src/middleware/user_context.rs"), templates::USER_CONTEXT_MIDDLEWARE).await?;
+        middleware_mod.push_str("\npub mod user_context;");
+    }
 
-    let mut file = fs::File::create(format!("{}/src/server/server.rs", project_name)).await?;
-    file.write_all(server_file.as_bytes()).await?;
+    if Path::new(&project.path("src/middleware")).exists() {
+        write_if_changed(project.mod_path("middleware"), middleware_mod).await?;
+    }
 
     Ok(())
 }
 
-async fn generate_mod_files(project_name: &str) -> Result<()> {
-    // models/mod.rs
+async fn generate_mod_files(project: &Scaffold) -> Result<()> {
+    // models/mod.rs, or models.rs in the modern layout
     let models_mod = r#"// Data models and structures for the application
 // Define your database models, request/response DTOs, and domain models here"#;
 
-    let mut file = fs::File::create(format!("{}/src/models/mod.rs", project_name)).await?;
-    file.write_all(models_mod.as_bytes()).await?;
+    write_if_changed(project.mod_path("models"), models_mod).await?;
 
     // utils/mod.rs
     let utils_mod = r#"// Utility functions and helpers
 // Common utilities, helpers, and shared functionality across the application"#;
 
-    let mut file = fs::File::create(format!("{}/src/utils/mod.rs", project_name)).await?;
-    file.write_all(utils_mod.as_bytes()).await?;
+    write_if_changed(project.mod_path("utils"), utils_mod).await?;
 
     // handlers/mod.rs (if not already created)
-    let handlers_mod = r#"// Request handlers for the Actix Web application
-pub mod handlers;"#;
+    let mut handlers_mod = r#"// Request handlers for the Actix Web application
+pub mod handlers;"#.to_string();
+    if project.options.with_fallback {
+        handlers_mod.push_str("\npub mod fallback;");
+    }
 
-    let mut file = fs::File::create(format!("{}/src/handlers/mod.rs", project_name)).await?;
-    file.write_all(handlers_mod.as_bytes()).await?;
+    write_if_changed(project.mod_path("handlers"), handlers_mod).await?;
 
     // routes/mod.rs (if not already created)
     let routes_mod = r#"// Route configuration module
 pub mod routes;"#;
 
-    let mut file = fs::File::create(format!("{}/src/routes/mod.rs", project_name)).await?;
-    file.write_all(routes_mod.as_bytes()).await?;
+    write_if_changed(project.mod_path("routes"), routes_mod).await?;
 
     // server/mod.rs (if not already created)
     let server_mod = r#"// Server configuration and startup logic
 pub mod server;"#;
 
-    let mut file = fs::File::create(format!("{}/src/server/mod.rs", project_name)).await?;
-    file.write_all(server_mod.as_bytes()).await?;
+    write_if_changed(project.mod_path("server"), server_mod).await?;
 
     Ok(())
 }
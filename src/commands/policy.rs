@@ -0,0 +1,275 @@
+use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::utils::conversions::to_pascal_case;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct PolicyArgs {
+    /// Name of the policy, e.g. `owner_only` or `published-posts`
+    pub name: String,
+    /// Overwrite the policy if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+pub async fn execute(args: PolicyArgs) -> anyhow::Result<()> {
+    report(format!("🔐 Generating policy: {}", args.name));
+
+    let name = args.name.replace('-', "_").to_lowercase();
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name || name == "mod" {
+        anyhow::bail!("❌ Invalid policy name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = ctx.module_file("policies", &name);
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    let type_name = format!("{}Policy", to_pascal_case(&name));
+    fs::create_dir_all(ctx.module_dir("policies")).await?;
+    let mut generated = vec![file_path.clone()];
+    // The first policy also brings the `Policy` trait and the types it decides on
+    let mod_file_path = ctx.mod_file("policies");
+    if !Path::new(&mod_file_path).exists() {
+        write_if_changed(&mod_file_path, POLICIES_MODULE.replace("{name}", &name)).await?;
+        generated.push(mod_file_path);
+    }
+    generate_policy(&name, &type_name, &file_path).await?;
+    ctx.register("policies", &name).await?;
+    if args.fix {
+        fix_generated(&ctx, &generated).await?;
+    }
+
+    report(format!("✅ Policy '{}' created successfully!", name));
+    report("📝 Generated files:");
+    for file in &generated {
+        report(format!("   - {}", file));
+    }
+    report(format!(
+        "🔗 Implement `policies::Resource` for the guarded models, then call `{}.enforce(&subject, Action::Update, &item)?` \
+         in handlers of /private-api taking a `subject: Subject`",
+        type_name
+    ));
+
+    Ok(())
+}
+
+/// `policies/mod.rs` declaring the `{name}` policy: the `Policy` trait, the `Subject` handlers extract from the JWT claims, the
+/// `Action`s and the `Resource` attributes policies decide on
+const POLICIES_MODULE: &str = r#"// Attribute-based access control: policies decide whether a subject may perform an action on a resource
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpMessage, HttpRequest};
+use cargo_mold::auth::Claims;
+use serde_json::Value;
+
+pub mod {name};
+
+/// What a subject attempts on a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Read,
+    Create,
+    Update,
+    Delete,
+}
+
+/// Whom a request acts for: the `sub` of its JWT and the custom claims as attributes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subject {
+    pub id: String,
+    pub attributes: Value,
+}
+
+impl Subject {
+    pub fn new(id: impl Into<String>, attributes: Value) -> Self {
+        Self { id: id.into(), attributes }
+    }
+
+    pub fn from_claims(claims: &Claims) -> Self {
+        Self::new(claims.sub.clone(), claims.data.clone())
+    }
+
+    /// Custom claim `name`, e.g. `department`
+    pub fn attribute(&self, name: &str) -> Option<&Value> {
+        self.attributes.get(name)
+    }
+
+    /// Whether the `roles` claim lists `role`, or the `role` claim is `role`
+    pub fn has_role(&self, role: &str) -> bool {
+        let listed = self.attribute("roles")
+            .and_then(Value::as_array)
+            .is_some_and(|roles| roles.iter().any(|r| r.as_str() == Some(role)));
+        listed || self.attribute("role").and_then(Value::as_str) == Some(role)
+    }
+}
+
+/// Handlers under the JWT protected scope take a `subject: Subject`, built from the claims the JWT
+/// middleware stored; requests without them are answered with a 401
+impl FromRequest for Subject {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let subject = req.extensions().get::<Claims>()
+            .map(Subject::from_claims)
+            .ok_or_else(|| actix_web::error::ErrorUnauthorized("Missing credentials"));
+        ready(subject)
+    }
+}
+
+/// Attributes of a resource policies decide on, implemented by the models they guard
+pub trait Resource {
+    /// Id of the subject owning the resource, `None` when nobody does
+    fn owner_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// Any other attribute, e.g. `visibility`
+    fn attribute(&self, _name: &str) -> Option<Value> {
+        None
+    }
+}
+
+/// An access rule over the subject, the action and the resource
+pub trait Policy {
+    fn authorize(&self, subject: &Subject, action: Action, resource: &dyn Resource) -> bool;
+
+    /// `authorize` for handlers: `policy.enforce(&subject, Action::Delete, &item)?` answers a 403 when denied
+    fn enforce(&self, subject: &Subject, action: Action, resource: &dyn Resource) -> Result<(), actix_web::Error> {
+        if self.authorize(subject, action, resource) {
+            Ok(())
+        } else {
+            Err(actix_web::error::ErrorForbidden(format!("{:?} is not allowed on this resource", action)))
+        }
+    }
+}
+"#;
+
+/// Rules and tests of the policies cargo-mold knows, the others get a skeleton to fill in
+fn example(name: &str) -> (&'static str, &'static str) {
+    match name {
+        "owner_only" => (
+            r#"    // Administrators may do anything, everyone else only act on what they own, whatever the action
+    let _ = action;
+    subject.has_role("admin") || resource.owner_id() == Some(subject.id.as_str())"#,
+            r#"    #[test]
+    fn allows_the_owner_every_action() {
+        let owner = Subject::new("ada", json!({}));
+        for action in [Action::Read, Action::Create, Action::Update, Action::Delete] {
+            assert!(authorize(&owner, action, &Item(Some("ada"))));
+        }
+    }
+
+    #[test]
+    fn denies_other_subjects_and_unowned_resources() {
+        let other = Subject::new("bob", json!({ "roles": ["editor"] }));
+        assert!(!authorize(&other, Action::Read, &Item(Some("ada"))));
+        assert!(!authorize(&other, Action::Delete, &Item(Some("ada"))));
+        assert!(!authorize(&other, Action::Update, &Item(None)));
+    }
+
+    #[test]
+    fn allows_administrators_everything() {
+        let admin = Subject::new("root", json!({ "roles": ["admin"] }));
+        assert!(authorize(&admin, Action::Delete, &Item(Some("ada"))));
+        assert!(authorize(&admin, Action::Update, &Item(None)));
+    }
+
+    #[test]
+    fn enforce_answers_a_403_when_denied() {
+        let other = Subject::new("bob", json!({}));
+        let error = OwnerOnlyPolicy.enforce(&other, Action::Update, &Item(Some("ada"))).unwrap_err();
+        assert_eq!(error.as_response_error().status_code(), StatusCode::FORBIDDEN);
+        assert!(OwnerOnlyPolicy.enforce(&other, Action::Update, &Item(Some("bob"))).is_ok());
+    }"#,
+        ),
+        _ => (
+            r#"    // TODO: replace with the actual rules over the subject's and the resource's attributes
+    let _ = (subject, resource);
+    action == Action::Read"#,
+            "",
+        ),
+    }
+}
+
+/// Writes `{type_name}` and the `authorize` function it delegates to, with tests of its decisions
+async fn generate_policy(name: &str, type_name: &str, file_path: &str) -> Result<()> {
+    let (rules, tests) = example(name);
+    let tests = if tests.is_empty() {
+        format!(
+            r#"    #[test]
+    fn allows_reading() {{
+        let subject = Subject::new("ada", json!({{}}));
+        assert!(authorize(&subject, Action::Read, &Item(Some("bob"))));
+    }}
+
+    #[test]
+    fn denies_changes() {{
+        let subject = Subject::new("ada", json!({{}}));
+        for action in [Action::Create, Action::Update, Action::Delete] {{
+            assert!({type_name}.enforce(&subject, action, &Item(Some("ada"))).is_err());
+        }}
+    }}"#
+        )
+    } else {
+        tests.to_string()
+    };
+    let test_imports = if tests.contains("StatusCode") {
+        "    use actix_web::http::StatusCode;\n"
+    } else {
+        ""
+    };
+
+    let content = format!(
+        r#"// `{name}` access policy, checked by handlers with `{type_name}.enforce(&subject, action, &item)?`
+use crate::policies::{{Action, Policy, Resource, Subject}};
+
+pub struct {type_name};
+
+/// Whether `subject` may perform `action` on `resource`
+pub fn authorize(subject: &Subject, action: Action, resource: &dyn Resource) -> bool {{
+{rules}
+}}
+
+impl Policy for {type_name} {{
+    fn authorize(&self, subject: &Subject, action: Action, resource: &dyn Resource) -> bool {{
+        authorize(subject, action, resource)
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+{test_imports}    use serde_json::json;
+
+    /// Resource owned by the subject of the given id, if any
+    struct Item(Option<&'static str>);
+
+    impl Resource for Item {{
+        fn owner_id(&self) -> Option<&str> {{
+            self.0
+        }}
+    }}
+
+{tests}
+}}
+"#
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
@@ -0,0 +1,488 @@
+use clap::Args;
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::commands::resource::{Layer, NamingArgs};
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{check_project, rust_sources, write_if_changed};
+use crate::utils::output::report;
+use crate::utils::wiring::{configure_targets, find_code, inline_module, mask_comments};
+
+#[derive(Args)]
+pub struct RenameArgs {
+    /// Current name of the resource
+    pub old: String,
+    /// Name to give it, e.g. `articles` for `posts`
+    pub new: String,
+    /// Run `cargo check` afterwards and fail when the project no longer compiles
+    #[arg(long)]
+    pub check: bool,
+    /// Naming flags the resource was generated with, so its type names are found
+    #[command(flatten)]
+    pub naming: NamingArgs,
+}
+
+/// Top-level modules of the files generated for a resource besides its `Layer`s, with their file name suffix
+const COMPANION_MODULES: [(&str, &str); 4] = [
+    ("repositories", "_repository"),
+    ("services", "_service"),
+    ("stores", "_store"),
+    ("admin", "_admin"),
+];
+
+/// A module of the resource: its top-level module and its file name before and after the rename
+struct ModuleRename {
+    module: &'static str,
+    old: String,
+    new: String,
+}
+
+pub async fn execute(args: RenameArgs) -> anyhow::Result<()> {
+    report(format!("✏️ Renaming resource: {} → {}", args.old, args.new));
+
+    for name in [&args.old, &args.new] {
+        let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+            && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid_name {
+            anyhow::bail!("❌ Invalid resource name '{}': use lowercase letters, digits and '_', starting with a letter", name);
+        }
+    }
+    if args.old == args.new {
+        anyhow::bail!("❌ '{}' already has that name", args.old);
+    }
+
+    let mut ctx = ProjectContext::load().await?;
+
+    let modules: Vec<ModuleRename> = resource_modules(&args.old)
+        .into_iter()
+        .zip(resource_modules(&args.new))
+        .filter(|((module, old), _)| ctx.has_module(module, old))
+        .map(|((module, old), (_, new))| ModuleRename { module, old, new })
+        .collect();
+    if !Layer::ALL.iter().any(|layer| layer.exists(&ctx, &args.old)) {
+        anyhow::bail!("❌ Resource '{}' not found, there is no model, handler or routes module of that name", args.old);
+    }
+    if let Some(taken) = modules.iter().find(|module| ctx.has_module(module.module, &module.new)) {
+        anyhow::bail!("❌ '{}' already exists, pick another name", ctx.module_location(taken.module, &taken.new));
+    }
+
+    let renamer = Renamer::new(&args.old, &args.new, &args.naming.type_name(&args.old), &args.naming.type_name(&args.new));
+    let nested = nested_modules(&ctx, &modules).await?;
+    // Every identifier of the resource's own code that was rewritten, to follow its references elsewhere
+    let mut renamed_idents = BTreeMap::new();
+    let mut moved = Vec::new();
+    let mut edited = BTreeSet::new();
+    for module in &modules {
+        moved.extend(move_module(&ctx, &renamer, module, &mut renamed_idents).await?);
+        edited.insert(ctx.mod_file(module.module));
+    }
+
+    let own_files: Vec<PathBuf> = moved.iter().map(|(_, to)| PathBuf::from(to)).collect();
+    let mut sources = rust_sources(&ctx.src_dir()).await?;
+    let tests_dir = ctx.crate_path("tests");
+    if Path::new(&tests_dir).is_dir() {
+        sources.extend(rust_sources(&tests_dir).await?);
+    }
+    sources.retain(|path| !own_files.contains(path));
+    sources.sort();
+
+    let mut leftovers = Vec::new();
+    for path in &sources {
+        let source = fs::read_to_string(path).await?;
+        let rewritten = rewrite_references(&source, &modules, &renamed_idents);
+        let rewritten = rename_nested(&ctx, &renamer, path, rewritten, &nested, &mut renamed_idents);
+        if rewritten != source {
+            write_if_changed(path, &rewritten).await?;
+            edited.insert(path.to_string_lossy().into_owned());
+        }
+        for (line, ident) in renamer.leftovers(&rewritten, &renamed_idents) {
+            leftovers.push(format!("{}:{}: `{}`", path.display(), line, ident));
+        }
+    }
+    // Files moved out of the edited modules are listed as moves
+    edited.retain(|path| Path::new(path).exists() && !moved.iter().any(|(_, to)| to == path));
+
+    if let Some(deprecation) = ctx.config_mut().deprecated.remove(&args.old) {
+        ctx.config_mut().deprecated.insert(args.new.clone(), deprecation);
+        ctx.save_config().await?;
+        edited.insert(ctx.path(".cargo-mold"));
+    }
+    let migration = rename_table(&ctx, &args.old, &args.new).await?;
+    let schema = ctx.path(&format!("schemas/{}.schema.json", args.old));
+    if Path::new(&schema).exists() {
+        leftovers.push(format!("{}: regenerate it with `cargo mold g schema {}`", schema, args.new));
+    }
+
+    report(format!("✅ Resource '{}' renamed to '{}'!", args.old, args.new));
+    if !moved.is_empty() {
+        report("📝 Renamed files:");
+        for (from, to) in &moved {
+            report(format!("   - {} → {}", from, to));
+        }
+    }
+    if !edited.is_empty() || migration.is_some() {
+        report("📝 Edited files:");
+        for file in &edited {
+            report(format!("   - {}", file));
+        }
+        if let Some(migration) = &migration {
+            report(format!("   - {} (renames the table, apply it with `cargo run --bin migrate`)", migration));
+        }
+    }
+    if !leftovers.is_empty() {
+        report("⚠️ Some references could not be rewritten safely, update them by hand:");
+        for leftover in &leftovers {
+            report(format!("   - {}", leftover));
+        }
+    }
+    if args.check {
+        check_project(&ctx).await?;
+    }
+
+    Ok(())
+}
+
+/// Top-level module and file name of every module `cargo mold` may generate for the resource `name`
+fn resource_modules(name: &str) -> Vec<(&'static str, String)> {
+    Layer::ALL.iter()
+        .map(|layer| (layer.module(), layer.file_name(name)))
+        .chain(COMPANION_MODULES.iter().map(|(module, suffix)| (*module, format!("{}{}", name, suffix))))
+        .collect()
+}
+
+/// Modules of the resources nested under the renamed one, found in the `.configure(...)` calls of its
+/// routes. Their scope (`/{posts_id}/comments`), path parameters and tests name it too
+async fn nested_modules(ctx: &ProjectContext, modules: &[ModuleRename]) -> Result<Vec<(&'static str, String)>> {
+    let routes_module = Layer::Routes.module();
+    let Some(routes) = modules.iter().find(|module| module.module == routes_module) else {
+        return Ok(Vec::new());
+    };
+    let routes_file = match fs::read_to_string(ctx.module_file(routes_module, &routes.old)).await {
+        Ok(source) => source,
+        Err(_) => {
+            let mod_file = fs::read_to_string(ctx.mod_file(routes_module)).await.unwrap_or_default();
+            match inline_module(&mod_file, &routes.old) {
+                Some((block, _)) => mod_file[block].to_string(),
+                None => return Ok(Vec::new()),
+            }
+        }
+    };
+    Ok(configure_targets(&routes_file).iter()
+        // `comments_routes::comments_routes`, possibly called from a feature-gated closure
+        .filter_map(|target| {
+            let module = target.split("::").next()?;
+            let module = module.rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).next()?;
+            module.strip_suffix("_routes").map(str::to_string)
+        })
+        .flat_map(|child| resource_modules(&child))
+        .filter(|(module, file)| ctx.has_module(module, file))
+        .collect())
+}
+
+/// `source`, read from `path`, with the resource renamed throughout the code of the nested resources
+/// it holds: their whole file, or their inline block of a `mod.rs`
+fn rename_nested(
+    ctx: &ProjectContext,
+    renamer: &Renamer,
+    path: &Path,
+    mut source: String,
+    nested: &[(&'static str, String)],
+    renamed_idents: &mut BTreeMap<String, String>,
+) -> String {
+    for (module, file) in nested {
+        if Path::new(&ctx.module_file(module, file)) == path {
+            return renamer.rewrite(&source, renamed_idents);
+        }
+        if Path::new(&ctx.mod_file(module)) == path
+            && let Some((block, _)) = inline_module(&source, file) {
+            let rewritten = renamer.rewrite(&source[block.clone()], renamed_idents);
+            source.replace_range(block, &rewritten);
+        }
+    }
+    source
+}
+
+/// Rewrites a module of the resource under its new name, recording the identifiers renamed in it.
+/// Returns the old and new paths of a module with its own file, None for an inline block of `mod.rs`
+async fn move_module(
+    ctx: &ProjectContext,
+    renamer: &Renamer,
+    module: &ModuleRename,
+    renamed_idents: &mut BTreeMap<String, String>,
+) -> Result<Option<(String, String)>> {
+    let old_path = ctx.module_file(module.module, &module.old);
+    let mod_path = ctx.mod_file(module.module);
+    let mut mod_file = fs::read_to_string(&mod_path).await.unwrap_or_default();
+
+    if !Path::new(&old_path).exists() {
+        let Some((block, _)) = inline_module(&mod_file, &module.old) else {
+            return Ok(None);
+        };
+        let rewritten = renamer.rewrite(&mod_file[block.clone()], renamed_idents);
+        mod_file.replace_range(block, &rewritten);
+        write_if_changed(&mod_path, mod_file).await?;
+        return Ok(None);
+    }
+
+    let source = fs::read_to_string(&old_path).await?;
+    let new_path = ctx.module_file(module.module, &module.new);
+    write_if_changed(&new_path, renamer.rewrite(&source, renamed_idents)).await?;
+    fs::remove_file(&old_path).await?;
+    renamed_idents.insert(module.old.clone(), module.new.clone());
+
+    // Attributes such as a feature gate stay on the declaration, only its name changes
+    match find_code(&mask_comments(&mod_file), &format!("pub mod {};", module.old)) {
+        Some((start, end)) => {
+            mod_file.replace_range(start..end, &format!("pub mod {};", module.new));
+            write_if_changed(&mod_path, mod_file).await?;
+        }
+        None => ctx.register(module.module, &module.new).await?,
+    }
+    Ok(Some((old_path, new_path)))
+}
+
+/// Renames the resource inside identifiers, in the snake_case, SCREAMING_CASE and PascalCase forms
+/// its generated code uses (`posts_handlers`, `POSTS_STORE`, `PostsService`...)
+struct Renamer {
+    old: Vec<String>,
+    new: Vec<String>,
+    old_type: String,
+    new_type: String,
+}
+
+impl Renamer {
+    fn new(old: &str, new: &str, old_type: &str, new_type: &str) -> Self {
+        Self {
+            old: old.split('_').map(str::to_string).collect(),
+            new: new.split('_').map(str::to_string).collect(),
+            old_type: old_type.to_string(),
+            new_type: new_type.to_string(),
+        }
+    }
+
+    /// `ident` with the resource renamed, None when it doesn't name the resource
+    fn rename(&self, ident: &str) -> Option<String> {
+        // The type itself, which also spells the upper case name when it is a single word like `C`
+        if ident == self.old_type {
+            return Some(self.new_type.clone());
+        }
+        let renamed = self.rename_type(&self.rename_words(ident));
+        (renamed != ident).then_some(renamed)
+    }
+
+    /// Swaps the `_`-separated words spelling the old name, in lower or upper case
+    fn rename_words(&self, ident: &str) -> String {
+        let words: Vec<&str> = ident.split('_').collect();
+        let mut renamed: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < words.len() {
+            let window = words.get(i..i + self.old.len()).unwrap_or_default();
+            let lower = window.len() == self.old.len() && window.iter().zip(&self.old).all(|(word, old)| word == old);
+            let upper = window.len() == self.old.len() && window.iter().zip(&self.old).all(|(word, old)| *word == old.to_uppercase());
+            if lower || upper {
+                renamed.extend(self.new.iter().map(|word| if upper { word.to_uppercase() } else { word.clone() }));
+                i += self.old.len();
+            } else {
+                renamed.push(words[i].to_string());
+                i += 1;
+            }
+        }
+        renamed.join("_")
+    }
+
+    /// Swaps the old type name where it is a whole word of a PascalCase identifier
+    fn rename_type(&self, ident: &str) -> String {
+        // SCREAMING_CASE constants aren't made of words of the type, e.g. `CONTENT_TYPE` for a `C` resource
+        if ident != self.old_type && !ident.contains(|c: char| c.is_ascii_lowercase()) {
+            return ident.to_string();
+        }
+        let mut renamed = String::new();
+        let mut rest = ident;
+        while let Some(start) = rest.find(&self.old_type) {
+            let end = start + self.old_type.len();
+            // `Posts` in `PostsService` or `InMemoryPosts`, not in `Postscript`
+            let whole = !rest[end..].starts_with(|c: char| c.is_ascii_lowercase());
+            renamed.push_str(&rest[..start]);
+            renamed.push_str(if whole { &self.new_type } else { &self.old_type });
+            rest = &rest[end..];
+        }
+        renamed.push_str(rest);
+        renamed
+    }
+
+    /// `source` with the resource renamed in every identifier, including those in strings and
+    /// comments (route paths, docs), recording what was renamed in `renamed_idents`
+    fn rewrite(&self, source: &str, renamed_idents: &mut BTreeMap<String, String>) -> String {
+        replace_idents(source, source, |ident, before| {
+            // actix's own items, e.g. `web::post` in a resource named `post`
+            if before.ends_with("web::") {
+                return None;
+            }
+            let renamed = self.rename(ident)?;
+            renamed_idents.insert(ident.to_string(), renamed.clone());
+            Some(renamed)
+        })
+    }
+
+    /// Lines and identifiers of the code of `source` still naming the resource
+    fn leftovers(&self, source: &str, renamed_idents: &BTreeMap<String, String>) -> Vec<(usize, String)> {
+        let code = mask_comments(source);
+        let renamed: BTreeSet<&String> = renamed_idents.values().collect();
+        let mut leftovers = Vec::new();
+        for (start, ident) in idents(&code) {
+            // New names may contain the old one, e.g. `posts` renamed to `posts_archive`
+            if code[..start].ends_with("web::") || renamed.contains(&ident.to_string()) || self.rename(ident).is_none() {
+                continue;
+            }
+            let line = code[..start].matches('\n').count() + 1;
+            if !leftovers.contains(&(line, ident.to_string())) {
+                leftovers.push((line, ident.to_string()));
+            }
+        }
+        leftovers
+    }
+}
+
+/// Byte offset and text of the identifiers (and keywords) of `code`, skipping numbers
+fn idents(code: &str) -> Vec<(usize, &str)> {
+    let bytes = code.as_bytes();
+    let is_ident_byte = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut idents = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_ident_byte(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && is_ident_byte(bytes[i]) {
+            i += 1;
+        }
+        if !bytes[start].is_ascii_digit() {
+            idents.push((start, &code[start..i]));
+        }
+    }
+    idents
+}
+
+/// `source` with the identifiers found in `code` (the same text, possibly with comments masked)
+/// replaced by what `replace` returns given the identifier and the code before it
+fn replace_idents(source: &str, code: &str, mut replace: impl FnMut(&str, &str) -> Option<String>) -> String {
+    let mut rewritten = String::with_capacity(source.len());
+    let mut copied = 0;
+    for (start, ident) in idents(code) {
+        if let Some(replacement) = replace(ident, &code[..start]) {
+            rewritten.push_str(&source[copied..start]);
+            rewritten.push_str(&replacement);
+            copied = start + ident.len();
+        }
+    }
+    rewritten.push_str(&source[copied..]);
+    rewritten
+}
+
+/// `source`, written by hand or by other commands, with the references that are safe to follow rewritten:
+/// paths through the renamed modules (`posts_handlers::get_posts`, `models::posts::Posts`) and the
+/// resource's own types. Other mentions, like a local `posts` variable, are left alone
+fn rewrite_references(source: &str, modules: &[ModuleRename], renamed_idents: &BTreeMap<String, String>) -> String {
+    let code = mask_comments(source);
+    let renamed_module = |ident: &str, before: &str| {
+        modules.iter().find(|module| {
+            // The model module is named like the resource, only its paths are unambiguous
+            module.old == ident && (module.module != "models" || before.trim_end().ends_with("models::"))
+        })
+    };
+    replace_idents(source, &code, |ident, before| {
+        if let Some(module) = renamed_module(ident, before) {
+            return Some(module.new.clone());
+        }
+        // Items reached through a renamed module were renamed along with it
+        let parent = before.strip_suffix("::").map(|path| {
+            let start = path.rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+            (&path[start..], &path[..start])
+        });
+        let through_module = parent.is_some_and(|(parent, before)| renamed_module(parent, before).is_some());
+        let is_type = ident.starts_with(|c: char| c.is_ascii_uppercase());
+        if through_module || is_type {
+            return renamed_idents.get(ident).cloned();
+        }
+        None
+    })
+}
+
+/// Writes a migration renaming the resource's table, when `g repository --orm sqlx` created one.
+/// Returns its path
+async fn rename_table(ctx: &ProjectContext, old: &str, new: &str) -> Result<Option<String>> {
+    let migrations_dir = ctx.crate_path("migrations");
+    let Ok(mut entries) = fs::read_dir(&migrations_dir).await else {
+        return Ok(None);
+    };
+    let mut migrated = false;
+    while let Some(entry) = entries.next_entry().await? {
+        migrated |= entry.file_name().to_string_lossy().ends_with(&format!("_create_{}.sql", old));
+    }
+    if !migrated {
+        return Ok(None);
+    }
+
+    let migration_path = format!(
+        "{}/{}_rename_{}_to_{}.sql",
+        migrations_dir,
+        chrono::Utc::now().format("%Y%m%d%H%M%S"),
+        old,
+        new
+    );
+    let content = format!(
+        "-- The `{old}` resource was renamed to `{new}`, its repository now queries the `{new}` table\n\
+         ALTER TABLE {old} RENAME TO {new};\n"
+    );
+    write_if_changed(&migration_path, content).await?;
+    Ok(Some(migration_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_the_resource_in_each_case() {
+        let renamer = Renamer::new("blog_post", "article", "BlogPost", "Article");
+        for (ident, renamed) in [
+            ("blog_post_handlers", Some("article_handlers")),
+            ("BLOG_POST_STORE", Some("ARTICLE_STORE")),
+            ("InMemoryBlogPostRepository", Some("InMemoryArticleRepository")),
+            ("blog_post_id", Some("article_id")),
+            ("blog", None),
+            ("BlogPostscript", None),
+        ] {
+            assert_eq!(renamer.rename(ident).as_deref(), renamed, "{}", ident);
+        }
+    }
+
+    #[test]
+    fn leaves_screaming_case_constants_alone() {
+        let renamer = Renamer::new("c", "cc", "C", "Cc");
+        for ident in ["CONTENT_TYPE", "CREATED", "LOCATION", "NO_CONTENT"] {
+            assert_eq!(renamer.rename(ident), None, "{}", ident);
+        }
+        assert_eq!(renamer.rename("C").as_deref(), Some("Cc"));
+        assert_eq!(renamer.rename("C_STORE").as_deref(), Some("CC_STORE"));
+        assert_eq!(renamer.rename("CPathParams").as_deref(), Some("CcPathParams"));
+
+        let source = "use actix_web::http::header::CONTENT_TYPE;\nfn created() -> StatusCode { StatusCode::CREATED }\n";
+        assert!(renamer.leftovers(source, &BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn rewrites_paths_through_renamed_modules_only() {
+        let modules = [ModuleRename { module: "handlers", old: "posts_handlers".to_string(), new: "articles_handlers".to_string() }];
+        let renamed_idents = BTreeMap::from([("get_posts".to_string(), "get_articles".to_string())]);
+        let source = "use crate::handlers::posts_handlers;\nlet posts = posts_handlers::get_posts;\n// posts_handlers\n";
+        assert_eq!(
+            rewrite_references(source, &modules, &renamed_idents),
+            "use crate::handlers::articles_handlers;\nlet posts = articles_handlers::get_articles;\n// posts_handlers\n"
+        );
+    }
+}
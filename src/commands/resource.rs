@@ -1,243 +1,4660 @@
-use clap::Args;
+use clap::{Args, ValueEnum};
 use anyhow::Result;
+use serde::Deserialize;
 use std::path::Path;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+
+use crate::utils::conversions::{to_pascal_case, to_pascal_case_with_acronyms, DEFAULT_ACRONYMS};
+use crate::utils::fields::{
+    add_foreign_keys, is_identifier, is_type, parse_fields, parse_model, parse_primary_key, parse_relations, parse_status_machine, Field,
+    FieldKind,
+    Relation, RelationKind, StatusMachine,
+};
+use crate::commands::admin::{admin_file_path, generate_admin};
+use crate::commands::example::{example_file_path, example_name, generate_example};
+use crate::commands::repository::generate_repository;
+use crate::commands::schema::parse_json_case;
+use crate::commands::service::generate_service;
+use crate::commands::validator::VALIDATOR_DEPENDENCY;
+use crate::utils::manifest::Manifest;
+use crate::utils::project::{Deprecation, HandlerNaming, JsonCase, ModulesStyle, ProjectConfig, ProjectContext};
+use crate::utils::utils::{check_project, document_env, fix_generated, register_module, write_if_changed};
+use crate::utils::output::report;
+use crate::utils::wiring::{
+    find_code, has_code, inline_module, insert_configure, insert_gated_configure, insert_list_items,
+    insert_module_declaration, mask_comments, module_declaration,
+};
+
+const PUBLIC_ROUTES_OPENING: &str = "pub fn public_routes(cfg: &mut web::ServiceConfig) {";
+const ADMIN_SCOPE: &str = "web::scope(\"/admin\")";
+/// Fields added to the model by `--timestamps`
+pub const TIMESTAMP_FIELDS: [&str; 2] = ["created_at", "updated_at"];
+/// Fields of a freshly generated `--base-model`
+pub const BASE_FIELDS: [&str; 3] = ["id", "created_at", "updated_at"];
+/// Requirement added to the project's `[dependencies]` for the timestamps of `models::traits` and the date columns of `g from-schema`
+pub const CHRONO_DEPENDENCY: &str = r#"{ version = "0.4", features = ["serde"] }"#;
+/// Appended to `--tz-type local` models, whose wall-clock timestamps `Timestamped` returns as instants
+const LOCAL_TO_UTC: &str = r#"
+/// `at`, a wall-clock time of the server's time zone, as an instant. Times skipped by a daylight
+/// saving change don't exist there and are read as UTC, repeated ones as their first occurrence
+fn local_to_utc(at: NaiveDateTime) -> DateTime<Utc> {
+    Local.from_local_datetime(&at).earliest().map_or_else(|| at.and_utc(), |at| at.to_utc())
+}
+"#;
 
 #[derive(Args)]
 pub struct ResourceArgs {
     pub name: String,
+    /// Model fields as `name:Type` pairs (e.g. `--fields name:String age:i32`),
+    /// `name:enum(A,B)` for an inline enum or `name:ref(Type)` for a `type_id` foreign key.
+    /// Lists and maps are written `tags:Vec<String>` and `metadata:Map<String,String>` (a `HashMap`).
+    /// `name:Type=value` gives the field a default for the request bodies leaving it out, e.g. `active:bool=true`
+    #[arg(long, num_args = 1..)]
+    pub fields: Vec<String>,
+    /// CRUD methods to generate
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = Method::ALL)]
+    pub methods: Vec<Method>,
+    /// Register the routes under the JWT protected scope
+    #[arg(long)]
+    pub protected: bool,
+    /// Return RFC 7807 `application/problem+json` errors from the handlers
+    #[arg(long)]
+    pub problem_json: bool,
+    /// Rust type of the `{id}` path parameter
+    #[arg(long, default_value = "String")]
+    pub id_type: String,
+    /// Identify items by several of their fields instead of an id, e.g. `user_id,role_id` for a join table:
+    /// item routes become `/{user_id}/{role_id}`, extracted as a `web::Path<(T1, T2)>`
+    #[arg(long, value_name = "FIELDS", value_delimiter = ',', conflicts_with_all = ["id_type", "with_service", "with_store", "parent", "admin", "with_example"])]
+    pub primary_key: Vec<String>,
+    /// Dispatch create requests to separate JSON and form handlers based on their `Content-Type`
+    #[arg(long)]
+    pub content_guards: bool,
+    /// Add a test checking that every wired route still reaches its handler
+    #[arg(long)]
+    pub with_route_check: bool,
+    /// Delegate the handlers to a generated service and in-memory repository, returning `ServiceResult`s
+    #[arg(long, conflicts_with = "problem_json")]
+    pub with_service: bool,
+    /// Have the handlers call through a generated `{Name}Store` trait object, backed by an in-memory store
+    #[arg(long, conflicts_with = "with_service")]
+    pub with_store: bool,
+    /// Answer GET requests with a weak `ETag` and a 304 when `If-None-Match` matches it
+    #[arg(long)]
+    pub with_etag: bool,
+    /// Answer the list route with a `Page<T>` envelope (`items`, `total`, `page`, `per_page`) read from
+    /// `?page=&per_page=`, with `Link` headers to the next and previous pages
+    #[arg(long, conflicts_with = "with_etag")]
+    pub paginate: bool,
+    /// Add a `status` field moving through these states, e.g. `draft->submitted->approved`
+    /// (comma-separate several chains to allow more transitions)
+    #[arg(long, value_name = "TRANSITIONS")]
+    pub status_machine: Option<String>,
+    /// Add `created_at`/`updated_at` fields, set by `new` and exposed through `models::traits::Timestamped`
+    #[arg(long)]
+    pub timestamps: bool,
+    /// Time zone handling of the `--timestamps` fields: `utc` (`DateTime<Utc>`, `TIMESTAMPTZ`), `local`
+    /// (the server's wall-clock `NaiveDateTime`, `TIMESTAMP`) or `offset` (`DateTime<FixedOffset>`, `TIMESTAMPTZ`)
+    #[arg(long, value_enum, value_name = "TZ", default_value_t = TzType::Utc, requires = "timestamps")]
+    pub tz_type: TzType,
+    /// Compose the model with the shared fields of `models::{NAME}` (`id`, `created_at`, `updated_at` in a
+    /// `{Name}Fields` struct generated once), flattened into its JSON
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["timestamps", "readonly"])]
+    pub base_model: Option<String>,
+    /// Derive `validator::Validate` on the model and take the create and update bodies as a generated
+    /// `ValidatedJson<T>`, answering invalid ones with a 400 listing the field errors
+    #[arg(long, conflicts_with = "readonly")]
+    pub validate: bool,
+    /// Comparison traits the model derives among `PartialEq`, `Eq` and `Hash`, rejected when a field type
+    /// doesn't support them. By default, every one all the fields support (`f64` fields rule out `Eq` and `Hash`)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "DERIVES")]
+    pub derives: Option<Vec<Derive>>,
+    /// Import the common actix and serde items in models and handlers through a generated `crate::prelude`
+    #[arg(long)]
+    pub with_prelude: bool,
+    /// Generate an immutable model (private fields behind getters) served by the list and get routes only
+    #[arg(long, conflicts_with_all = ["status_machine", "admin", "content_guards"])]
+    pub readonly: bool,
+    /// Generate server-rendered admin pages under `/admin/{name}` (needs `maud`)
+    #[arg(long)]
+    pub admin: bool,
+    /// Generate `examples/{name}_usage.rs`, calling the resource's routes in-process and printing the answers
+    #[arg(long)]
+    pub with_example: bool,
+    /// Nest the routes under an existing resource (`/{parent}/{parent_id}/{name}`)
+    #[arg(long, conflicts_with = "protected")]
+    pub parent: Option<String>,
+    /// Relations to existing resources as `name:belongs_to` or `name:has_many` pairs
+    /// (e.g. `--relations users:belongs_to comments:has_many`); `belongs_to` adds a `{name}_id` field
+    #[arg(long, num_args = 1..)]
+    pub relations: Vec<String>,
+    /// Mark the routes as deprecated since this `YYYY-MM-DD` date (today when omitted): responses carry a
+    /// `Deprecation` header and calls are logged. Recorded in `.cargo-mold`, so regenerating keeps it
+    #[arg(long, value_name = "SINCE", num_args = 0..=1, default_missing_value = "today")]
+    pub deprecate: Option<String>,
+    /// `YYYY-MM-DD` date after which the deprecated routes may stop answering, sent as a `Sunset` header
+    #[arg(long, value_name = "DATE", requires = "deprecate")]
+    pub sunset: Option<String>,
+    /// Prepended to the module and file names of the resource (`--file-prefix api` gives `models/api_posts.rs`),
+    /// while its routes keep the plain name
+    #[arg(long, value_name = "PREFIX")]
+    pub file_prefix: Option<String>,
+    /// Only compile the resource with this Cargo feature: its `pub mod` declarations and route registration
+    /// get `#[cfg(feature = "...")]` and the feature is declared in `Cargo.toml`
+    #[arg(long, value_name = "NAME", conflicts_with = "admin")]
+    pub feature: Option<String>,
+    /// Naming convention of the handler functions, the project's `handler_naming` by default
+    #[arg(long = "naming", value_enum, value_name = "CONVENTION")]
+    pub handler_naming: Option<HandlerNaming>,
+    /// Case of the model's JSON field names (`camel` serializes `first_name` as `firstName`),
+    /// the project's `json_case` by default
+    #[arg(long, value_enum, value_name = "CASE")]
+    pub json_case: Option<JsonCase>,
+    /// Only (re)generate these layers; `mod.rs` and `routes.rs` are only updated for them
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = Layer::ALL)]
+    pub only: Vec<Layer>,
+    /// Overwrite the files of the generated layers when they already exist
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+    /// Run `cargo check` afterwards and fail when the project no longer compiles
+    #[arg(long)]
+    pub check: bool,
+    #[command(flatten)]
+    pub naming: NamingArgs,
+}
+
+/// The files making up a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layer {
+    Model,
+    Handler,
+    Routes,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 3] = [Layer::Model, Layer::Handler, Layer::Routes];
+
+    /// Top-level module holding this layer's files
+    pub fn module(&self) -> &'static str {
+        match self {
+            Layer::Model => "models",
+            Layer::Handler => "handlers",
+            Layer::Routes => "routes",
+        }
+    }
+
+    /// Name of this layer's module for the given resource
+    pub fn file_name(&self, resource_name: &str) -> String {
+        match self {
+            Layer::Model => resource_name.to_string(),
+            Layer::Handler => format!("{}_handlers", resource_name),
+            Layer::Routes => format!("{}_routes", resource_name),
+        }
+    }
+
+    /// Path of the file holding this layer's module for the given resource, its `mod.rs` when inline
+    pub fn file_path(&self, ctx: &ProjectContext, resource_name: &str) -> String {
+        ctx.module_location(self.module(), &self.file_name(resource_name))
+    }
+
+    /// Whether this layer's module of the given resource exists
+    pub fn exists(&self, ctx: &ProjectContext, resource_name: &str) -> bool {
+        ctx.has_module(self.module(), &self.file_name(resource_name))
+    }
+
+    /// Writes this layer's module of the given resource, following the project's modules style
+    async fn write(&self, ctx: &ProjectContext, resource_name: &str, source: &str) -> Result<()> {
+        ctx.write_module(self.module(), &self.file_name(resource_name), source).await
+    }
+}
+
+impl std::fmt::Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Layer::Model => "model",
+            Layer::Handler => "handler",
+            Layer::Routes => "routes",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Controls how resource names are turned into Rust type names
+#[derive(Args, Clone, Default)]
+pub struct NamingArgs {
+    /// Uppercase recognized acronyms in type names (`http_server` -> `HTTPServer`)
+    #[arg(long)]
+    pub force_pascal_acronyms: bool,
+    /// Acronyms to recognize instead of the defaults (HTTP, URL, ID, API, ...)
+    #[arg(long, value_delimiter = ',', requires = "force_pascal_acronyms")]
+    pub acronyms: Vec<String>,
+    /// Appended to type names, e.g. `Entity` for `UserEntity`
+    #[arg(long, value_name = "SUFFIX")]
+    pub type_suffix: Option<String>,
+}
+
+impl NamingArgs {
+    /// Returns the PascalCase type name for a resource
+    pub fn type_name(&self, name: &str) -> String {
+        let type_name = if !self.force_pascal_acronyms {
+            to_pascal_case(name)
+        } else if self.acronyms.is_empty() {
+            to_pascal_case_with_acronyms(name, DEFAULT_ACRONYMS)
+        } else {
+            to_pascal_case_with_acronyms(name, &self.acronyms)
+        };
+        type_name + self.type_suffix.as_deref().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    List,
+    Get,
+    Create,
+    Update,
+    Delete,
+}
+
+impl Method {
+    pub const ALL: [Method; 5] = [Method::List, Method::Get, Method::Create, Method::Update, Method::Delete];
+
+    /// Whether the method only reads items, the ones kept by `--readonly`
+    pub fn is_read(self) -> bool {
+        matches!(self, Method::List | Method::Get)
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Method::List => "list",
+            Method::Get => "get",
+            Method::Create => "create",
+            Method::Update => "update",
+            Method::Delete => "delete",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How `--timestamps` fields keep their time zone, in the model and in the migrated column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TzType {
+    /// `DateTime<Utc>` in a `TIMESTAMPTZ` column
+    #[default]
+    Utc,
+    /// The server's wall-clock time as a `NaiveDateTime` in a `TIMESTAMP` column
+    Local,
+    /// `DateTime<FixedOffset>` in a `TIMESTAMPTZ` column, which keeps the instant but not the offset
+    Offset,
+}
+
+impl TzType {
+    /// Type of the timestamp fields
+    pub fn rust_type(self) -> &'static str {
+        match self {
+            TzType::Utc => "DateTime<Utc>",
+            TzType::Local => "NaiveDateTime",
+            TzType::Offset => "DateTime<FixedOffset>",
+        }
+    }
+
+    /// Expression of the current time in the fields' type
+    fn now(self) -> &'static str {
+        match self {
+            TzType::Utc => "Utc::now()",
+            TzType::Local => "Local::now().naive_local()",
+            TzType::Offset => "Local::now().fixed_offset()",
+        }
+    }
+
+    /// The chrono items used by the model
+    fn imports(self) -> &'static str {
+        match self {
+            TzType::Utc => "use chrono::{DateTime, Utc};",
+            TzType::Local => "use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};",
+            TzType::Offset => "use chrono::{DateTime, FixedOffset, Local, Utc};",
+        }
+    }
+
+    /// `field` of the model as the `DateTime<Utc>` returned by `Timestamped`
+    fn to_utc(self, field: &str) -> String {
+        match self {
+            TzType::Utc => format!("self.{}", field),
+            TzType::Local => format!("local_to_utc(self.{})", field),
+            TzType::Offset => format!("self.{}.to_utc()", field),
+        }
+    }
+}
+
+impl std::fmt::Display for TzType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TzType::Utc => "utc",
+            TzType::Local => "local",
+            TzType::Offset => "offset",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Comparison traits a model derives on top of `Debug`, `Clone`, `Default` and the serde ones,
+/// each supported by fewer field types than the previous one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
+pub enum Derive {
+    #[value(name = "PartialEq")]
+    PartialEq,
+    #[value(name = "Eq")]
+    Eq,
+    #[value(name = "Hash")]
+    Hash,
+}
+
+impl Derive {
+    pub const ALL: [Derive; 3] = [Derive::PartialEq, Derive::Eq, Derive::Hash];
+}
+
+impl std::fmt::Display for Derive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Derive::PartialEq => "PartialEq",
+            Derive::Eq => "Eq",
+            Derive::Hash => "Hash",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Everything needed to generate a resource, whether it comes from the CLI or a spec file
+pub struct Resource {
+    /// Name of the resource's modules and files, including the file prefix
+    pub name: String,
+    /// Segment of the resource's routes, the name without the file prefix
+    pub path: String,
+    /// Prefix of the module names, shared with the parent and related resources
+    pub file_prefix: String,
+    pub type_name: String,
+    pub fields: Vec<Field>,
+    pub status_machine: Option<StatusMachine>,
+    /// Whether the model records when it was created and last updated
+    pub timestamps: bool,
+    /// Type of the timestamp fields and of their columns
+    pub tz_type: TzType,
+    /// Module of the shared fields flattened into the model
+    pub base_model: Option<String>,
+    /// Whether the model derives `Validate`, checked by the create and update handlers
+    pub validate: bool,
+    /// Comparison traits the model derives, checked against its fields
+    pub derives: Vec<Derive>,
+    pub methods: Vec<Method>,
+    pub protected: bool,
+    pub problem_json: bool,
+    pub content_guards: bool,
+    pub route_check: bool,
+    pub etag: bool,
+    pub paginate: bool,
+    pub service: bool,
+    pub store: bool,
+    pub prelude: bool,
+    pub readonly: bool,
+    pub admin: bool,
+    /// Whether `examples/{name}_usage.rs` is generated
+    pub example: bool,
+    pub parent: Option<String>,
+    pub relations: Vec<Relation>,
+    /// Deprecation given on the command line, otherwise the one recorded in `.cargo-mold` applies
+    pub deprecation: Option<Deprecation>,
+    /// Cargo feature the resource is compiled with, always compiled when `None`
+    pub feature: Option<String>,
+    pub handler_naming: HandlerNaming,
+    pub json_case: JsonCase,
+    pub id_type: String,
+    /// Fields of a composite primary key identifying the items in place of `id_type`, empty for an id
+    pub primary_key: Vec<Field>,
+    pub layers: Vec<Layer>,
+    pub force: bool,
+}
+
+impl Resource {
+    pub fn has(&self, method: Method) -> bool {
+        self.methods.contains(&method)
+    }
+
+    pub fn generates(&self, layer: Layer) -> bool {
+        self.layers.contains(&layer)
+    }
+
+    /// Whether the handlers look items up in a service or store instead of answering with placeholders
+    fn has_storage(&self) -> bool {
+        self.service || self.store
+    }
+
+    /// Name of the handler answering `method`, following the resource's naming convention
+    pub fn handler(&self, method: Method) -> String {
+        let verb = match (self.handler_naming, method) {
+            (HandlerNaming::Rails, method) => {
+                return match method {
+                    Method::List => "index",
+                    Method::Get => "show",
+                    Method::Create => "store",
+                    Method::Update => "update",
+                    Method::Delete => "destroy",
+                }.to_string();
+            }
+            (HandlerNaming::Rest, Method::List) => "get",
+            (HandlerNaming::Rest, Method::Get) => "find",
+            (_, Method::List) => "list",
+            (_, Method::Get) => "get",
+            (_, Method::Create) => "create",
+            (_, Method::Update) => "update",
+            (_, Method::Delete) => "delete",
+        };
+        match self.handler_naming {
+            HandlerNaming::NounVerb => format!("{}_{}", self.name, verb),
+            _ => format!("{}_{}", verb, self.name),
+        }
+    }
+
+    /// Module name of another resource generated with the same file prefix, e.g. the parent
+    pub fn module_of(&self, resource: &str) -> String {
+        format!("{}{}", self.file_prefix, resource)
+    }
+
+    /// Segments of the item routes after the collection: `/{id}`, or one per field of a composite key
+    fn item_pattern(&self) -> String {
+        if self.primary_key.is_empty() {
+            return "/{id}".to_string();
+        }
+        self.primary_key.iter().map(|field| format!("/{{{}}}", field.name)).collect()
+    }
+
+    /// Segments of an item's uri in the generated tests, parsing as the id or key types
+    fn sample_item_segments(&self) -> String {
+        if self.primary_key.is_empty() {
+            return "/1".to_string();
+        }
+        self.primary_key.iter()
+            .map(|field| match field.ty.as_str() {
+                "bool" => "/true",
+                "char" => "/x",
+                _ => "/1",
+            })
+            .collect()
+    }
+
+    /// Whether a handler extracts the item id from the path
+    fn has_item_params(&self) -> bool {
+        self.has(Method::Update) || self.has(Method::Delete) || self.has(Method::Get)
+    }
+
+    /// Whether the list handler answers conditionally, item handlers always answer in full
+    fn conditional_get(&self) -> bool {
+        self.etag && self.has(Method::List)
+    }
+
+    /// Whether the list handler answers with a `Page<T>`
+    fn paginates(&self) -> bool {
+        self.paginate && self.has(Method::List)
+    }
+
+    /// Extractor of the JSON bodies of the create and update handlers
+    fn json_extractor(&self) -> &'static str {
+        if self.validate { "ValidatedJson" } else { "web::Json" }
+    }
+
+    /// Import of `ValidatedJson` when the handlers take it
+    fn validated_json_import(&self) -> &'static str {
+        if self.validate && (self.has(Method::Create) || self.has(Method::Update)) {
+            "use crate::extractors::validated_json::ValidatedJson;\n"
+        } else {
+            ""
+        }
+    }
+
+    /// Paths of the files generated for the selected layers
+    pub fn files(&self, ctx: &ProjectContext) -> Vec<String> {
+        let mut files: Vec<String> = self.layers.iter().map(|layer| layer.file_path(ctx, &self.name)).collect();
+        if self.admin {
+            files.push(admin_file_path(ctx, &self.name));
+        }
+        if self.example {
+            files.push(example_file_path(ctx, &self.name));
+        }
+        files
+    }
+}
+
+impl TryFrom<ResourceArgs> for Resource {
+    type Error = anyhow::Error;
+
+    fn try_from(args: ResourceArgs) -> Result<Self> {
+        if !is_type(&args.id_type) {
+            anyhow::bail!("Invalid id type '{}'", args.id_type);
+        }
+        if args.content_guards && !args.methods.contains(&Method::Create) {
+            anyhow::bail!("--content-guards needs the create method");
+        }
+        let mut methods = args.methods;
+        if args.readonly {
+            methods.retain(|method| method.is_read());
+            if methods.is_empty() {
+                anyhow::bail!("--readonly only generates the list and get methods");
+            }
+        }
+        if let Some(suffix) = &args.naming.type_suffix
+            && !suffix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            anyhow::bail!("Invalid type suffix '{}': use letters and digits only", suffix);
+        }
+        // Prefixes are separated from the name by an underscore, added when missing
+        let file_prefix = match args.file_prefix.as_deref() {
+            None | Some("") => String::new(),
+            Some(prefix) if prefix.trim_end_matches('_').chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                && prefix.starts_with(|c: char| c.is_ascii_lowercase()) => {
+                format!("{}_", prefix.trim_end_matches('_'))
+            }
+            Some(prefix) => anyhow::bail!("Invalid file prefix '{}': use lowercase letters, digits and '_', starting with a letter", prefix),
+        };
+        if let Some(feature) = &args.feature
+            && !(feature.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && feature.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')) {
+            anyhow::bail!("Invalid feature name '{}': use letters, digits, '-' and '_', starting with a letter or digit", feature);
+        }
+        let type_name = args.naming.type_name(&args.name);
+        let mut fields = parse_fields(&args.fields, &type_name)?;
+        let relations = parse_relations(&args.relations)?;
+        add_foreign_keys(&mut fields, &relations);
+        let status_machine = args.status_machine.as_deref().map(parse_status_machine).transpose()?;
+        if status_machine.is_some() && fields.iter().any(|field| field.name == "status") {
+            anyhow::bail!("--status-machine adds a `status` field, remove it from --fields");
+        }
+        if args.timestamps && fields.iter().any(|field| TIMESTAMP_FIELDS.contains(&field.name.as_str())) {
+            anyhow::bail!("--timestamps adds the `created_at` and `updated_at` fields, remove them from --fields");
+        }
+        if let Some(base) = &args.base_model {
+            check_base_model(base, &fields)?;
+        }
+        let derives = resolve_derives(args.derives.as_deref(), &fields, args.base_model.is_some())?;
+        let primary_key = if args.primary_key.is_empty() { Vec::new() } else { parse_primary_key(&args.primary_key, &fields)? };
+        let deprecation = args.deprecate.as_deref().map(|since| parse_deprecation(since, args.sunset.as_deref())).transpose()?;
+        Ok(Self {
+            fields,
+            status_machine,
+            timestamps: args.timestamps,
+            tz_type: args.tz_type,
+            base_model: args.base_model,
+            validate: args.validate,
+            derives,
+            type_name,
+            name: format!("{}{}", file_prefix, args.name),
+            path: args.name,
+            file_prefix,
+            methods,
+            protected: args.protected,
+            problem_json: args.problem_json,
+            content_guards: args.content_guards,
+            route_check: args.with_route_check,
+            etag: args.with_etag,
+            paginate: args.paginate,
+            service: args.with_service,
+            store: args.with_store,
+            prelude: args.with_prelude,
+            readonly: args.readonly,
+            admin: args.admin,
+            example: args.with_example,
+            parent: args.parent,
+            relations,
+            deprecation,
+            feature: args.feature,
+            handler_naming: args.handler_naming.unwrap_or_default(),
+            json_case: args.json_case.unwrap_or_default(),
+            id_type: args.id_type,
+            primary_key,
+            layers: args.only,
+            force: args.force,
+        })
+    }
+}
+
+/// Rejects base model names that aren't module names, and fields the base model already provides
+pub fn check_base_model(base: &str, fields: &[Field]) -> Result<()> {
+    if !is_identifier(base) || base.chars().any(|c| c.is_ascii_uppercase()) {
+        anyhow::bail!("Invalid base model '{}': use lowercase letters, digits and '_'", base);
+    }
+    if let Some(field) = fields.iter().find(|field| BASE_FIELDS.contains(&field.name.as_str())) {
+        anyhow::bail!("The base model provides the `{}` field, remove it from the fields", field.name);
+    }
+    Ok(())
+}
+
+/// Type of the shared fields of a base model, e.g. `BaseFields` for `base`
+pub fn base_type_name(base: &str) -> String {
+    format!("{}Fields", to_pascal_case(base))
+}
+
+/// Derives of a model: the `requested` ones, rejected when a field type is known not to support them,
+/// or else every derive all the fields support. Nothing is derived by default when a field has a type
+/// cargo-mold doesn't know, and base models are only known to derive `PartialEq`
+pub fn resolve_derives(requested: Option<&[Derive]>, fields: &[Field], base_model: bool) -> Result<Vec<Derive>> {
+    // Inline enums are generated with every derive
+    let known: Vec<(&Field, Option<Derive>)> = fields.iter()
+        .filter(|field| !matches!(field.kind, FieldKind::Enum(_)))
+        .map(|field| (field, strongest_derive(&field.ty)))
+        .collect();
+    let base = if base_model { Some(Derive::PartialEq) } else { None };
+
+    let Some(requested) = requested else {
+        if known.iter().any(|(_, strongest)| strongest.is_none()) {
+            return Ok(Vec::new());
+        }
+        let strongest = known.iter().filter_map(|(_, strongest)| *strongest).chain(base).min().unwrap_or(Derive::Hash);
+        return Ok(Derive::ALL.into_iter().filter(|derive| *derive <= strongest).collect());
+    };
+
+    let mut derives = requested.to_vec();
+    // `Eq` is a subtrait of `PartialEq`
+    if derives.contains(&Derive::Eq) {
+        derives.push(Derive::PartialEq);
+    }
+    derives.sort();
+    derives.dedup();
+    for derive in &derives {
+        if let Some((field, Some(strongest))) = known.iter().find(|(_, strongest)| strongest.is_some_and(|strongest| strongest < *derive)) {
+            let supported: Vec<String> = Derive::ALL.iter()
+                .filter(|supported| *supported <= strongest)
+                .map(|supported| format!("`{}`", supported))
+                .collect();
+            anyhow::bail!(
+                "Can't derive `{}`: the `{}` field is a `{}`, which only supports {}",
+                derive, field.name, field.ty, supported.join(" and ")
+            );
+        }
+        if base.is_some_and(|base| base < *derive) {
+            anyhow::bail!("Can't derive `{}`: the flattened base model only derives `PartialEq`", derive);
+        }
+    }
+    Ok(derives)
+}
+
+/// The strongest `Derive` a field type supports, `None` for the types cargo-mold doesn't know
+fn strongest_derive(ty: &str) -> Option<Derive> {
+    let item = ty.strip_prefix("Vec<").or_else(|| ty.strip_prefix("Option<")).and_then(|rest| rest.strip_suffix('>'));
+    if let Some(item) = item {
+        return strongest_derive(item);
+    }
+    let entry = ty.strip_prefix("HashMap<").and_then(|rest| rest.strip_suffix('>'))
+        .and_then(|entry| entry.split_once(", "));
+    if let Some((key, value)) = entry {
+        // Maps compare but don't hash
+        return Some(strongest_derive(key)?.min(strongest_derive(value)?).min(Derive::Eq));
+    }
+    match ty {
+        "f32" | "f64" => Some(Derive::PartialEq),
+        "String" | "bool" | "char" | "Uuid" | "uuid::Uuid" | "NaiveDate" | "NaiveDateTime" | "NaiveTime" => Some(Derive::Hash),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => Some(Derive::Hash),
+        ty if ty.starts_with("DateTime<") => Some(Derive::Hash),
+        _ => None,
+    }
+}
+
+/// Deprecation of the resource's routes: the one given on the command line, else the one recorded in `.cargo-mold`
+fn deprecation<'a>(ctx: &'a ProjectContext, resource: &'a Resource) -> Option<&'a Deprecation> {
+    resource.deprecation.as_ref().or_else(|| ctx.config().deprecated.get(&resource.name))
+}
+
+/// Checks the dates of `--deprecate` and `--sunset`, turning `today` into today's date
+fn parse_deprecation(since: &str, sunset: Option<&str>) -> Result<Deprecation> {
+    let parse = |flag: &str, date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("Invalid {} date '{}', expected YYYY-MM-DD", flag, date))
+    };
+    let since = if since == "today" { chrono::Utc::now().date_naive() } else { parse("--deprecate", since)? };
+    let sunset = sunset.map(|date| parse("--sunset", date)).transpose()?;
+    if sunset.is_some_and(|sunset| sunset <= since) {
+        anyhow::bail!("--sunset must come after the deprecation date ({})", since);
+    }
+    Ok(Deprecation {
+        since: since.to_string(),
+        sunset: sunset.map(|sunset| sunset.to_string()),
+    })
+}
+
+/// Wiring that couldn't be done because an anchor was missing, with what to add by hand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WiringWarning {
+    /// File the wiring belongs in
+    pub file: String,
+    /// The anchor that wasn't found
+    pub missing: String,
+    /// What to add by hand
+    pub fix: String,
+}
+
+impl std::fmt::Display for WiringWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} not found\n     add {}", self.file, self.missing, self.fix)
+    }
+}
+
+/// Reports the wiring left to do by hand, if any
+pub fn print_wiring_warnings(warnings: &[WiringWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    report("⚠️ Some wiring could not be done automatically:");
+    for warning in warnings {
+        report(format!("   - {}", warning));
+    }
+}
+
+pub async fn execute(args: ResourceArgs) -> anyhow::Result<()> {
+    report(format!("📁 Generating resource: {}", args.name));
+
+    let mut ctx = ProjectContext::load().await?;
+
+    let (fix, check) = (args.fix, args.check);
+    let handler_naming = args.handler_naming.unwrap_or(ctx.config().handler_naming);
+    let json_case = args.json_case.unwrap_or(ctx.config().json_case);
+    let mut resource = Resource::try_from(args)?;
+    resource.handler_naming = handler_naming;
+    resource.json_case = json_case;
+
+    if let Some(parent) = &resource.parent
+        && !Layer::Routes.exists(&ctx, &resource.module_of(parent)) {
+        anyhow::bail!(
+            "❌ Parent resource '{}' not found (expected {}).\n\
+             Generate it first with: `cargo mold g resource {}`",
+            parent, Layer::Routes.file_path(&ctx, &resource.module_of(parent)), parent
+        );
+    }
+    // Relations may point back at the resource itself, e.g. a category belonging to another one
+    for relation in &resource.relations {
+        let module = resource.module_of(&relation.resource);
+        if relation.resource != resource.path && !Layer::Model.exists(&ctx, &module) {
+            anyhow::bail!(
+                "❌ Related resource '{}' not found (expected {}).\n\
+                 Generate it first with: `cargo mold g resource {}`",
+                relation.resource, Layer::Model.file_path(&ctx, &module), relation.resource
+            );
+        }
+    }
+    let feature_added = match &resource.feature {
+        Some(feature) => {
+            let mut manifest = Manifest::load(&ctx).await?;
+            let added = manifest.add_feature(feature)?;
+            manifest.save().await?;
+            added
+        }
+        None => false,
+    };
+    let mut warnings = generate_resource(&ctx, &resource).await?;
+    if resource.generates(Layer::Routes) || resource.admin {
+        warnings.extend(register_routes(&ctx, &[&resource]).await?);
+    }
+    if let Some(deprecation) = &resource.deprecation {
+        ctx.config_mut().deprecated.insert(resource.name.clone(), deprecation.clone());
+        ctx.save_config().await?;
+    }
+    if fix {
+        fix_generated(&ctx, &resource.files(&ctx)).await?;
+    }
+
+    report(format!("✅ Resource '{}' created successfully!", resource.name));
+    report("📝 Generated files:");
+    for file in resource.files(&ctx) {
+        report(format!("   - {}", file));
+    }
+    if let Some(feature) = &resource.feature {
+        if feature_added {
+            report(format!("📦 Added the `{}` feature to Cargo.toml", feature));
+        }
+        report(format!("🚩 '{}' is only compiled with `--features {}`", resource.name, feature));
+        if ctx.config().openapi {
+            report("📚 Its handlers were left out of the OpenAPI document, whose `paths(...)` can't be gated");
+        }
+    }
+    if resource.example {
+        report(format!("▶️ Try the routes with `cargo run --example {}`", example_name(&resource.name)));
+    }
+    print_wiring_warnings(&warnings);
+    if check {
+        check_project(&ctx).await?;
+    }
+
+    Ok(())
+}
+
+/// Generates the model, handlers and routes of a resource and registers its modules,
+/// leaving the wiring into `routes.rs` to `register_routes`. Returns the module declarations
+/// that couldn't be added
+pub async fn generate_resource(ctx: &ProjectContext, resource: &Resource) -> Result<Vec<WiringWarning>> {
+    if !resource.force {
+        let mut existing: Vec<String> = resource.layers.iter()
+            .filter(|layer| layer.exists(ctx, &resource.name))
+            .map(|layer| layer.file_path(ctx, &resource.name))
+            .collect();
+        let admin_path = admin_file_path(ctx, &resource.name);
+        if resource.admin && Path::new(&admin_path).exists() {
+            existing.push(admin_path);
+        }
+        if !existing.is_empty() {
+            anyhow::bail!(
+                "❌ Resource '{}' already exists:\n   - {}\n\
+                 Use --force to overwrite, or --only to regenerate some layers",
+                resource.name,
+                existing.join("\n   - ")
+            );
+        }
+    }
+
+    if resource.problem_json {
+        generate_problem_module(ctx).await?;
+    }
+    if resource.validate && (resource.generates(Layer::Model) || resource.generates(Layer::Handler)) {
+        generate_validated_json_module(ctx).await?;
+    }
+    if resource.etag && resource.generates(Layer::Handler) {
+        generate_etag_module(ctx).await?;
+    }
+    if resource.paginates() && resource.generates(Layer::Handler) {
+        generate_pagination_module(ctx).await?;
+    }
+    if !resource.service && resource.generates(Layer::Handler) {
+        generate_responses_module(ctx).await?;
+    }
+    if resource.generates(Layer::Routes) && deprecation(ctx, resource).is_some() {
+        generate_deprecation_module(ctx).await?;
+    }
+    if resource.prelude && (resource.generates(Layer::Model) || resource.generates(Layer::Handler)) {
+        generate_prelude_module(ctx).await?;
+    }
+    if resource.service && resource.generates(Layer::Handler) {
+        generate_service_result_module(ctx).await?;
+        generate_service_layer(ctx, resource).await?;
+    }
+    if resource.store && resource.generates(Layer::Handler) {
+        generate_store_layer(ctx, resource).await?;
+    }
+    if resource.generates(Layer::Model) {
+        if identifiable(resource).is_some() || resource.timestamps {
+            generate_traits_module(ctx).await?;
+        }
+        if let Some(base) = &resource.base_model {
+            generate_base_module(ctx, base).await?;
+        }
+        generate_model(ctx, resource).await?;
+    }
+    if resource.generates(Layer::Handler) {
+        generate_handler(ctx, resource).await?;
+    }
+    if resource.generates(Layer::Routes) {
+        generate_routes(ctx, resource).await?;
+    }
+    if resource.admin {
+        generate_admin(ctx, resource).await?;
+    }
+    if resource.example {
+        generate_example(ctx, resource).await?;
+    }
+    let mut warnings = update_modules(ctx, resource).await?;
+    // `paths(...)` of the OpenAPI document can't be gated, so feature-gated handlers stay out of it
+    if ctx.config().openapi && resource.generates(Layer::Handler) && resource.feature.is_none() {
+        warnings.extend(register_openapi_paths(ctx, resource).await?);
+    }
+    Ok(warnings)
+}
+
+/// `#[schema(...)]` of a field typed with a `chrono::` path in `--with-openapi` projects, documenting it
+/// as the RFC 3339 string it serializes to since utoipa only knows chrono types with its `chrono` feature
+fn chrono_schema(ctx: &ProjectContext, ty: &str) -> &'static str {
+    match (ctx.config().openapi, ty.strip_prefix("Option<").unwrap_or(ty).starts_with("chrono::"), ty.starts_with("Option<")) {
+        (true, true, false) => "    #[schema(value_type = String)]\n",
+        (true, true, true) => "    #[schema(value_type = Option<String>)]\n",
+        _ => "",
+    }
+}
+
+async fn generate_model(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let pascal_case = &resource.type_name;
+    // Read-only models keep their fields private so they can't be changed once built
+    let visibility = if resource.readonly { "" } else { "pub " };
+    let mut fields: String = resource.fields.iter()
+        .map(|field| {
+            let relation = resource.relations.iter()
+                .find(|relation| relation.kind == RelationKind::BelongsTo && relation.foreign_key() == field.name);
+            let doc = match (&field.kind, relation) {
+                (FieldKind::Ref { role, target }, _) => format!("    /// Id of the `{}` referenced as this item's {}\n", target, role),
+                (_, Some(relation)) => format!("    /// Id of the `{}` this item belongs to\n", relation.resource),
+                _ => String::new(),
+            };
+            // Request bodies may leave out the fields with a default
+            let default = match &field.default {
+                Some(_) => format!("    #[serde(default = \"default_{}\")]\n", field.name),
+                None => String::new(),
+            };
+            format!("{}{}{}    {}{}: {},\n", doc, default, chrono_schema(ctx, &field.ty), visibility, field.name, field.ty)
+        })
+        .collect();
+    if let Some(base) = &resource.base_model {
+        fields.insert_str(0, &format!("    /// Fields shared through `models::{}`, flattened into the JSON\n    #[serde(flatten)]\n    pub base: {},\n", base, base_type_name(base)));
+    }
+    if resource.status_machine.is_some() {
+        fields.push_str(&format!("    pub status: {}Status,\n", pascal_case));
+    }
+    // Documented as RFC 3339 strings, utoipa only knows chrono types with its `chrono` feature
+    let timestamp_schema = if ctx.config().openapi { "    #[schema(value_type = String, format = DateTime)]\n" } else { "" };
+    if resource.timestamps {
+        for (field, doc) in TIMESTAMP_FIELDS.iter().zip(["When the item was created", "When the item was last changed"]) {
+            // Set by the server, so request bodies may leave them out
+            fields.push_str(&format!(
+                "    /// {}\n    #[serde(default)]\n{}    {}{}: {},\n",
+                doc, timestamp_schema, visibility, field, resource.tz_type.rust_type()
+            ));
+        }
+    }
+
+    // `new` takes every declared field, `Default` stays available for the no-args path
+    let mut constructor = if resource.fields.is_empty() && !resource.timestamps && resource.base_model.is_none() {
+        "    pub fn new() -> Self {\n        Self::default()\n    }\n".to_string()
+    } else {
+        let params: Vec<String> = resource.fields.iter()
+            .map(|field| format!("{}: {}", field.name, field.ty))
+            .collect();
+        let mut names: Vec<String> = resource.fields.iter().map(|field| field.name.clone()).collect();
+        if let Some(base) = &resource.base_model {
+            names.insert(0, format!("base: {}::new()", base_type_name(base)));
+        }
+        // Items start in the initial state
+        if resource.status_machine.is_some() {
+            names.push(format!("status: {}Status::default()", pascal_case));
+        }
+        let mut now = String::new();
+        if resource.timestamps {
+            now = format!("        let now = {};\n", resource.tz_type.now());
+            names.extend(TIMESTAMP_FIELDS.iter().map(|field| format!("{}: now", field)));
+        }
+        let allow = if params.len() > 7 { "    #[allow(clippy::too_many_arguments)]\n" } else { "" };
+        format!(
+            "{}    pub fn new({}) -> Self {{\n{}        Self {{ {} }}\n    }}\n",
+            allow,
+            params.join(", "),
+            now,
+            names.join(", ")
+        )
+    };
+    if resource.timestamps && !resource.readonly {
+        constructor.push_str(&format!(
+            "\n    /// Records a change to the item, to be called whenever it is updated\n    pub fn touch(&mut self) {{\n        self.updated_at = {};\n    }}\n",
+            resource.tz_type.now()
+        ));
+    }
+    if !resource.primary_key.is_empty() {
+        let names: Vec<String> = resource.primary_key.iter().map(|field| format!("{:?}", field.name)).collect();
+        let types: Vec<&str> = resource.primary_key.iter().map(|field| field.ty.as_str()).collect();
+        let values: Vec<String> = resource.primary_key.iter()
+            .map(|field| if field.ty == "String" { format!("self.{}.clone()", field.name) } else { format!("self.{}", field.name) })
+            .collect();
+        constructor.push_str(&format!(
+            "\n    /// Columns of the composite primary key, in the order of the item routes' segments\n    pub const PRIMARY_KEY: [&str; {}] = [{}];\n\n    /// The key identifying the item, as the item routes extract it\n    pub fn key(&self) -> ({}) {{\n        ({})\n    }}\n",
+            names.len(), names.join(", "), types.join(", "), values.join(", ")
+        ));
+    }
+
+    let mut doc = String::new();
+    if resource.readonly {
+        constructor.extend(resource.fields.iter().map(getter));
+        doc = format!(
+            "/// Read-only `{}`: an immutable value object whose fields are only exposed through getters.\n\
+             /// The API only lists and reads it, there are no create, update or delete routes\n",
+            pascal_case
+        );
+    }
+
+    let mut imports = if resource.prelude { "use crate::prelude::*;" } else { "use serde::{Deserialize, Serialize};" }.to_string();
+    if resource.fields.iter().any(|field| uses_hash_map(&field.ty)) {
+        imports.insert_str(0, "use std::collections::HashMap;\n\n");
+    }
+    let id_type = identifiable(resource);
+    let traits: Vec<&str> = [(id_type.is_some(), "Identifiable"), (resource.timestamps, "Timestamped")]
+        .into_iter()
+        .filter_map(|(implemented, name)| implemented.then_some(name))
+        .collect();
+    if resource.timestamps {
+        imports.push('\n');
+        imports.push_str(resource.tz_type.imports());
+    }
+    if resource.validate {
+        imports.push_str("\nuse validator::Validate;");
+    }
+    let mut crate_imports = Vec::new();
+    if let Some(base) = &resource.base_model {
+        crate_imports.push(format!("use crate::models::{}::{};", base, base_type_name(base)));
+    }
+    match traits.as_slice() {
+        [] => {}
+        [name] => crate_imports.push(format!("use crate::models::traits::{};", name)),
+        names => crate_imports.push(format!("use crate::models::traits::{{{}}};", names.join(", "))),
+    }
+    if !crate_imports.is_empty() {
+        imports.push_str(&format!("\n\n{}", crate_imports.join("\n")));
+    }
+    // Documented projects describe the model in the OpenAPI schemas
+    let openapi = ctx.config().openapi;
+    let mut schema: String = resource.derives.iter().map(|derive| format!(", {}", derive)).collect();
+    if openapi {
+        schema.push_str(", utoipa::ToSchema");
+    }
+    if resource.validate {
+        schema.push_str(", Validate");
+    }
+    let rename = resource.json_case.serde_attribute().map(|attribute| format!("{}\n", attribute)).unwrap_or_default();
+    let mut content = format!(
+        r#"{}
+
+{}#[derive(Debug, Serialize, Deserialize, Clone, Default{})]
+{}pub struct {} {{
+{}}}
+
+impl {} {{
+{}}}
+"#,
+        imports, doc, schema, rename, pascal_case, fields, pascal_case, constructor
+    );
+
+    for field in resource.fields.iter().filter(|field| field.default.is_some()) {
+        content.push_str(&format!(
+            "\n/// `{}` of the request bodies leaving it out\nfn default_{}() -> {} {{\n    {}\n}}\n",
+            field.name, field.name, field.ty, field.default.as_deref().unwrap_or_default()
+        ));
+    }
+    if let Some(id_type) = id_type {
+        content.push_str(&format!(
+            "\nimpl Identifiable for {} {{\n    type Id = {};\n\n    fn id(&self) -> &{} {{\n        &self.id\n    }}\n}}\n",
+            pascal_case, id_type, id_type
+        ));
+    }
+    if resource.timestamps {
+        content.push_str(&format!(
+            r#"
+impl Timestamped for {} {{
+    fn created_at(&self) -> DateTime<Utc> {{
+        {}
+    }}
+
+    fn updated_at(&self) -> DateTime<Utc> {{
+        {}
+    }}
+}}
+"#,
+            pascal_case,
+            resource.tz_type.to_utc("created_at"),
+            resource.tz_type.to_utc("updated_at")
+        ));
+        if resource.tz_type == TzType::Local {
+            content.push_str(LOCAL_TO_UTC);
+        }
+    }
+
+    for field in &resource.fields {
+        if let FieldKind::Enum(variants) = &field.kind {
+            content.push_str(&field_enum(pascal_case, field, variants, openapi));
+        }
+    }
+
+    let values: Vec<String> = resource.fields.iter().map(|field| sample_value(&field.ty)).collect();
+    let mut tests = traits_tests(resource, &values);
+    let base_keys = match &resource.base_model {
+        Some(base) => base_model_keys(ctx, base).await?,
+        None => Vec::new(),
+    };
+    tests.push_str(&json_case_test(resource, &base_keys));
+    tests.push_str(&defaults_test(resource));
+    tests.push_str(&derives_test(resource));
+    if resource.base_model.is_some() {
+        tests.push_str(&base_model_test(resource, &base_keys));
+    }
+    if !resource.fields.is_empty() {
+        content.push_str(&query_builder(resource));
+
+        let assertions: String = resource.fields.iter().zip(&values)
+            .map(|(field, value)| match field.ty.as_str() {
+                "bool" => format!("        assert!(model.{});\n", field.name),
+                "String" => format!("        assert_eq!(model.{}, \"sample\");\n", field.name),
+                _ => format!("        assert_eq!(model.{}, {});\n", field.name, value),
+            })
+            .collect();
+        tests.push_str(&format!(
+            r#"
+    #[test]
+    fn new_sets_every_field() {{
+        let model = {}::new({});
+{}    }}
+{}"#,
+            pascal_case, values.join(", "), assertions, query_builder_test(resource)
+        ));
+        if resource.readonly {
+            tests.push_str(&readonly_test(resource));
+        }
+    }
+    if let Some(machine) = &resource.status_machine {
+        content.push_str(&status_machine(pascal_case, machine, openapi));
+        tests.push_str(&status_machine_test(pascal_case, machine));
+    }
+    if !tests.is_empty() {
+        content.push_str(&format!("\n#[cfg(test)]\nmod tests {{\n    use super::*;\n{}}}\n", tests));
+    }
+
+    Layer::Model.write(ctx, &resource.name, &content).await
+}
+
+/// Test of the strongest comparison derive of the model: equal items make a single `HashSet` entry,
+/// or compare equal with `PartialEq` alone
+fn derives_test(resource: &Resource) -> String {
+    let pascal_case = &resource.type_name;
+    if resource.derives.contains(&Derive::Hash) && resource.derives.contains(&Derive::Eq) {
+        format!(
+            r#"
+    #[test]
+    fn equal_items_hash_alike() {{
+        let items: std::collections::HashSet<{pascal_case}> = [{pascal_case}::default(), {pascal_case}::default()].into_iter().collect();
+        assert_eq!(items.len(), 1);
+    }}
+"#
+        )
+    } else if resource.derives.contains(&Derive::PartialEq) {
+        format!(
+            r#"
+    #[test]
+    fn clones_compare_equal() {{
+        let item = {pascal_case}::default();
+        assert_eq!(item.clone(), item);
+    }}
+"#
+        )
+    } else {
+        String::new()
+    }
+}
+
+/// Test checking the JSON field names of the model follow its `JsonCase`, both ways. The `base_keys`
+/// flattened from its base model follow the base's own case
+fn json_case_test(resource: &Resource, base_keys: &[String]) -> String {
+    let mut names: Vec<&str> = resource.fields.iter().map(|field| field.name.as_str()).collect();
+    if resource.status_machine.is_some() {
+        names.push("status");
+    }
+    if resource.timestamps {
+        names.extend(TIMESTAMP_FIELDS);
+    }
+    if names.is_empty() && base_keys.is_empty() {
+        return String::new();
+    }
+    // serde_json objects keep their keys sorted
+    let mut keys: Vec<String> = names.iter()
+        .map(|name| resource.json_case.field_name(name))
+        .chain(base_keys.iter().cloned())
+        .map(|key| format!("{:?}", key))
+        .collect();
+    keys.sort();
+    let case = match resource.json_case {
+        JsonCase::Snake => "snake_case",
+        JsonCase::Camel => "camel_case",
+    };
+
+    format!(
+        r#"
+    #[test]
+    fn uses_{case}_json_field_names() {{
+        let json = serde_json::to_value({pascal_case}::default()).unwrap();
+        let keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        assert_eq!(keys, [{keys}]);
+        // Request bodies are read with the same names
+        assert!(serde_json::from_value::<{pascal_case}>(json).is_ok());
+    }}
+"#,
+        pascal_case = resource.type_name,
+        keys = keys.join(", ")
+    )
+}
+
+/// JSON keys of the fields of a generated base model
+async fn base_model_keys(ctx: &ProjectContext, base: &str) -> Result<Vec<String>> {
+    let source = ctx.read_module("models", base).await?;
+    let json_case = parse_json_case(&source);
+    Ok(parse_model(&source)
+        .map(|(_, fields)| fields.iter().map(|field| json_case.field_name(&field.name)).collect())
+        .unwrap_or_default())
+}
+
+/// Test checking the fields of the base model are serialized next to the model's own, not nested
+fn base_model_test(resource: &Resource, base_keys: &[String]) -> String {
+    let assertions: String = base_keys.iter()
+        .map(|key| format!("        assert!(json.get({:?}).is_some());\n", key))
+        .collect();
+    format!(
+        r#"
+    #[test]
+    fn serializes_the_base_fields_flatly() {{
+        let model = {pascal_case}::default();
+        let json = serde_json::to_value(&model).unwrap();
+        assert!(json.get("base").is_none());
+{assertions}        let read: {pascal_case} = serde_json::from_value(json).unwrap();
+        assert_eq!(read.base, model.base);
+    }}
+"#,
+        pascal_case = resource.type_name
+    )
+}
+
+/// Test deserializing a body without the fields that have a default, as the create handler would
+fn defaults_test(resource: &Resource) -> String {
+    let defaulted: Vec<&Field> = resource.fields.iter().filter(|field| field.default.is_some()).collect();
+    if defaulted.is_empty() {
+        return String::new();
+    }
+    let removals: String = defaulted.iter()
+        .map(|field| format!("        object.remove({:?});\n", resource.json_case.field_name(&field.name)))
+        .collect();
+    let assertions: String = defaulted.iter()
+        .map(|field| {
+            let default = field.default.as_deref().unwrap_or_default();
+            match default {
+                "true" => format!("        assert!(model.{});\n", field.name),
+                "false" => format!("        assert!(!model.{});\n", field.name),
+                // Compared with the literal, `String == &str`
+                _ => format!("        assert_eq!(model.{}, {});\n", field.name, default.strip_suffix(".to_string()").unwrap_or(default)),
+            }
+        })
+        .collect();
+
+    format!(
+        r#"
+    #[test]
+    fn omitted_fields_take_their_defaults() {{
+        let mut body = serde_json::to_value({pascal_case}::default()).unwrap();
+        let object = body.as_object_mut().unwrap();
+{removals}        let model: {pascal_case} = serde_json::from_value(body).unwrap();
+{assertions}    }}
+"#,
+        pascal_case = resource.type_name
+    )
+}
+
+/// Type of the model's `id` field, when it has one and implements `Identifiable` through it
+fn identifiable(resource: &Resource) -> Option<&str> {
+    resource.fields.iter().find(|field| field.name == "id").map(|field| field.ty.as_str())
+}
+
+/// Tests going through `Identifiable` and `Timestamped` like generic code would, given the
+/// sample `values` of the model's fields
+fn traits_tests(resource: &Resource, values: &[String]) -> String {
+    let pascal_case = &resource.type_name;
+    let mut tests = String::new();
+    if let Some(position) = resource.fields.iter().position(|field| field.name == "id") {
+        tests.push_str(&format!(
+            r#"
+    #[test]
+    fn exposes_its_id_through_identifiable() {{
+        fn id_of<T: Identifiable>(item: &T) -> &T::Id {{
+            item.id()
+        }}
+
+        let model = {}::new({});
+        assert_eq!(id_of(&model), &{});
+    }}
+"#,
+            pascal_case, values.join(", "), values[position]
+        ));
+    }
+    if resource.timestamps {
+        let touch = if resource.readonly {
+            String::new()
+        } else {
+            "\n        let mut model = model;\n        model.touch();\n        assert!(model.updated_at() >= created_at);\n        assert_eq!(model.created_at(), created_at);\n".to_string()
+        };
+        tests.push_str(&format!(
+            r#"
+    #[test]
+    fn new_stamps_the_creation_through_timestamped() {{
+        fn stamps<T: Timestamped>(item: &T) -> (DateTime<Utc>, DateTime<Utc>) {{
+            (item.created_at(), item.updated_at())
+        }}
+
+        let before = Utc::now();
+        let model = {}::new({});
+        let (created_at, updated_at) = stamps(&model);
+        assert!(before <= created_at && created_at <= Utc::now());
+        assert_eq!(created_at, updated_at);
+{}    }}
+"#,
+            pascal_case, values.join(", "), touch
+        ));
+        // Only the zone-aware types serialize with an offset
+        let offset = if resource.tz_type == TzType::Local { "is_err" } else { "is_ok" };
+        tests.push_str(&format!(
+            r#"
+    #[test]
+    fn stores_{tz}_timestamps() {{
+        let model = {pascal_case}::new({values});
+        let created_at: {ty} = model.created_at;
+        let json = serde_json::to_value(&model).unwrap();
+        assert_eq!(json["{key}"], serde_json::to_value(created_at).unwrap());
+        assert!(json["{key}"].as_str().unwrap().parse::<DateTime<chrono::FixedOffset>>().{offset}());
+    }}
+"#,
+            tz = resource.tz_type,
+            values = values.join(", "),
+            ty = resource.tz_type.rust_type(),
+            key = resource.json_case.field_name("created_at"),
+        ));
+    }
+    tests
+}
+
+/// Writes `models/traits.rs`, the `Identifiable` and `Timestamped` traits implemented by the
+/// models that have an `id` or `--timestamps`, unless it exists already
+async fn generate_traits_module(ctx: &ProjectContext) -> Result<()> {
+    if ctx.has_module("models", "traits") {
+        return Ok(());
+    }
+
+    let mut manifest = Manifest::load(ctx).await?;
+    if manifest.add_dependency("chrono", CHRONO_DEPENDENCY)? {
+        manifest.save().await?;
+        report(format!("📦 Added chrono to Cargo.toml: chrono = {}", CHRONO_DEPENDENCY));
+    }
+
+    let content = r#"// Traits shared by the generated models, so generic code can work over any of them
+use chrono::{DateTime, Utc};
+
+/// A model identified by its `id` field
+pub trait Identifiable {
+    type Id;
+
+    fn id(&self) -> &Self::Id;
+}
+
+/// A model recording when it was created and last changed, generated with `--timestamps`
+pub trait Timestamped {
+    fn created_at(&self) -> DateTime<Utc>;
+
+    fn updated_at(&self) -> DateTime<Utc>;
+}
+"#;
+    ctx.write_module("models", "traits", content).await?;
+    if ctx.config().modules_style == ModulesStyle::Files {
+        ctx.register("models", "traits").await?;
+    }
+    Ok(())
+}
+
+/// Writes `models/{base}.rs`, the `id`, `created_at` and `updated_at` shared by the models
+/// generated with `--base-model {base}`, unless it exists already
+async fn generate_base_module(ctx: &ProjectContext, base: &str) -> Result<()> {
+    if ctx.has_module("models", base) {
+        return Ok(());
+    }
+
+    let mut manifest = Manifest::load(ctx).await?;
+    if manifest.add_dependency("chrono", CHRONO_DEPENDENCY)? {
+        manifest.save().await?;
+        report(format!("📦 Added chrono to Cargo.toml: chrono = {}", CHRONO_DEPENDENCY));
+    }
+
+    let config = ctx.config();
+    let (schema, timestamp_schema) = if config.openapi {
+        (", utoipa::ToSchema", "    #[schema(value_type = String, format = DateTime)]\n")
+    } else {
+        ("", "")
+    };
+    let rename = config.json_case.serde_attribute().map(|attribute| format!("{}\n", attribute)).unwrap_or_default();
+    let content = format!(
+        r#"// Fields shared by the models generated with `--base-model {base}`, flattened into their JSON.
+// Fields added here reach every one of them
+use chrono::{{DateTime, Utc}};
+use serde::{{Deserialize, Serialize}};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq{schema})]
+{rename}pub struct {base_type} {{
+    /// Identifier of the item, set when it is stored
+    #[serde(default)]
+    pub id: String,
+    /// When the item was created
+    #[serde(default)]
+{timestamp_schema}    pub created_at: DateTime<Utc>,
+    /// When the item was last changed
+    #[serde(default)]
+{timestamp_schema}    pub updated_at: DateTime<Utc>,
+}}
+
+impl {base_type} {{
+    /// Fields of an item created now, its id still to be assigned
+    pub fn new() -> Self {{
+        let now = Utc::now();
+        Self {{ id: String::new(), created_at: now, updated_at: now }}
+    }}
+
+    /// Records a change to the item, to be called whenever it is updated
+    pub fn touch(&mut self) {{
+        self.updated_at = Utc::now();
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn new_stamps_the_creation_and_touch_the_change() {{
+        let created = {base_type}::new();
+        assert_eq!(created.created_at, created.updated_at);
+
+        let mut changed = created.clone();
+        changed.touch();
+        assert!(changed.updated_at >= created.updated_at);
+        assert_eq!(changed.created_at, created.created_at);
+    }}
+}}
+"#,
+        base_type = base_type_name(base)
+    );
+    ctx.write_module("models", base, &content).await?;
+    if config.modules_style == ModulesStyle::Files {
+        ctx.register("models", base).await?;
+    }
+    Ok(())
+}
+
+/// Inline enum of a `name:enum(A,B)` field, defaulting to its first variant
+pub fn field_enum(pascal_case: &str, field: &Field, variants: &[String], openapi: bool) -> String {
+    let schema = if openapi { ", utoipa::ToSchema" } else { "" };
+    let variants: String = variants.iter().enumerate()
+        .map(|(i, variant)| {
+            let default = if i == 0 { "    #[default]\n" } else { "" };
+            format!("{}    {},\n", default, variant)
+        })
+        .collect();
+
+    format!(
+        r#"
+/// Values of the `{name}` of a `{pascal_case}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize{schema})]
+#[serde(rename_all = "snake_case")]
+pub enum {ty} {{
+{variants}}}
+"#,
+        name = field.name,
+        ty = field.ty
+    )
+}
+
+/// Getter of a read-only model field, returning copies of primitives and borrowing everything else
+fn getter(field: &Field) -> String {
+    let name = &field.name;
+    let (ty, value) = match field.ty.as_str() {
+        "String" => ("&str".to_string(), format!("&self.{}", name)),
+        ty if is_ordered(ty) || ty == "bool" || ty == "char" => (ty.to_string(), format!("self.{}", name)),
+        ty => (format!("&{}", ty), format!("&self.{}", name)),
+    };
+    format!("\n    pub fn {}(&self) -> {} {{\n        {}\n    }}\n", name, ty, value)
+}
+
+/// Checks that private fields don't keep a read-only model from serializing
+fn readonly_test(resource: &Resource) -> String {
+    let assertions: String = resource.fields.iter()
+        .map(|field| format!("        assert!(value.get(\"{}\").is_some());\n", field.name))
+        .collect();
+    format!(
+        r#"
+    #[test]
+    fn serializes_every_private_field() {{
+        let value = serde_json::to_value({}::default()).unwrap();
+{}    }}
+"#,
+        resource.type_name, assertions
+    )
+}
+
+/// Whether predicates on a field of this type also get `_gt`/`_lt` variants
+fn is_ordered(ty: &str) -> bool {
+    matches!(
+        ty,
+        "f32" | "f64" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+    )
+}
+
+/// The comparisons generated for a field: (method suffix, variant suffix, operator)
+fn comparisons(field: &Field) -> Vec<(&'static str, &'static str, &'static str)> {
+    let mut comparisons = vec![("", "Eq", "==")];
+    if is_ordered(&field.ty) {
+        comparisons.push(("_gt", "Gt", ">"));
+        comparisons.push(("_lt", "Lt", "<"));
+    }
+    comparisons
+}
+
+/// `{Name}Query`, a chainable filter builder collecting `{Name}Predicate`s from the model fields
+fn query_builder(resource: &Resource) -> String {
+    let pascal_case = &resource.type_name;
+    let mut variants = String::new();
+    let mut methods = String::new();
+    let mut arms = String::new();
+
+    for field in &resource.fields {
+        let field_pascal = to_pascal_case(&field.name);
+        // Strings are compared against borrowed values, like `by_name("alice")`
+        let param_type = if field.ty == "String" { "&str" } else { field.ty.as_str() };
+        let stored = if field.ty == "String" { format!("{}.to_string()", field.name) } else { field.name.clone() };
+
+        for (suffix, variant, operator) in comparisons(field) {
+            variants.push_str(&format!("    {}{}({}),\n", field_pascal, variant, field.ty));
+            methods.push_str(&format!(
+                r#"
+    pub fn by_{name}{suffix}(mut self, {name}: {param_type}) -> Self {{
+        self.predicates.push({pascal_case}Predicate::{field_pascal}{variant}({stored}));
+        self
+    }}
+"#,
+                name = field.name
+            ));
+            arms.push_str(&format!(
+                "            {}Predicate::{}{}(value) => item.{} {} *value,\n",
+                pascal_case, field_pascal, variant, field.name, operator
+            ));
+        }
+    }
+
+    format!(
+        r#"
+/// A filter on `{pascal_case}` collected by `{pascal_case}Query`
+#[derive(Debug, Clone, PartialEq)]
+pub enum {pascal_case}Predicate {{
+{variants}}}
+
+/// Chainable filters on `{pascal_case}`, to be translated by whatever storage backs the handlers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct {pascal_case}Query {{
+    predicates: Vec<{pascal_case}Predicate>,
+}}
+
+impl {pascal_case}Query {{
+    pub fn new() -> Self {{
+        Self::default()
+    }}
+
+    pub fn predicates(&self) -> &[{pascal_case}Predicate] {{
+        &self.predicates
+    }}
+
+    /// Whether `item` satisfies every predicate, handy for in-memory storage
+    pub fn matches(&self, item: &{pascal_case}) -> bool {{
+        self.predicates.iter().all(|predicate| match predicate {{
+{arms}        }})
+    }}
+{methods}}}
+"#
+    )
+}
+
+/// `{Name}Status` with the allowed transitions, and `{Name}::transition` refusing the others
+fn status_machine(pascal_case: &str, machine: &StatusMachine, openapi: bool) -> String {
+    let schema = if openapi { ", utoipa::ToSchema" } else { "" };
+    let variants: String = machine.states.iter().enumerate()
+        .map(|(i, state)| {
+            let default = if i == 0 { "    #[default]\n" } else { "" };
+            format!("{}    {},\n", default, to_pascal_case(state))
+        })
+        .collect();
+    let edges: Vec<String> = machine.transitions.iter()
+        .map(|(from, to)| format!("({}Status::{}, {}Status::{})", pascal_case, to_pascal_case(from), pascal_case, to_pascal_case(to)))
+        .collect();
+
+    format!(
+        r#"
+/// States a `{pascal_case}` moves through, starting in the first one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize{schema})]
+#[serde(rename_all = "snake_case")]
+pub enum {pascal_case}Status {{
+{variants}}}
+
+/// Whether a `{pascal_case}` may move from `from` to `to`
+pub fn can_transition(from: {pascal_case}Status, to: {pascal_case}Status) -> bool {{
+    matches!(
+        (from, to),
+        {edges}
+    )
+}}
+
+/// A transition `can_transition` doesn't allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct {pascal_case}TransitionError {{
+    pub from: {pascal_case}Status,
+    pub to: {pascal_case}Status,
+}}
+
+impl std::fmt::Display for {pascal_case}TransitionError {{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{
+        write!(f, "a {pascal_case} can't move from {{:?}} to {{:?}}", self.from, self.to)
+    }}
+}}
+
+impl std::error::Error for {pascal_case}TransitionError {{}}
+
+impl {pascal_case} {{
+    /// Moves to `to`, leaving the status untouched when the transition isn't allowed
+    pub fn transition(&mut self, to: {pascal_case}Status) -> Result<(), {pascal_case}TransitionError> {{
+        if !can_transition(self.status, to) {{
+            return Err({pascal_case}TransitionError {{ from: self.status, to }});
+        }}
+        self.status = to;
+        Ok(())
+    }}
+}}
+"#,
+        edges = edges.join("\n            | ")
+    )
+}
+
+/// Test walking every allowed transition and checking that a disallowed one is refused
+fn status_machine_test(pascal_case: &str, machine: &StatusMachine) -> String {
+    let status = |state: &str| format!("{}Status::{}", pascal_case, to_pascal_case(state));
+    let legal: String = machine.transitions.iter()
+        .map(|(from, to)| format!(
+            "        model.status = {};\n        assert!(model.transition({}).is_ok());\n        assert_eq!(model.status, {});\n",
+            status(from), status(to), status(to)
+        ))
+        .collect();
+
+    // Self-transitions are never allowed, so there is always an illegal move to test
+    let (from, to) = machine.states.iter()
+        .flat_map(|from| machine.states.iter().map(move |to| (from, to)))
+        .find(|(from, to)| from != to && !machine.transitions.contains(&((*from).clone(), (*to).clone())))
+        .unwrap_or((&machine.states[0], &machine.states[0]));
+
+    format!(
+        r#"
+    #[test]
+    fn status_follows_the_allowed_transitions() {{
+        let mut model = {pascal_case}::default();
+        assert_eq!(model.status, {initial});
+{legal}
+        model.status = {from};
+        let error = model.transition({to}).unwrap_err();
+        assert_eq!(error, {pascal_case}TransitionError {{ from: {from}, to: {to} }});
+        assert_eq!(model.status, {from});
+    }}
+"#,
+        initial = status(&machine.states[0]),
+        from = status(from),
+        to = status(to)
+    )
+}
+
+/// Test checking that chaining every `by_*` method of `{Name}Query` collects its predicates in order
+fn query_builder_test(resource: &Resource) -> String {
+    let pascal_case = &resource.type_name;
+    let mut calls = String::new();
+    let mut expected = String::new();
+
+    for field in &resource.fields {
+        let field_pascal = to_pascal_case(&field.name);
+        let value = sample_value(&field.ty);
+        let argument = if field.ty == "String" { "\"sample\"".to_string() } else { value.clone() };
+        for (suffix, variant, _) in comparisons(field) {
+            calls.push_str(&format!("\n            .by_{}{}({})", field.name, suffix, argument));
+            expected.push_str(&format!("                {}Predicate::{}{}({}),\n", pascal_case, field_pascal, variant, value));
+        }
+    }
+
+    format!(
+        r#"
+    #[test]
+    fn query_collects_predicates_in_order() {{
+        let query = {pascal_case}Query::new(){calls};
+        assert_eq!(
+            query.predicates(),
+            &[
+{expected}            ]
+        );
+    }}
+"#
+    )
+}
+
+/// Whether the type names the `HashMap` of `--fields name:Map<K,V>`, imported by the model
+pub fn uses_hash_map(ty: &str) -> bool {
+    ty.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':')).any(|name| name == "HashMap")
+}
+
+/// A non-default literal of the given type for generated tests, its default value when unknown
+pub fn sample_value(ty: &str) -> String {
+    if let Some(item) = ty.strip_prefix("Vec<").and_then(|rest| rest.strip_suffix('>')) {
+        return format!("vec![{}]", sample_value(item));
+    }
+    let entry = ty.strip_prefix("HashMap<").and_then(|rest| rest.strip_suffix('>'))
+        .and_then(|entry| entry.split_once(", "));
+    if let Some((key, value)) = entry {
+        return format!("HashMap::from([({}, {})])", sample_value(key), sample_value(value));
+    }
+    match ty {
+        "String" => "\"sample\".to_string()".to_string(),
+        "bool" => "true".to_string(),
+        "char" => "'x'".to_string(),
+        "f32" | "f64" => "1.5".to_string(),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            "42".to_string()
+        }
+        _ => format!("<{}>::default()", ty),
+    }
+}
+
+/// Typed path parameters so malformed ids are rejected before reaching the handler
+fn path_params(resource: &Resource) -> String {
+    let parent_id = match &resource.parent {
+        Some(parent) => format!("    pub {}_id: String,\n", parent),
+        None => String::new(),
+    };
+    format!(
+        r#"
+/// Path parameters identifying a single `{}`
+#[derive(Debug, Deserialize)]
+pub struct {}PathParams {{
+{}    pub id: {},
+}}
+"#,
+        resource.type_name, resource.type_name, parent_id, resource.id_type
+    )
+}
+
+/// `#[tracing::instrument]` line of a handler in projects created with tracing: the span is named after
+/// the handler and records the given path parameters, never the bodies or app data
+fn instrument(tracing: bool, fields: &[String]) -> String {
+    match (tracing, fields.is_empty()) {
+        (false, _) => String::new(),
+        (true, true) => "#[tracing::instrument(skip_all)]\n".to_string(),
+        (true, false) => format!("#[tracing::instrument(skip_all, fields({}))]\n", fields.join(", ")),
+    }
+}
+
+/// Span fields of a handler taking the parent id as `parent_arg`, empty for top-level resources
+fn parent_span_fields(resource: &Resource, parent_arg: &str) -> Vec<String> {
+    resource.parent.iter().map(|parent| format!("{}_id = %{}", parent, parent_arg)).collect()
+}
+
+/// Span fields of a handler extracting `{Name}PathParams` as `path`
+fn item_span_fields(resource: &Resource) -> Vec<String> {
+    let mut fields: Vec<String> = resource.parent.iter()
+        .map(|parent| format!("{0}_id = %path.{0}_id", parent))
+        .collect();
+    // Ids without a known `Display` impl are recorded with `Debug`
+    let sigil = |ty: &str| if ty == "String" || is_ordered(ty) { "%" } else { "?" };
+    if resource.primary_key.is_empty() {
+        fields.push(format!("id = {}path.id", sigil(&resource.id_type)));
+    }
+    for (i, field) in resource.primary_key.iter().enumerate() {
+        fields.push(format!("{} = {}path.{}", field.name, sigil(&field.ty), i));
+    }
+    fields
+}
+
+/// Route of a resource as wired by `register_routes`, e.g. `/api/posts/{posts_id}/comments`
+fn documented_path(resource: &Resource) -> String {
+    let scope = if resource.protected { "/private-api" } else { "/api" };
+    match &resource.parent {
+        Some(parent) => format!("{}/{}/{{{}_id}}/{}", scope, parent, parent, resource.path),
+        None => format!("{}/{}", scope, resource.path),
+    }
+}
+
+/// `#[utoipa::path]` attribute of the handler answering `method`, in projects created with `--with-openapi`
+fn openapi_path(resource: &Resource, method: Method, openapi: bool) -> String {
+    if !openapi {
+        return String::new();
+    }
+    let resource_name = &resource.path;
+    let pascal_case = &resource.type_name;
+    let (verb, item) = match method {
+        Method::List => ("get", false),
+        Method::Create => ("post", false),
+        Method::Get => ("get", true),
+        Method::Update => ("put", true),
+        Method::Delete => ("delete", true),
+    };
+
+    let mut params: Vec<String> = resource.parent.iter()
+        .map(|parent| format!("(\"{0}_id\" = String, Path, description = \"Id of the parent {0}\")", parent))
+        .collect();
+    if item && resource.primary_key.is_empty() {
+        params.push(format!("(\"id\" = {}, Path, description = \"Id of the {}\")", resource.id_type, resource_name));
+    } else if item {
+        params.extend(resource.primary_key.iter().map(|field| {
+            format!("(\"{0}\" = {1}, Path, description = \"`{0}` of the {2}'s key\")", field.name, field.ty, resource_name)
+        }));
+    }
+    let paginated = method == Method::List && resource.paginates();
+    if paginated {
+        params.push("(\"page\" = Option<i64>, Query, description = \"Page to return, from 1\")".to_string());
+        params.push("(\"per_page\" = Option<i64>, Query, description = \"Items per page, 20 by default and clamped to `MAX_PER_PAGE` (100 by default)\")".to_string());
+    }
+    let request_body = match method {
+        // Content guards route both bodies to the same path, documented by the JSON handler
+        Method::Create if resource.content_guards => Some(format!(
+            "request_body(content(({0} = \"application/json\"), ({0} = \"application/x-www-form-urlencoded\")))",
+            pascal_case
+        )),
+        Method::Create | Method::Update => Some(format!("request_body = {}", pascal_case)),
+        _ => None,
+    };
+    let mut responses = vec![match method {
+        Method::List if paginated => format!(
+            "(status = 200, description = \"A page of {}, with `Link` headers to the next and previous ones\", body = Page<{}>)",
+            resource_name, pascal_case
+        ),
+        Method::List => format!("(status = 200, description = \"Every {}\", body = [{}])", resource_name, pascal_case),
+        Method::Create => format!("(status = 201, description = \"The created {}\", body = {})", resource_name, pascal_case),
+        Method::Get => format!("(status = 200, description = \"The {}\", body = {})", resource_name, pascal_case),
+        Method::Update => format!("(status = 200, description = \"The updated {}\", body = {})", resource_name, pascal_case),
+        Method::Delete => format!("(status = 204, description = \"The {} was deleted\")", resource_name),
+    }];
+    if method == Method::List && resource.conditional_get() {
+        responses.push("(status = 304, description = \"Unchanged since the `If-None-Match` ETag\")".to_string());
+    }
+    if paginated {
+        responses.push("(status = 400, description = \"`page` or `per_page` below 1\")".to_string());
+    }
+    if item && resource.has_storage() {
+        responses.push(format!("(status = 404, description = \"No {} with this id\")", resource_name));
+    }
+
+    let mut args = vec![
+        verb.to_string(),
+        format!("path = \"{}{}\"", documented_path(resource), if item { resource.item_pattern() } else { String::new() }),
+        format!("tag = \"{}\"", resource_name),
+    ];
+    if !params.is_empty() {
+        args.push(format!("params({})", params.join(", ")));
+    }
+    args.extend(request_body);
+    args.push(format!("responses(\n        {},\n    )", responses.join(",\n        ")));
+    format!("#[utoipa::path(\n    {},\n)]\n", args.join(",\n    "))
+}
+
+/// Handlers annotated by `openapi_path`, as listed in the `ApiDoc`
+fn documented_handlers(resource: &Resource) -> Vec<String> {
+    let resource_name = &resource.name;
+    let mut handlers = Vec::new();
+    if resource.has(Method::List) {
+        handlers.push(resource.handler(Method::List));
+    }
+    if resource.has(Method::Create) {
+        let suffix = if resource.content_guards { "_json" } else { "" };
+        handlers.push(format!("{}{}", resource.handler(Method::Create), suffix));
+    }
+    if resource.has(Method::Get) {
+        handlers.push(resource.handler(Method::Get));
+    }
+    if resource.has(Method::Update) {
+        handlers.push(resource.handler(Method::Update));
+    }
+    if resource.has(Method::Delete) {
+        handlers.push(resource.handler(Method::Delete));
+    }
+    handlers.iter()
+        .map(|handler| format!("crate::handlers::{}_handlers::{}", resource_name, handler))
+        .collect()
+}
+
+/// TODOs for nesting the resources related through `--relations` into a GET response
+fn relation_todos(resource: &Resource) -> String {
+    resource.relations.iter()
+        .map(|relation| match relation.kind {
+            RelationKind::BelongsTo => format!(
+                "    // TODO: fetch the related `{}` through `{}` and nest it in the response\n",
+                relation.resource, relation.foreign_key()
+            ),
+            RelationKind::HasMany => format!(
+                "    // TODO: fetch the `{}` whose `{}_id` matches and nest them in the response\n",
+                relation.resource, resource.name
+            ),
+        })
+        .collect()
+}
+
+/// Message of a missing item, looked up in the message catalog of `--i18n` projects
+fn not_found_message(resource: &Resource, i18n: bool) -> String {
+    if i18n {
+        format!("t_with(\"errors.not_found\", &lang, &[(\"resource\", \"{}\"), (\"id\", &id)])", resource.path)
+    } else {
+        format!("format!(\"{} {{}} not found\", id)", resource.path)
+    }
+}
+
+/// Handlers answering with placeholder responses, to be filled in by hand
+fn stub_handlers(resource: &Resource, config: &ProjectConfig) -> String {
+    let tracing = config.tracing;
+    let resource_name = &resource.name;
+    let pascal_case = &resource.type_name;
+    let has_item_routes = resource.has_item_params();
+    let has_get = resource.has(Method::List) || resource.has(Method::Get);
+    let conditional_get = resource.conditional_get();
+    let paginated = resource.paginates();
+    let mut content = String::new();
+
+    if resource.prelude {
+        content.push_str("use crate::prelude::*;\n");
+    } else {
+        let mut actix_items = Vec::new();
+        // `ValidatedJson` bodies take the place of `web::Json`
+        let create_uses_web = !resource.validate || resource.content_guards || resource.parent.is_some();
+        if (resource.has(Method::Create) && create_uses_web) || has_item_routes {
+            actix_items.push("web");
+        }
+        if conditional_get || paginated {
+            actix_items.push("HttpRequest");
+        }
+        actix_items.push("HttpResponse");
+        if actix_items.len() == 1 {
+            content.push_str("use actix_web::HttpResponse;\n");
+        } else {
+            content.push_str(&format!("use actix_web::{{{}}};\n", actix_items.join(", ")));
+        }
+        // For the path parameters, composite keys are extracted as tuples instead
+        if has_item_routes && resource.primary_key.is_empty() {
+            content.push_str("use serde::Deserialize;\n");
+        }
+    }
+    if resource.has(Method::Create) || resource.has(Method::Update) || has_get {
+        content.push_str(&format!("use crate::models::{}::{};\n", resource_name, pascal_case));
+        content.push_str(resource.validated_json_import());
+    }
+    if conditional_get {
+        content.push_str("use crate::utils::etag::conditional_json;\n");
+    }
+    if paginated {
+        content.push_str("use crate::utils::pagination::{Page, PaginationParams};\n");
+    }
+    let responds = resource.has(Method::Create) || resource.has(Method::Get) || resource.has(Method::Update)
+        || resource.has(Method::Delete) || (resource.has(Method::List) && !conditional_get && !paginated);
+    if responds {
+        content.push_str("use crate::utils::responses;\n");
+    }
+
+    // With problem+json, handlers return `Problem` as their `ResponseError`
+    let response_type = if resource.problem_json {
+        content.push_str("use crate::errors::problem::Problem;\n");
+        "Result<HttpResponse, Problem>"
+    } else {
+        "HttpResponse"
+    };
+    let respond = |response: String| {
+        if resource.problem_json { format!("Ok({})", response) } else { response }
+    };
+
+    // Composite keys are extracted as a tuple of the key fields
+    let item_path = if resource.primary_key.is_empty() {
+        format!("web::Path<{}PathParams>", pascal_case)
+    } else {
+        let types: Vec<&str> = resource.primary_key.iter().map(|field| field.ty.as_str()).collect();
+        format!("web::Path<({})>", types.join(", "))
+    };
+    if has_item_routes && resource.primary_key.is_empty() {
+        content.push_str(&path_params(resource));
+    }
+
+    // Nested resources also receive the parent id when creating
+    let parent_path = if resource.parent.is_some() { "path: web::Path<String>, " } else { "" };
+    let create_span = instrument(tracing, &parent_span_fields(resource, "path"));
+    let item_span = instrument(tracing, &item_span_fields(resource));
+    let span = instrument(tracing, &[]);
+    let openapi = |method: Method| openapi_path(resource, method, config.openapi);
+
+    // With content guards, JSON and form bodies are routed to their own handler
+    let create_handlers: &[(&str, &str)] = if resource.content_guards {
+        &[("_json", resource.json_extractor()), ("_form", "web::Form")]
+    } else {
+        &[("", resource.json_extractor())]
+    };
+    if resource.has(Method::Create) {
+        for (suffix, extractor) in create_handlers {
+            let doc = if *suffix == "_form" { String::new() } else { openapi(Method::Create) };
+            content.push_str(&format!(
+                r#"
+{}{}pub async fn {}{}({}{}_data: {}<{}>) -> {} {{
+    {}
+}}
+"#,
+                doc, create_span, resource.handler(Method::Create), suffix, parent_path, resource_name, extractor, pascal_case, response_type,
+                respond(format!("responses::created({}_data.into_inner())", resource_name))
+            ));
+        }
+    }
+
+    // Conditional GETs answer 304 while the serialized body keeps the ETag the client already has
+    let get_doc = openapi(Method::List);
+    if conditional_get {
+        content.push_str(&format!(
+            r#"
+{}{}pub async fn {}(req: HttpRequest) -> {} {{
+    // TODO: load the stored items
+{}    let items: Vec<{}> = Vec::new();
+    {}
+}}
+"#,
+            get_doc, span, resource.handler(Method::List), response_type, relation_todos(resource), pascal_case,
+            respond("conditional_json(&req, &items)".to_string())
+        ));
+    } else if paginated {
+        content.push_str(&format!(
+            r#"
+{}{}pub async fn {}(req: HttpRequest, pagination: PaginationParams) -> {} {{
+    // TODO: load the stored items, or only the page and their total count
+{}    let items: Vec<{}> = Vec::new();
+    {}
+}}
+"#,
+            get_doc, span, resource.handler(Method::List), response_type, relation_todos(resource), pascal_case,
+            respond("Page::from_all(items, &pagination).respond(&req)".to_string())
+        ));
+    } else if resource.has(Method::List) {
+        content.push_str(&format!(
+            r#"
+{}{}pub async fn {}() -> {} {{
+    // TODO: load the stored items
+{}    let items: Vec<{}> = Vec::new();
+    {}
+}}
+"#,
+            get_doc, span, resource.handler(Method::List), response_type, relation_todos(resource), pascal_case,
+            respond("responses::ok(items)".to_string())
+        ));
+    }
+
+    if resource.has(Method::Get) {
+        content.push_str(&format!(
+            r#"
+{}{}pub async fn {}(path: {}) -> {} {{
+    // TODO: load the stored item with this id
+    let item = {}::default();
+    {}
+}}
+"#,
+            openapi(Method::Get), item_span, resource.handler(Method::Get), item_path, response_type, pascal_case,
+            respond("responses::ok(item)".to_string())
+        ));
+    }
+
+    if resource.has(Method::Update) {
+        content.push_str(&format!(
+            r#"
+{}{}pub async fn {}(path: {}, {}_data: {}<{}>) -> {} {{
+    {}
+}}
+"#,
+            openapi(Method::Update), item_span, resource.handler(Method::Update), item_path, resource_name, resource.json_extractor(), pascal_case, response_type,
+            respond(format!("responses::ok({}_data.into_inner())", resource_name))
+        ));
+    }
+
+    if resource.has(Method::Delete) {
+        content.push_str(&format!(
+            r#"
+{}{}pub async fn {}(path: {}) -> {} {{
+    {}
+}}
+"#,
+            openapi(Method::Delete), item_span, resource.handler(Method::Delete), item_path, response_type,
+            respond("responses::no_content()".to_string())
+        ));
+    }
+
+    content
+}
+
+/// Handlers delegating to the resource's service and answering with `ServiceResult`
+fn service_handlers(resource: &Resource, config: &ProjectConfig) -> String {
+    let tracing = config.tracing;
+    let resource_name = &resource.name;
+    let pascal_case = &resource.type_name;
+    let conditional_get = resource.conditional_get();
+    let paginated = resource.paginates();
+    // Both answer the list with an `HttpResponse` built by hand, rendering errors themselves
+    let raw_list = conditional_get || paginated;
+    let mut content = String::new();
+
+    if resource.has(Method::Create) {
+        content.push_str("use std::sync::atomic::{AtomicU64, Ordering};\n");
+    }
+    content.push_str("use std::sync::LazyLock;\n\n");
+    if resource.has(Method::Create) || resource.has(Method::Delete) {
+        content.push_str("use actix_web::http::StatusCode;\n");
+    }
+    if resource.prelude {
+        if raw_list {
+            content.push_str("use actix_web::ResponseError;\n");
+        }
+        content.push_str("use crate::prelude::*;\n");
+    } else {
+        if raw_list {
+            content.push_str("use actix_web::{web, HttpRequest, HttpResponse, ResponseError};\n");
+        } else {
+            content.push_str("use actix_web::web;\n");
+        }
+        if resource.has_item_params() {
+            content.push_str("use serde::Deserialize;\n");
+        }
+    }
+    let result_items = if raw_list { "{AppError, ServiceResult}" } else { "ServiceResult" };
+    content.push_str(&format!(
+        "use crate::errors::service_result::{};\n\
+         use crate::models::{}::{};\n\
+         use crate::repositories::{}_repository::InMemory{}Repository;\n\
+         use crate::services::{}_service::{}Service;\n",
+        result_items, resource_name, pascal_case, resource_name, pascal_case, resource_name, pascal_case
+    ));
+    content.push_str(resource.validated_json_import());
+    // Item handlers answer in the language negotiated from `Accept-Language`
+    let localized = config.i18n && resource.has_item_params();
+    if localized {
+        content.push_str("use crate::i18n::{t_with, Lang};\n");
+    }
+    if conditional_get {
+        content.push_str("use crate::utils::etag::conditional_json;\n");
+    }
+    if paginated {
+        content.push_str("use crate::utils::pagination::{Page, PaginationParams};\n");
+    }
+
+    content.push_str(&format!(
+        r#"
+/// Service the handlers delegate to; swap the in-memory repository for a persistent one when ready
+pub type Service = {pascal_case}Service<InMemory{pascal_case}Repository>;
+
+/// Shared by every worker, registered as app data by the routes
+pub static SERVICE: LazyLock<web::Data<Service>> =
+    LazyLock::new(|| web::Data::new(Service::new(InMemory{pascal_case}Repository::new())));
+"#
+    ));
+    if resource.has(Method::Create) {
+        content.push_str("\n/// Id given to the next created item\nstatic NEXT_ID: AtomicU64 = AtomicU64::new(1);\n");
+    }
+    if resource.has_item_params() {
+        content.push_str(&path_params(resource));
+    }
+
+    // Nested resources also receive the parent id when creating
+    let parent_path = if resource.parent.is_some() { "_parent: web::Path<String>, " } else { "" };
+    let item_path = format!("web::Path<{}PathParams>", pascal_case);
+    let not_found = not_found_message(resource, config.i18n);
+    let lang = if localized { ", lang: Lang" } else { "" };
+    let json_extractor = resource.json_extractor();
+    let create_span = instrument(tracing, &parent_span_fields(resource, "_parent"));
+    let item_span = instrument(tracing, &item_span_fields(resource));
+    let span = instrument(tracing, &[]);
+    let openapi = |method: Method| openapi_path(resource, method, config.openapi);
+    let (list_doc, get_doc) = (openapi(Method::List), openapi(Method::Get));
+    let (update_doc, delete_doc) = (openapi(Method::Update), openapi(Method::Delete));
+    let (create_handler, list_handler, find_handler) =
+        (resource.handler(Method::Create), resource.handler(Method::List), resource.handler(Method::Get));
+    let (update_handler, delete_handler) = (resource.handler(Method::Update), resource.handler(Method::Delete));
+    // Related resources are fetched alongside a single item, or alongside the list without a GET `/{id}`
+    let (list_todos, item_todos) = if resource.has(Method::Get) {
+        (String::new(), relation_todos(resource))
+    } else {
+        (relation_todos(resource), String::new())
+    };
+
+    let create_handlers: &[(&str, &str)] = if resource.content_guards {
+        &[("_json", resource.json_extractor()), ("_form", "web::Form")]
+    } else {
+        &[("", resource.json_extractor())]
+    };
+    if resource.has(Method::Create) {
+        for (suffix, extractor) in create_handlers {
+            let doc = if *suffix == "_form" { String::new() } else { openapi(Method::Create) };
+            content.push_str(&format!(
+                r#"
+{doc}{create_span}pub async fn {create_handler}{suffix}({parent_path}service: web::Data<Service>, {resource_name}_data: {extractor}<{pascal_case}>) -> ServiceResult<{pascal_case}> {{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed).to_string();
+    ServiceResult::from(service.create(id, {resource_name}_data.into_inner()).await).with_status(StatusCode::CREATED)
+}}
+"#
+            ));
+        }
+    }
+
+    // Conditional GETs answer 304 while the serialized body keeps the ETag the client already has
+    if conditional_get {
+        content.push_str(&format!(
+            r#"
+{list_doc}{span}pub async fn {list_handler}(req: HttpRequest, service: web::Data<Service>) -> HttpResponse {{
+{list_todos}    match service.list().await.map_err(AppError::from) {{
+        Ok(items) => conditional_json(&req, &items),
+        Err(e) => e.error_response(),
+    }}
+}}
+"#
+        ));
+    } else if paginated {
+        content.push_str(&format!(
+            r#"
+{list_doc}{span}pub async fn {list_handler}(req: HttpRequest, pagination: PaginationParams, service: web::Data<Service>) -> HttpResponse {{
+{list_todos}    let page = async {{
+        let total = service.count().await?;
+        let items = service.list_page(pagination.offset(), pagination.limit()).await?;
+        Ok::<_, AppError>(Page::new(items, total, &pagination))
+    }};
+    match page.await {{
+        Ok(page) => page.respond(&req),
+        Err(e) => e.error_response(),
+    }}
+}}
+"#
+        ));
+    } else if resource.has(Method::List) {
+        content.push_str(&format!(
+            r#"
+{list_doc}{span}pub async fn {list_handler}(service: web::Data<Service>) -> ServiceResult<Vec<{pascal_case}>> {{
+{list_todos}    service.list().await.into()
+}}
+"#
+        ));
+    }
+
+    if resource.has(Method::Get) {
+        content.push_str(&format!(
+            r#"
+{get_doc}{item_span}pub async fn {find_handler}(path: {item_path}, service: web::Data<Service>{lang}) -> ServiceResult<{pascal_case}> {{
+{item_todos}    let id = path.id.to_string();
+    ServiceResult::found(service.get(&id).await, {not_found})
+}}
+"#
+        ));
+    }
+
+    if resource.has(Method::Update) {
+        content.push_str(&format!(
+            r#"
+{update_doc}{item_span}pub async fn {update_handler}(path: {item_path}, service: web::Data<Service>, {resource_name}_data: {json_extractor}<{pascal_case}>{lang}) -> ServiceResult<{pascal_case}> {{
+    let id = path.id.to_string();
+    ServiceResult::found(service.update(&id, {resource_name}_data.into_inner()).await, {not_found})
+}}
+"#
+        ));
+    }
+
+    if resource.has(Method::Delete) {
+        content.push_str(&format!(
+            r#"
+{delete_doc}{item_span}pub async fn {delete_handler}(path: {item_path}, service: web::Data<Service>{lang}) -> ServiceResult<()> {{
+    let id = path.id.to_string();
+    let deleted = service.delete(&id).await.map(|deleted| deleted.then_some(()));
+    ServiceResult::found(deleted, {not_found}).with_status(StatusCode::NO_CONTENT)
+}}
+"#
+        ));
+    }
+
+    content
+}
+
+/// Handlers calling through the resource's `{Name}Store` trait object, registered as app data
+fn store_handlers(resource: &Resource, config: &ProjectConfig) -> String {
+    let tracing = config.tracing;
+    let resource_name = &resource.name;
+    let pascal_case = &resource.type_name;
+    let conditional_get = resource.conditional_get();
+    let paginated = resource.paginates();
+    let mut content = String::from("use std::sync::{Arc, LazyLock};\n\n");
+
+    if resource.prelude {
+        content.push_str("use crate::prelude::*;\n");
+    } else {
+        let mut actix_items = vec!["web"];
+        if resource.has(Method::Create) || conditional_get || paginated {
+            actix_items.push("HttpRequest");
+        }
+        actix_items.push("HttpResponse");
+        content.push_str(&format!("use actix_web::{{{}}};\n", actix_items.join(", ")));
+        if resource.has_item_params() {
+            content.push_str("use serde::Deserialize;\n");
+        }
+    }
+    // The OpenAPI documentation of the GET routes names the model too
+    let documents_model = config.openapi && (resource.has(Method::List) || resource.has(Method::Get));
+    if resource.has(Method::Create) || resource.has(Method::Update) || documents_model {
+        content.push_str(&format!("use crate::models::{}::{};\n", resource_name, pascal_case));
+        content.push_str(resource.validated_json_import());
+    }
+    // Item handlers answer in the language negotiated from `Accept-Language`
+    let localized = config.i18n && resource.has_item_params();
+    if localized {
+        content.push_str("use crate::i18n::{t_with, Lang};\n");
+    }
+    let responds = resource.has(Method::Create) || resource.has(Method::Get) || resource.has(Method::Update)
+        || resource.has(Method::Delete) || !(conditional_get || paginated);
+    if responds {
+        content.push_str("use crate::utils::responses;\n");
+    }
+    content.push_str(&format!(
+        "use crate::stores::{}_store::{{InMemory{}Store, {}Store}};\n",
+        resource_name, pascal_case, pascal_case
+    ));
+    if conditional_get {
+        content.push_str("use crate::utils::etag::conditional_json;\n");
+    }
+    if paginated {
+        content.push_str("use crate::utils::pagination::{Page, PaginationParams};\n");
+    }
+
+    // With problem+json, handlers return `Problem` as their `ResponseError`
+    let response_type = if resource.problem_json {
+        content.push_str("use crate::errors::problem::Problem;\n");
+        "Result<HttpResponse, Problem>"
+    } else {
+        "HttpResponse"
+    };
+    let respond = |response: String| {
+        if resource.problem_json { format!("Ok({})", response) } else { response }
+    };
+    let message = not_found_message(resource, config.i18n);
+    let not_found = if resource.problem_json {
+        format!("Err(Problem::not_found({}))", message)
+    } else {
+        format!("responses::not_found({})", message)
+    };
+    let lang = if localized { ", lang: Lang" } else { "" };
+    let json_extractor = resource.json_extractor();
+
+    content.push_str(&format!(
+        r#"
+/// Store the handlers call through; tests can register any other `{pascal_case}Store` instead
+pub type Store = Arc<dyn {pascal_case}Store>;
+
+/// Shared by every worker, registered as app data by the routes.
+/// Swap the in-memory store for a persistent implementation when ready
+pub static STORE: LazyLock<web::Data<Store>> =
+    LazyLock::new(|| web::Data::new(Arc::new(InMemory{pascal_case}Store::default())));
+"#
+    ));
+    if resource.has_item_params() {
+        content.push_str(&path_params(resource));
+    }
+
+    // Nested resources also receive the parent id when creating
+    let parent_path = if resource.parent.is_some() { "_parent: web::Path<String>, " } else { "" };
+    let item_path = format!("web::Path<{}PathParams>", pascal_case);
+    let create_span = instrument(tracing, &parent_span_fields(resource, "_parent"));
+    let item_span = instrument(tracing, &item_span_fields(resource));
+    let span = instrument(tracing, &[]);
+    let openapi = |method: Method| openapi_path(resource, method, config.openapi);
+    let (list_doc, get_doc) = (openapi(Method::List), openapi(Method::Get));
+    let (update_doc, delete_doc) = (openapi(Method::Update), openapi(Method::Delete));
+    let (create_handler, list_handler, find_handler) =
+        (resource.handler(Method::Create), resource.handler(Method::List), resource.handler(Method::Get));
+    let (update_handler, delete_handler) = (resource.handler(Method::Update), resource.handler(Method::Delete));
+    // Related resources are fetched alongside a single item, or alongside the list without a GET `/{id}`
+    let (list_todos, item_todos) = if resource.has(Method::Get) {
+        (String::new(), relation_todos(resource))
+    } else {
+        (relation_todos(resource), String::new())
+    };
+
+    let create_handlers: &[(&str, &str)] = if resource.content_guards {
+        &[("_json", resource.json_extractor()), ("_form", "web::Form")]
+    } else {
+        &[("", resource.json_extractor())]
+    };
+    if resource.has(Method::Create) {
+        let created = respond(
+            "responses::created_at(&location, item)".to_string()
+        );
+        for (suffix, extractor) in create_handlers {
+            let doc = if *suffix == "_form" { String::new() } else { openapi(Method::Create) };
+            content.push_str(&format!(
+                r#"
+{doc}{create_span}pub async fn {create_handler}{suffix}(req: HttpRequest, {parent_path}store: web::Data<Store>, {resource_name}_data: {extractor}<{pascal_case}>) -> {response_type} {{
+    let item = {resource_name}_data.into_inner();
+    let id = store.create(item.clone());
+    let location = format!("{{}}/{{}}", req.path().trim_end_matches('/'), id);
+    {created}
+}}
+"#
+            ));
+        }
+    }
+
+    // Conditional GETs answer 304 while the serialized body keeps the ETag the client already has
+    if conditional_get {
+        let listed = respond("conditional_json(&req, &store.list())".to_string());
+        content.push_str(&format!(
+            r#"
+{list_doc}{span}pub async fn {list_handler}(req: HttpRequest, store: web::Data<Store>) -> {response_type} {{
+{list_todos}    {listed}
+}}
+"#
+        ));
+    } else if paginated {
+        let listed = respond("Page::from_all(store.list(), &pagination).respond(&req)".to_string());
+        content.push_str(&format!(
+            r#"
+{list_doc}{span}pub async fn {list_handler}(req: HttpRequest, pagination: PaginationParams, store: web::Data<Store>) -> {response_type} {{
+{list_todos}    {listed}
+}}
+"#
+        ));
+    } else if resource.has(Method::List) {
+        let listed = respond("responses::ok(store.list())".to_string());
+        content.push_str(&format!(
+            r#"
+{list_doc}{span}pub async fn {list_handler}(store: web::Data<Store>) -> {response_type} {{
+{list_todos}    {listed}
+}}
+"#
+        ));
+    }
+
+    let found = respond("responses::ok(item)".to_string());
+    if resource.has(Method::Get) {
+        content.push_str(&format!(
+            r#"
+{get_doc}{item_span}pub async fn {find_handler}(path: {item_path}, store: web::Data<Store>{lang}) -> {response_type} {{
+{item_todos}    let id = path.id.to_string();
+    match store.get(&id) {{
+        Some(item) => {found},
+        None => {not_found},
+    }}
+}}
+"#
+        ));
+    }
+
+    if resource.has(Method::Update) {
+        content.push_str(&format!(
+            r#"
+{update_doc}{item_span}pub async fn {update_handler}(path: {item_path}, store: web::Data<Store>, {resource_name}_data: {json_extractor}<{pascal_case}>{lang}) -> {response_type} {{
+    let id = path.id.to_string();
+    match store.update(&id, {resource_name}_data.into_inner()) {{
+        Some(item) => {found},
+        None => {not_found},
+    }}
+}}
+"#
+        ));
+    }
+
+    if resource.has(Method::Delete) {
+        let deleted = respond("responses::no_content()".to_string());
+        content.push_str(&format!(
+            r#"
+{delete_doc}{item_span}pub async fn {delete_handler}(path: {item_path}, store: web::Data<Store>{lang}) -> {response_type} {{
+    let id = path.id.to_string();
+    if store.delete(&id) {{
+        {deleted}
+    }} else {{
+        {not_found}
+    }}
+}}
+"#
+        ));
+    }
+
+    content
+}
+
+async fn generate_handler(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let resource_name = &resource.name;
+    let has_item_params = resource.has_item_params();
+    let conditional_get = resource.conditional_get();
+    let config = ctx.config();
+    let tracing = config.tracing;
+    let mut content = if resource.service {
+        service_handlers(resource, config)
+    } else if resource.store {
+        store_handlers(resource, config)
+    } else {
+        stub_handlers(resource, config)
+    };
+
+    let (routes_module, uri) = match &resource.parent {
+        Some(parent) => (resource.module_of(parent), format!("/{}/1/{}", parent, resource.path)),
+        None => (resource_name.clone(), format!("/{}", resource.path)),
+    };
+    let mut tests = Vec::new();
+
+    // A String id accepts any segment, so there is nothing to reject
+    let typed_key = if resource.primary_key.is_empty() {
+        resource.id_type != "String"
+    } else {
+        resource.primary_key.iter().any(|field| field.ty != "String")
+    };
+    if has_item_params && typed_key {
+        let request = if resource.has(Method::Delete) {
+            "test::TestRequest::delete()"
+        } else if resource.has(Method::Update) {
+            "test::TestRequest::put()"
+        } else {
+            "test::TestRequest::get()"
+        };
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn malformed_id_is_a_bad_request() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        let req = {request}.uri("{uri}{malformed}").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }}
+"#,
+            malformed = "/not-an-id".repeat(resource.primary_key.len().max(1))
+        ));
+    }
+    if conditional_get {
+        // Service and store handlers share a static instance, so the test gets a fresh one of its own
+        let (app, get_uri) = if resource.service {
+            (
+                format!(
+                    "App::new().app_data(web::Data::new(Service::new(InMemory{}Repository::new()))).route(\"/\", web::get().to({}))",
+                    resource.type_name, resource.handler(Method::List)
+                ),
+                "/".to_string(),
+            )
+        } else if resource.store {
+            (
+                format!(
+                    "App::new().app_data(web::Data::new(Arc::new(InMemory{}Store::default()) as Store)).route(\"/\", web::get().to({}))",
+                    resource.type_name, resource.handler(Method::List)
+                ),
+                "/".to_string(),
+            )
+        } else {
+            (format!("App::new().configure({}_routes)", routes_module), uri.clone())
+        };
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn get_is_not_modified_while_the_etag_matches() {{
+        let app = test::init_service({app}).await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("{get_uri}").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(header::ETAG).expect("missing ETag").clone();
+
+        let req = test::TestRequest::get().uri("{get_uri}").insert_header((header::IF_NONE_MATCH, etag)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_MODIFIED);
+
+        let req = test::TestRequest::get().uri("{get_uri}").insert_header((header::IF_NONE_MATCH, "W/\"stale\"")).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    }}
+"#
+        ));
+    }
+    if resource.has_storage() && resource.has(Method::Get) {
+        let missing_id = if resource.id_type == "String" { "missing" } else { "0" };
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn missing_{resource_name}_is_not_found() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        let req = test::TestRequest::get().uri("{uri}/{missing_id}").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }}
+"#
+        ));
+        if config.i18n {
+            tests.push(format!(
+                r#"
+    #[actix_web::test]
+    async fn missing_{resource_name}_is_reported_in_the_accepted_language() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        let req = test::TestRequest::get()
+            .uri("{uri}/{missing_id}")
+            .insert_header(("Accept-Language", "es-ES,es;q=0.9"))
+            .to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(String::from_utf8_lossy(&body).contains("{resource_name} {missing_id} no encontrado"));
+    }}
+"#
+            ));
+        }
+    }
+    // Feature-gated handlers stay out of the OpenAPI document, so there is nothing to check
+    let documented = config.openapi && resource.feature.is_none();
+    if documented {
+        // Mirrors `documented_handlers`
+        let mut paths = Vec::new();
+        if resource.has(Method::List) || resource.has(Method::Create) {
+            paths.push(documented_path(resource));
+        }
+        if resource.has(Method::Update) || resource.has(Method::Delete) || resource.has(Method::Get) {
+            paths.push(format!("{}{}", documented_path(resource), resource.item_pattern()));
+        }
+        let assertions: String = paths.iter()
+            .map(|path| format!("        assert!(doc[\"paths\"][\"{}\"].is_object());\n", path))
+            .collect();
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn {resource_name}_routes_are_documented() {{
+        let app = test::init_service(App::new().configure(openapi_routes)).await;
+        let req = test::TestRequest::get().uri("/openapi.json").to_request();
+        let doc: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+{assertions}    }}
+"#
+        ));
+    }
+    // The span is checked on the first GET handler through the routes, as a request would reach it
+    let traced = if resource.has(Method::List) {
+        Some((resource.handler(Method::List), uri.clone()))
+    } else if resource.has(Method::Get) {
+        Some((resource.handler(Method::Get), format!("{}{}", uri, resource.sample_item_segments())))
+    } else {
+        None
+    };
+    if let (true, Some((handler, get_uri))) = (tracing, traced) {
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn {handler}_runs_in_a_span_named_after_it() {{
+        use std::sync::{{Arc, Mutex}};
+        use tracing_subscriber::layer::{{Context, Layer, SubscriberExt}};
+
+        /// Records the name of every span opened
+        struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+        impl<S: tracing::Subscriber> Layer<S> for SpanNames {{
+            fn on_new_span(&self, attrs: &tracing::span::Attributes<'_>, _id: &tracing::span::Id, _ctx: Context<'_, S>) {{
+                self.0.lock().unwrap().push(attrs.metadata().name());
+            }}
+        }}
+
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(SpanNames(names.clone())));
+
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        test::call_service(&app, test::TestRequest::get().uri("{get_uri}").to_request()).await;
+        assert!(names.lock().unwrap().contains(&"{handler}"));
+    }}
+"#
+        ));
+    }
+    let create_then_get = resource.store && resource.has(Method::Create) && resource.has(Method::Get);
+    if create_then_get {
+        // Nested collections need the parent id segment the handlers extract
+        let (collection, collection_uri) = match &resource.parent {
+            Some(parent) => (format!("/{{{}_id}}/{}", parent, resource.path), format!("/1/{}", resource.path)),
+            None => (format!("/{}", resource.path), format!("/{}", resource.path)),
+        };
+        // Through `super`, as the store is a local named like the Rails-style `store` handler
+        let create = format!("super::{}{}", resource.handler(Method::Create), if resource.content_guards { "_json" } else { "" });
+        let find = format!("super::{}", resource.handler(Method::Get));
+        let pascal_case = &resource.type_name;
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn created_{resource_name}_can_be_fetched() {{
+        let store: Store = Arc::new(InMemory{pascal_case}Store::default());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(store.clone()))
+                .route("{collection}", web::post().to({create}))
+                .route("{collection}/{{id}}", web::get().to({find})),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("{collection_uri}").set_json({pascal_case}::default()).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let location = res.headers().get(header::LOCATION).expect("missing Location").to_str().unwrap().to_string();
+        assert_eq!(store.list().len(), 1);
+
+        let req = test::TestRequest::get().uri(&location).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    }}
+"#
+        ));
+    }
+    if !tests.is_empty() {
+        let tests = tests.join("");
+        let mut http_items = Vec::new();
+        if conditional_get || create_then_get {
+            http_items.push("header");
+        }
+        // The span test is the only one not checking a status
+        if tests.contains("StatusCode::") {
+            http_items.push("StatusCode");
+        }
+        let actix_items = match http_items.as_slice() {
+            [] => "test, App".to_string(),
+            [item] => format!("http::{}, test, App", item),
+            items => format!("http::{{{}}}, test, App", items.join(", ")),
+        };
+        let super_import = if (resource.has_storage() && conditional_get) || create_then_get { "    use super::*;\n" } else { "" };
+        let mut imports = String::new();
+        if documented {
+            imports.push_str("    use crate::openapi::openapi_routes;\n");
+        }
+        // Only the documentation test is left when a resource has no other route to check
+        if tests.contains(&format!("configure({}_routes)", routes_module)) {
+            imports.push_str(&format!("    use crate::routes::{0}_routes::{0}_routes;\n", routes_module));
+        }
+        content.push_str(&format!(
+            "\n#[cfg(test)]\nmod tests {{\n{}    use actix_web::{{{}}};\n{}{}}}\n",
+            super_import, actix_items, imports, tests
+        ));
+    }
+
+    Layer::Handler.write(ctx, resource_name, &content).await
+}
+
+/// Generates the shared RFC 7807 `Problem` error type, keeping any existing (possibly edited) copy
+async fn generate_problem_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = ctx.module_file("errors", "problem");
+    if Path::new(&file_path).exists() {
+        return Ok(());
+    }
+
+    let content = r#"use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, ResponseError};
+use serde::Serialize;
+use std::fmt;
+
+/// Error body following RFC 7807 Problem Details, served as `application/problem+json`
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    /// URI identifying the problem type, `about:blank` when the status says it all
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short, human-readable summary of the problem type
+    pub title: String,
+    /// HTTP status code of this occurrence
+    pub status: u16,
+    /// Human-readable explanation specific to this occurrence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// URI reference identifying this specific occurrence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or("Unknown Error").to_string(),
+            status: status.as_u16(),
+            detail: None,
+            instance: None,
+        }
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND).with_detail(detail)
+    }
+
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST).with_detail(detail)
+    }
+
+    pub fn internal(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR).with_detail(detail)
+    }
+
+    pub fn with_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {}", self.title, detail),
+            None => write!(f, "{}", self.title),
+        }
+    }
+}
+
+/// `JsonConfig` rejecting malformed or mistyped JSON bodies with a 400 problem+json response
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default()
+        .error_handler(|err, _req| Problem::bad_request(err.to_string()).into())
+}
+
+/// `PathConfig` rejecting malformed path parameters with a 400 problem+json response
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default()
+        .error_handler(|err, _req| Problem::bad_request(err.to_string()).into())
+}
+
+impl ResponseError for Problem {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type("application/problem+json")
+            .json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::http::header::CONTENT_TYPE;
+
+    #[actix_web::test]
+    async fn not_found_renders_problem_json() {
+        let response = Problem::not_found("user 42 does not exist")
+            .with_instance("/api/user/42")
+            .error_response();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), "application/problem+json");
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["type"], "about:blank");
+        assert_eq!(body["title"], "Not Found");
+        assert_eq!(body["status"], 404);
+        assert_eq!(body["detail"], "user 42 does not exist");
+        assert_eq!(body["instance"], "/api/user/42");
+    }
+}
+"#;
+
+    fs::create_dir_all(ctx.module_dir("errors")).await?;
+    write_if_changed(&file_path, content).await?;
+    ctx.register("errors", "problem").await?;
+    Ok(())
+}
+
+/// Generates the shared `ValidatedJson<T>` extractor of `--validate` resources, keeping any existing
+/// (possibly edited) copy, and adds validator to the project's dependencies
+async fn generate_validated_json_module(ctx: &ProjectContext) -> Result<()> {
+    if ctx.has_module("extractors", "validated_json") {
+        return Ok(());
+    }
+
+    let mut manifest = Manifest::load(ctx).await?;
+    if manifest.add_dependency("validator", VALIDATOR_DEPENDENCY)? {
+        manifest.save().await?;
+        report(format!("📦 Added validator to Cargo.toml: validator = {}", VALIDATOR_DEPENDENCY));
+    }
+    add_error_conversion(ctx, &VALIDATOR_CONVERSION).await?;
+
+    let content = r##"// `ValidatedJson<T>`: the `web::Json<T>` extractor, running `validator::Validate` before the handler
+use std::fmt;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+
+use actix_web::dev::Payload;
+use actix_web::error::JsonPayloadError;
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use validator::{Validate, ValidationErrors};
+
+/// JSON body deserialized into `T` and validated, taken by handlers in place of `web::Json<T>`.
+/// Bodies failing either step never reach the handler: they are answered with a 400 problem+json
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ValidatedJson<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Why a body was rejected, answered as RFC 7807 Problem Details
+#[derive(Debug)]
+pub enum InvalidBody {
+    /// The body isn't JSON of the expected shape, or couldn't be read (e.g. too large)
+    Malformed { status: StatusCode, detail: String },
+    /// The body broke `#[validate(...)]` rules, listed by field in the `errors` of the response
+    Invalid(ValidationErrors),
+}
+
+impl fmt::Display for InvalidBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidBody::Malformed { detail, .. } => f.write_str(detail),
+            InvalidBody::Invalid(_) => f.write_str("The request body failed validation"),
+        }
+    }
+}
+
+impl ResponseError for InvalidBody {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            InvalidBody::Malformed { status, .. } => *status,
+            InvalidBody::Invalid(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let mut body = json!({
+            "type": "about:blank",
+            "title": status.canonical_reason().unwrap_or("Unknown Error"),
+            "status": status.as_u16(),
+            "detail": self.to_string(),
+        });
+        if let InvalidBody::Invalid(errors) = self {
+            body["errors"] = serde_json::to_value(errors).unwrap_or_default();
+        }
+        HttpResponse::build(status)
+            .content_type("application/problem+json")
+            .json(body)
+    }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for ValidatedJson<T> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let body = json.await.map_err(|err| match err.as_error::<JsonPayloadError>() {
+                Some(payload_err) => InvalidBody::Malformed {
+                    status: payload_err.status_code(),
+                    detail: payload_err.to_string(),
+                }
+                .into(),
+                // Answered by the error handler of a registered `JsonConfig`
+                None => err,
+            })?;
+            let body = body.into_inner();
+            body.validate().map_err(InvalidBody::Invalid)?;
+            Ok(ValidatedJson(body))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::CONTENT_TYPE;
+    use actix_web::{test, App};
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Signup {
+        #[validate(length(min = 3))]
+        name: String,
+        #[validate(range(min = 18))]
+        age: u8,
+    }
+
+    async fn signup(body: ValidatedJson<Signup>) -> HttpResponse {
+        HttpResponse::Ok().body(format!("{} ({})", body.name, body.age))
+    }
+
+    async fn post(body: &str) -> (StatusCode, Option<String>, web::Bytes) {
+        let app = test::init_service(App::new().route("/", web::post().to(signup))).await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((CONTENT_TYPE, "application/json"))
+            .set_payload(body.to_string())
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let content_type = res.headers().get(CONTENT_TYPE).map(|value| value.to_str().unwrap().to_string());
+        (res.status(), content_type, test::read_body(res).await)
+    }
+
+    #[actix_web::test]
+    async fn valid_bodies_reach_the_handler() {
+        let (status, _, body) = post(r#"{"name": "Ada", "age": 36}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Ada (36)");
+    }
+
+    #[actix_web::test]
+    async fn invalid_bodies_get_a_400_listing_the_field_errors() {
+        let (status, content_type, body) = post(r#"{"name": "Al", "age": 12}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type.as_deref(), Some("application/problem+json"));
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["status"], 400);
+        assert_eq!(body["detail"], "The request body failed validation");
+        assert_eq!(body["errors"]["name"][0]["code"], "length");
+        assert_eq!(body["errors"]["age"][0]["code"], "range");
+    }
+
+    #[actix_web::test]
+    async fn malformed_bodies_get_a_400_without_field_errors() {
+        let (status, content_type, body) = post(r#"{"name": "Ada""#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type.as_deref(), Some("application/problem+json"));
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["title"], "Bad Request");
+        assert!(body["detail"].as_str().is_some_and(|detail| !detail.is_empty()));
+        assert!(body.get("errors").is_none());
+    }
+}
+"##;
+    ctx.write_module("extractors", "validated_json", content).await?;
+    if ctx.config().modules_style == ModulesStyle::Files {
+        ctx.register("extractors", "validated_json").await?;
+    }
+    Ok(())
+}
+
+/// Generates the `prelude` module re-exporting the imports shared by generated files,
+/// keeping any existing (possibly extended) copy
+async fn generate_prelude_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = format!("{}/prelude.rs", ctx.src_dir());
+    if Path::new(&file_path).exists() {
+        return Ok(());
+    }
+
+    let content = r#"// Items used across models and handlers, brought in with `use crate::prelude::*;`
+pub use actix_web::{web, HttpRequest, HttpResponse, Responder};
+pub use serde::{Deserialize, Serialize};
+"#;
+
+    write_if_changed(&file_path, content).await?;
+    register_module(&ctx.lib_file(), "prelude").await?;
+    Ok(())
+}
+
+/// Generates the shared conditional-request helpers, keeping any existing (possibly edited) copy
+async fn generate_etag_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = ctx.module_file("utils", "etag");
+    if Path::new(&file_path).exists() {
+        return Ok(());
+    }
+
+    let content = r#"// Conditional GET support through weak ETags
+use actix_web::http::header;
+use actix_web::{HttpRequest, HttpResponse};
+use serde::Serialize;
+
+/// Weak ETag of a response body, stable across builds and Rust versions (64-bit FNV-1a)
+pub fn etag(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("W/\"{:016x}\"", hash)
+}
+
+/// Serializes `value` as JSON, answering 304 Not Modified when the request's
+/// `If-None-Match` already holds its ETag and 200 with the body and `ETag` otherwise
+pub fn conditional_json<T: Serialize>(req: &HttpRequest, value: &T) -> HttpResponse {
+    let body = match serde_json::to_vec(value) {
+        Ok(body) => body,
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+
+    let tag = etag(&body);
+    if if_none_match(req, &tag) {
+        HttpResponse::NotModified().insert_header((header::ETAG, tag)).finish()
+    } else {
+        HttpResponse::Ok()
+            .content_type("application/json")
+            .insert_header((header::ETAG, tag))
+            .body(body)
+    }
+}
+
+/// Whether `If-None-Match` lists `tag` (or `*`), using the weak comparison of RFC 9110
+fn if_none_match(req: &HttpRequest, tag: &str) -> bool {
+    let Some(header) = req.headers().get(header::IF_NONE_MATCH).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    header.split(',').any(|candidate| candidate.trim() == "*" || opaque(candidate) == opaque(tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn etag_is_weak_and_depends_on_the_body() {
+        assert!(etag(b"[]").starts_with("W/\""));
+        assert_eq!(etag(b"[]"), etag(b"[]"));
+        assert_ne!(etag(b"[]"), etag(b"[1]"));
+    }
+
+    #[test]
+    fn matching_tag_is_not_modified() {
+        let tag = etag(b"[1,2]");
+        let strong = tag.trim_start_matches("W/");
+        for header in [tag.as_str(), strong, "*", "\"other\", W/\"x\"", ""] {
+            let req = TestRequest::get().insert_header((header::IF_NONE_MATCH, header)).to_http_request();
+            let expected = if header.is_empty() || header.contains("other") {
+                StatusCode::OK
+            } else {
+                StatusCode::NOT_MODIFIED
+            };
+            assert_eq!(conditional_json(&req, &[1, 2]).status(), expected, "If-None-Match: {}", header);
+        }
+    }
+}
+"#;
+
+    fs::create_dir_all(ctx.module_dir("utils")).await?;
+    write_if_changed(&file_path, content).await?;
+    ctx.register("utils", "etag").await?;
+    Ok(())
+}
+
+/// Generates the `Page<T>` envelope and `PaginationParams` of paginated list handlers, keeping any existing (possibly edited) copy.
+/// `--with-openapi` projects get `Page<T>` documented as a schema
+async fn generate_pagination_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = ctx.module_file("utils", "pagination");
+    if Path::new(&file_path).exists() {
+        return Ok(());
+    }
+
+    let schema = if ctx.config().openapi { ", utoipa::ToSchema" } else { "" };
+    let content = format!(
+        r#"// Page-based pagination of list endpoints: `?page=2&per_page=20` in, a `Page<T>` envelope
+// and RFC 5988 `Link` headers pointing at the next and previous pages out
+use std::fmt;
+use std::future::{{ready, Ready}};
+use std::sync::OnceLock;
+
+use actix_web::dev::Payload;
+use actix_web::http::header::LINK;
+use actix_web::http::StatusCode;
+use actix_web::{{web, FromRequest, HttpRequest, HttpResponse, ResponseError}};
+use serde::{{Deserialize, Serialize}};
+use serde_json::json;
+
+/// Items per page when the query doesn't say
+pub const DEFAULT_PER_PAGE: i64 = 20;
+/// Largest `per_page` served unless `MAX_PER_PAGE` says otherwise
+pub const DEFAULT_MAX_PER_PAGE: i64 = 100;
+
+/// Largest `per_page` served, bigger requests are clamped to it: `MAX_PER_PAGE`, read once
+pub fn max_per_page() -> i64 {{
+    static MAX: OnceLock<i64> = OnceLock::new();
+    *MAX.get_or_init(|| {{
+        std::env::var("MAX_PER_PAGE").ok()
+            .and_then(|max| max.parse().ok())
+            .filter(|max| *max > 0)
+            .unwrap_or(DEFAULT_MAX_PER_PAGE)
+    }})
+}}
+
+/// `?page=&per_page=` query of a list endpoint, the first page of `DEFAULT_PER_PAGE` items when left out.
+/// Taken by handlers as an extractor, which answers 400 to the queries `validate` rejects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct PaginationParams {{
+    /// Page to return, counting from 1
+    pub page: i64,
+    pub per_page: i64,
+}}
+
+impl Default for PaginationParams {{
+    fn default() -> Self {{
+        Self {{ page: 1, per_page: DEFAULT_PER_PAGE }}
+    }}
+}}
+
+impl PaginationParams {{
+    pub fn new(page: i64, per_page: i64) -> Self {{
+        Self {{ page, per_page }}
+    }}
+
+    /// Rejects pages and page sizes below 1, and clamps `per_page` to `max_per_page()`
+    pub fn validate(self) -> Result<Self, InvalidPagination> {{
+        self.validate_with_max(max_per_page())
+    }}
+
+    /// `validate` against another cap, e.g. for a list whose items are expensive to load
+    pub fn validate_with_max(self, max_per_page: i64) -> Result<Self, InvalidPagination> {{
+        if self.page < 1 {{
+            return Err(InvalidPagination::Page(self.page));
+        }}
+        if self.per_page < 1 {{
+            return Err(InvalidPagination::PerPage(self.per_page));
+        }}
+        Ok(Self {{ per_page: self.per_page.min(max_per_page), ..self }})
+    }}
+
+    /// Items before the page, e.g. the `OFFSET` of a query whose `LIMIT` is `limit()`
+    pub fn offset(&self) -> usize {{
+        (self.page.max(1) as usize - 1).saturating_mul(self.limit())
+    }}
+
+    /// Items on the page, `per_page` as a count
+    pub fn limit(&self) -> usize {{
+        self.per_page.max(1) as usize
+    }}
+}}
+
+/// Why a `?page=&per_page=` query was rejected, answered with a 400
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidPagination {{
+    /// The query isn't made of integers
+    Malformed(String),
+    Page(i64),
+    PerPage(i64),
+}}
+
+impl fmt::Display for InvalidPagination {{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {{
+        match self {{
+            InvalidPagination::Malformed(detail) => write!(f, "Invalid pagination query: {{}}", detail),
+            InvalidPagination::Page(page) => write!(f, "page must be 1 or more, got {{}}", page),
+            InvalidPagination::PerPage(per_page) => write!(f, "per_page must be 1 or more, got {{}}", per_page),
+        }}
+    }}
+}}
+
+impl ResponseError for InvalidPagination {{
+    fn status_code(&self) -> StatusCode {{
+        StatusCode::BAD_REQUEST
+    }}
+
+    fn error_response(&self) -> HttpResponse {{
+        HttpResponse::BadRequest().json(json!({{ "error": self.to_string() }}))
+    }}
+}}
+
+impl FromRequest for PaginationParams {{
+    type Error = InvalidPagination;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {{
+        let params = web::Query::<Self>::from_query(req.query_string())
+            .map_err(|e| InvalidPagination::Malformed(e.to_string()))
+            .and_then(|query| query.into_inner().validate());
+        ready(params)
+    }}
+}}
+
+/// One page of a list, with what clients need to fetch the others
+#[derive(Debug, Clone, PartialEq, Serialize{schema})]
+pub struct Page<T> {{
+    pub items: Vec<T>,
+    /// Number of items in the whole list
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}}
+
+impl<T> Page<T> {{
+    /// Page of `total` items made of `items`, loaded for `params` (e.g. with `LIMIT` and `OFFSET`)
+    pub fn new(items: Vec<T>, total: usize, params: &PaginationParams) -> Self {{
+        Self {{ items, total, page: params.page.max(1) as usize, per_page: params.limit() }}
+    }}
+
+    /// Cuts the page out of a list loaded whole
+    pub fn from_all(all: Vec<T>, params: &PaginationParams) -> Self {{
+        let total = all.len();
+        let items = all.into_iter().skip(params.offset()).take(params.limit()).collect();
+        Self::new(items, total, params)
+    }}
+
+    /// Number of the last page, 1 for an empty list
+    pub fn last_page(&self) -> usize {{
+        self.total.div_ceil(self.per_page).max(1)
+    }}
+
+    /// `Link` header of the next and previous pages of the list at `req`, `None` when there is a single page
+    pub fn links(&self, req: &HttpRequest) -> Option<String> {{
+        let mut links = Vec::new();
+        if self.page < self.last_page() {{
+            links.push(link(req, self.page + 1, self.per_page, "next"));
+        }}
+        // Past the end, the previous page is the last one
+        if self.page > 1 {{
+            links.push(link(req, (self.page - 1).min(self.last_page()), self.per_page, "prev"));
+        }}
+        (!links.is_empty()).then(|| links.join(", "))
+    }}
+}}
+
+impl<T: Serialize> Page<T> {{
+    /// 200 with the page as JSON and its `Link` header
+    pub fn respond(&self, req: &HttpRequest) -> HttpResponse {{
+        let mut response = HttpResponse::Ok();
+        if let Some(links) = self.links(req) {{
+            response.insert_header((LINK, links));
+        }}
+        response.json(self)
+    }}
+}}
+
+/// `<{{path}}?{{query}}>; rel="{{rel}}"` for `page`, keeping the other parameters of the request's query
+fn link(req: &HttpRequest, page: usize, per_page: usize, rel: &str) -> String {{
+    let mut params: Vec<String> = req.query_string()
+        .split('&')
+        .filter(|param| {{
+            let name = param.split_once('=').map_or(*param, |(name, _)| name);
+            !param.is_empty() && name != "page" && name != "per_page"
+        }})
+        .map(str::to_string)
+        .collect();
+    params.push(format!("page={{}}&per_page={{}}", page, per_page));
+    format!("<{{}}?{{}}>; rel=\"{{}}\"", req.path(), params.join("&"), rel)
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::test::TestRequest;
+
+    fn extract(uri: &str) -> Result<PaginationParams, InvalidPagination> {{
+        let req = TestRequest::get().uri(uri).to_http_request();
+        PaginationParams::extract(&req).into_inner()
+    }}
+
+    #[test]
+    fn params_default_to_the_first_page() {{
+        let params = extract("/api/items").unwrap();
+        assert_eq!((params.page, params.per_page, params.offset()), (1, DEFAULT_PER_PAGE, 0));
+        assert_eq!(PaginationParams::new(3, 10).offset(), 20);
+    }}
+
+    #[test]
+    fn huge_pages_are_clamped() {{
+        let params = extract("/api/items?per_page=10000").unwrap();
+        assert_eq!(params.per_page, max_per_page());
+        assert_eq!(PaginationParams::new(1, 10000).validate_with_max(50).unwrap().limit(), 50);
+    }}
+
+    #[test]
+    fn pages_below_one_are_rejected() {{
+        assert_eq!(extract("/api/items?page=0"), Err(InvalidPagination::Page(0)));
+        assert_eq!(extract("/api/items?per_page=-5"), Err(InvalidPagination::PerPage(-5)));
+        assert!(matches!(extract("/api/items?page=two"), Err(InvalidPagination::Malformed(_))));
+        assert_eq!(InvalidPagination::Page(0).error_response().status(), StatusCode::BAD_REQUEST);
+    }}
+
+    #[actix_web::test]
+    async fn page_renders_the_envelope_and_links() {{
+        let req = TestRequest::get().uri("/api/items?sort=name&page=2&per_page=2").to_http_request();
+        let page = Page::from_all((1..=5).collect(), &PaginationParams::new(2, 2));
+        let response = page.respond(&req);
+
+        assert_eq!(
+            response.headers().get(LINK).unwrap(),
+            "</api/items?sort=name&page=3&per_page=2>; rel=\"next\", </api/items?sort=name&page=1&per_page=2>; rel=\"prev\""
+        );
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({{ "items": [3, 4], "total": 5, "page": 2, "per_page": 2 }}));
+    }}
+
+    #[test]
+    fn single_page_has_no_links() {{
+        let req = TestRequest::get().uri("/api/items").to_http_request();
+        let page = Page::from_all(vec![1, 2], &PaginationParams::default());
+        assert_eq!(page.links(&req), None);
+        assert_eq!(page.last_page(), 1);
+    }}
+}}
+"#
+    );
+
+    fs::create_dir_all(ctx.module_dir("utils")).await?;
+    write_if_changed(&file_path, content).await?;
+    ctx.register("utils", "pagination").await?;
+    document_env(ctx, "Pagination", "MAX_PER_PAGE", "100").await?;
+    Ok(())
+}
+
+/// `DEPRECATION` constant of a routes file: the deprecation date as a Unix timestamp and the sunset as an HTTP date
+fn deprecation_const(resource_name: &str, deprecation: &Deprecation) -> Result<String> {
+    let midnight = |date: &str| -> Result<chrono::DateTime<chrono::Utc>> {
+        let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("❌ Invalid date '{}' in the deprecation of '{}' in .cargo-mold", date, resource_name))?;
+        Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
+    };
+    let since = midnight(&deprecation.since)?;
+    let (sunset, sunset_doc) = match &deprecation.sunset {
+        Some(date) => (
+            format!("Some(\"{}\")", midnight(date)?.format("%a, %d %b %Y %H:%M:%S GMT")),
+            format!(", may stop answering after {}", date),
+        ),
+        None => ("None".to_string(), String::new()),
+    };
+    Ok(format!(
+        "\n/// Deprecated since {}{} (recorded under `deprecated.{}` in `.cargo-mold`)\n\
+         const DEPRECATION: Deprecation = Deprecation {{ since: {}, sunset: {} }};\n",
+        deprecation.since, sunset_doc, resource_name, since.timestamp(), sunset
+    ))
+}
+
+/// Generates the `Deprecation` middleware of deprecated routes, keeping any existing (possibly edited) copy
+async fn generate_deprecation_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = ctx.module_file("utils", "deprecation");
+    if Path::new(&file_path).exists() {
+        return Ok(());
+    }
+
+    // Calls are logged through `tracing` when the project has it
+    let log = if ctx.config().tracing {
+        "tracing::warn!(method = %req.method(), path = req.path(), \"deprecated endpoint called\");"
+    } else {
+        "eprintln!(\"deprecated endpoint called: {} {}\", req.method(), req.path());"
+    };
+    let content = format!(
+        r#"// Deprecation (RFC 9745) and Sunset (RFC 8594) headers for routes on their way out
+use actix_web::body::MessageBody;
+use actix_web::dev::{{ServiceRequest, ServiceResponse}};
+use actix_web::http::header::{{HeaderName, HeaderValue}};
+use actix_web::middleware::Next;
+use actix_web::Error;
+
+/// When routes were deprecated and when they may stop answering.
+/// Wrap a scope with `from_fn(|req, next| DEPRECATION.apply(req, next))`
+#[derive(Debug, Clone, Copy)]
+pub struct Deprecation {{
+    /// Unix timestamp of the deprecation, sent as `Deprecation: @<timestamp>`
+    pub since: i64,
+    /// HTTP date sent as the `Sunset` header, if the removal is planned
+    pub sunset: Option<&'static str>,
+}}
+
+impl Deprecation {{
+    /// Logs the call and adds the headers to its response
+    pub async fn apply<B: MessageBody>(self, req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, Error> {{
+        {log}
+        let mut res = next.call(req).await?;
+        let headers = res.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&format!("@{{}}", self.since)) {{
+            headers.insert(HeaderName::from_static("deprecation"), value);
+        }}
+        if let Some(sunset) = self.sunset {{
+            headers.insert(HeaderName::from_static("sunset"), HeaderValue::from_static(sunset));
+        }}
+        Ok(res)
+    }}
+}}
+"#
+    );
+
+    fs::create_dir_all(ctx.module_dir("utils")).await?;
+    write_if_changed(&file_path, content).await?;
+    ctx.register("utils", "deprecation").await?;
+    Ok(())
+}
+
+/// Generates the response builders shared by handlers, keeping any existing (possibly edited) copy
+async fn generate_responses_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = ctx.module_file("utils", "responses");
+    if Path::new(&file_path).exists() {
+        return Ok(());
+    }
+
+    let content = r#"// Response builders shared by handlers, so status codes and content types are decided in one place
+use actix_web::http::header;
+use actix_web::HttpResponse;
+use serde::Serialize;
+
+/// 200 OK with `data` as JSON
+pub fn ok<T: Serialize>(data: T) -> HttpResponse {
+    HttpResponse::Ok().json(data)
+}
+
+/// 201 Created with `data` as JSON
+pub fn created<T: Serialize>(data: T) -> HttpResponse {
+    HttpResponse::Created().json(data)
+}
+
+/// 201 Created with `data` as JSON and a `Location` header pointing at it
+pub fn created_at<T: Serialize>(location: &str, data: T) -> HttpResponse {
+    HttpResponse::Created().insert_header((header::LOCATION, location)).json(data)
+}
+
+/// 204 No Content
+pub fn no_content() -> HttpResponse {
+    HttpResponse::NoContent().finish()
+}
+
+/// 404 Not Found with a `{"error": message}` JSON body
+pub fn not_found(message: impl Into<String>) -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({ "error": message.into() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::http::StatusCode;
+
+    fn content_type(response: &HttpResponse) -> Option<&str> {
+        response.headers().get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok())
+    }
+
+    #[test]
+    fn each_helper_answers_with_its_status_and_content_type() {
+        let cases = [
+            (ok([1, 2]), StatusCode::OK, Some("application/json")),
+            (created([1, 2]), StatusCode::CREATED, Some("application/json")),
+            (created_at("/items/1", [1, 2]), StatusCode::CREATED, Some("application/json")),
+            (no_content(), StatusCode::NO_CONTENT, None),
+            (not_found("item 1 not found"), StatusCode::NOT_FOUND, Some("application/json")),
+        ];
+        for (response, status, expected) in &cases {
+            assert_eq!(response.status(), *status);
+            assert_eq!(content_type(response), *expected, "{}", status);
+        }
+    }
+
+    #[test]
+    fn created_at_points_at_the_item() {
+        let response = created_at("/items/1", [1, 2]);
+        assert_eq!(response.headers().get(header::LOCATION).unwrap(), "/items/1");
+    }
+
+    #[actix_web::test]
+    async fn not_found_wraps_the_message() {
+        let body = to_bytes(not_found("item 1 not found").into_body()).await.unwrap();
+        assert_eq!(body, "{\"error\":\"item 1 not found\"}");
+    }
 }
+"#;
 
-pub async fn execute(args: ResourceArgs) -> anyhow::Result<()> {
-    println!("📁 Generating resource: {}", args.name);
-    
-    if !Path::new(".cargo-mold").exists() {
-        anyhow::bail!(
-            "❌ Not a cargo-mold project.\n\
-             Run this command in a project created with `cargo mold new`\n\
-             Or create a new project with: `cargo mold new {}`",
-            args.name
-        );
+    fs::create_dir_all(ctx.module_dir("utils")).await?;
+    write_if_changed(&file_path, content).await?;
+    ctx.register("utils", "responses").await?;
+    Ok(())
+}
+
+/// Writes `errors/service_result.rs` unless it exists, then gives its `AppError` the conversions of the error crates the project depends on
+async fn generate_service_result_module(ctx: &ProjectContext) -> Result<()> {
+    let file_path = ctx.module_file("errors", "service_result");
+    if !Path::new(&file_path).exists() {
+        write_service_result_module(ctx, &file_path).await?;
+    }
+    let content = fs::read_to_string(&file_path).await.unwrap_or_default();
+    let missing: Vec<&ErrorConversion> = [&SQLX_CONVERSION, &VALIDATOR_CONVERSION].into_iter()
+        .filter(|conversion| !content.contains(&format!("impl From<{}> for AppError", conversion.error)))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    // The conversions are optional, a manifest that doesn't parse only postpones them
+    let manifest = match Manifest::load(ctx).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            report(format!("⚠️ Skipped the error conversions of {}: {}", file_path, e));
+            return Ok(());
+        }
+    };
+    for conversion in missing {
+        if manifest.has_dependency(conversion.dependency) {
+            add_error_conversion(ctx, conversion).await?;
+        }
     }
-    
-    generate_model(&args.name).await?;
-    generate_handler(&args.name).await?;
-    generate_routes(&args.name).await?;
-    update_modules(&args.name).await?;
-    
-    println!("✅ Resource '{}' created successfully!", args.name);
-    println!("📝 Generated files:");
-    println!("   - src/models/{}.rs", args.name);
-    println!("   - src/handlers/{}_handlers.rs", args.name);
-    println!("   - src/routes/{}_routes.rs", args.name);
-    
     Ok(())
 }
 
-async fn generate_model(resource_name: &str) -> Result<()> {
-    let pascal_case = to_pascal_case(resource_name);
-    let content = format!(
-        r#"use serde::{{Deserialize, Serialize}};
+async fn write_service_result_module(ctx: &ProjectContext, file_path: &str) -> Result<()> {
+    let content = r#"// Maps service-layer results to HTTP responses so handlers can return them as they are
+use std::convert::Infallible;
+use std::fmt;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct {} {{
-}}
+use actix_web::body::BoxBody;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, Responder, ResponseError};
+use cargo_mold::auth::AuthError;
+use serde::Serialize;
 
-impl {} {{
-    pub fn new() -> Self {{
-        Self {{
-        }}
-    }}
-}}
-"#, 
-        pascal_case, pascal_case
-    );
+/// Stable, machine-readable codes of the `AppError` variants, sent as the `code` of error bodies.
+/// Clients switch on them rather than on messages, so add new codes instead of renaming these
+pub mod codes {
+    pub const NOT_FOUND: &str = "resource.not_found";
+    pub const BAD_REQUEST: &str = "request.invalid";
+    pub const CONFLICT: &str = "resource.conflict";
+    pub const INTERNAL: &str = "server.internal";
+    pub const UNAUTHORIZED: &str = "auth.unauthorized";
+}
+
+/// Errors reported by the service layer, each answered with its own status and code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppError {
+    NotFound(String),
+    BadRequest(String),
+    Conflict(String),
+    Internal(String),
+    Unauthorized(String),
+}
+
+impl AppError {
+    /// Code of the variant, from the `codes` registry
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => codes::NOT_FOUND,
+            AppError::BadRequest(_) => codes::BAD_REQUEST,
+            AppError::Conflict(_) => codes::CONFLICT,
+            AppError::Internal(_) => codes::INTERNAL,
+            AppError::Unauthorized(_) => codes::UNAUTHORIZED,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(message)
+            | AppError::BadRequest(message)
+            | AppError::Conflict(message)
+            | AppError::Internal(message)
+            | AppError::Unauthorized(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// RFC 7807 Problem Details, extended with the error's `code`
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        HttpResponse::build(status)
+            .content_type("application/problem+json")
+            .json(serde_json::json!({
+                "type": "about:blank",
+                "title": status.canonical_reason().unwrap_or("Unknown Error"),
+                "status": status.as_u16(),
+                "detail": self.to_string(),
+                "code": self.code(),
+            }))
+    }
+}
+
+/// Errors of the in-memory repositories, which can't fail
+impl From<Infallible> for AppError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// Token and encryption errors: bad credentials are the client's, missing keys and settings the server's
+impl From<AuthError> for AppError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::Decryption(_) | AuthError::Encoding(_) => AppError::Unauthorized(e.to_string()),
+            AuthError::Encryption(_) | AuthError::Key(_) | AuthError::MissingEnv(_) => AppError::Internal(e.to_string()),
+        }
+    }
+}
+
+/// Outcome of a service call: the value as JSON (200 unless told otherwise) or the `AppError`
+#[derive(Debug)]
+pub struct ServiceResult<T> {
+    result: Result<T, AppError>,
+    status: StatusCode,
+}
+
+impl<T> ServiceResult<T> {
+    /// Answers a missing value with `AppError::NotFound(message)`
+    pub fn found<E: Into<AppError>>(result: Result<Option<T>, E>, message: impl Into<String>) -> Self {
+        match result {
+            Ok(Some(value)) => Ok::<_, AppError>(value).into(),
+            Ok(None) => Err(AppError::NotFound(message.into())).into(),
+            Err(e) => Err(e.into()).into(),
+        }
+    }
+
+    /// Status of a successful response, e.g. 201 after a creation or 204 to drop the body
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn into_result(self) -> Result<T, AppError> {
+        self.result
+    }
+}
+
+impl<T, E: Into<AppError>> From<Result<T, E>> for ServiceResult<T> {
+    fn from(result: Result<T, E>) -> Self {
+        Self {
+            result: result.map_err(Into::into),
+            status: StatusCode::OK,
+        }
+    }
+}
+
+impl<T: Serialize> Responder for ServiceResult<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+        match self.result {
+            Ok(_) if self.status == StatusCode::NO_CONTENT => HttpResponse::NoContent().finish(),
+            Ok(value) => HttpResponse::build(self.status).json(value),
+            Err(e) => e.error_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::http::header::CONTENT_TYPE;
+    use actix_web::test::TestRequest;
+
+    async fn render<T: Serialize>(result: ServiceResult<T>) -> (StatusCode, serde_json::Value) {
+        let res = result.respond_to(&TestRequest::default().to_http_request());
+        let status = res.status();
+        let body = to_bytes(res.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[actix_web::test]
+    async fn ok_renders_the_value_as_json() {
+        let (status, body) = render(ServiceResult::from(Ok::<_, AppError>(vec![1, 2]))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!([1, 2]));
+
+        let (status, _) = render(ServiceResult::from(Ok::<_, AppError>(1)).with_status(StatusCode::CREATED)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    #[actix_web::test]
+    async fn err_renders_the_app_error() {
+        let (status, body) = render(ServiceResult::<u32>::from(Err(AppError::Conflict("taken".to_string())))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["detail"], "taken");
+
+        let missing = ServiceResult::<u32>::found(Ok::<_, AppError>(None), "user 7 not found");
+        let (status, body) = render(missing).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["detail"], "user 7 not found");
+    }
+
+    #[actix_web::test]
+    async fn every_error_renders_its_code_and_status() {
+        let errors = [
+            (AppError::NotFound("gone".to_string()), StatusCode::NOT_FOUND, "resource.not_found"),
+            (AppError::BadRequest("bad".to_string()), StatusCode::BAD_REQUEST, "request.invalid"),
+            (AppError::Conflict("taken".to_string()), StatusCode::CONFLICT, "resource.conflict"),
+            (AppError::Internal("oops".to_string()), StatusCode::INTERNAL_SERVER_ERROR, "server.internal"),
+            (AppError::Unauthorized("expired".to_string()), StatusCode::UNAUTHORIZED, "auth.unauthorized"),
+        ];
+        for (error, expected_status, code) in errors {
+            let content_type = error.error_response().headers().get(CONTENT_TYPE).cloned();
+            assert_eq!(content_type.unwrap(), "application/problem+json");
+
+            let (status, body) = render(ServiceResult::<u32>::from(Err(error))).await;
+            assert_eq!(status, expected_status);
+            assert_eq!(body["status"], expected_status.as_u16());
+            assert_eq!(body["code"], code);
+        }
+    }
+
+    #[test]
+    fn lower_level_errors_convert_with_the_question_mark() {
+        use cargo_mold::auth::DecryptError;
+
+        fn verify(tampered: bool) -> Result<(), AppError> {
+            if tampered {
+                Err::<(), _>(AuthError::Decryption(DecryptError::AuthFailed))?;
+            }
+            Err::<(), _>(AuthError::MissingEnv("JWT_SECRET".to_string()))?;
+            Ok(())
+        }
+
+        assert!(matches!(verify(true), Err(AppError::Unauthorized(_))));
+        assert!(matches!(verify(false), Err(AppError::Internal(_))));
+        // Usable wherever a standard error is expected
+        let error: Box<dyn std::error::Error> = Box::new(AppError::NotFound("gone".to_string()));
+        assert_eq!(error.to_string(), "gone");
+    }
+}
+"#;
+
+    fs::create_dir_all(ctx.module_dir("errors")).await?;
+    write_if_changed(file_path, content).await?;
+    ctx.register("errors", "service_result").await?;
+    Ok(())
+}
+
+/// A `From` conversion of a lower-level error into the generated `AppError`, so services can `?` it
+pub struct ErrorConversion {
+    /// Crate of the error, the conversion is only added to projects depending on it
+    pub dependency: &'static str,
+    pub error: &'static str,
+    pub conversion: &'static str,
+    /// Test added to the `tests` module of `service_result.rs` along with the conversion
+    pub test: &'static str,
+}
+
+pub const SQLX_CONVERSION: ErrorConversion = ErrorConversion {
+    dependency: "sqlx",
+    error: "sqlx::Error",
+    conversion: r#"/// Database errors: missing rows are 404s and constraint violations the client's fault.
+/// Other failures keep their details out of the response
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => AppError::NotFound(e.to_string()),
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict(db.message().to_string()),
+            sqlx::Error::Database(db) if db.is_foreign_key_violation() || db.is_check_violation() => {
+                AppError::BadRequest(db.message().to_string())
+            }
+            _ => AppError::Internal("database error".to_string()),
+        }
+    }
+}
 
-    let file_path = format!("src/models/{}.rs", resource_name);
-    let mut file = fs::File::create(&file_path).await?;
-    file.write_all(content.as_bytes()).await?;
+"#,
+    test: r#"
+    #[test]
+    fn sqlx_errors_convert_with_the_question_mark() {
+        fn find() -> Result<(), AppError> {
+            Err::<(), _>(sqlx::Error::RowNotFound)?;
+            Ok(())
+        }
+
+        assert!(matches!(find(), Err(AppError::NotFound(_))));
+        assert_eq!(AppError::from(sqlx::Error::PoolTimedOut), AppError::Internal("database error".to_string()));
+    }
+"#,
+};
+
+pub const VALIDATOR_CONVERSION: ErrorConversion = ErrorConversion {
+    dependency: "validator",
+    error: "validator::ValidationErrors",
+    conversion: r#"/// Failed `validator::Validate` checks, listed field by field
+impl From<validator::ValidationErrors> for AppError {
+    fn from(e: validator::ValidationErrors) -> Self {
+        AppError::BadRequest(e.to_string())
+    }
+}
+
+"#,
+    test: r#"
+    #[test]
+    fn validation_errors_convert_with_the_question_mark() {
+        fn validate() -> Result<(), AppError> {
+            let mut errors = validator::ValidationErrors::new();
+            errors.add("email", validator::ValidationError::new("email"));
+            Err::<(), _>(errors)?;
+            Ok(())
+        }
+
+        assert!(matches!(validate(), Err(AppError::BadRequest(message)) if message.contains("email")));
+    }
+"#,
+};
+
+/// Adds `conversion` to the `AppError` of `errors/service_result.rs` when the project has one without it.
+/// Returns whether it was added
+pub async fn add_error_conversion(ctx: &ProjectContext, conversion: &ErrorConversion) -> Result<bool> {
+    let file_path = ctx.module_file("errors", "service_result");
+    let Ok(mut content) = fs::read_to_string(&file_path).await else {
+        return Ok(false);
+    };
+    if content.contains(&format!("impl From<{}> for AppError", conversion.error)) {
+        return Ok(false);
+    }
+    let Some(at) = content.find("/// Outcome of a service call") else {
+        return Ok(false);
+    };
+    content.insert_str(at, conversion.conversion);
+    // The `tests` module closes the file
+    if content.contains("mod tests {")
+        && let Some(end) = content.trim_end().rfind('}') {
+        content.insert_str(end, conversion.test);
+    }
+    write_if_changed(&file_path, content).await?;
+    Ok(true)
+}
+
+/// Generates the in-memory repository and the service the handlers delegate to, unless they exist
+async fn generate_service_layer(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let repository = format!("{}_repository", resource.name);
+    let repository_path = ctx.module_file("repositories", &repository);
+    if !Path::new(&repository_path).exists() {
+        fs::create_dir_all(ctx.module_dir("repositories")).await?;
+        generate_repository(&resource.name, &resource.type_name, None, &[], &repository_path).await?;
+        ctx.register("repositories", &repository).await?;
+    }
+
+    let service = format!("{}_service", resource.name);
+    let service_path = ctx.module_file("services", &service);
+    if !Path::new(&service_path).exists() {
+        fs::create_dir_all(ctx.module_dir("services")).await?;
+        generate_service(ctx, &resource.name, &resource.type_name, &service_path).await?;
+        ctx.register("services", &service).await?;
+    }
     Ok(())
 }
 
-async fn generate_handler(resource_name: &str) -> Result<()> {
-    let pascal_case = to_pascal_case(resource_name);
+/// Generates the resource's `{Name}Store` trait and in-memory store, keeping an existing (possibly edited) one
+async fn generate_store_layer(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let store = format!("{}_store", resource.name);
+    let store_path = ctx.module_file("stores", &store);
+    if Path::new(&store_path).exists() {
+        return Ok(());
+    }
+
+    let resource_name = &resource.name;
+    let pascal_case = &resource.type_name;
     let content = format!(
-        r#"use actix_web::{{web, HttpResponse}};
-use crate::models::{}::{};
+        r#"use std::collections::HashMap;
+use std::sync::atomic::{{AtomicU64, Ordering}};
+use std::sync::{{Arc, Mutex}};
 
-pub async fn create_{}({}_data: web::Json<{}>) -> HttpResponse {{
-    HttpResponse::Created().json({}_data)
+use crate::models::{resource_name}::{pascal_case};
+
+/// Storage the `{resource_name}` handlers call through, so they can be tested against a fake
+pub trait {pascal_case}Store: Send + Sync {{
+    fn list(&self) -> Vec<{pascal_case}>;
+    fn get(&self, id: &str) -> Option<{pascal_case}>;
+    /// Stores a new item and returns the id it was given
+    fn create(&self, item: {pascal_case}) -> String;
+    fn update(&self, id: &str, item: {pascal_case}) -> Option<{pascal_case}>;
+    fn delete(&self, id: &str) -> bool;
 }}
 
-pub async fn get_{}() -> HttpResponse {{
-    HttpResponse::Ok().finish()
+/// In-memory `{pascal_case}Store`, handy for prototyping and tests
+#[derive(Clone, Default)]
+pub struct InMemory{pascal_case}Store {{
+    items: Arc<Mutex<HashMap<String, {pascal_case}>>>,
+    last_id: Arc<AtomicU64>,
 }}
 
-pub async fn update_{}(path: web::Path<String>, {}_data: web::Json<{}>) -> HttpResponse {{
-    HttpResponse::Ok().json({}_data.clone())
+impl {pascal_case}Store for InMemory{pascal_case}Store {{
+    fn list(&self) -> Vec<{pascal_case}> {{
+        self.items.lock().unwrap().values().cloned().collect()
+    }}
+
+    fn get(&self, id: &str) -> Option<{pascal_case}> {{
+        self.items.lock().unwrap().get(id).cloned()
+    }}
+
+    fn create(&self, item: {pascal_case}) -> String {{
+        let id = (self.last_id.fetch_add(1, Ordering::Relaxed) + 1).to_string();
+        self.items.lock().unwrap().insert(id.clone(), item);
+        id
+    }}
+
+    fn update(&self, id: &str, item: {pascal_case}) -> Option<{pascal_case}> {{
+        let mut items = self.items.lock().unwrap();
+        let existing = items.get_mut(id)?;
+        *existing = item.clone();
+        Some(item)
+    }}
+
+    fn delete(&self, id: &str) -> bool {{
+        self.items.lock().unwrap().remove(id).is_some()
+    }}
 }}
 
-pub async fn delete_{}(path: web::Path<String>) -> HttpResponse {{
-    HttpResponse::NoContent().finish()
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn in_memory_store_supports_crud() {{
+        let store = InMemory{pascal_case}Store::default();
+
+        let id = store.create({pascal_case}::default());
+        assert!(store.get(&id).is_some());
+        assert_eq!(store.list().len(), 1);
+
+        assert!(store.update(&id, {pascal_case}::default()).is_some());
+        assert!(store.update("missing", {pascal_case}::default()).is_none());
+
+        assert!(store.delete(&id));
+        assert!(!store.delete(&id));
+        assert!(store.list().is_empty());
+    }}
 }}
-"#,
-        resource_name, pascal_case,  // use statements
-        resource_name, resource_name, pascal_case,
-        resource_name,
-        resource_name,
-        resource_name, resource_name, pascal_case,
-        resource_name,
-        resource_name
+"#
     );
 
-    let file_path = format!("src/handlers/{}_handlers.rs", resource_name);
-    let mut file = fs::File::create(&file_path).await?;
-    file.write_all(content.as_bytes()).await?;
-    Ok(())
+    fs::create_dir_all(ctx.module_dir("stores")).await?;
+    write_if_changed(&store_path, content).await?;
+    ctx.register("stores", &store).await
 }
 
-async fn generate_routes(resource_name: &str) -> Result<()> {
-    let content = format!(
-        r#"use actix_web::web;
-use crate::handlers::{}_handlers;
+async fn generate_routes(ctx: &ProjectContext, resource: &Resource) -> Result<()> {
+    let resource_name = &resource.name;
+    let route_table: Vec<Method> = [Method::List, Method::Create, Method::Get, Method::Update, Method::Delete].iter()
+        .copied()
+        .filter(|method| resource.has(*method))
+        .collect();
+    let routes: String = route_table.iter()
+        .map(|method| {
+            if *method == Method::Create && resource.content_guards {
+                return [("json", "application/json"), ("form", "application/x-www-form-urlencoded")].iter()
+                    .map(|(kind, content_type)| format!(
+                        "\n            .route(\"\", web::post().guard(guard::Header(\"content-type\", \"{}\")).to({}_handlers::{}_{}))",
+                        content_type, resource_name, resource.handler(Method::Create), kind
+                    ))
+                    .collect();
+            }
+            let item = resource.item_pattern();
+            let (path, verb) = match method {
+                Method::List => ("", "get"),
+                Method::Create => ("", "post"),
+                Method::Get => (item.as_str(), "get"),
+                Method::Update => (item.as_str(), "put"),
+                Method::Delete => (item.as_str(), "delete"),
+            };
+            format!(
+                "\n            .route(\"{}\", web::{}().to({}_handlers::{}))",
+                path, verb, resource_name, resource.handler(*method)
+            )
+        })
+        .collect();
+
+    // Malformed bodies and ids are reported with the same problem+json format as handler errors,
+    // otherwise malformed ids are turned into a 400 instead of actix's default 404
+    let has_item_routes = resource.has_item_params();
+    let mut problem_import = "";
+    let mut app_data = String::new();
+    if resource.service {
+        app_data.push_str(&format!("\n            .app_data({}_handlers::SERVICE.clone())", resource_name));
+    }
+    if resource.store {
+        app_data.push_str(&format!("\n            .app_data({}_handlers::STORE.clone())", resource_name));
+    }
+    if resource.problem_json {
+        problem_import = "\nuse crate::errors::problem;";
+        app_data.push_str("\n            .app_data(problem::json_config())");
+        if has_item_routes {
+            app_data.push_str("\n            .app_data(problem::path_config())");
+        }
+    } else if has_item_routes {
+        app_data.push_str("\n            .app_data(web::PathConfig::default().error_handler(|err, _req| actix_web::error::ErrorBadRequest(err)))");
+    }
+
+    // Nested resources are configured inside the parent scope, right after its id segment
+    let scope = match &resource.parent {
+        Some(parent) => format!("/{{{}_id}}/{}", parent, resource.path),
+        None => format!("/{}", resource.path),
+    };
+
+    let actix_import = if resource.content_guards { "{guard, web}" } else { "web" };
+
+    // Deprecated routes answer with `Deprecation`/`Sunset` headers, from dates fixed at generation
+    let deprecation = deprecation(ctx, resource);
+    let (deprecation_imports, deprecation_const) = match deprecation {
+        Some(deprecation) => {
+            app_data.insert_str(0, "\n            .wrap(from_fn(|req, next| DEPRECATION.apply(req, next)))");
+            (
+                "\nuse actix_web::middleware::from_fn;\nuse crate::utils::deprecation::Deprecation;".to_string(),
+                deprecation_const(resource_name, deprecation)?,
+            )
+        }
+        None => (String::new(), String::new()),
+    };
 
+    let mut content = format!(
+        r#"use actix_web::{};{}
+use crate::handlers::{}_handlers;{}
+{}
 pub fn {}_routes(cfg: &mut web::ServiceConfig) {{
     cfg.service(
-        web::scope("/{}")
-            .route("", web::get().to({}_handlers::get_{}))
-            .route("", web::post().to({}_handlers::create_{}))
-            .route("/{{id}}", web::get().to({}_handlers::get_{}))
-            .route("/{{id}}", web::put().to({}_handlers::update_{}))
-            .route("/{{id}}", web::delete().to({}_handlers::delete_{}))
+        web::scope("{}"){}{}
     );
 }}
 "#,
+        actix_import,
+        deprecation_imports,
         resource_name,
+        problem_import,
+        deprecation_const,
         resource_name,
-        resource_name,
-        resource_name, resource_name,
-        resource_name, resource_name,
-        resource_name, resource_name,
-        resource_name, resource_name,
-        resource_name, resource_name
+        scope,
+        app_data,
+        routes
     );
 
-    let file_path = format!("src/routes/{}_routes.rs", resource_name);
-    let mut file = fs::File::create(&file_path).await?;
-    file.write_all(content.as_bytes()).await?;
-    Ok(())
-}
+    // Nested routes are only reachable through the parent's scope
+    let (routes_module, uri) = match &resource.parent {
+        Some(parent) => (resource.module_of(parent), format!("/{}/1/{}", parent, resource.path)),
+        None => (resource_name.clone(), format!("/{}", resource.path)),
+    };
+    let mut tests = Vec::new();
+    if let (Some(parent), true) = (&resource.parent, resource.has(Method::List)) {
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn {resource_name}_routes_are_nested_under_{parent}() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        let req = test::TestRequest::get().uri("{uri}").to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.status().is_success());
+    }}
+"#
+        ));
+    }
+    if resource.content_guards {
+        let pascal_case = &resource.type_name;
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn create_dispatches_on_content_type() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
 
-async fn update_modules(resource_name: &str) -> Result<()> {
-    // Update models/mod.rs
-    let models_mod_path = "src/models/mod.rs";
-    if Path::new(models_mod_path).exists() {
-        let mut models_mod = fs::read_to_string(models_mod_path).await?;
-        if !models_mod.contains(&format!("pub mod {};", resource_name)) {
-            models_mod.push_str(&format!("\npub mod {};", resource_name));
-            fs::write(models_mod_path, models_mod).await?;
-        }
-    }
-    
-    // Update handlers/mod.rs
-    let handlers_mod_path = "src/handlers/mod.rs";
-    if Path::new(handlers_mod_path).exists() {
-        let mut handlers_mod = fs::read_to_string(handlers_mod_path).await?;
-        if !handlers_mod.contains(&format!("pub mod {}_handlers;", resource_name)) {
-            handlers_mod.push_str(&format!("\npub mod {}_handlers;", resource_name));
-            fs::write(handlers_mod_path, handlers_mod).await?;
-        }
-    }
-    
-    // Update routes/mod.rs
-    let routes_mod_path = "src/routes/mod.rs";
-    if Path::new(routes_mod_path).exists() {
-        let mut routes_mod = fs::read_to_string(routes_mod_path).await?;
-        if !routes_mod.contains(&format!("pub mod {}_routes;", resource_name)) {
-            routes_mod.push_str(&format!("\npub mod {}_routes;", resource_name));
-            fs::write(routes_mod_path, routes_mod).await?;
-        }
-    }
-    
-    // Update main routes.rs to include the new routes
-    let routes_file_path = "src/routes/routes.rs";
-    if Path::new(routes_file_path).exists() {
-        let mut routes_file = fs::read_to_string(routes_file_path).await?;
-        
-        if routes_file.contains("pub fn public_routes") && 
-        !routes_file.contains(&format!("{}_routes::{}_routes", resource_name, resource_name)) {
-            
-            // 1. Add the use statement at the top with other use statements
-            let use_statement = format!("use crate::routes::{}_routes;\n", resource_name);
-            
-            // Find a good place to insert the use statement (after the last existing use)
-            if let Some(last_use_pos) = routes_file.rfind("use ") {
-                if let Some(next_newline) = routes_file[last_use_pos..].find('\n') {
-                    let insert_pos = last_use_pos + next_newline + 1;
-                    routes_file.insert_str(insert_pos, &use_statement);
-                }
+        let json = test::TestRequest::post().uri("{uri}").set_json({pascal_case}::default()).to_request();
+        assert_eq!(test::call_service(&app, json).await.status(), StatusCode::CREATED);
+
+        let form = test::TestRequest::post().uri("{uri}").set_form({pascal_case}::default()).to_request();
+        assert_eq!(test::call_service(&app, form).await.status(), StatusCode::CREATED);
+
+        let text = test::TestRequest::post()
+            .uri("{uri}")
+            .insert_header(("content-type", "text/plain"))
+            .set_payload("{{}}")
+            .to_request();
+        assert!(test::call_service(&app, text).await.status().is_client_error());
+    }}
+"#
+        ));
+    }
+    if deprecation.is_some() {
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn {resource_name}_routes_advertise_their_deprecation() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("{uri}").to_request()).await;
+        assert!(res.headers().get("deprecation").is_some_and(|value| value.to_str().unwrap().starts_with('@')));
+{sunset_check}    }}
+"#,
+            sunset_check = if deprecation.is_some_and(|deprecation| deprecation.sunset.is_some()) {
+                "        assert!(res.headers().contains_key(\"sunset\"));\n"
             } else {
-                // If no use statements found, add after the module comments
-                if let Some(mod_end_pos) = routes_file.find("use actix_web::web;") {
-                    let insert_pos = mod_end_pos + "use actix_web::web;".len();
-                    routes_file.insert_str(insert_pos, &format!("\n{}", use_statement));
-                }
+                ""
             }
-            
-            // 2. Add the route configuration inside public_routes scope
-            if let Some(scope_pos) = routes_file.find("web::scope(\"/api\")") {
-                // Find the closing parenthesis of the scope
-                if let Some(scope_end_pos) = find_matching_parenthesis(&routes_file, scope_pos) {
-                    // Look for the closing brace of the service configuration
-                    if let Some(service_end_pos) = routes_file[scope_end_pos..].find(')') {
-                        let insert_pos = scope_end_pos + service_end_pos;
-                        
-                        // Insert before the closing parenthesis of the service call
-                        routes_file.insert_str(insert_pos, 
-                            &format!("\n            .configure({}_routes::{}_routes)", resource_name, resource_name));
-                    }
+        ));
+    }
+    if resource.readonly {
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn only_read_routes_are_registered() {{
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        for (method, uri) in [
+            (Method::POST, "{uri}"),
+            (Method::PUT, "{uri}/1"),
+            (Method::PATCH, "{uri}/1"),
+            (Method::DELETE, "{uri}/1"),
+        ] {{
+            let req = test::TestRequest::default().method(method.clone()).uri(uri).to_request();
+            let status = test::call_service(&app, req).await.status();
+            assert!(
+                status == StatusCode::NOT_FOUND || status == StatusCode::METHOD_NOT_ALLOWED,
+                "{{}} {{}} should not be routed on a read-only resource ({{}})",
+                method, uri, status
+            );
+        }}
+    }}
+"#
+        ));
+    }
+    if resource.route_check {
+        let mut handlers: Vec<String> = Vec::new();
+        let mut requests = String::new();
+        for method in &route_table {
+            let (verb, item) = match method {
+                Method::List => ("GET", false),
+                Method::Create => ("POST", false),
+                Method::Get => ("GET", true),
+                Method::Update => ("PUT", true),
+                Method::Delete => ("DELETE", true),
+            };
+            let handler = resource.handler(*method);
+            let handler_names = if *method == Method::Create && resource.content_guards {
+                vec![format!("{}_json", handler), format!("{}_form", handler)]
+            } else {
+                vec![handler]
+            };
+            for name in handler_names {
+                let path = format!("{}_handlers::{}", resource_name, name);
+                if !handlers.contains(&path) {
+                    handlers.push(path);
                 }
             }
+            let item_uri = if item { format!("{}{}", uri, resource.sample_item_segments()) } else { uri.clone() };
+            requests.push_str(&format!("            (Method::{}, \"{}\"),\n", verb, item_uri));
         }
-        fs::write(routes_file_path, routes_file).await?;
+        // Service and store handlers answer a missing item with 404 too, but never with an empty body
+        let check = if resource.has_storage() {
+            r#"            let res = test::call_service(&app, req).await;
+            let status = res.status();
+            let unrouted = status == StatusCode::METHOD_NOT_ALLOWED
+                || (status == StatusCode::NOT_FOUND && test::read_body(res).await.is_empty());
+            assert!(!unrouted, "{} {} is not routed to a handler ({})", method, uri, status);"#
+        } else {
+            r#"            let status = test::call_service(&app, req).await.status();
+            assert!(
+                status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+                "{} {} is not routed to a handler ({})",
+                method, uri, status
+            );"#
+        };
+        tests.push(format!(
+            r#"
+    #[actix_web::test]
+    async fn every_route_reaches_a_handler() {{
+        // Naming the handlers makes a removed or renamed one fail right here
+        let _handlers = ({});
+
+        let app = test::init_service(App::new().configure({routes_module}_routes)).await;
+        for (method, uri) in [
+{requests}        ] {{
+            let req = test::TestRequest::default()
+                .method(method.clone())
+                .uri(uri)
+                .insert_header(("content-type", "application/json"))
+                .to_request();
+{check}
+        }}
+    }}
+"#,
+            if handlers.len() == 1 { format!("{},", handlers[0]) } else { handlers.join(", ") }
+        ));
     }
-    
-    Ok(())
+    if !tests.is_empty() {
+        let mut actix_items = vec!["test", "App"];
+        let mut imports = String::new();
+        if resource.content_guards || resource.route_check || resource.readonly {
+            actix_items.insert(0, "http::StatusCode");
+        }
+        if resource.route_check || resource.readonly {
+            actix_items.insert(0, "http::Method");
+        }
+        if resource.route_check {
+            imports.push_str(&format!("    use crate::handlers::{}_handlers;\n", resource_name));
+        }
+        if resource.content_guards {
+            imports.push_str(&format!("    use crate::models::{}::{};\n", resource_name, resource.type_name));
+        }
+        let imports = format!("    use actix_web::{{{}}};\n{}", actix_items.join(", "), imports);
+        content.push_str(&format!(
+            "\n#[cfg(test)]\nmod tests {{\n{}    use crate::routes::{}_routes::{}_routes;\n{}}}\n",
+            imports, routes_module, routes_module, tests.join("")
+        ));
+    }
+
+    Layer::Routes.write(ctx, resource_name, &content).await
 }
 
-fn to_pascal_case(s: &str) -> String {
-    let mut result = String::new();
-    let mut capitalize_next = true;
-    
-    for c in s.chars() {
-        if c == '_' || c == '-' {
-            capitalize_next = true;
-        } else if capitalize_next {
-            result.push(c.to_ascii_uppercase());
-            capitalize_next = false;
+/// Registers the generated layers in their `mod.rs`, behind the resource's feature if any, next to its
+/// other declarations so custom items stay where they are. Inline modules are declared by their block
+/// already, which only needs gating
+async fn update_modules(ctx: &ProjectContext, resource: &Resource) -> Result<Vec<WiringWarning>> {
+    let mut warnings = Vec::new();
+    let gate = resource.feature.as_ref().map(|feature| cfg_feature(feature)).unwrap_or_default();
+    for layer in &resource.layers {
+        let mod_path = ctx.mod_file(layer.module());
+        let module = layer.file_name(&resource.name);
+        let declaration = format!("pub mod {};", module);
+
+        if ctx.config().modules_style == ModulesStyle::Inline {
+            let mut mod_file = fs::read_to_string(&mod_path).await?;
+            if let Some((block, _)) = inline_module(&mod_file, &module)
+                && gate_declaration(&mut mod_file, block.start, &gate) {
+                write_if_changed(&mod_path, mod_file).await?;
+            }
+        } else if Path::new(&mod_path).exists() {
+            let mut mod_file = fs::read_to_string(&mod_path).await?;
+            match module_declaration(&mod_file, &module) {
+                Some(start) => {
+                    if gate_declaration(&mut mod_file, start, &gate) {
+                        write_if_changed(&mod_path, mod_file).await?;
+                    }
+                }
+                None => {
+                    insert_module_declaration(&mut mod_file, &gate, &declaration);
+                    write_if_changed(&mod_path, mod_file).await?;
+                }
+            }
         } else {
-            result.push(c);
+            warnings.push(WiringWarning {
+                file: mod_path,
+                missing: "the module file".to_string(),
+                fix: format!("`{}` to it, and `pub mod {};` to {}", declaration, layer.module(), ctx.lib_file()),
+            });
         }
     }
-    result
+
+    Ok(warnings)
 }
 
-fn find_matching_parenthesis(content: &str, start_pos: usize) -> Option<usize> {
-    let mut count = 1;
-    let chars: Vec<char> = content[start_pos..].chars().collect();
-    
-    for (i, c) in chars.iter().enumerate().skip(1) {
-        match c {
-            '(' => count += 1,
-            ')' => {
-                count -= 1;
-                if count == 0 {
-                    return Some(start_pos + i);
-                }
+/// `#[cfg(feature = "...")]` line compiling the next item only with `feature`
+fn cfg_feature(feature: &str) -> String {
+    format!("#[cfg(feature = \"{}\")]\n", feature)
+}
+
+/// Puts `gate` before the declaration starting at `start` unless it already is. Returns whether it was added
+fn gate_declaration(mod_file: &mut String, start: usize, gate: &str) -> bool {
+    if gate.is_empty() || mod_file[..start].ends_with(gate) {
+        return false;
+    }
+    mod_file.insert_str(start, gate);
+    true
+}
+
+/// Lists the documented handlers of a resource in the `ApiDoc` of `--with-openapi` projects
+async fn register_openapi_paths(ctx: &ProjectContext, resource: &Resource) -> Result<Vec<WiringWarning>> {
+    let openapi_path = format!("{}/openapi.rs", ctx.src_dir());
+    let handlers = documented_handlers(resource);
+    let missing = |missing: &str| WiringWarning {
+        file: openapi_path.clone(),
+        missing: missing.to_string(),
+        fix: format!("`{}` to the `paths(...)` of `#[openapi(...)]`", handlers.join("`, `")),
+    };
+
+    if !Path::new(&openapi_path).exists() {
+        return Ok(vec![missing("the OpenAPI document")]);
+    }
+    let mut openapi_file = fs::read_to_string(&openapi_path).await?;
+    if !insert_list_items(&mut openapi_file, "paths(", &handlers) {
+        return Ok(vec![missing("`paths(`")]);
+    }
+    write_if_changed(&openapi_path, openapi_file).await?;
+    Ok(Vec::new())
+}
+
+/// Wires the given resources into the main routes.rs (or their parent's routes module),
+/// reading and writing each file only once. Returns what couldn't be wired for lack of an anchor
+pub async fn register_routes(ctx: &ProjectContext, resources: &[&Resource]) -> Result<Vec<WiringWarning>> {
+    let mut warnings = Vec::new();
+    let routes_file_path = ctx.routes_file();
+    let api_resources: Vec<&&Resource> = resources.iter()
+        .filter(|r| r.parent.is_none() && r.generates(Layer::Routes))
+        .collect();
+    let admin_resources: Vec<&&Resource> = resources.iter().filter(|r| r.admin).collect();
+
+    if Path::new(&routes_file_path).exists() {
+        let mut routes_file = fs::read_to_string(&routes_file_path).await?;
+        for resource in api_resources {
+            let (routes_fn, scope) = if resource.protected {
+                ("pub fn private_routes", "web::scope(\"/private-api\")")
+            } else {
+                ("pub fn public_routes", "web::scope(\"/api\")")
+            };
+            let module = format!("{}_routes", resource.name);
+
+            if !has_code(&routes_file, routes_fn) {
+                warnings.push(WiringWarning {
+                    file: routes_file_path.clone(),
+                    missing: format!("`{}`", routes_fn),
+                    fix: format!("`{}(cfg: &mut web::ServiceConfig)` configuring `{}` inside `{}`", routes_fn, routes_configure(&module), scope),
+                });
+            } else if !configure_routes(&mut routes_file, scope, resource, &module) {
+                warnings.push(WiringWarning {
+                    file: routes_file_path.clone(),
+                    missing: format!("`{}`", scope),
+                    fix: format!("`use crate::routes::{};` and `cfg.service({}{})` to `{}`", module, scope, routes_configure(&module), routes_fn),
+                });
+            }
+        }
+
+        // Admin pages live next to the API, in their own `/admin` scope
+        for resource in admin_resources {
+            if !has_code(&routes_file, ADMIN_SCOPE)
+                && let Some((_, end)) = find_code(&mask_comments(&routes_file), PUBLIC_ROUTES_OPENING) {
+                routes_file.insert_str(end, &format!("\n    cfg.service({});\n", ADMIN_SCOPE));
+            }
+            let module = format!("{}_admin", resource.name);
+            let function = format!("{}_admin_routes", resource.name);
+            if !insert_configure(&mut routes_file, ADMIN_SCOPE, "admin", &module, &function) {
+                warnings.push(WiringWarning {
+                    file: routes_file_path.clone(),
+                    missing: "`pub fn public_routes`".to_string(),
+                    fix: format!(
+                        "`use crate::admin::{};` and `cfg.service({}.configure({}::{}))` to your routes",
+                        module, ADMIN_SCOPE, module, function
+                    ),
+                });
+            }
+        }
+        write_if_changed(&routes_file_path, routes_file).await?;
+    } else if !api_resources.is_empty() || !admin_resources.is_empty() {
+        let configures: Vec<String> = api_resources.iter()
+            .map(|r| routes_configure(&format!("{}_routes", r.name)))
+            .chain(admin_resources.iter().map(|r| format!(".configure({}_admin::{}_admin_routes)", r.name, r.name)))
+            .collect();
+        warnings.push(WiringWarning {
+            file: routes_file_path.clone(),
+            missing: "the routes file".to_string(),
+            fix: format!("it, configuring `{}` in your API scope", configures.join("`, `")),
+        });
+    }
+
+    // Nested resources go into the routes module of their parent
+    for resource in resources.iter().filter(|r| r.generates(Layer::Routes)) {
+        if let Some(parent) = &resource.parent {
+            let parent_module = resource.module_of(parent);
+            let parent_routes_path = Layer::Routes.file_path(ctx, &parent_module);
+            let module = format!("{}_routes", resource.name);
+            let scope = format!("web::scope(\"/{}\")", parent);
+            if !Layer::Routes.exists(ctx, &parent_module) {
+                warnings.push(WiringWarning {
+                    file: parent_routes_path,
+                    missing: "the parent routes file".to_string(),
+                    fix: format!("it with `cargo mold g resource {}`, then configure `{}` inside `{}`", parent, routes_configure(&module), scope),
+                });
+                continue;
+            }
+            let mut parent_routes = ctx.read_module("routes", &Layer::Routes.file_name(&parent_module)).await?;
+            if configure_routes(&mut parent_routes, &scope, resource, &module) {
+                Layer::Routes.write(ctx, &parent_module, &parent_routes).await?;
+            } else {
+                warnings.push(WiringWarning {
+                    file: parent_routes_path,
+                    missing: format!("`{}`", scope),
+                    fix: format!("`use crate::routes::{};` and `{}` inside the parent's scope", module, routes_configure(&module)),
+                });
             }
-            _ => {}
         }
     }
-    None
-}
\ No newline at end of file
+
+    Ok(warnings)
+}
+
+/// Configures the routes module of `resource` inside `scope`, through a gated closure for feature-gated resources
+fn configure_routes(routes_file: &mut String, scope: &str, resource: &Resource, module: &str) -> bool {
+    match &resource.feature {
+        Some(feature) => insert_gated_configure(routes_file, scope, "routes", module, module, feature),
+        None => insert_configure(routes_file, scope, "routes", module, module),
+    }
+}
+
+/// `.configure(...)` call wiring a routes module named after its configure function
+fn routes_configure(module: &str) -> String {
+    format!(".configure({}::{})", module, module)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::utils::project::{ModuleLayout, CONFIG_FILE};
+
+    /// `g resource` as parsed from the command line
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: ResourceArgs,
+    }
+
+    fn resource(args: &[&str]) -> Result<Resource> {
+        Resource::try_from(Cli::parse_from(std::iter::once("resource").chain(args.iter().copied())).args)
+    }
+
+    /// Generates the resource of `args` into an empty project, returning the project and the resource
+    async fn generate(args: &[&str]) -> (tempfile::TempDir, ProjectContext, Resource) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(CONFIG_FILE), "").unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\n").unwrap();
+        let ctx = ProjectContext::load_from(dir.path()).await.unwrap();
+        let resource = resource(args).unwrap();
+        generate_resource(&ctx, &resource).await.unwrap();
+        (dir, ctx, resource)
+    }
+
+    /// Source of the `layer` module generated for `resource`
+    fn read(ctx: &ProjectContext, layer: Layer, resource: &Resource) -> String {
+        std::fs::read_to_string(layer.file_path(ctx, &resource.name)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn generates_inline_enums_defaulting_to_their_first_variant() {
+        let (_dir, ctx, resource) = generate(&["posts", "--fields", "status:enum(Draft,Published)"]).await;
+        let model = read(&ctx, Layer::Model, &resource);
+        assert!(model.contains("    pub status: PostsStatus,\n"), "{}", model);
+        assert!(model.contains("pub enum PostsStatus {\n    #[default]\n    Draft,\n    Published,\n}"), "{}", model);
+        assert!(model.contains("#[serde(rename_all = \"snake_case\")]\npub enum PostsStatus"), "{}", model);
+    }
+
+    #[tokio::test]
+    async fn generates_documented_foreign_keys_for_references() {
+        let (_dir, ctx, resource) = generate(&["posts", "--fields", "title:String", "owner:ref(User)"]).await;
+        let model = read(&ctx, Layer::Model, &resource);
+        assert!(model.contains("    /// Id of the `User` referenced as this item's owner\n    pub user_id: String,\n"), "{}", model);
+    }
+
+    #[test]
+    fn rejects_invalid_enum_variants_and_references() {
+        let error = resource(&["posts", "--fields", "status:enum(Draft,Draft)"]).err().unwrap();
+        assert!(error.to_string().contains("duplicated variant 'Draft'"), "{}", error);
+        let error = resource(&["posts", "--fields", "owner:ref(user)"]).err().unwrap();
+        assert!(error.to_string().contains("'user' is not a valid type name"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn prefixes_files_and_suffixes_types_keeping_routes_plain() {
+        let (_dir, ctx, resource) = generate(&["users", "--fields", "name:String", "--type-suffix", "Entity", "--file-prefix", "api"]).await;
+        assert!(read(&ctx, Layer::Model, &resource).contains("pub struct UsersEntity {"));
+        assert!(read(&ctx, Layer::Handler, &resource).contains("use crate::models::api_users::UsersEntity;"));
+        let routes = read(&ctx, Layer::Routes, &resource);
+        assert!(routes.contains("pub fn api_users_routes(cfg: &mut web::ServiceConfig)"), "{}", routes);
+        assert!(routes.contains("web::scope(\"/users\")"), "{}", routes);
+        assert!(routes.contains(".route(\"\", web::get().to(api_users_handlers::get_api_users))"), "{}", routes);
+    }
+
+    #[test]
+    fn separates_file_prefixes_with_a_single_underscore() {
+        assert_eq!(resource(&["users", "--file-prefix", "api"]).unwrap().name, "api_users");
+        assert_eq!(resource(&["users", "--file-prefix", "api_"]).unwrap().name, "api_users");
+        assert_eq!(resource(&["users", "--file-prefix", "api"]).unwrap().path, "users");
+    }
+
+    #[test]
+    fn rejects_invalid_suffixes_and_prefixes() {
+        let error = resource(&["users", "--type-suffix", "Ent-ity"]).err().unwrap();
+        assert!(error.to_string().starts_with("Invalid type suffix 'Ent-ity'"), "{}", error);
+        let error = resource(&["users", "--file-prefix", "Api"]).err().unwrap();
+        assert!(error.to_string().starts_with("Invalid file prefix 'Api'"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn imports_hash_map_for_map_fields_only() {
+        let (_dir, ctx, resource) = generate(&["posts", "--fields", "tags:Vec<String>", "metadata:Map<String,String>"]).await;
+        let model = read(&ctx, Layer::Model, &resource);
+        assert!(model.starts_with("use std::collections::HashMap;\n\n"), "{}", model);
+        assert!(model.contains("    pub tags: Vec<String>,\n    pub metadata: HashMap<String, String>,\n"), "{}", model);
+
+        let (_dir, ctx, resource) = generate(&["posts", "--fields", "tags:Vec<String>"]).await;
+        assert!(!read(&ctx, Layer::Model, &resource).contains("HashMap"));
+    }
+
+    #[test]
+    fn finds_hash_maps_nested_in_types() {
+        assert!(uses_hash_map("HashMap<String, u32>"));
+        assert!(uses_hash_map("Vec<HashMap<String, bool>>"));
+        assert!(!uses_hash_map("Vec<String>"));
+        assert!(!uses_hash_map("MyHashMap"));
+    }
+
+    #[test]
+    fn samples_collections_from_their_items() {
+        assert_eq!(sample_value("Vec<String>"), "vec![\"sample\".to_string()]");
+        assert_eq!(sample_value("HashMap<String, Vec<u32>>"), "HashMap::from([(\"sample\".to_string(), vec![42])])");
+        assert_eq!(sample_value("Uuid"), "<Uuid>::default()");
+    }
+
+    #[tokio::test]
+    async fn declares_modules_in_either_layout() {
+        let layouts = [
+            (ModuleLayout::Legacy, "src/models/mod.rs", "src/models.rs"),
+            (ModuleLayout::Modern, "src/models.rs", "src/models/mod.rs"),
+        ];
+        for (layout, mod_file, other) in layouts {
+            let dir = tempfile::tempdir().unwrap();
+            let config = ProjectConfig { module_layout: layout, ..ProjectConfig::default() };
+            std::fs::write(dir.path().join(CONFIG_FILE), config.to_file_content().unwrap()).unwrap();
+            std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"app\"\nversion = \"0.1.0\"\n").unwrap();
+            std::fs::create_dir_all(dir.path().join("src/models")).unwrap();
+            std::fs::write(dir.path().join(mod_file), "pub mod user;\n\n/// Shared by the models\npub fn shared() {}\n").unwrap();
+            let ctx = ProjectContext::load_from(dir.path()).await.unwrap();
+            generate_resource(&ctx, &resource(&["posts"]).unwrap()).await.unwrap();
+
+            let declarations = std::fs::read_to_string(dir.path().join(mod_file)).unwrap();
+            assert_eq!(declarations, "pub mod user;\npub mod posts;\n\n/// Shared by the models\npub fn shared() {}\n");
+            assert!(!dir.path().join(other).exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_items_by_each_field_of_a_composite_key() {
+        let (_dir, ctx, resource) = generate(&["memberships", "--fields", "user_id:String", "role_id:u32", "--primary-key", "user_id,role_id"]).await;
+        assert_eq!(resource.item_pattern(), "/{user_id}/{role_id}");
+        let routes = read(&ctx, Layer::Routes, &resource);
+        assert!(routes.contains(".route(\"/{user_id}/{role_id}\", web::get().to(memberships_handlers::find_memberships))"), "{}", routes);
+        let handlers = read(&ctx, Layer::Handler, &resource);
+        assert!(handlers.contains("pub async fn delete_memberships(path: web::Path<(String, u32)>)"), "{}", handlers);
+        let model = read(&ctx, Layer::Model, &resource);
+        assert!(model.contains("pub const PRIMARY_KEY: [&str; 2] = [\"user_id\", \"role_id\"];"), "{}", model);
+    }
+}
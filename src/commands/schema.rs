@@ -0,0 +1,345 @@
+use clap::Args;
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use tokio::fs;
+
+use crate::utils::conversions::{to_pascal_case, to_snake_case};
+use crate::utils::fields::{parse_fields, parse_model, Field, FieldKind};
+use crate::utils::project::{JsonCase, ProjectContext};
+use crate::utils::utils::write_if_changed;
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct SchemaArgs {
+    /// Name of the model, read from its generated file unless `--fields` is given
+    pub name: String,
+    /// Describe these `name:Type` fields instead of the generated model
+    #[arg(long, num_args = 1..)]
+    pub fields: Vec<String>,
+}
+
+/// Dialect declared by the generated schemas
+const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A model as described by its schema
+struct Model {
+    type_name: String,
+    fields: Vec<Field>,
+    /// Serialized values of the enums defined next to the model, by type name
+    enums: BTreeMap<String, Vec<String>>,
+    /// Doc comment of each documented field
+    docs: BTreeMap<String, String>,
+    /// Case of the serialized field names
+    json_case: JsonCase,
+}
+
+pub async fn execute(args: SchemaArgs) -> anyhow::Result<()> {
+    report(format!("🧾 Generating JSON Schema: {}", args.name));
+
+    let ctx = ProjectContext::load().await?;
+    let model = if args.fields.is_empty() {
+        load_model(&ctx, &args.name).await?
+    } else {
+        model_from_fields(&args.name, &args.fields, ctx.config().json_case)?
+    };
+
+    let schema = json_schema(&args.name, &model);
+    let file_path = ctx.path(&format!("schemas/{}.schema.json", args.name));
+    fs::create_dir_all(ctx.path("schemas")).await?;
+    write_if_changed(&file_path, format!("{}\n", serde_json::to_string_pretty(&schema)?)).await?;
+
+    report(format!("✅ JSON Schema of '{}' created successfully!", model.type_name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+
+    Ok(())
+}
+
+async fn load_model(ctx: &ProjectContext, name: &str) -> Result<Model> {
+    let path = ctx.module_location("models", name);
+    let source = ctx.read_module("models", name).await.map_err(|_| {
+        anyhow::anyhow!("❌ Model '{}' not found, generate it with `cargo mold g resource {}` or pass --fields", path, name)
+    })?;
+    let Some((type_name, fields)) = parse_model(&source) else {
+        anyhow::bail!("❌ Could not find a `pub struct` in '{}'", path);
+    };
+
+    Ok(Model {
+        type_name,
+        fields,
+        enums: parse_enums(&source),
+        docs: parse_field_docs(&source),
+        json_case: parse_json_case(&source),
+    })
+}
+
+/// The model `cargo mold g resource <name> --fields ...` would generate
+fn model_from_fields(name: &str, specs: &[String], json_case: JsonCase) -> Result<Model> {
+    let type_name = to_pascal_case(name);
+    let fields = parse_fields(specs, &type_name)?;
+    let mut enums = BTreeMap::new();
+    let mut docs = BTreeMap::new();
+    for field in &fields {
+        match &field.kind {
+            // Generated enums serialize their variants in snake_case
+            FieldKind::Enum(variants) => {
+                enums.insert(field.ty.clone(), variants.iter().map(|variant| to_snake_case(variant)).collect());
+            }
+            FieldKind::Ref { role, target } => {
+                docs.insert(field.name.clone(), format!("Id of the `{}` referenced as this item's {}", target, role));
+            }
+            FieldKind::Plain => {}
+        }
+    }
+    Ok(Model { type_name, fields, enums, docs, json_case })
+}
+
+/// Unit enums of a model file with their serialized values, honouring `rename_all = "snake_case"`
+fn parse_enums(source: &str) -> BTreeMap<String, Vec<String>> {
+    let mut enums = BTreeMap::new();
+    let mut snake_case = false;
+    let mut lines = source.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line.starts_with("#[serde(") && line.contains("rename_all = \"snake_case\"") {
+            snake_case = true;
+            continue;
+        }
+        let Some(name) = line.strip_prefix("pub enum ").and_then(|rest| rest.strip_suffix('{')) else {
+            if !line.starts_with("#[") && !line.starts_with("///") {
+                snake_case = false;
+            }
+            continue;
+        };
+        let mut values = Vec::new();
+        let mut unit = true;
+        for line in lines.by_ref().take_while(|line| *line != "}") {
+            if line.is_empty() || line.starts_with("#[") || line.starts_with("//") {
+                continue;
+            }
+            let variant = line.trim_end_matches(',');
+            unit &= variant.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            values.push(if snake_case { to_snake_case(variant) } else { variant.to_string() });
+        }
+        // Enums carrying data don't serialize as plain strings
+        if unit {
+            enums.insert(name.trim().to_string(), values);
+        }
+        snake_case = false;
+    }
+    enums
+}
+
+/// Case of the field names of the first `pub struct`, camel when it has `rename_all = "camelCase"`
+pub fn parse_json_case(source: &str) -> JsonCase {
+    let attributes = source.lines().map(str::trim).take_while(|line| !line.starts_with("pub struct "));
+    let camel_case = attributes
+        .filter(|line| line.starts_with("#[serde("))
+        .last()
+        .is_some_and(|line| line.contains("rename_all = \"camelCase\""));
+    if camel_case { JsonCase::Camel } else { JsonCase::Snake }
+}
+
+/// Doc comments of the fields of the first `pub struct`, e.g. the relation of a foreign key
+fn parse_field_docs(source: &str) -> BTreeMap<String, String> {
+    let mut docs = BTreeMap::new();
+    let mut lines = source.lines().map(str::trim).skip_while(|line| !line.starts_with("pub struct "));
+    lines.next();
+    let mut doc: Vec<&str> = Vec::new();
+    for line in lines.take_while(|line| *line != "}") {
+        if let Some(text) = line.strip_prefix("///") {
+            doc.push(text.trim());
+        } else if line.starts_with("#[") {
+            continue;
+        } else if let Some((name, _)) = line.strip_prefix("pub ").unwrap_or(line).split_once(':') {
+            if !doc.is_empty() {
+                docs.insert(name.trim().to_string(), doc.join(" "));
+            }
+            doc.clear();
+        }
+    }
+    docs
+}
+
+/// Draft 2020-12 schema of the JSON a model serializes to
+fn json_schema(name: &str, model: &Model) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in &model.fields {
+        let (mut schema, optional) = match generic(&field.ty, "Option") {
+            Some(inner) => (nullable(type_schema(inner, &model.enums)), true),
+            None => (type_schema(&field.ty, &model.enums), false),
+        };
+        if let (Some(doc), Value::Object(schema)) = (model.docs.get(&field.name), &mut schema) {
+            schema.insert("description".to_string(), json!(doc));
+        }
+        let name = model.json_case.field_name(&field.name);
+        if !optional {
+            required.push(name.clone());
+        }
+        properties.insert(name, schema);
+    }
+
+    let mut schema = json!({
+        "$schema": DRAFT_2020_12,
+        "$id": format!("{}.schema.json", name),
+        "title": model.type_name,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    });
+    let defs: Map<String, Value> = model.enums.iter()
+        .map(|(enum_name, values)| (enum_name.clone(), json!({ "type": "string", "enum": values })))
+        .collect();
+    if !defs.is_empty() {
+        schema["$defs"] = Value::Object(defs);
+    }
+    schema
+}
+
+/// `T` when `ty` is `wrapper<T>`, e.g. `Option<String>`
+fn generic<'a>(ty: &'a str, wrapper: &str) -> Option<&'a str> {
+    let (outer, inner) = ty.strip_suffix('>')?.split_once('<')?;
+    (outer.trim().rsplit("::").next() == Some(wrapper)).then_some(inner.trim())
+}
+
+/// The schema of `schema` or `null`
+fn nullable(mut schema: Value) -> Value {
+    match schema.get("type").and_then(Value::as_str).map(str::to_string) {
+        Some(ty) => {
+            schema["type"] = json!([ty, "null"]);
+            schema
+        }
+        None => json!({ "anyOf": [schema, { "type": "null" }] }),
+    }
+}
+
+/// Schema of a Rust type as serde serializes it. Enums of the model are referenced from `$defs`,
+/// other types are assumed to be models with their own `{name}.schema.json`
+fn type_schema(ty: &str, enums: &BTreeMap<String, Vec<String>>) -> Value {
+    let ty = ty.trim();
+    if let Some(inner) = generic(ty, "Vec").or_else(|| generic(ty, "VecDeque")) {
+        return json!({ "type": "array", "items": type_schema(inner, enums) });
+    }
+    if let Some(inner) = generic(ty, "HashSet").or_else(|| generic(ty, "BTreeSet")) {
+        return json!({ "type": "array", "items": type_schema(inner, enums), "uniqueItems": true });
+    }
+    if let Some(entries) = generic(ty, "HashMap").or_else(|| generic(ty, "BTreeMap")) {
+        let value = entries.split_once(',').map_or(entries, |(_, value)| value);
+        return json!({ "type": "object", "additionalProperties": type_schema(value, enums) });
+    }
+    if let Some(inner) = generic(ty, "Box") {
+        return type_schema(inner, enums);
+    }
+    if let Some(inner) = generic(ty, "Option") {
+        return nullable(type_schema(inner, enums));
+    }
+    if generic(ty, "DateTime").is_some() {
+        return json!({ "type": "string", "format": "date-time" });
+    }
+
+    let base = ty.rsplit("::").next().unwrap_or(ty);
+    let integer = |minimum: i64, maximum: i64| json!({ "type": "integer", "minimum": minimum, "maximum": maximum });
+    match base {
+        "String" | "str" | "&str" => json!({ "type": "string" }),
+        "char" => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        "bool" => json!({ "type": "boolean" }),
+        "f32" | "f64" => json!({ "type": "number" }),
+        "i8" => integer(i8::MIN.into(), i8::MAX.into()),
+        "i16" => integer(i16::MIN.into(), i16::MAX.into()),
+        "i32" => integer(i32::MIN.into(), i32::MAX.into()),
+        "u8" => integer(0, u8::MAX.into()),
+        "u16" => integer(0, u16::MAX.into()),
+        "u32" => integer(0, u32::MAX.into()),
+        "i64" | "i128" | "isize" => json!({ "type": "integer" }),
+        "u64" | "u128" | "usize" => json!({ "type": "integer", "minimum": 0 }),
+        "NaiveDate" => json!({ "type": "string", "format": "date" }),
+        "NaiveTime" => json!({ "type": "string", "format": "time" }),
+        "NaiveDateTime" => json!({ "type": "string" }),
+        "Uuid" => json!({ "type": "string", "format": "uuid" }),
+        "Value" => json!({}),
+        name if enums.contains_key(name) => json!({ "$ref": format!("#/$defs/{}", name) }),
+        name => json!({ "$ref": format!("{}.schema.json", to_snake_case(name)) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_of(specs: &[&str]) -> Value {
+        let specs: Vec<String> = specs.iter().map(|spec| spec.to_string()).collect();
+        json_schema("posts", &model_from_fields("posts", &specs, JsonCase::Snake).unwrap())
+    }
+
+    #[test]
+    fn describes_a_known_field_set() {
+        let schema = schema_of(&["title:String", "views:u32", "summary:Option<String>", "status:enum(Draft,Published)", "author:ref(User)"]);
+        assert_eq!(schema, json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "posts.schema.json",
+            "title": "Posts",
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "views": { "type": "integer", "minimum": 0, "maximum": 4294967295u32 },
+                "summary": { "type": ["string", "null"] },
+                "status": { "$ref": "#/$defs/PostsStatus" },
+                "user_id": { "type": "string", "description": "Id of the `User` referenced as this item's author" },
+            },
+            "required": ["title", "views", "status", "user_id"],
+            "$defs": {
+                "PostsStatus": { "type": "string", "enum": ["draft", "published"] },
+            },
+        }));
+        jsonschema::draft202012::meta::validate(&schema).unwrap();
+    }
+
+    #[test]
+    fn accepts_only_instances_of_the_model() {
+        let schema = schema_of(&["title:String", "tags:Vec<String>", "status:enum(Draft,Published)"]);
+        let validator = jsonschema::draft202012::new(&schema).unwrap();
+        assert!(validator.is_valid(&json!({ "title": "Hello", "tags": ["rust"], "status": "draft" })));
+        assert!(!validator.is_valid(&json!({ "title": "Hello", "tags": ["rust"], "status": "archived" })));
+        assert!(!validator.is_valid(&json!({ "title": "Hello", "status": "draft" })));
+    }
+
+    #[test]
+    fn maps_collections_and_other_models() {
+        let enums = BTreeMap::new();
+        assert_eq!(type_schema("Vec<u64>", &enums), json!({ "type": "array", "items": { "type": "integer", "minimum": 0 } }));
+        assert_eq!(type_schema("HashSet<String>", &enums), json!({ "type": "array", "items": { "type": "string" }, "uniqueItems": true }));
+        assert_eq!(type_schema("HashMap<String, bool>", &enums), json!({ "type": "object", "additionalProperties": { "type": "boolean" } }));
+        assert_eq!(type_schema("chrono::DateTime<Utc>", &enums), json!({ "type": "string", "format": "date-time" }));
+        assert_eq!(type_schema("UserProfile", &enums), json!({ "$ref": "user_profile.schema.json" }));
+        assert_eq!(type_schema("Option<UserProfile>", &enums), json!({ "anyOf": [{ "$ref": "user_profile.schema.json" }, { "type": "null" }] }));
+    }
+
+    #[test]
+    fn reads_enums_docs_and_case_from_a_model_file() {
+        let source = "\
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = \"camelCase\")]
+pub struct Post {
+    /// Id of the `User` referenced as this item's author
+    pub user_id: String,
+    pub status: PostStatus,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = \"snake_case\")]
+pub enum PostStatus {
+    #[default]
+    InReview,
+    Published,
+}
+
+pub enum Shape {
+    Circle(f64),
+}
+";
+        assert_eq!(parse_json_case(source), JsonCase::Camel);
+        assert_eq!(parse_enums(source), BTreeMap::from([("PostStatus".to_string(), vec!["in_review".to_string(), "published".to_string()])]));
+        assert_eq!(parse_field_docs(source), BTreeMap::from([("user_id".to_string(), "Id of the `User` referenced as this item's author".to_string())]));
+    }
+}
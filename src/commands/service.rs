@@ -1,13 +1,111 @@
 use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::commands::resource::NamingArgs;
+use crate::utils::output::report;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
 
 #[derive(Args)]
 pub struct ServiceArgs {
     /// Name of the service
     pub name: String,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+    #[command(flatten)]
+    pub naming: NamingArgs,
 }
 
 pub async fn execute(args: ServiceArgs) -> anyhow::Result<()> {
-    println!("🛠️ Generating service: {}", args.name);
-    // Your service generation logic here
+    report(format!("🛠️ Generating service: {}", args.name));
+
+    let ctx = ProjectContext::load().await?;
+
+    let module = format!("{}_service", args.name);
+    let file_path = ctx.module_file("services", &module);
+    fs::create_dir_all(ctx.module_dir("services")).await?;
+    generate_service(&ctx, &args.name, &args.naming.type_name(&args.name), &file_path).await?;
+    ctx.register("services", &module).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Service '{}' created successfully!", args.name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+
+    Ok(())
+}
+
+/// Writes the service to `file_path`, layered on the repository of the same name when it exists
+pub async fn generate_service(ctx: &ProjectContext, service_name: &str, pascal_case: &str, file_path: &str) -> Result<()> {
+    let repository_path = ctx.module_file("repositories", &format!("{}_repository", service_name));
+
+    // Layer the service on top of the repository when one was generated for this name
+    let content = if Path::new(&repository_path).exists() {
+        format!(
+            r#"use crate::models::{service_name}::{pascal_case};
+use crate::repositories::{service_name}_repository::{pascal_case}Repository;
+
+/// Business logic for `{pascal_case}`, backed by a `{pascal_case}Repository`
+pub struct {pascal_case}Service<R: {pascal_case}Repository> {{
+    repository: R,
+}}
+
+impl<R: {pascal_case}Repository> {pascal_case}Service<R> {{
+    pub fn new(repository: R) -> Self {{
+        Self {{ repository }}
+    }}
+
+    pub async fn list(&self) -> Result<Vec<{pascal_case}>, R::Error> {{
+        self.repository.find_all().await
+    }}
+
+    /// `limit` items after the first `offset`, for a page of the list
+    pub async fn list_page(&self, offset: usize, limit: usize) -> Result<Vec<{pascal_case}>, R::Error> {{
+        self.repository.find_page(offset, limit).await
+    }}
+
+    pub async fn count(&self) -> Result<usize, R::Error> {{
+        self.repository.count().await
+    }}
+
+    pub async fn get(&self, id: &str) -> Result<Option<{pascal_case}>, R::Error> {{
+        self.repository.find_by_id(id).await
+    }}
+
+    pub async fn create(&self, id: String, entity: {pascal_case}) -> Result<{pascal_case}, R::Error> {{
+        self.repository.create(id, entity).await
+    }}
+
+    pub async fn update(&self, id: &str, entity: {pascal_case}) -> Result<Option<{pascal_case}>, R::Error> {{
+        self.repository.update(id, entity).await
+    }}
+
+    pub async fn delete(&self, id: &str) -> Result<bool, R::Error> {{
+        self.repository.delete(id).await
+    }}
+}}
+"#
+        )
+    } else {
+        format!(
+            r#"/// Business logic for `{pascal_case}`
+#[derive(Clone, Default)]
+pub struct {pascal_case}Service;
+
+impl {pascal_case}Service {{
+    pub fn new() -> Self {{
+        Self
+    }}
+}}
+"#
+        )
+    };
+
+    write_if_changed(file_path, content).await?;
     Ok(())
 }
@@ -0,0 +1,195 @@
+use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::commands::resource::{print_wiring_warnings, WiringWarning};
+use crate::utils::manifest::Manifest;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
+use crate::utils::output::report;
+use crate::utils::wiring::insert_configure;
+
+#[derive(Args)]
+pub struct SseArgs {
+    /// Name of the event stream, served at `/api/<name>`
+    pub name: String,
+    /// Overwrite the stream if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+/// Requirement added to the project's `[dependencies]` for building the response stream
+const FUTURES_UTIL_DEPENDENCY: &str = r#""0.3""#;
+
+pub async fn execute(args: SseArgs) -> anyhow::Result<()> {
+    report(format!("📡 Generating event stream: {}", args.name));
+
+    let name = args.name.replace('-', "_").to_lowercase();
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        anyhow::bail!("❌ Invalid stream name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = ctx.module_file("sse", &name);
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    let mut manifest = Manifest::load(&ctx).await?;
+    let futures_added = manifest.add_dependency("futures-util", FUTURES_UTIL_DEPENDENCY)?;
+
+    fs::create_dir_all(ctx.module_dir("sse")).await?;
+    generate_sse(&name, &file_path).await?;
+    ctx.register("sse", &name).await?;
+    manifest.save().await?;
+    let warnings = register_sse_routes(&ctx, &name).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Event stream '{}' created successfully!", name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    if futures_added {
+        report(format!("📦 Added futures-util to Cargo.toml: futures-util = {}", FUTURES_UTIL_DEPENDENCY));
+    }
+    report(format!("📣 Emit events with `crate::sse::{}::push(...)`, clients listen on GET /api/{}", name, name));
+    print_wiring_warnings(&warnings);
+
+    Ok(())
+}
+
+/// Configures the stream's routes in the `/api` scope of `routes.rs`
+async fn register_sse_routes(ctx: &ProjectContext, name: &str) -> Result<Vec<WiringWarning>> {
+    let routes_file_path = ctx.routes_file();
+    let function = format!("{}_sse_routes", name);
+    let warning = |missing: &str| WiringWarning {
+        file: routes_file_path.clone(),
+        missing: missing.to_string(),
+        fix: format!("`use crate::sse::{};` and `.configure({}::{})` inside `web::scope(\"/api\")`", name, name, function),
+    };
+
+    if !Path::new(&routes_file_path).exists() {
+        return Ok(vec![warning("the routes file")]);
+    }
+    let mut routes_file = fs::read_to_string(&routes_file_path).await?;
+    if !insert_configure(&mut routes_file, "web::scope(\"/api\")", "sse", name, &function) {
+        return Ok(vec![warning("`web::scope(\"/api\")`")]);
+    }
+    write_if_changed(&routes_file_path, routes_file).await?;
+    Ok(Vec::new())
+}
+
+/// Writes the broadcast channel of the stream, its `push` function and the `text/event-stream` handler
+async fn generate_sse(name: &str, file_path: &str) -> Result<()> {
+    let content = format!(
+        r#"// `{name}` Server-Sent Events: clients GET the stream, the server emits with `push`
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use actix_web::web::{{self, Bytes}};
+use actix_web::HttpResponse;
+use futures_util::stream;
+use tokio::sync::broadcast::{{self, error::RecvError}};
+use tokio::time::{{interval_at, Instant}};
+
+/// Events kept for clients that fall behind; slower ones skip the oldest
+const CAPACITY: usize = 64;
+/// Interval of the comments keeping idle connections open through proxies
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Shared by every worker, so events pushed from anywhere in the process reach every client
+static {upper}_EVENTS: OnceLock<broadcast::Sender<String>> = OnceLock::new();
+
+fn sender() -> &'static broadcast::Sender<String> {{
+    {upper}_EVENTS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}}
+
+/// Sends `data` as a `{name}` event to the connected clients, returning how many there are
+pub fn push(data: impl Into<String>) -> usize {{
+    sender().send(data.into()).unwrap_or(0)
+}}
+
+/// `data` as a `{name}` event, one `data:` line per line of it
+fn frame(data: &str) -> Bytes {{
+    let mut frame = String::from("event: {name}\n");
+    for line in data.split('\n') {{
+        frame.push_str("data: ");
+        frame.push_str(line);
+        frame.push('\n');
+    }}
+    frame.push('\n');
+    Bytes::from(frame)
+}}
+
+/// Streams every event pushed after the client connected
+pub async fn stream_{name}() -> HttpResponse {{
+    let events = sender().subscribe();
+    let keep_alive = interval_at(Instant::now() + KEEP_ALIVE, KEEP_ALIVE);
+    let body = stream::unfold((events, keep_alive), |(mut events, mut keep_alive)| async move {{
+        let chunk = loop {{
+            tokio::select! {{
+                event = events.recv() => match event {{
+                    Ok(data) => break frame(&data),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }},
+                _ = keep_alive.tick() => break Bytes::from_static(b": keep-alive\n\n"),
+            }}
+        }};
+        Some((Ok::<_, actix_web::Error>(chunk), (events, keep_alive)))
+    }});
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(body)
+}}
+
+pub fn {name}_sse_routes(cfg: &mut web::ServiceConfig) {{
+    cfg.route("/{name}", web::get().to(stream_{name}));
+}}
+
+#[cfg(test)]
+mod tests {{
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use actix_web::body::MessageBody;
+    use actix_web::http::header::CONTENT_TYPE;
+    use actix_web::test::{{call_service, init_service, TestRequest}};
+    use actix_web::App;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn {name}_streams_pushed_events() {{
+        let app = init_service(App::new().configure({name}_sse_routes)).await;
+        let res = call_service(&app, TestRequest::get().uri("/{name}").to_request()).await;
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "text/event-stream");
+
+        assert!(push("{{\"id\":1}}") >= 1);
+        let mut body = res.into_body();
+        let chunk = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await.unwrap().unwrap();
+        assert_eq!(&chunk[..], b"event: {name}\ndata: {{\"id\":1}}\n\n");
+    }}
+
+    #[test]
+    fn multiline_data_gets_a_line_each() {{
+        assert_eq!(&frame("a\nb")[..], b"event: {name}\ndata: a\ndata: b\n\n");
+    }}
+}}
+"#,
+        upper = name.to_uppercase(),
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
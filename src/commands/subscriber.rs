@@ -0,0 +1,258 @@
+use clap::{Args, ValueEnum};
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::utils::fields::parse_model;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{document_env, fix_generated, write_if_changed};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct SubscriberArgs {
+    /// Name of the subscriber, also used as the queue or topic name
+    pub name: String,
+    /// Message broker to consume from
+    #[arg(long, value_enum, default_value_t = Broker::Rabbitmq)]
+    pub broker: Broker,
+    /// Model the messages are deserialized into (defaults to the model named like the subscriber, if any)
+    #[arg(long)]
+    pub model: Option<String>,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Broker {
+    /// RabbitMQ through lapin
+    Rabbitmq,
+    /// Kafka through rdkafka
+    Kafka,
+}
+
+impl Broker {
+    fn dependency(&self) -> &'static str {
+        match self {
+            Broker::Rabbitmq => "lapin = \"2.5\"",
+            Broker::Kafka => "rdkafka = { version = \"0.36\", features = [\"tokio\"] }",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Broker::Rabbitmq => "RabbitMQ",
+            Broker::Kafka => "Kafka",
+        }
+    }
+
+    /// Environment variable holding the broker address, with its default
+    fn address_env(&self) -> (&'static str, &'static str) {
+        match self {
+            Broker::Rabbitmq => ("AMQP_URL", "amqp://127.0.0.1:5672/%2f"),
+            Broker::Kafka => ("KAFKA_BROKERS", "localhost:9092"),
+        }
+    }
+}
+
+pub async fn execute(args: SubscriberArgs) -> anyhow::Result<()> {
+    report(format!("📨 Generating subscriber: {}", args.name));
+
+    let ctx = ProjectContext::load().await?;
+
+    let message_type = resolve_message_type(&ctx, &args).await?;
+    let file_path = ctx.module_file("subscribers", &args.name);
+
+    fs::create_dir_all(ctx.module_dir("subscribers")).await?;
+    generate_subscriber(&args, message_type.as_ref(), &file_path).await?;
+    ctx.register("subscribers", &args.name).await?;
+    spawn_on_startup(&ctx, &args.name).await?;
+    let (env_var, default_address) = args.broker.address_env();
+    document_env(&ctx, args.broker.title(), env_var, default_address).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Subscriber '{}' created successfully!", args.name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    report("📦 Add the broker client to your Cargo.toml:");
+    report(format!("   {}", args.broker.dependency()));
+    report("   futures = \"0.3\"");
+
+    Ok(())
+}
+
+/// The `(model module, type name)` messages are decoded into, `None` for raw JSON values
+async fn resolve_message_type(ctx: &ProjectContext, args: &SubscriberArgs) -> Result<Option<(String, String)>> {
+    let model = match &args.model {
+        Some(model) => model.clone(),
+        None if ctx.has_module("models", &args.name) => args.name.clone(),
+        None => return Ok(None),
+    };
+
+    let path = ctx.module_location("models", &model);
+    let source = ctx.read_module("models", &model).await.map_err(|_| {
+        anyhow::anyhow!("❌ Model '{}' not found, generate it with `cargo mold g resource {}`", path, model)
+    })?;
+    match parse_model(&source) {
+        Some((type_name, _)) => Ok(Some((model, type_name))),
+        None => anyhow::bail!("❌ Could not find a `pub struct` in '{}'", path),
+    }
+}
+
+async fn generate_subscriber(args: &SubscriberArgs, message_type: Option<&(String, String)>, file_path: &str) -> Result<()> {
+    let name = &args.name;
+    let (env_var, default_address) = args.broker.address_env();
+
+    let (model_import, message, sample) = match message_type {
+        Some((module, type_name)) => (
+            format!("use crate::models::{}::{};\n", module, type_name),
+            type_name.clone(),
+            format!("{}::default()", type_name),
+        ),
+        None => (String::new(), "serde_json::Value".to_string(), "serde_json::json!({ \"id\": 1 })".to_string()),
+    };
+
+    let (broker_imports, consume) = match args.broker {
+        Broker::Rabbitmq => (
+            "use lapin::options::{BasicAckOptions, BasicConsumeOptions, BasicNackOptions, QueueDeclareOptions};\n\
+             use lapin::types::FieldTable;\n\
+             use lapin::{Connection, ConnectionProperties};\n",
+            format!(
+                r#"/// Consumes the `{name}` queue, acking handled messages and rejecting undecodable ones
+pub async fn consume() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {{
+    let address = std::env::var("{env_var}").unwrap_or_else(|_| "{default_address}".to_string());
+    let connection = Connection::connect(&address, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel
+        .queue_declare(QUEUE, QueueDeclareOptions::default(), FieldTable::default())
+        .await?;
+
+    let mut consumer = channel
+        .basic_consume(QUEUE, CONSUMER_TAG, BasicConsumeOptions::default(), FieldTable::default())
+        .await?;
+    while let Some(delivery) = consumer.next().await {{
+        let delivery = delivery?;
+        match decode(&delivery.data) {{
+            Ok(message) => {{
+                handle(message).await;
+                delivery.ack(BasicAckOptions::default()).await?;
+            }}
+            Err(e) => {{
+                eprintln!("⚠️ Dropping undecodable {name} message: {{}}", e);
+                delivery.nack(BasicNackOptions::default()).await?;
+            }}
+        }}
+    }}
+    Ok(())
+}}
+"#
+            ),
+        ),
+        Broker::Kafka => (
+            "use rdkafka::config::ClientConfig;\n\
+             use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};\n\
+             use rdkafka::Message;\n",
+            format!(
+                r#"/// Consumes the `{name}` topic, committing each message once handled or skipped
+pub async fn consume() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {{
+    let brokers = std::env::var("{env_var}").unwrap_or_else(|_| "{default_address}".to_string());
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", &brokers)
+        .set("group.id", CONSUMER_TAG)
+        .set("enable.auto.commit", "false")
+        .create()?;
+    consumer.subscribe(&[QUEUE])?;
+
+    let mut stream = consumer.stream();
+    while let Some(message) = stream.next().await {{
+        let message = message?;
+        match message.payload().map(decode) {{
+            Some(Ok(decoded)) => handle(decoded).await,
+            Some(Err(e)) => eprintln!("⚠️ Skipping undecodable {name} message: {{}}", e),
+            None => eprintln!("⚠️ Skipping empty {name} message"),
+        }}
+        consumer.commit_message(&message, CommitMode::Async)?;
+    }}
+    Ok(())
+}}
+"#
+            ),
+        ),
+    };
+
+    let content = format!(
+        r#"// `{name}` message consumer, started with the server
+use futures::StreamExt;
+{broker_imports}
+{model_import}
+/// Queue or topic consumed by this subscriber
+pub const QUEUE: &str = "{name}";
+/// Consumer tag (RabbitMQ) or group id (Kafka)
+const CONSUMER_TAG: &str = concat!(env!("CARGO_PKG_NAME"), "-{name}");
+
+/// Starts consuming in the background, logging the error if the consumer stops
+pub fn spawn() {{
+    tokio::spawn(async {{
+        if let Err(e) = consume().await {{
+            eprintln!("❌ {name} subscriber stopped: {{}}", e);
+        }}
+    }});
+}}
+
+{consume}
+/// Deserializes a JSON message payload
+pub fn decode(payload: &[u8]) -> Result<{message}, serde_json::Error> {{
+    serde_json::from_slice(payload)
+}}
+
+/// Processes one decoded message
+pub async fn handle(message: {message}) {{
+    // TODO: handle the message
+    println!("📨 {name}: {{:?}}", message);
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn decodes_json_messages() {{
+        let payload = serde_json::to_vec(&{sample}).unwrap();
+        assert!(decode(&payload).is_ok());
+        assert!(decode(b"not json").is_err());
+    }}
+}}
+"#
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
+
+/// Starts the subscriber from `server.rs`, right before the server itself
+async fn spawn_on_startup(ctx: &ProjectContext, name: &str) -> Result<()> {
+    let server_path = ctx.server_file();
+    if !Path::new(&server_path).exists() {
+        report(format!("⚠️ {} not found, call `subscribers::{}::spawn()` at startup yourself", server_path, name));
+        return Ok(());
+    }
+
+    let mut server = fs::read_to_string(&server_path).await?;
+    let spawn_call = format!("crate::subscribers::{}::spawn();", name);
+    if server.contains(&spawn_call) {
+        return Ok(());
+    }
+
+    let anchor = server.find("    println!(\"🚀 Starting")
+        .or_else(|| server.find("pub async fn run()").and_then(|pos| server[pos..].find('\n').map(|end| pos + end + 1)));
+    match anchor {
+        Some(pos) => {
+            server.insert_str(pos, &format!("    {}\n", spawn_call));
+            write_if_changed(&server_path, server).await?;
+        }
+        None => report(format!("⚠️ Could not find `run` in {}, call `subscribers::{}::spawn()` at startup yourself", server_path, name)),
+    }
+    Ok(())
+}
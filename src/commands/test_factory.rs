@@ -0,0 +1,304 @@
+use clap::Args;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+use tokio::fs;
+
+use crate::utils::fields::{parse_fields, parse_model, Field};
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, register_module, write_if_changed};
+use crate::utils::output::report;
+use crate::utils::wiring::{insert_module_declaration, module_declaration};
+
+#[derive(Args)]
+pub struct TestFactoryArgs {
+    /// Name of the model the factory builds, read from its generated file
+    pub name: String,
+    /// Only let the factory set these `name:Type` fields of the model, `name:Type=value` picking their default
+    #[arg(long, num_args = 1..)]
+    pub fields: Vec<String>,
+    /// Overwrite the factory if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+/// Top-level module of the factories, only compiled for the tests
+const TEST_SUPPORT_MODULE: &str = "test_support";
+
+pub async fn execute(args: TestFactoryArgs) -> anyhow::Result<()> {
+    report(format!("🏭 Generating test factory: {}", args.name));
+
+    let ctx = ProjectContext::load().await?;
+    let path = ctx.module_location("models", &args.name);
+    let source = ctx.read_module("models", &args.name).await.map_err(|_| {
+        anyhow::anyhow!("❌ Model '{}' not found, generate it with `cargo mold g resource {}`", path, args.name)
+    })?;
+    let Some((type_name, model_fields)) = parse_model(&source) else {
+        anyhow::bail!("❌ Could not find a `pub struct` in '{}'", path);
+    };
+    if model_fields.is_empty() {
+        anyhow::bail!("❌ '{}' has no public fields to set, read-only models can only be built through their `new`", type_name);
+    }
+
+    let fields = if args.fields.is_empty() {
+        model_fields.clone()
+    } else {
+        let fields = parse_fields(&args.fields, &type_name)?;
+        if let Some(unknown) = fields.iter().find(|field| !model_fields.iter().any(|model_field| model_field.name == field.name)) {
+            anyhow::bail!("❌ '{}' has no public field '{}'", type_name, unknown.name);
+        }
+        fields
+    };
+
+    let file_path = ctx.module_file(TEST_SUPPORT_MODULE, &format!("{}_factory", args.name));
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    fs::create_dir_all(ctx.module_dir(TEST_SUPPORT_MODULE)).await?;
+    let factory = Factory {
+        name: &args.name,
+        type_name: &type_name,
+        fields: &fields,
+        // The rest of the model keeps its `Default`
+        partial: fields.len() < model_fields.len(),
+    };
+    generate_factory(&factory, &source, &file_path).await?;
+    register_test_support(&ctx).await?;
+    register_module(&ctx.mod_file(TEST_SUPPORT_MODULE), &format!("{}_factory", args.name)).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Test factory '{}Factory' created successfully!", type_name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    report(format!(
+        "🧪 Build test data with `crate::test_support::{}_factory::{}Factory::new().with_{}(...).build()`",
+        args.name, type_name, fields[0].name
+    ));
+
+    Ok(())
+}
+
+/// Declares `test_support` in `lib.rs` behind `#[cfg(test)]`, so release builds leave the factories out
+async fn register_test_support(ctx: &ProjectContext) -> Result<()> {
+    let lib_path = ctx.lib_file();
+    let mut lib_file = fs::read_to_string(&lib_path).await.unwrap_or_default();
+    if module_declaration(&lib_file, TEST_SUPPORT_MODULE).is_none() {
+        insert_module_declaration(&mut lib_file, "#[cfg(test)]\n", &format!("pub mod {};", TEST_SUPPORT_MODULE));
+        write_if_changed(&lib_path, lib_file).await?;
+    }
+    Ok(())
+}
+
+/// What a factory builds
+struct Factory<'a> {
+    name: &'a str,
+    type_name: &'a str,
+    fields: &'a [Field],
+    /// Whether the factory only sets some of the model's fields
+    partial: bool,
+}
+
+/// Whether the field holds an id, which the factory numbers
+fn is_id(field: &Field) -> bool {
+    field.name == "id" || field.name.ends_with("_id")
+}
+
+fn is_integer(ty: &str) -> bool {
+    matches!(ty, "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize")
+}
+
+/// Whether the factory starts the field with a value made from the item's number
+fn is_numbered(field: &Field) -> bool {
+    field.default.is_none() && (field.ty == "String" || (is_id(field) && is_integer(&field.ty)))
+}
+
+/// Value the factory starts a field with: its `--fields` default, or one telling the built items apart,
+/// `n` being the number of the item
+fn factory_value(field: &Field) -> String {
+    if let Some(default) = &field.default {
+        return default.clone();
+    }
+    match field.ty.as_str() {
+        "String" if is_id(field) => "n.to_string()".to_string(),
+        "String" => format!("format!(\"{} {{}}\", n)", field.name.replace('_', " ")),
+        "u64" if is_id(field) => "n".to_string(),
+        ty if is_integer(ty) && is_id(field) => format!("n as {}", ty),
+        ty if is_integer(ty) => "1".to_string(),
+        "f32" | "f64" => "1.0".to_string(),
+        "bool" => "false".to_string(),
+        "char" => "'a'".to_string(),
+        // Timestamps start at the time the item is built, like the model's `new` sets them
+        "DateTime<Utc>" => "Utc::now()".to_string(),
+        "DateTime<Local>" => "Local::now()".to_string(),
+        "DateTime<FixedOffset>" => "chrono::Local::now().fixed_offset()".to_string(),
+        "NaiveDateTime" => "chrono::Local::now().naive_local()".to_string(),
+        _ => "Default::default()".to_string(),
+    }
+}
+
+/// `use` lines bringing the types of the factory in scope: the model and the types defined next to it
+/// from its module, the others through the model's own imports
+fn imports(factory: &Factory<'_>, source: &str) -> Vec<String> {
+    let identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let used: BTreeSet<&str> = factory.fields.iter()
+        .flat_map(|field| field.ty.split(|c: char| !identifier(c)))
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    let mut external = Vec::new();
+    let mut local = vec![factory.type_name.to_string()];
+    // Top-level items only, they aren't indented
+    for line in source.lines() {
+        if let Some(defined) = line.strip_prefix("pub enum ").or_else(|| line.strip_prefix("pub struct ")) {
+            let defined = defined.split(|c: char| !identifier(c)).next().unwrap_or_default();
+            if defined != factory.type_name && used.contains(defined) {
+                local.push(defined.to_string());
+            }
+        } else if let Some(path) = line.strip_prefix("use ").and_then(|rest| rest.trim_end().strip_suffix(';')) {
+            let (prefix, names) = match path.split_once('{') {
+                Some((prefix, names)) => (prefix, names.trim_end_matches('}')),
+                None => path.rsplit_once("::").map_or(("", path), |(prefix, name)| (&path[..prefix.len() + 2], name)),
+            };
+            let names: Vec<&str> = names.split(',').map(str::trim).filter(|name| used.contains(name)).collect();
+            match names.as_slice() {
+                [] => {}
+                [name] => external.push(format!("use {}{};", prefix, name)),
+                names => external.push(format!("use {}{{{}}};", prefix, names.join(", "))),
+            }
+        }
+    }
+
+    let model = match local.as_slice() {
+        [name] => format!("use crate::models::{}::{};", factory.name, name),
+        names => format!("use crate::models::{}::{{{}}};", factory.name, names.join(", ")),
+    };
+    if !external.is_empty() {
+        external.push(String::new());
+    }
+    external.push(model);
+    external
+}
+
+/// Value a test overrides the field with, and the assertion checking it on `item`
+fn override_value(field: &Field) -> Option<(String, String)> {
+    let name = &field.name;
+    match field.ty.as_str() {
+        "String" => Some(("\"override\"".to_string(), format!("assert_eq!(item.{}, \"override\");", name))),
+        "bool" => Some(("true".to_string(), format!("assert!(item.{});", name))),
+        ty if is_integer(ty) => Some(("7".to_string(), format!("assert_eq!(item.{}, 7);", name))),
+        "f32" | "f64" => Some(("2.5".to_string(), format!("assert_eq!(item.{}, 2.5);", name))),
+        "char" => Some(("'z'".to_string(), format!("assert_eq!(item.{}, 'z');", name))),
+        _ => None,
+    }
+}
+
+/// Writes the builder starting from sensible values, its `with_*` methods and `build`
+async fn generate_factory(factory: &Factory<'_>, source: &str, file_path: &str) -> Result<()> {
+    let type_name = factory.type_name;
+    let fields = factory.fields;
+    let values: Vec<String> = fields.iter().map(factory_value).collect();
+    let numbered = fields.iter().any(is_numbered);
+
+    let struct_fields: String = fields.iter().map(|field| format!("    {}: {},\n", field.name, field.ty)).collect();
+    let initializers: Vec<String> = fields.iter().zip(&values)
+        .map(|(field, value)| format!("            {}: {},\n", field.name, value))
+        .collect();
+    let setters: String = fields.iter()
+        .map(|field| {
+            let (param, value) = if field.ty == "String" {
+                ("impl Into<String>".to_string(), format!("{}.into()", field.name))
+            } else {
+                (field.ty.clone(), field.name.clone())
+            };
+            format!(
+                "\n    pub fn with_{name}(mut self, {name}: {param}) -> Self {{\n        self.{name} = {value};\n        self\n    }}\n",
+                name = field.name
+            )
+        })
+        .collect();
+    let mut built: String = fields.iter().map(|field| format!("            {}: self.{},\n", field.name, field.name)).collect();
+    if factory.partial {
+        built.push_str(&format!("            ..{}::default()\n", type_name));
+    }
+
+    let mut uses = Vec::new();
+    let mut numbering = "";
+    let imports = imports(factory, source);
+    if numbered {
+        uses.push("use std::sync::atomic::{AtomicU64, Ordering};".to_string());
+        // Grouped with the model's own `std` imports
+        if !imports[0].starts_with("use std::") {
+            uses.push(String::new());
+        }
+        numbering = "\n/// Numbers the built items so their ids and strings don't collide\nstatic SEQUENCE: AtomicU64 = AtomicU64::new(1);\n";
+    }
+    uses.extend(imports);
+    let next = if numbered { "        let n = SEQUENCE.fetch_add(1, Ordering::Relaxed);\n" } else { "" };
+
+    let mut test_body = String::new();
+    if let Some((field, (value, assertion))) = fields.iter().find_map(|field| override_value(field).map(|value| (field, value))) {
+        test_body.push_str(&format!(
+            "        let item = {}Factory::new().with_{}({}).build();\n        {}\n",
+            type_name, field.name, value, assertion
+        ));
+    } else {
+        test_body.push_str(&format!("        let _item = {}Factory::new().build();\n", type_name));
+    }
+    if let Some(field) = fields.iter().find(|field| field.ty == "String" && is_numbered(field)) {
+        test_body.push_str(&format!(
+            "\n        // Each item gets its own values\n        assert_ne!({t}Factory::new().build().{f}, {t}Factory::new().build().{f});\n",
+            t = type_name,
+            f = field.name
+        ));
+    }
+
+    let content = format!(
+        r#"// Test data builder of `{type_name}`: starts from sensible values, numbered so items don't collide,
+// overridden with `with_*` before `build`, e.g. `{type_name}Factory::new().with_{first}(...).build()`.
+// Only compiled for the tests, like the rest of `test_support`
+{uses}
+{numbering}
+pub struct {type_name}Factory {{
+{struct_fields}}}
+
+impl {type_name}Factory {{
+    pub fn new() -> Self {{
+{next}        Self {{
+{initializers}        }}
+    }}
+{setters}
+    pub fn build(self) -> {type_name} {{
+        {type_name} {{
+{built}        }}
+    }}
+}}
+
+impl Default for {type_name}Factory {{
+    fn default() -> Self {{
+        Self::new()
+    }}
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn builds_with_defaults_and_overrides() {{
+{test_body}    }}
+}}
+"#,
+        uses = uses.join("\n"),
+        first = fields[0].name,
+        initializers = initializers.concat(),
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use clap::Args;
+use anyhow::Result;
+use std::path::Path;
+use tokio::fs;
+
+use crate::commands::resource::{add_error_conversion, VALIDATOR_CONVERSION};
+use crate::utils::manifest::Manifest;
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::{fix_generated, write_if_changed};
+use crate::utils::output::report;
+
+#[derive(Args)]
+pub struct ValidatorArgs {
+    /// Name of the validator, e.g. `strong_password` or `phone-number`
+    pub name: String,
+    /// Overwrite the validator if it already exists
+    #[arg(long)]
+    pub force: bool,
+    /// Run `cargo clippy --fix` on the generated files
+    #[arg(long)]
+    pub fix: bool,
+}
+
+/// Requirement added to the project's `[dependencies]` when validator isn't listed yet
+pub const VALIDATOR_DEPENDENCY: &str = r#"{ version = "0.20", features = ["derive"] }"#;
+
+pub async fn execute(args: ValidatorArgs) -> anyhow::Result<()> {
+    report(format!("🛡️ Generating validator: {}", args.name));
+
+    let name = args.name.replace('-', "_").to_lowercase();
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid_name {
+        anyhow::bail!("❌ Invalid validator name '{}': use letters, digits, '-' and '_', starting with a letter", args.name);
+    }
+
+    let ctx = ProjectContext::load().await?;
+
+    let file_path = ctx.module_file("validators", &name);
+    if Path::new(&file_path).exists() && !args.force {
+        anyhow::bail!("❌ '{}' already exists, use --force to overwrite it", file_path);
+    }
+
+    let mut manifest = Manifest::load(&ctx).await?;
+    let validator_added = manifest.add_dependency("validator", VALIDATOR_DEPENDENCY)?;
+
+    fs::create_dir_all(ctx.module_dir("validators")).await?;
+    generate_validator(&name, &file_path).await?;
+    ctx.register("validators", &name).await?;
+    manifest.save().await?;
+    add_error_conversion(&ctx, &VALIDATOR_CONVERSION).await?;
+    if args.fix {
+        fix_generated(&ctx, std::slice::from_ref(&file_path)).await?;
+    }
+
+    report(format!("✅ Validator '{}' created successfully!", name));
+    report("📝 Generated files:");
+    report(format!("   - {}", file_path));
+    if validator_added {
+        report(format!("📦 Added validator to Cargo.toml: validator = {}", VALIDATOR_DEPENDENCY));
+    }
+    report(format!(
+        "🔗 Use it with `#[validate(custom(function = \"crate::validators::{}::validate_{}\"))]`",
+        name, name
+    ));
+
+    Ok(())
+}
+
+/// Rules and tests of the validators cargo-mold knows, the others get a placeholder to fill in
+fn example(name: &str) -> (&'static str, &'static str) {
+    match name {
+        "strong_password" => (
+            r#"    let long_enough = value.chars().count() >= 12;
+    let mixed = value.chars().any(|c| c.is_lowercase())
+        && value.chars().any(|c| c.is_uppercase())
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| !c.is_alphanumeric());
+    if !long_enough {
+        return Err(invalid("must be at least 12 characters long"));
+    }
+    if !mixed {
+        return Err(invalid("must mix lowercase and uppercase letters, digits and symbols"));
+    }
+    Ok(())"#,
+            r#"    #[test]
+    fn accepts_a_strong_password() {
+        assert!(validate_strong_password("correct-Horse-7-battery").is_ok());
+    }
+
+    #[test]
+    fn rejects_short_or_uniform_passwords() {
+        assert!(validate_strong_password("Sh0rt!").is_err());
+        assert!(validate_strong_password("alllowercaseletters").is_err());
+        assert!(validate_strong_password("NoDigitsOrSymbolsHere").is_err());
+    }"#,
+        ),
+        "phone_number" => (
+            r#"    // E.164: an optional `+` and 7 to 15 digits, ignoring spaces, dashes, dots and parentheses
+    let digits = value.strip_prefix('+').unwrap_or(value);
+    let mut count = 0;
+    for c in digits.chars() {
+        match c {
+            '0'..='9' => count += 1,
+            ' ' | '-' | '.' | '(' | ')' => {}
+            _ => return Err(invalid("may only contain digits, spaces, dashes, dots and parentheses")),
+        }
+    }
+    if !(7..=15).contains(&count) {
+        return Err(invalid("must have between 7 and 15 digits"));
+    }
+    Ok(())"#,
+            r#"    #[test]
+    fn accepts_international_and_formatted_numbers() {
+        assert!(validate_phone_number("+14155550123").is_ok());
+        assert!(validate_phone_number("(415) 555-0123").is_ok());
+    }
+
+    #[test]
+    fn rejects_letters_and_wrong_lengths() {
+        assert!(validate_phone_number("415-CALL-NOW").is_err());
+        assert!(validate_phone_number("12345").is_err());
+        assert!(validate_phone_number("+1234567890123456").is_err());
+    }"#,
+        ),
+        _ => (
+            r#"    // TODO: replace with the actual rules
+    if value.trim().is_empty() {
+        return Err(invalid("must not be blank"));
+    }
+    Ok(())"#,
+            "",
+        ),
+    }
+}
+
+/// Writes a `validate_{name}` function usable from `#[validate(custom(...))]`, with its tests
+async fn generate_validator(name: &str, file_path: &str) -> Result<()> {
+    let (rules, tests) = example(name);
+    let tests = if tests.is_empty() {
+        format!(
+            r#"    #[test]
+    fn accepts_a_value() {{
+        assert!(validate_{name}("value").is_ok());
+    }}
+
+    #[test]
+    fn rejects_a_blank_value() {{
+        let error = validate_{name}("  ").unwrap_err();
+        assert_eq!(error.code, "{name}");
+    }}"#
+        )
+    } else {
+        tests.to_string()
+    };
+
+    let content = format!(
+        r#"// `{name}` validator, for fields annotated with
+// `#[validate(custom(function = "crate::validators::{name}::validate_{name}"))]`
+use validator::ValidationError;
+
+pub fn validate_{name}(value: &str) -> Result<(), ValidationError> {{
+{rules}
+}}
+
+/// Error reported under the `{name}` code
+fn invalid(message: &'static str) -> ValidationError {{
+    let mut error = ValidationError::new("{name}");
+    error.message = Some(message.into());
+    error
+}}
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+{tests}
+}}
+"#
+    );
+
+    write_if_changed(file_path, content).await?;
+    Ok(())
+}
@@ -1,12 +1,16 @@
-use clap::{Parser, Subcommand};
-
-mod commands;
-mod templates;
+use cargo_mold::commands;
+use cargo_mold::utils::output::{self, OutputStyle};
+use cargo_mold::utils::plan::GenerationPlan;
+use clap::{CommandFactory, Parser, Subcommand};
+use std::process::ExitCode;
 
 #[derive(Parser)]
 #[command(name = "cargo-mold")]
 #[command(about = "NestJS-inspired code generator for Rust", long_about = None)]
 struct Cli {
+    /// How to print the emoji of messages (defaults to plain when `NO_EMOJI` is set)
+    #[arg(long, value_enum, global = true)]
+    output_style: Option<OutputStyle>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -15,14 +19,24 @@ struct Cli {
 enum Commands {
     /// Create a new project
     New(commands::new::NewArgs),
+    /// Create a new project in the current directory
+    Init(commands::new::InitArgs),
     /// Generate code components (shortcut: g)
     #[command(name = "g")]
     Generate(GenerateArgs),
+    /// Print a shell completion script to stdout
+    Completions(commands::completions::CompletionsArgs),
 }
 
 // Wrapper struct for generate subcommands
 #[derive(Parser)]
 pub struct GenerateArgs {
+    /// Describe the files the command would create or edit, without writing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// With --dry-run, print the plan as a JSON `{ "creates": [...], "edits": [{ "path", "diff" }] }` manifest
+    #[arg(long, global = true, requires = "dry_run")]
+    json: bool,
     #[command(subcommand)]
     command: GenerateCommands,
 }
@@ -30,25 +44,111 @@ pub struct GenerateArgs {
 #[derive(Subcommand)]
 enum GenerateCommands {
     /// Generate a resource module
-    Resource(commands::resource::ResourceArgs),
+    Resource(Box<commands::resource::ResourceArgs>),
     /// Generate a service
     Service(commands::service::ServiceArgs),
     /// Generate a controller  
     Controller(commands::controller::ControllerArgs),
     /// Generate a module
     Module(commands::module::ModuleArgs),
+    /// Generate a repository
+    Repository(commands::repository::RepositoryArgs),
+    /// Generate a message-queue consumer
+    Subscriber(commands::subscriber::SubscriberArgs),
+    /// Generate a standalone serde struct in `src/models`, e.g. a login request body
+    Dto(commands::dto::DtoArgs),
+    /// Generate a `From` conversion between two models
+    Mapper(commands::mapper::MapperArgs),
+    /// Generate a clap-based companion binary in `src/bin`
+    Cli(commands::cli::CliArgs),
+    /// Generate a Server-Sent Events stream with a `push` function
+    Sse(commands::sse::SseArgs),
+    /// Generate an interceptor rewriting the successful JSON responses of a scope
+    Interceptor(commands::interceptor::InterceptorArgs),
+    /// Generate a custom field validator in `src/validators`
+    Validator(commands::validator::ValidatorArgs),
+    /// Generate an attribute-based access policy in `src/policies`, checked by handlers before acting
+    Policy(commands::policy::PolicyArgs),
+    /// Generate the JSON Schema of a model in `schemas/`
+    Schema(commands::schema::SchemaArgs),
+    /// Generate a test data builder of a model in `src/test_support`, only compiled for the tests
+    TestFactory(commands::test_factory::TestFactoryArgs),
+    /// Generate a reqwest client for an external API in `src/clients`, optionally behind a circuit breaker
+    Client(commands::client::ClientArgs),
+    /// Rename a resource: its files, types, functions, routes and the references to them
+    Rename(commands::rename::RenameArgs),
+    /// Reorder the `.wrap` calls of the server and routes as listed in `.cargo-mold`
+    MiddlewareOrder(commands::middleware_order::MiddlewareOrderArgs),
+    /// Generate several resources described in a spec file
+    #[command(name = "from")]
+    FromSpec(commands::from_spec::FromSpecArgs),
+    /// Generate a resource's model from the columns of a SQLite or PostgreSQL table
+    FromSchema(commands::from_schema::FromSchemaArgs),
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
-    match cli.command {
+    if let Some(style) = cli.output_style {
+        output::set_style(style);
+    }
+
+    // Errors carry the same markers as the other messages
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", output::render(&format!("Error: {:?}", e), output::style()));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Commands) -> anyhow::Result<()> {
+    match command {
         Commands::New(args) => commands::new::execute(args).await,
-        Commands::Generate(args) => match args.command {
-            GenerateCommands::Resource(args) => commands::resource::execute(args).await,
-            GenerateCommands::Service(args) => commands::service::execute(args).await,
-            GenerateCommands::Controller(args) => commands::controller::execute(args).await,
-            GenerateCommands::Module(args) => commands::module::execute(args).await,
-        },
+        Commands::Init(args) => commands::new::execute_init(args).await,
+        Commands::Generate(args) if args.dry_run => {
+            if matches!(&args.command, GenerateCommands::FromSpec(spec) if spec.watch) {
+                anyhow::bail!("❌ --watch keeps regenerating, it can't be combined with --dry-run");
+            }
+            // Messages go to stderr so stdout holds nothing but the manifest
+            if args.json {
+                output::report_to_stderr();
+            }
+            let plan = GenerationPlan::dry_run(|| generate(args.command)).await?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&plan)?);
+            } else {
+                plan.report();
+            }
+            Ok(())
+        }
+        Commands::Generate(args) => generate(args.command).await,
+        Commands::Completions(args) => commands::completions::execute(args, Cli::command()).await,
+    }
+}
+
+async fn generate(command: GenerateCommands) -> anyhow::Result<()> {
+    match command {
+        GenerateCommands::Resource(args) => commands::resource::execute(*args).await,
+        GenerateCommands::Service(args) => commands::service::execute(args).await,
+        GenerateCommands::Controller(args) => commands::controller::execute(args).await,
+        GenerateCommands::Module(args) => commands::module::execute(args).await,
+        GenerateCommands::Repository(args) => commands::repository::execute(args).await,
+        GenerateCommands::Subscriber(args) => commands::subscriber::execute(args).await,
+        GenerateCommands::Dto(args) => commands::dto::execute(args).await,
+        GenerateCommands::Mapper(args) => commands::mapper::execute(args).await,
+        GenerateCommands::Cli(args) => commands::cli::execute(args).await,
+        GenerateCommands::Sse(args) => commands::sse::execute(args).await,
+        GenerateCommands::Validator(args) => commands::validator::execute(args).await,
+        GenerateCommands::Policy(args) => commands::policy::execute(args).await,
+        GenerateCommands::Schema(args) => commands::schema::execute(args).await,
+        GenerateCommands::Interceptor(args) => commands::interceptor::execute(args).await,
+        GenerateCommands::TestFactory(args) => commands::test_factory::execute(args).await,
+        GenerateCommands::Client(args) => commands::client::execute(args).await,
+        GenerateCommands::Rename(args) => commands::rename::execute(args).await,
+        GenerateCommands::MiddlewareOrder(args) => commands::middleware_order::execute(args).await,
+        GenerateCommands::FromSpec(args) => commands::from_spec::execute(args).await,
+        GenerateCommands::FromSchema(args) => commands::from_schema::execute(args).await,
     }
 }
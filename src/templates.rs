@@ -1 +1,1814 @@
+//! Static file templates copied verbatim into generated projects
 
+/// `src/middleware/request_id.rs`: tags every request with an `X-Request-Id`
+pub const REQUEST_ID_MIDDLEWARE: &str = r#"// Request id propagation middleware
+// Reuses the incoming `X-Request-Id` header or generates a new UUID, stores it in the
+// request extensions and echoes it back in the response
+use std::fmt;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest incoming id accepted as-is, anything longer is replaced by a fresh one
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Id of the current request, usable as a handler argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<RequestId>()
+                .cloned()
+                .ok_or_else(|| actix_web::error::ErrorInternalServerError("RequestIdMiddleware is not registered")),
+        )
+    }
+}
+
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddlewareService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LEN)
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let mut res = service.call(req).await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    async fn echo(request_id: RequestId) -> HttpResponse {
+        HttpResponse::Ok().body(request_id.0)
+    }
+
+    #[actix_web::test]
+    async fn echoes_supplied_request_id() {
+        let app = test::init_service(
+            App::new().wrap(RequestIdMiddleware).route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((REQUEST_ID_HEADER, "abc-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "abc-123");
+        assert_eq!(test::read_body(res).await, "abc-123");
+    }
+
+    #[actix_web::test]
+    async fn generates_missing_request_id() {
+        let app = test::init_service(
+            App::new().wrap(RequestIdMiddleware).route("/", web::get().to(echo)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+
+        let header = res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(test::read_body(res).await, header.as_bytes());
+    }
+}
+"#;
+
+/// `src/middleware/security_headers.rs` of `--security-headers`, `{{content_security_policy}}` being the default policy
+pub const SECURITY_HEADERS_MIDDLEWARE: &str = r#"// Security headers middleware
+// Sets `X-Content-Type-Options`, `X-Frame-Options`, `Strict-Transport-Security` and
+// `Content-Security-Policy` on every response, unless the handler already chose its own
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{
+    HeaderName, HeaderValue, CONTENT_SECURITY_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use actix_web::Error;
+
+/// Policy of `Content-Security-Policy` unless `SECURITY_CSP` says otherwise
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "{{content_security_policy}}";
+/// Browsers only honor `Strict-Transport-Security` over HTTPS, where it pins the site to HTTPS for a year
+pub const DEFAULT_STRICT_TRANSPORT_SECURITY: &str = "max-age=31536000; includeSubDomains";
+
+/// The headers set on every response, each left out when `None`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityHeaders {
+    /// `nosniff`, so browsers don't guess a content type other than the declared one
+    pub content_type_options: Option<String>,
+    /// `DENY`, so other sites can't frame the pages (clickjacking)
+    pub frame_options: Option<String>,
+    pub strict_transport_security: Option<String>,
+    pub content_security_policy: Option<String>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self {
+            content_type_options: Some("nosniff".to_string()),
+            frame_options: Some("DENY".to_string()),
+            strict_transport_security: Some(DEFAULT_STRICT_TRANSPORT_SECURITY.to_string()),
+            content_security_policy: Some(DEFAULT_CONTENT_SECURITY_POLICY.to_string()),
+        }
+    }
+}
+
+impl SecurityHeaders {
+    /// The defaults, each overridden by its variable when set: `SECURITY_CONTENT_TYPE_OPTIONS`,
+    /// `SECURITY_FRAME_OPTIONS`, `SECURITY_HSTS` and `SECURITY_CSP`. `off` leaves the header out
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |var: &str, default: Option<String>| match std::env::var(var) {
+            Ok(value) if value.trim().eq_ignore_ascii_case("off") => None,
+            Ok(value) if !value.trim().is_empty() => Some(value.trim().to_string()),
+            _ => default,
+        };
+        Self {
+            content_type_options: read("SECURITY_CONTENT_TYPE_OPTIONS", defaults.content_type_options),
+            frame_options: read("SECURITY_FRAME_OPTIONS", defaults.frame_options),
+            strict_transport_security: read("SECURITY_HSTS", defaults.strict_transport_security),
+            content_security_policy: read("SECURITY_CSP", defaults.content_security_policy),
+        }
+    }
+
+    /// The enabled headers, skipping values that aren't valid in a header
+    fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        [
+            (X_CONTENT_TYPE_OPTIONS, &self.content_type_options),
+            (X_FRAME_OPTIONS, &self.frame_options),
+            (STRICT_TRANSPORT_SECURITY, &self.strict_transport_security),
+            (CONTENT_SECURITY_POLICY, &self.content_security_policy),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, HeaderValue::from_str(value.as_deref()?).ok()?)))
+        .collect()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersService {
+            service: Rc::new(service),
+            headers: Rc::new(self.headers()),
+        }))
+    }
+}
+
+pub struct SecurityHeadersService<S> {
+    service: Rc<S>,
+    headers: Rc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl<S, B> Service<ServiceRequest> for SecurityHeadersService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let headers = Rc::clone(&self.headers);
+        Box::pin(async move {
+            let mut res = service.call(req).await?;
+            for (name, value) in headers.iter() {
+                if !res.headers().contains_key(name) {
+                    res.headers_mut().insert(name.clone(), value.clone());
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn sets_the_security_headers() {
+        let app = test::init_service(
+            App::new()
+                .wrap(SecurityHeaders::default())
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+
+        assert_eq!(res.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+        assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "DENY");
+        assert_eq!(res.headers().get(STRICT_TRANSPORT_SECURITY).unwrap(), DEFAULT_STRICT_TRANSPORT_SECURITY);
+        assert_eq!(res.headers().get(CONTENT_SECURITY_POLICY).unwrap(), DEFAULT_CONTENT_SECURITY_POLICY);
+    }
+
+    #[actix_web::test]
+    async fn disabled_headers_are_left_out_and_handlers_keep_theirs() {
+        let headers = SecurityHeaders { strict_transport_security: None, ..SecurityHeaders::default() };
+        let app = test::init_service(App::new().wrap(headers).route(
+            "/",
+            web::get().to(|| async { HttpResponse::Ok().insert_header((X_FRAME_OPTIONS, "SAMEORIGIN")).finish() }),
+        ))
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+
+        assert!(res.headers().get(STRICT_TRANSPORT_SECURITY).is_none());
+        assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "SAMEORIGIN");
+        assert_eq!(res.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(), "nosniff");
+    }
+}
+"#;
+
+/// `src/middleware/idempotency.rs`: replays the cached response of mutating requests retried with the same `Idempotency-Key`
+pub const IDEMPOTENCY_MIDDLEWARE: &str = r#"// Idempotency-Key middleware
+// POST, PUT, PATCH and DELETE requests carrying an `Idempotency-Key` header are handled once: the
+// response is cached for a TTL and replayed to retries with the same key, while a retry arriving
+// before the first attempt finished is answered with a 409 Conflict
+use std::collections::HashMap;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::web::Bytes;
+use actix_web::{Error, HttpResponse};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on replayed responses
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long responses are kept by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Longest key accepted, longer ones are answered with a 400
+const MAX_KEY_LEN: usize = 255;
+
+/// A response kept for replay
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: Vec<(HeaderName, HeaderValue)>,
+    pub body: Bytes,
+}
+
+impl CachedResponse {
+    fn to_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status);
+        for (name, value) in &self.headers {
+            response.append_header((name.clone(), value.clone()));
+        }
+        response.body(self.body.clone())
+    }
+}
+
+/// Result of `IdempotencyStore::claim`
+#[derive(Debug, Clone)]
+pub enum Claim {
+    /// The key is new (or expired): the request runs and its response is stored under the key
+    Acquired,
+    /// A request with the key is still being handled
+    InFlight,
+    /// The key was already answered
+    Completed(CachedResponse),
+}
+
+/// Where keys and their responses are kept. `InMemoryIdempotencyStore` only sees the requests of
+/// this process, implement the trait over a shared store (e.g. Redis) when running several instances
+pub trait IdempotencyStore: Send + Sync {
+    /// Claims `key` for `ttl` unless it is in flight or already answered, atomically
+    fn claim(&self, key: &str, ttl: Duration) -> Claim;
+    /// Stores the response of a claimed key, replayed until `ttl` elapses
+    fn complete(&self, key: &str, response: CachedResponse, ttl: Duration);
+    /// Forgets a claimed key without a response (e.g. the handler failed), so a retry runs again
+    fn release(&self, key: &str);
+}
+
+enum Entry {
+    InFlight,
+    Completed(CachedResponse),
+}
+
+/// `IdempotencyStore` kept in memory, shared by the workers of the process
+#[derive(Default)]
+pub struct InMemoryIdempotencyStore {
+    entries: Mutex<HashMap<String, (Entry, Instant)>>,
+}
+
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    fn claim(&self, key: &str, ttl: Duration) -> Claim {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        match entries.get(key) {
+            Some((Entry::InFlight, _)) => Claim::InFlight,
+            Some((Entry::Completed(response), _)) => Claim::Completed(response.clone()),
+            None => {
+                entries.insert(key.to_string(), (Entry::InFlight, now + ttl));
+                Claim::Acquired
+            }
+        }
+    }
+
+    fn complete(&self, key: &str, response: CachedResponse, ttl: Duration) {
+        let expires_at = Instant::now() + ttl;
+        self.entries.lock().unwrap().insert(key.to_string(), (Entry::Completed(response), expires_at));
+    }
+
+    fn release(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// Wrap the app (or a scope) with it. Clones share their store, so build it once outside
+/// `HttpServer::new` for every worker to see the same keys
+#[derive(Clone)]
+pub struct IdempotencyMiddleware {
+    store: Arc<dyn IdempotencyStore>,
+    ttl: Duration,
+}
+
+impl Default for IdempotencyMiddleware {
+    fn default() -> Self {
+        Self::new(Arc::new(InMemoryIdempotencyStore::default()))
+    }
+}
+
+impl IdempotencyMiddleware {
+    pub fn new(store: Arc<dyn IdempotencyStore>) -> Self {
+        Self { store, ttl: DEFAULT_TTL }
+    }
+
+    /// How long responses are replayed, `DEFAULT_TTL` unless set
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for IdempotencyMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = IdempotencyMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddlewareService {
+            service: Rc::new(service),
+            store: Arc::clone(&self.store),
+            ttl: self.ttl,
+        }))
+    }
+}
+
+pub struct IdempotencyMiddlewareService<S> {
+    service: Rc<S>,
+    store: Arc<dyn IdempotencyStore>,
+    ttl: Duration,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let store = Arc::clone(&self.store);
+        let ttl = self.ttl;
+
+        Box::pin(async move {
+            let mutating = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+            let Some(header) = req.headers().get(IDEMPOTENCY_KEY_HEADER).filter(|_| mutating) else {
+                return service.call(req).await.map(ServiceResponse::map_into_boxed_body);
+            };
+            let key = match header.to_str() {
+                // The same key sent to another endpoint is a different request
+                Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => format!("{} {} {}", req.method(), req.path(), key),
+                _ => return Ok(req.into_response(bad_key())),
+            };
+
+            match store.claim(&key, ttl) {
+                Claim::Acquired => {}
+                Claim::InFlight => {
+                    let conflict = HttpResponse::Conflict().json(serde_json::json!({
+                        "error": "a request with this Idempotency-Key is still being processed"
+                    }));
+                    return Ok(req.into_response(conflict));
+                }
+                Claim::Completed(cached) => {
+                    let mut replay = cached.to_response();
+                    replay.headers_mut().insert(
+                        HeaderName::from_static(IDEMPOTENT_REPLAYED_HEADER),
+                        HeaderValue::from_static("true"),
+                    );
+                    return Ok(req.into_response(replay));
+                }
+            }
+
+            let res = match service.call(req).await {
+                Ok(res) => res,
+                Err(e) => {
+                    store.release(&key);
+                    return Err(e);
+                }
+            };
+            // Server errors are not cached, so the request can be retried
+            if res.status().is_server_error() {
+                store.release(&key);
+                return Ok(res.map_into_boxed_body());
+            }
+
+            let (req, res) = res.into_parts();
+            let status = res.status();
+            let headers = res.headers().iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+            let body = match to_bytes(res.into_body()).await {
+                Ok(body) => body,
+                Err(e) => {
+                    store.release(&key);
+                    return Err(actix_web::error::ErrorInternalServerError(e.into()));
+                }
+            };
+            let cached = CachedResponse { status, headers, body };
+            let response = cached.to_response();
+            store.complete(&key, cached, ttl);
+            Ok(ServiceResponse::new(req, response))
+        })
+    }
+}
+
+fn bad_key() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LEN)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{web, App};
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    async fn create() -> HttpResponse {
+        let id = CREATED.fetch_add(1, Ordering::SeqCst) + 1;
+        HttpResponse::Created().json(serde_json::json!({ "id": id }))
+    }
+
+    #[actix_web::test]
+    async fn replayed_post_returns_the_cached_response() {
+        let app = init_service(
+            App::new().wrap(IdempotencyMiddleware::default()).route("/items", web::post().to(create)),
+        )
+        .await;
+        let post = |key: &str| TestRequest::post().uri("/items").insert_header((IDEMPOTENCY_KEY_HEADER, key)).to_request();
+
+        let first = call_service(&app, post("retry-me")).await;
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+        let first_body = read_body(first).await;
+
+        let replay = call_service(&app, post("retry-me")).await;
+        assert_eq!(replay.status(), StatusCode::CREATED);
+        assert_eq!(replay.headers().get(IDEMPOTENT_REPLAYED_HEADER).unwrap(), "true");
+        assert_eq!(read_body(replay).await, first_body);
+
+        // Another key is another request
+        let other = call_service(&app, post("another")).await;
+        assert_ne!(read_body(other).await, first_body);
+    }
+
+    #[test]
+    fn in_flight_keys_are_not_claimed_twice() {
+        let store = InMemoryIdempotencyStore::default();
+        assert!(matches!(store.claim("key", DEFAULT_TTL), Claim::Acquired));
+        assert!(matches!(store.claim("key", DEFAULT_TTL), Claim::InFlight));
+
+        store.release("key");
+        assert!(matches!(store.claim("key", DEFAULT_TTL), Claim::Acquired));
+    }
+
+    #[test]
+    fn expired_responses_are_forgotten() {
+        let store = InMemoryIdempotencyStore::default();
+        let response = CachedResponse { status: StatusCode::CREATED, headers: Vec::new(), body: Bytes::new() };
+        store.claim("key", DEFAULT_TTL);
+        store.complete("key", response, Duration::ZERO);
+        assert!(matches!(store.claim("key", DEFAULT_TTL), Claim::Acquired));
+    }
+}
+"#;
+
+/// `src/middleware/user_context.rs`: tags the logs of authenticated requests with the token subject
+pub const USER_CONTEXT_MIDDLEWARE: &str = r#"// Authenticated user logging context
+// Runs the rest of the request inside a `user` span carrying the subject of the JWT, so every
+// event logged while handling it says which user it was for
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpMessage};
+use cargo_mold::auth::Claims;
+use tracing::Instrument;
+
+/// Reads the claims the JWT middleware stores in the request extensions, so it must run after it:
+/// `.wrap` it *before* `.wrap(auth_service.jwt_middleware())`, the last one wrapped runs first
+pub struct UserContextMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for UserContextMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = UserContextMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(UserContextMiddlewareService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct UserContextMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for UserContextMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let span = match req.extensions().get::<Claims>() {
+            Some(claims) => tracing::info_span!("user", user.id = %claims.sub),
+            None => {
+                tracing::warn!("no JWT claims on the request, is UserContextMiddleware wrapped before the JWT middleware?");
+                tracing::info_span!("user", user.id = tracing::field::Empty)
+            }
+        };
+
+        Box::pin(self.service.call(req).instrument(span))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::{test, web, App, HttpResponse};
+    use cargo_mold::auth::AuthService;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::{registry, Layer};
+
+    use super::*;
+
+    /// Records the `user.id` of every `user` span
+    #[derive(Clone, Default)]
+    struct UserIds(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for UserIds {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+            if attrs.metadata().name() == "user" {
+                attrs.record(&mut UserIdVisitor(&self.0));
+            }
+        }
+    }
+
+    struct UserIdVisitor<'a>(&'a Mutex<Vec<String>>);
+
+    impl Visit for UserIdVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "user.id" {
+                self.0.lock().unwrap().push(format!("{:?}", value));
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn authenticated_requests_are_logged_with_their_subject() {
+        let user_ids = UserIds::default();
+        let _guard = tracing::subscriber::set_default(registry().with(user_ids.clone()));
+
+        let auth_service = AuthService::new("test-secret".to_string(), "test-key".to_string());
+        let token = auth_service.generate_token("ada@example.com".to_string(), (), 5);
+        let app = test::init_service(
+            App::new()
+                .wrap(UserContextMiddleware)
+                .wrap(auth_service.jwt_middleware())
+                .route("/", web::get().to(|| async { HttpResponse::Ok().finish() })),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert!(res.status().is_success());
+        assert_eq!(*user_ids.0.lock().unwrap(), ["ada@example.com"]);
+    }
+}
+"#;
+
+/// `tests/common/mod.rs`: builds the app like `server::run` for the integration tests, and signs the tokens
+/// of the `/private-api` ones. `{{crate_name}}`, `{{imports}}`, `{{startup}}` and `{{wraps}}` are filled
+/// from the enabled features
+pub const INTEGRATION_HARNESS: &str = r#"// Shared setup of the integration tests: declare `mod common;` in a `tests/*.rs` file
+// and build the application once per test with `common::test_app().await`
+// Every test file compiles this module on its own and may not use every helper
+#![allow(dead_code)]
+
+use std::sync::Once;
+
+use actix_http::Request;
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::{test, web, App, Error};
+use cargo_mold::auth::auth::{ENCRYPTION_KEY_ENV, JWT_SECRET_ENV};
+use cargo_mold::auth::AuthService;
+use {{crate_name}}::routes;
+use {{crate_name}}::server::server::MAX_BODY_SIZE;
+{{imports}}
+/// Keys signing the tests' tokens when `JWT_SECRET` and `ENCRYPTION_KEY` aren't set
+const TEST_KEYS: [(&str, &str); 2] = [
+    (JWT_SECRET_ENV, "integration-tests-jwt-secret-0123456789"),
+    (ENCRYPTION_KEY_ENV, "integration-tests-encryption-key-0123456789"),
+];
+
+/// The application configured like `server::run` does, with its middlewares, app data and routes
+pub async fn test_app() -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    app_with(routes::routes::public_routes).await
+}
+
+/// The application serving the JWT protected `/private-api` routes, send them an `auth_header`
+pub async fn test_private_app() -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+    // `private_routes` builds its `AuthService` from the environment
+    test_auth_service();
+    app_with(routes::routes::private_routes).await
+}
+
+/// The `AuthService` of `private_routes`, reading the environment after filling in the missing `TEST_KEYS`
+pub fn test_auth_service() -> AuthService {
+    static KEYS: Once = Once::new();
+    KEYS.call_once(|| {
+        for (var, key) in TEST_KEYS {
+            if std::env::var_os(var).is_none() {
+                std::env::set_var(var, key);
+            }
+        }
+    });
+    AuthService::from_env().unwrap_or_else(|e| panic!("Invalid auth configuration: {}", e))
+}
+
+/// `Authorization` header carrying a token of `service` for `sub`, valid for an hour:
+/// `TestRequest::get().insert_header(common::auth_header(&service, "ada@example.com"))`
+pub fn auth_header(service: &AuthService, sub: &str) -> (&'static str, String) {
+    let token = service.generate_token(sub.to_string(), serde_json::Value::Null, 60);
+    ("Authorization", format!("Bearer {}", token))
+}
+
+async fn app_with(
+    routes: fn(&mut web::ServiceConfig),
+) -> impl Service<Request, Response = ServiceResponse<impl MessageBody>, Error = Error> {
+{{startup}}    test::init_service(
+        App::new()
+            .app_data(web::PayloadConfig::new(MAX_BODY_SIZE))
+            .app_data(web::JsonConfig::default().limit(MAX_BODY_SIZE))
+            .app_data(web::FormConfig::default().limit(MAX_BODY_SIZE)){{wraps}}
+            .configure(routes),
+    )
+    .await
+}
+"#;
+
+/// `tests/api.rs`: first integration test, built on the harness
+pub const INTEGRATION_API_TEST: &str = r#"// Integration tests of the public API
+mod common;
+
+use actix_web::test;
+
+#[actix_web::test]
+async fn hello_answers() {
+    let app = common::test_app().await;
+
+    let req = test::TestRequest::get().uri("/api/hello").to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert!(res.status().is_success());
+    assert_eq!(test::read_body(res).await, "Hello, World! from Actix Web");
+}
+"#;
+
+/// `tests/private_api.rs`: tests of the JWT protected routes, signing their tokens with `auth_header`
+pub const INTEGRATION_PRIVATE_API_TEST: &str = r#"// Integration tests of the JWT protected API
+mod common;
+
+use actix_web::http::StatusCode;
+use actix_web::test;
+use serde_json::Value;
+
+#[actix_web::test]
+async fn auth_header_carries_a_valid_token() {
+    let service = common::test_auth_service();
+    let (name, value) = common::auth_header(&service, "ada@example.com");
+
+    assert_eq!(name, "Authorization");
+    let token = value.strip_prefix("Bearer ").unwrap();
+    assert!(service.verify_token::<Value>(token));
+    assert!(!service.is_token_expired::<Value>(token));
+}
+
+#[actix_web::test]
+async fn private_api_answers_authenticated_requests() {
+    let app = common::test_private_app().await;
+    let service = common::test_auth_service();
+
+    let req = test::TestRequest::get()
+        .uri("/private-api/")
+        .insert_header(common::auth_header(&service, "ada@example.com"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn private_api_rejects_requests_without_a_token() {
+    let app = common::test_private_app().await;
+
+    let req = test::TestRequest::get().uri("/private-api/").to_request();
+    // The JWT middleware fails the request, whatever wraps it may turn that into a response
+    let status = match test::try_call_service(&app, req).await {
+        Ok(res) => res.status(),
+        Err(e) => e.as_response_error().status_code(),
+    };
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+"#;
+
+/// `tests/license_header.rs` of `--license-header`: checks every source file starts with the header of `LICENSE_HEADER`
+pub const LICENSE_HEADER_TEST: &str = r#"// Every Rust source of the crate starts with the license header of `LICENSE_HEADER`,
+// `{year}` and `{author}` standing for any year and author
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Whether `line` is `template` with anything in place of its `{year}` and `{author}`
+fn matches_template(line: &str, template: &str) -> bool {
+    let pattern = template.replace("{author}", "{year}");
+    let parts: Vec<&str> = pattern.split("{year}").collect();
+    let Some(mut rest) = line.strip_prefix(parts[0]) else {
+        return false;
+    };
+    if parts.len() == 1 {
+        return rest.is_empty();
+    }
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(parts[parts.len() - 1])
+}
+
+/// Every `.rs` file below `dir`
+fn rust_sources(dir: &Path, sources: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            rust_sources(&path, sources);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            sources.push(path);
+        }
+    }
+}
+
+#[test]
+fn sources_start_with_the_license_header() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let template = fs::read_to_string(root.join("LICENSE_HEADER")).unwrap();
+
+    let mut sources = vec![root.join("build.rs")];
+    sources.retain(|path| path.exists());
+    for dir in ["src", "tests", "examples", "benches"] {
+        rust_sources(&root.join(dir), &mut sources);
+    }
+    assert!(!sources.is_empty());
+
+    for path in sources {
+        let content = fs::read_to_string(&path).unwrap();
+        let mut lines = content.lines();
+        for template in template.lines() {
+            let line = lines.next().unwrap_or_default();
+            assert!(matches_template(line, template), "{} doesn't start with the license header: {:?}", path.display(), line);
+        }
+    }
+}
+"#;
+
+/// `src/telemetry.rs`: tracing subscriber exporting spans over OTLP, `{{otel_endpoint}}` is replaced
+pub const TELEMETRY: &str = r#"// Tracing and OpenTelemetry setup
+use opentelemetry::global;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// OTLP/HTTP collector used when `OTEL_EXPORTER_OTLP_ENDPOINT` is not set
+const DEFAULT_OTLP_ENDPOINT: &str = "{{otel_endpoint}}";
+
+/// Installs the tracing subscriber, logging to stdout and exporting spans over OTLP/HTTP.
+/// Keep the returned provider and call `shutdown` on it to flush pending spans on exit
+pub fn init_telemetry() -> SdkTracerProvider {
+    let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_http();
+    // When the variable is set the exporter reads it (and appends `/v1/traces`) by itself
+    if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none() {
+        exporter = exporter.with_endpoint(format!("{}/v1/traces", DEFAULT_OTLP_ENDPOINT.trim_end_matches('/')));
+    }
+    let exporter = exporter.build().expect("failed to build the OTLP span exporter");
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(env!("CARGO_PKG_NAME")).build())
+        .build();
+    global::set_tracer_provider(provider.clone());
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME"))))
+        .init();
+
+    provider
+}
+"#;
+
+/// `src/settings.rs`: `Settings<T>` extractor reading typed configuration registered as app data
+pub const SETTINGS: &str = r#"// Route-scoped typed configuration
+// Register a value with `.app_data(web::Data::new(value))` on the app or on a scope,
+// then read it in handlers with a `settings: Settings<T>` argument
+use std::future::{ready, Ready};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use actix_web::dev::Payload;
+use actix_web::{web, Error, FromRequest, HttpRequest};
+
+/// Typed configuration slice registered as `web::Data<T>`, usable as a handler argument
+#[derive(Debug)]
+pub struct Settings<T: ?Sized>(web::Data<T>);
+
+impl<T: ?Sized> Settings<T> {
+    pub fn into_inner(self) -> Arc<T> {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ?Sized> Deref for Settings<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized + 'static> FromRequest for Settings<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.app_data::<web::Data<T>>().cloned().map(Settings).ok_or_else(|| {
+            actix_web::error::ErrorInternalServerError(format!(
+                "Settings<{}> is not registered, add it with .app_data(web::Data::new(...))",
+                std::any::type_name::<T>()
+            ))
+        }))
+    }
+}
+
+/// Feature flags and limits, read from the environment at startup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureFlags {
+    pub signups_enabled: bool,
+    pub max_page_size: usize,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self {
+            signups_enabled: true,
+            max_page_size: 100,
+        }
+    }
+}
+
+impl FeatureFlags {
+    /// Reads `FEATURE_SIGNUPS_ENABLED` and `FEATURE_MAX_PAGE_SIZE`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            signups_enabled: env_or("FEATURE_SIGNUPS_ENABLED", defaults.signups_enabled),
+            max_page_size: env_or("FEATURE_MAX_PAGE_SIZE", defaults.max_page_size),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App, HttpResponse};
+
+    async fn page_size(settings: Settings<FeatureFlags>) -> HttpResponse {
+        HttpResponse::Ok().body(settings.max_page_size.to_string())
+    }
+
+    #[actix_web::test]
+    async fn reads_registered_settings() {
+        let flags = FeatureFlags {
+            max_page_size: 25,
+            ..FeatureFlags::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(flags))
+                .route("/", web::get().to(page_size)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+
+        assert!(res.status().is_success());
+        assert_eq!(test::read_body(res).await, "25");
+    }
+
+    #[actix_web::test]
+    async fn missing_settings_are_a_server_error() {
+        let app = test::init_service(App::new().route("/", web::get().to(page_size))).await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+
+        assert!(res.status().is_server_error());
+    }
+}
+"#;
+
+/// `src/config.rs`: `AppConfig` read from the environment, reporting every missing or invalid variable at once
+pub const APP_CONFIG: &str = r#"// Application configuration, validated once at startup
+use std::fmt;
+
+/// Settings the application needs to start
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppConfig {
+    pub host: String,
+    pub port: u16,
+    pub jwt_secret: String,
+    pub encryption_key: String,
+}
+
+impl AppConfig {
+    pub fn builder() -> AppConfigBuilder {
+        AppConfigBuilder::default()
+    }
+
+    /// Reads `APP_HOST` (default 127.0.0.1), `APP_PORT` (default 8080), `JWT_SECRET` and `ENCRYPTION_KEY`
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_lookup(|var| std::env::var(var).ok())
+    }
+
+    /// Like `from_env`, reading the variables through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut builder = Self::builder();
+        if let Some(host) = lookup("APP_HOST") {
+            builder = builder.host(host);
+        }
+        if let Some(port) = lookup("APP_PORT") {
+            match port.parse() {
+                Ok(port) => builder = builder.port(port),
+                Err(_) => builder.problems.push(ConfigProblem::Invalid {
+                    var: "APP_PORT",
+                    reason: format!("'{}' is not a port number", port),
+                }),
+            }
+        }
+        if let Some(jwt_secret) = lookup("JWT_SECRET") {
+            builder = builder.jwt_secret(jwt_secret);
+        }
+        if let Some(encryption_key) = lookup("ENCRYPTION_KEY") {
+            builder = builder.encryption_key(encryption_key);
+        }
+        builder.build()
+    }
+}
+
+/// Builds an `AppConfig`, defaulting the optional settings and checking the required ones
+#[derive(Debug, Default)]
+pub struct AppConfigBuilder {
+    host: Option<String>,
+    port: Option<u16>,
+    jwt_secret: Option<String>,
+    encryption_key: Option<String>,
+    problems: Vec<ConfigProblem>,
+}
+
+impl AppConfigBuilder {
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    pub fn jwt_secret(mut self, jwt_secret: impl Into<String>) -> Self {
+        self.jwt_secret = Some(jwt_secret.into());
+        self
+    }
+
+    pub fn encryption_key(mut self, encryption_key: impl Into<String>) -> Self {
+        self.encryption_key = Some(encryption_key.into());
+        self
+    }
+
+    /// Fails with every problem found, not only the first one
+    pub fn build(self) -> Result<AppConfig, ConfigError> {
+        let mut problems = self.problems;
+        let jwt_secret = required(&mut problems, "JWT_SECRET", self.jwt_secret);
+        let encryption_key = required(&mut problems, "ENCRYPTION_KEY", self.encryption_key);
+
+        match (jwt_secret, encryption_key) {
+            (Some(jwt_secret), Some(encryption_key)) if problems.is_empty() => Ok(AppConfig {
+                host: self.host.unwrap_or_else(|| "127.0.0.1".to_string()),
+                port: self.port.unwrap_or(8080),
+                jwt_secret,
+                encryption_key,
+            }),
+            _ => Err(ConfigError { problems }),
+        }
+    }
+}
+
+fn required(problems: &mut Vec<ConfigProblem>, var: &'static str, value: Option<String>) -> Option<String> {
+    match value {
+        Some(value) if !value.trim().is_empty() => Some(value),
+        _ => {
+            problems.push(ConfigProblem::Missing(var));
+            None
+        }
+    }
+}
+
+/// A single missing or invalid setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigProblem {
+    Missing(&'static str),
+    Invalid { var: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigProblem::Missing(var) => write!(f, "{} is not set", var),
+            ConfigProblem::Invalid { var, reason } => write!(f, "{} is invalid: {}", var, reason),
+        }
+    }
+}
+
+/// Every problem that kept the configuration from loading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid configuration ({} problems)", self.problems.len())?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<AppConfig, ConfigError> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        AppConfig::from_lookup(|var| vars.get(var).cloned())
+    }
+
+    #[test]
+    fn defaults_the_optional_settings() {
+        let config = load(&[("JWT_SECRET", "secret"), ("ENCRYPTION_KEY", "key")]).unwrap();
+        assert_eq!((config.host.as_str(), config.port), ("127.0.0.1", 8080));
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let error = load(&[("APP_PORT", "eighty")]).unwrap_err();
+        assert_eq!(error.problems.len(), 3);
+        let message = error.to_string();
+        for var in ["APP_PORT", "JWT_SECRET", "ENCRYPTION_KEY"] {
+            assert!(message.contains(var), "{} missing from: {}", var, message);
+        }
+    }
+}
+"#;
+
+/// `src/i18n.rs`: message catalog loaded from `locales/*.json`, `Accept-Language` negotiation and `t`
+pub const I18N: &str = r#"// Localized messages
+// Every `locales/{lang}.json` holds a flat object of message keys and their translation. The files are
+// read once from `LOCALES_DIR`, by default the `locales` directory of the crate
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use actix_web::dev::Payload;
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::{Error, FromRequest, HttpRequest};
+
+/// Locale used when the client accepts none of the available ones, and for keys missing from the others
+pub const DEFAULT_LOCALE: &str = "en";
+
+static CATALOG: LazyLock<Catalog> = LazyLock::new(|| {
+    let dir = std::env::var("LOCALES_DIR")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/locales").to_string());
+    Catalog::load(&dir).unwrap_or_else(|e| panic!("Invalid locales in {}: {}", dir, e))
+});
+
+/// Translations by locale, then by key
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    locales: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    /// Reads every `{lang}.json` file of `dir`
+    pub fn load(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut catalog = Self::default();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(lang) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let messages = serde_json::from_str(&std::fs::read_to_string(&path)?).map_err(|e| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+                })?;
+                catalog.locales.insert(lang.to_lowercase(), messages);
+            }
+        }
+        Ok(catalog)
+    }
+
+    pub fn has_locale(&self, lang: &str) -> bool {
+        self.locales.contains_key(lang)
+    }
+
+    /// Message of `key` in `lang`, falling back to the default locale and then to the key itself
+    pub fn t(&self, key: &str, lang: &str) -> String {
+        [lang, DEFAULT_LOCALE].iter()
+            .find_map(|lang| self.locales.get(*lang)?.get(key))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// The available locale best matching an `Accept-Language` header (`es-ES` also matches `es`),
+    /// `DEFAULT_LOCALE` when none does
+    pub fn negotiate(&self, accept_language: &str) -> String {
+        let mut ranges: Vec<(&str, f32)> = accept_language.split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = match parts.find_map(|param| param.trim().strip_prefix("q=")) {
+                    Some(quality) => quality.trim().parse().ok()?,
+                    None => 1.0,
+                };
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+        // The sort is stable, so equally weighted ranges keep the client's order
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranges.iter()
+            .flat_map(|(tag, _)| {
+                let tag = tag.to_lowercase();
+                let primary = tag.split('-').next().unwrap_or_default().to_string();
+                [tag, primary]
+            })
+            .find(|lang| self.has_locale(lang))
+            .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+    }
+}
+
+/// Message of `key` in `lang` from the `locales` catalog
+pub fn t(key: &str, lang: &str) -> String {
+    CATALOG.t(key, lang)
+}
+
+/// Like `t`, replacing each `{name}` placeholder of the message with its value
+pub fn t_with(key: &str, lang: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(t(key, lang), |message, (name, value)| message.replace(&format!("{{{}}}", name), value))
+}
+
+/// Locale of the request, negotiated from its `Accept-Language` header. Usable as a handler argument
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lang(pub String);
+
+impl Deref for Lang {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromRequest for Lang {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let accept_language = req.headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        ready(Ok(Lang(CATALOG.negotiate(accept_language))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_a_key_in_each_locale() {
+        let args = [("resource", "users"), ("id", "1")];
+        assert_eq!(t_with("errors.not_found", "en", &args), "users 1 not found");
+        assert_eq!(t_with("errors.not_found", "es", &args), "users 1 no encontrado");
+        // Unknown locales and keys fall back to the default locale, then to the key
+        assert_eq!(t("errors.not_found", "xx"), t("errors.not_found", DEFAULT_LOCALE));
+        assert_eq!(t("missing.key", "es"), "missing.key");
+    }
+
+    #[test]
+    fn negotiates_the_best_available_locale() {
+        assert_eq!(CATALOG.negotiate("es-ES,es;q=0.9,en;q=0.8"), "es");
+        assert_eq!(CATALOG.negotiate("fr-FR, en;q=0.5, es;q=0.7"), "es");
+        assert_eq!(CATALOG.negotiate("fr"), DEFAULT_LOCALE);
+        assert_eq!(CATALOG.negotiate(""), DEFAULT_LOCALE);
+    }
+}
+"#;
+
+/// `locales/en.json`: messages of the default locale
+pub const LOCALE_EN: &str = r#"{
+    "errors.not_found": "{resource} {id} not found",
+    "errors.bad_request": "Invalid request"
+}
+"#;
+
+/// `locales/es.json`: Spanish messages, a starting point for other locales
+pub const LOCALE_ES: &str = r#"{
+    "errors.not_found": "{resource} {id} no encontrado",
+    "errors.bad_request": "Solicitud no válida"
+}
+"#;
+
+/// `src/openapi.rs`: `ApiDoc` aggregating the annotated handlers, served at `/openapi.json` and `/docs`
+pub const OPENAPI: &str = r#"// OpenAPI document of the API
+// Served as JSON at `/openapi.json` and browsable with RapiDoc at `/docs`
+use actix_web::{web, HttpResponse};
+use utoipa::OpenApi;
+use utoipa_rapidoc::RapiDoc;
+
+/// Every handler annotated with `#[utoipa::path]`; `cargo mold g resource` lists the handlers it generates.
+/// The schemas of their bodies are collected from the annotations
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::handlers::handlers::hello,
+    )
+)]
+pub struct ApiDoc;
+
+async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Serves the document at `/openapi.json` and RapiDoc at `/docs`
+pub fn openapi_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/openapi.json", web::get().to(openapi_json))
+        .service(RapiDoc::new("/openapi.json").path("/docs"));
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn openapi_json_documents_the_scaffolded_paths() {
+        let app = test::init_service(App::new().configure(openapi_routes)).await;
+
+        let req = test::TestRequest::get().uri("/openapi.json").to_request();
+        let doc: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(doc["openapi"].as_str().is_some_and(|version| version.starts_with("3.")));
+        assert!(doc["paths"]["/api/hello"]["get"].is_object());
+        assert_eq!(doc["paths"].as_object().map(|paths| paths.len()), Some(ApiDoc::openapi().paths.paths.len()));
+
+        let req = test::TestRequest::get().uri("/docs").to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+}
+"#;
+
+/// `build.rs` of `--build-info` projects: bakes the git commit and build time in for the `/version` handler
+pub const BUILD_RS: &str = r#"// Bakes the git commit and build time into the binary as `GIT_SHA` and `BUILD_TIMESTAMP`,
+// read back by the `/version` handler
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    // Builds outside a git checkout (e.g. from a source archive) report an unknown commit
+    let git_sha = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+
+    // `SOURCE_DATE_EPOCH` pins the timestamp for reproducible builds
+    let build_time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()));
+
+    println!("cargo:rustc-env=GIT_SHA={}", git_sha);
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", rfc3339(build_time));
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // Rebuild on new commits, wherever the repository root is; without git, only when this script
+    // changes (touch it to pick up a repository created after the first build)
+    match git(&["rev-parse", "--git-dir"]) {
+        Some(git_dir) => {
+            println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+            println!("cargo:rerun-if-changed={}/refs", git_dir);
+        }
+        None => println!("cargo:rerun-if-changed=build.rs"),
+    }
+}
+
+/// Trimmed output of a successful, non-empty git command
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_string())
+        .filter(|output| !output.is_empty())
+}
+
+/// `secs` since the Unix epoch as an RFC 3339 UTC timestamp, e.g. `2024-05-01T12:00:00Z`
+fn rfc3339(secs: u64) -> String {
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since the epoch (Howard Hinnant's `civil_from_days`)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, secs_of_day / 3_600, secs_of_day % 3_600 / 60, secs_of_day % 60
+    )
+}
+"#;
+
+/// `src/handlers/fallback.rs`: JSON answers to requests no route matches, wired by `--with-fallback`
+pub const FALLBACK_HANDLERS: &str = r#"// Fallback answers of unmatched requests, in the RFC 7807 Problem Details shape of the API's errors
+use actix_web::body::{BoxBody, EitherBody, MessageBody};
+use actix_web::dev::ServiceResponse;
+use actix_web::http::header::{HeaderValue, CONTENT_TYPE};
+use actix_web::http::StatusCode;
+use actix_web::middleware::{ErrorHandlerResponse, ErrorHandlers};
+use actix_web::{HttpRequest, HttpResponse};
+use serde_json::{json, Value};
+
+/// Codes of the fallback answers, sent as the `code` of their bodies
+pub mod codes {
+    pub const NOT_FOUND: &str = "route.not_found";
+    pub const METHOD_NOT_ALLOWED: &str = "route.method_not_allowed";
+}
+
+/// Problem Details body of `status`, extended with its `code`
+fn problem(status: StatusCode, detail: String, code: &str) -> Value {
+    json!({
+        "type": "about:blank",
+        "title": status.canonical_reason().unwrap_or("Unknown Error"),
+        "status": status.as_u16(),
+        "detail": detail,
+        "code": code,
+    })
+}
+
+/// Default service of the app: a JSON 404 for paths no route matches
+pub async fn not_found(req: HttpRequest) -> HttpResponse {
+    let detail = format!("No route matches {} {}", req.method(), req.path());
+    HttpResponse::NotFound()
+        .content_type("application/problem+json")
+        .json(problem(StatusCode::NOT_FOUND, detail, codes::NOT_FOUND))
+}
+
+/// Gives a JSON body to the empty 405s actix answers when a route matches the path but not the method,
+/// keeping their `Allow` header. 405s written by the handlers themselves are left alone
+pub fn method_not_allowed<B: MessageBody + 'static>() -> ErrorHandlers<B> {
+    ErrorHandlers::new().handler(StatusCode::METHOD_NOT_ALLOWED, |res: ServiceResponse<B>| {
+        if !res.response().body().size().is_eof() {
+            return Ok(ErrorHandlerResponse::Response(res.map_into_left_body()));
+        }
+        let detail = format!("{} is not allowed on {}", res.request().method(), res.request().path());
+        let body = problem(StatusCode::METHOD_NOT_ALLOWED, detail, codes::METHOD_NOT_ALLOWED);
+        let res = res.map_body(|head, _| {
+            head.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/problem+json"));
+            EitherBody::right(BoxBody::new(body.to_string()))
+        });
+        Ok(ErrorHandlerResponse::Response(res))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::ALLOW;
+    use actix_web::{test, web, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn unknown_paths_get_a_json_404() {
+        let app = test::init_service(
+            App::new()
+                .wrap(method_not_allowed())
+                .route("/known", web::get().to(|| async { HttpResponse::Ok().finish() }))
+                .default_service(web::to(not_found)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/unknown").to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/problem+json");
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "No route matches GET /unknown",
+                "code": codes::NOT_FOUND,
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn wrong_methods_get_a_json_405() {
+        let app = test::init_service(
+            App::new()
+                .wrap(method_not_allowed())
+                .service(web::resource("/known").route(web::get().to(|| async { HttpResponse::Ok().finish() })))
+                .default_service(web::to(not_found)),
+        )
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::delete().uri("/known").to_request()).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers().get(ALLOW).unwrap(), "GET");
+        let body: Value = test::read_body_json(res).await;
+        assert_eq!(body["status"], 405);
+        assert_eq!(body["code"], codes::METHOD_NOT_ALLOWED);
+    }
+
+    #[actix_web::test]
+    async fn handler_405s_keep_their_body() {
+        let app = test::init_service(App::new().wrap(method_not_allowed()).route(
+            "/locked",
+            web::post().to(|| async { HttpResponse::MethodNotAllowed().body("locked") }),
+        ))
+        .await;
+
+        let res = test::call_service(&app, test::TestRequest::post().uri("/locked").to_request()).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(test::read_body(res).await, "locked");
+    }
+}
+"#;
+
+/// Appended to `src/handlers/handlers.rs` by `--build-info`
+pub const VERSION_HANDLER: &str = r#"
+
+/// Build of the running binary: crate version, git commit and build time (baked in by `build.rs`)
+pub async fn version() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_sha": env!("GIT_SHA"),
+        "build_timestamp": env!("BUILD_TIMESTAMP"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, web, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn version_reports_the_build() {
+        let app = test::init_service(App::new().route("/version", web::get().to(version))).await;
+        let req = test::TestRequest::get().uri("/version").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(body["git_sha"].as_str().is_some_and(|sha| !sha.is_empty()));
+        assert!(body["build_timestamp"].as_str().is_some_and(|time| time.ends_with('Z')));
+    }
+}
+"#;
+
+/// Appended to `src/server/server.rs` by `--tls`: builds the rustls config from `TLS_CERT_PATH`/`TLS_KEY_PATH`
+pub const TLS_CONFIG: &str = r#"
+/// Loads the PEM certificate chain and private key pointed at by `TLS_CERT_PATH` and `TLS_KEY_PATH`.
+/// HTTP/2 is negotiated over ALPN by `bind_rustls_0_23`
+fn load_rustls_config() -> std::io::Result<rustls::ServerConfig> {
+    let cert_path = std::env::var("TLS_CERT_PATH").unwrap_or_else(|_| "certs/cert.pem".to_string());
+    let key_path = std::env::var("TLS_KEY_PATH").unwrap_or_else(|_| "certs/key.pem".to_string());
+    let invalid = |path: &str, err: &dyn std::fmt::Display| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{}: {}", path, err))
+    };
+
+    let certs = CertificateDer::pem_file_iter(&cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid(&cert_path, &e))?;
+    let key = PrivateKeyDer::from_pem_file(&key_path).map_err(|e| invalid(&key_path, &e))?;
+
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| invalid(&cert_path, &e))
+}
+"#;
+
+/// Appended to `src/server/server.rs`: checks the `.wrap` calls of the server and the routes
+/// still follow the `middleware` order of `.cargo-mold`
+pub const MIDDLEWARE_ORDER_TEST: &str = r#"
+#[cfg(test)]
+mod tests {
+    /// The `.wrap` calls cargo-mold writes, by the name `.cargo-mold` gives their middleware
+    const WRAPS: [(&str, &str); 7] = [
+        (".wrap(TracingLogger::default())", "tracing"),
+        (".wrap(Logger::new(ACCESS_LOG_FORMAT))", "access_log"),
+        (".wrap(security_headers.clone())", "security_headers"),
+        (".wrap(RequestIdMiddleware)", "request_id"),
+        (".wrap(idempotency.clone())", "idempotency"),
+        (".wrap(auth_service.jwt_middleware())", "auth"),
+        (".wrap(UserContextMiddleware)", "user_context"),
+    ];
+
+    /// Middlewares of the `.wrap` calls in `source`, in the order they are written
+    fn wrapped(source: &str) -> Vec<&'static str> {
+        source
+            .lines()
+            .filter_map(|line| WRAPS.iter().find(|(call, _)| line.trim() == *call))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    #[test]
+    fn middlewares_are_wrapped_in_the_configured_order() {
+        let order: Vec<&str> = include_str!("../../.cargo-mold")
+            .lines()
+            .find_map(|line| line.strip_prefix("middleware = ["))
+            .expect("`middleware` is missing from .cargo-mold")
+            .trim_end_matches(']')
+            .split(',')
+            .map(|name| name.trim().trim_matches('"'))
+            .collect();
+
+        for source in [include_str!("server.rs"), include_str!("../routes/routes.rs")] {
+            let wrapped = wrapped(source);
+            // The last one wrapped runs first, unlisted ones run innermost
+            let unlisted = wrapped.iter().filter(|name| !order.contains(name));
+            let listed = order.iter().rev().filter(|name| wrapped.contains(name));
+            let expected: Vec<&str> = unlisted.chain(listed).copied().collect();
+            assert_eq!(wrapped, expected, "run `cargo mold g middleware-order` after editing `middleware` in .cargo-mold");
+        }
+    }
+}
+"#;
+
+/// Doc comment of the `ACCESS_LOG_FORMAT` of `server.rs`, listing the tokens actix's `Logger` understands
+pub const ACCESS_LOG_TOKENS: &str = r#"/// Line the access log writes per request. `Logger` replaces these tokens:
+///   %a  peer IP address (%{r}a: the client's, as reported by the proxy headers)
+///   %t  time the request started
+///   %r  first line of the request: method, path and protocol
+///   %U  request path
+///   %s  response status code
+///   %b  size of the response body, in bytes
+///   %T  time taken to serve the request, in seconds
+///   %D  time taken to serve the request, in milliseconds
+///   %{FOO}i  request header FOO, %{FOO}o  response header FOO, %{FOO}e  environment variable FOO
+///   %%  a literal percent sign
+"#;
+
+/// Test of `server.rs` with `--access-log`, pasted in the tests of `MIDDLEWARE_ORDER_TEST`
+pub const ACCESS_LOG_TEST: &str = r#"
+    #[actix_web::test]
+    async fn access_log_wraps_the_app() {
+        use actix_web::middleware::Logger;
+        use actix_web::test::{call_service, init_service, TestRequest};
+        use actix_web::{web, App, HttpResponse};
+
+        let app = init_service(
+            App::new()
+                .wrap(Logger::new(super::ACCESS_LOG_FORMAT))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let res = call_service(&app, TestRequest::get().uri("/").to_request()).await;
+        assert!(res.status().is_success());
+    }
+"#;
+
+/// `examples/dev_cert.rs`: writes a self-signed certificate for local HTTPS with rcgen
+pub const DEV_CERT_EXAMPLE: &str = r#"// Generates a self-signed certificate for local development:
+//     cargo run --example dev_cert
+// Browsers will warn about it, never use it in production
+use std::fs;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cert_path = std::env::var("TLS_CERT_PATH").unwrap_or_else(|_| "certs/cert.pem".to_string());
+    let key_path = std::env::var("TLS_KEY_PATH").unwrap_or_else(|_| "certs/key.pem".to_string());
+
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string(), "127.0.0.1".to_string()])?;
+
+    for path in [&cert_path, &key_path] {
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    fs::write(&cert_path, certified.cert.pem())?;
+    fs::write(&key_path, certified.signing_key.serialize_pem())?;
+
+    println!("Wrote {} and {}", cert_path, key_path);
+    Ok(())
+}
+"#;
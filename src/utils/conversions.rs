@@ -1,33 +1,108 @@
+/// Acronyms recognized by `to_pascal_case_with_acronyms` when no custom dictionary is given
+pub const DEFAULT_ACRONYMS: &[&str] = &["API", "HTTP", "HTTPS", "ID", "JSON", "JWT", "SQL", "URL", "UUID"];
+
 pub fn to_pascal_case(s: &str) -> String {
-    s.split_whitespace()
-        .filter(|w| !w.is_empty())
+    to_pascal_case_with_acronyms::<&str>(s, &[])
+}
+
+/// Like `to_pascal_case`, but words matching one of `acronyms` (case-insensitively) are
+/// fully uppercased, so `http_server` becomes `HTTPServer` instead of `HttpServer`
+pub fn to_pascal_case_with_acronyms<A: AsRef<str>>(s: &str, acronyms: &[A]) -> String {
+    words(s)
         .map(|w| {
-            let mut c = w.chars();
-            match c.next() {
-                Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
-                None => String::new(),
+            if acronyms.iter().any(|a| a.as_ref().eq_ignore_ascii_case(w)) {
+                w.to_uppercase()
+            } else {
+                capitalize(w)
             }
         })
         .collect::<String>()
 }
 
 pub fn to_camel_case(s: &str) -> String {
-    let mut words = s.split_whitespace()
-        .filter(|w| !w.is_empty());
+    let mut words = words(s);
 
     match words.next() {
         Some(first) => {
             let mut result = first.to_lowercase();
             for w in words {
-                let mut c = w.chars();
-                if let Some(first_char) = c.next() {
-                    result.push_str(
-                        &(first_char.to_uppercase().collect::<String>() + c.as_str())
-                    );
-                }
+                result.push_str(&capitalize(w));
             }
             result
         }
         None => String::new(),
     }
-}
\ No newline at end of file
+}
+
+/// `UserProfile` -> `user_profile`, keeping acronyms together (`HTTPServer` -> `http_server`)
+pub fn to_snake_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut result = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let after_lower = !chars[i - 1].is_uppercase() && chars[i - 1] != '_';
+            let ends_acronym = chars[i - 1].is_uppercase() && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if after_lower || ends_acronym {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// Splits an identifier on whitespace, underscores and dashes
+fn words(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|w| !w.is_empty())
+}
+
+fn capitalize(w: &str) -> String {
+    let mut c = w.chars();
+    match c.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uppercases_every_default_acronym() {
+        for acronym in DEFAULT_ACRONYMS {
+            let word = acronym.to_lowercase();
+            assert_eq!(to_pascal_case_with_acronyms(&format!("{}_service", word), DEFAULT_ACRONYMS), format!("{}Service", acronym));
+        }
+    }
+
+    #[test]
+    fn uppercases_leading_and_trailing_acronyms() {
+        assert_eq!(to_pascal_case_with_acronyms("http_server", DEFAULT_ACRONYMS), "HTTPServer");
+        assert_eq!(to_pascal_case_with_acronyms("user_id", DEFAULT_ACRONYMS), "UserID");
+        assert_eq!(to_pascal_case_with_acronyms("json-api", DEFAULT_ACRONYMS), "JSONAPI");
+        assert_eq!(to_pascal_case_with_acronyms("Url parser", DEFAULT_ACRONYMS), "URLParser");
+    }
+
+    #[test]
+    fn only_matches_whole_words_of_the_given_dictionary() {
+        assert_eq!(to_pascal_case_with_acronyms("identity_provider", DEFAULT_ACRONYMS), "IdentityProvider");
+        assert_eq!(to_pascal_case_with_acronyms("grpc_client", &["gRPC"]), "GRPCClient");
+        assert_eq!(to_pascal_case("http_server"), "HttpServer");
+    }
+
+    #[test]
+    fn keeps_pascal_case_input() {
+        assert_eq!(to_pascal_case("UserProfile"), "UserProfile");
+        assert_eq!(to_pascal_case_with_acronyms("HTTPServer", DEFAULT_ACRONYMS), "HTTPServer");
+        assert_eq!(to_pascal_case_with_acronyms("Api", DEFAULT_ACRONYMS), "API");
+    }
+
+    #[test]
+    fn snake_case_keeps_acronyms_together() {
+        assert_eq!(to_snake_case("HTTPServer"), "http_server");
+        assert_eq!(to_snake_case("UserID"), "user_id");
+        assert_eq!(to_snake_case("UserProfile"), "user_profile");
+    }
+}
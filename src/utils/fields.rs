@@ -0,0 +1,494 @@
+use anyhow::{bail, Result};
+
+use crate::utils::conversions::{to_pascal_case, to_snake_case};
+
+/// A model field parsed from a `name:Type` specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub ty: String,
+    pub kind: FieldKind,
+    /// Rust expression of the value given to the field when a request body leaves it out, from `name:Type=value`
+    pub default: Option<String>,
+}
+
+/// What a field stands for beyond its Rust type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FieldKind {
+    #[default]
+    Plain,
+    /// `status:enum(Active,Inactive)`: `ty` is an enum generated next to the model, defaulting to its first variant
+    Enum(Vec<String>),
+    /// `owner:ref(User)`: the field is the `user_id` foreign key of the `User` playing the `owner` role
+    Ref { role: String, target: String },
+}
+
+/// Parses a single `name:Type`, `name:enum(A,B)` or `name:ref(Type)` field specification,
+/// optionally followed by a `=value` default like `active:bool=true`.
+/// Inline enums are named after the model and the field, e.g. `PostStatus` for `type_name` `Post`
+pub fn parse_field(spec: &str, type_name: &str) -> Result<Field> {
+    let Some((name, ty)) = spec.split_once(':') else {
+        bail!("Invalid field '{}': expected `name:Type`", spec);
+    };
+    let (ty, default) = match ty.split_once('=') {
+        Some((ty, default)) => (ty, Some(default)),
+        None => (ty, None),
+    };
+    let (name, ty) = (name.trim(), ty.trim());
+    let mut field = parse_field_type(spec, name, ty, type_name)?;
+    if let Some(literal) = default {
+        field.default = Some(default_expression(spec, &field, literal)?);
+    }
+    Ok(field)
+}
+
+fn parse_field_type(spec: &str, name: &str, ty: &str, type_name: &str) -> Result<Field> {
+
+    if !is_identifier(name) {
+        bail!("Invalid field '{}': '{}' is not a valid Rust identifier", spec, name);
+    }
+
+    if let Some(variants) = ty.strip_prefix("enum(").and_then(|rest| rest.strip_suffix(')')) {
+        let mut parsed: Vec<String> = Vec::new();
+        for variant in variants.split(',').map(str::trim) {
+            if !is_type_name(variant) {
+                bail!("Invalid field '{}': '{}' is not a valid enum variant, expected a PascalCase name like `Active`", spec, variant);
+            }
+            if parsed.iter().any(|v| v == variant) {
+                bail!("Invalid field '{}': duplicated variant '{}'", spec, variant);
+            }
+            parsed.push(variant.to_string());
+        }
+        return Ok(Field {
+            name: name.to_string(),
+            ty: format!("{}{}", type_name, to_pascal_case(name)),
+            kind: FieldKind::Enum(parsed),
+            default: None,
+        });
+    }
+
+    if let Some(target) = ty.strip_prefix("ref(").and_then(|rest| rest.strip_suffix(')')) {
+        let target = target.trim();
+        if !is_type_name(target) {
+            bail!("Invalid field '{}': '{}' is not a valid type name, expected a PascalCase name like `User`", spec, target);
+        }
+        return Ok(Field {
+            name: format!("{}_id", to_snake_case(target)),
+            ty: "String".to_string(),
+            kind: FieldKind::Ref { role: name.to_string(), target: target.to_string() },
+            default: None,
+        });
+    }
+
+    if !is_type(ty) {
+        bail!("Invalid field '{}': '{}' is not a valid Rust type", spec, ty);
+    }
+    let ty = field_type(spec, ty)?;
+
+    Ok(Field {
+        name: name.to_string(),
+        ty,
+        kind: FieldKind::Plain,
+        default: None,
+    })
+}
+
+/// Checks every type named in `ty`, written back in rustfmt's spacing with `Map<K,V>` spelled as the
+/// `HashMap<K, V>` the model imports. Map keys are limited to the types serde writes as JSON object keys
+fn field_type(spec: &str, ty: &str) -> Result<String> {
+    let ty = ty.trim();
+    let Some((outer, inner)) = ty.strip_suffix('>').and_then(|rest| rest.split_once('<')) else {
+        let path_ok = ty.split("::").all(is_identifier);
+        if !path_ok {
+            bail!("Invalid field '{}': '{}' is not a valid Rust type", spec, ty);
+        }
+        return Ok(ty.to_string());
+    };
+    let outer = outer.trim();
+    let Some(arguments) = type_arguments(inner) else {
+        bail!("Invalid field '{}': unbalanced `<>` in '{}'", spec, ty);
+    };
+    if !outer.split("::").all(is_identifier) || arguments.iter().any(|argument| argument.is_empty()) {
+        bail!("Invalid field '{}': '{}' is not a valid Rust type", spec, ty);
+    }
+    let arguments = arguments.iter().map(|argument| field_type(spec, argument)).collect::<Result<Vec<_>>>()?;
+    match (outer, arguments.as_slice()) {
+        ("Map" | "HashMap", [key, _]) if !is_map_key(key) => bail!(
+            "Invalid field '{}': `{}` can't be a map key, use String, an integer, bool or char",
+            spec, key
+        ),
+        ("Map" | "HashMap", [key, value]) => Ok(format!("HashMap<{}, {}>", key, value)),
+        ("Map" | "HashMap", _) => bail!("Invalid field '{}': maps take a key and a value type, like `Map<String,String>`", spec),
+        ("Vec" | "Option", [_]) => Ok(format!("{}<{}>", outer, arguments[0])),
+        ("Vec" | "Option", _) => bail!("Invalid field '{}': `{}` takes a single type, like `{}<String>`", spec, outer, outer),
+        _ => Ok(format!("{}<{}>", outer, arguments.join(", "))),
+    }
+}
+
+/// The comma-separated type arguments of a generic, split at the commas outside nested `<>`.
+/// `None` when the brackets don't balance
+fn type_arguments(inner: &str) -> Option<Vec<&str>> {
+    let mut arguments = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.checked_sub(1)?,
+            ',' if depth == 0 => {
+                arguments.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    (depth == 0).then(|| {
+        arguments.push(inner[start..].trim());
+        arguments
+    })
+}
+
+/// Whether serde can write the type as the key of a JSON object, which also makes it a path segment
+pub fn is_map_key(ty: &str) -> bool {
+    matches!(
+        ty,
+        "String" | "bool" | "char" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize"
+    )
+}
+
+/// Rust expression of the `literal` default of `field`, rejecting literals its type can't hold.
+/// Strings and chars may be given with or without their quotes
+fn default_expression(spec: &str, field: &Field, literal: &str) -> Result<String> {
+    fn parsed<T: std::str::FromStr + ToString>(literal: &str) -> Option<String> {
+        literal.parse::<T>().ok().map(|value| value.to_string())
+    }
+
+    let trimmed = literal.trim();
+    let expression = match (&field.kind, field.ty.as_str()) {
+        (FieldKind::Ref { .. }, _) => bail!("Invalid field '{}': references can't have a default", spec),
+        (FieldKind::Enum(variants), ty) => match variants.iter().find(|variant| *variant == trimmed || to_snake_case(variant) == trimmed) {
+            Some(variant) => Some(format!("{}::{}", ty, variant)),
+            None => bail!("Invalid field '{}': '{}' is not one of its variants {}", spec, trimmed, variants.join(", ")),
+        },
+        (_, "bool") => matches!(trimmed, "true" | "false").then(|| trimmed.to_string()),
+        (_, "i8") => parsed::<i8>(trimmed),
+        (_, "i16") => parsed::<i16>(trimmed),
+        (_, "i32") => parsed::<i32>(trimmed),
+        (_, "i64") => parsed::<i64>(trimmed),
+        (_, "i128") => parsed::<i128>(trimmed),
+        (_, "isize") => parsed::<i64>(trimmed),
+        (_, "u8") => parsed::<u8>(trimmed),
+        (_, "u16") => parsed::<u16>(trimmed),
+        (_, "u32") => parsed::<u32>(trimmed),
+        (_, "u64") => parsed::<u64>(trimmed),
+        (_, "u128") => parsed::<u128>(trimmed),
+        (_, "usize") => parsed::<u64>(trimmed),
+        // Debug keeps the decimal point a float literal needs, `1.0` rather than `1`
+        (_, "f32") => trimmed.parse::<f32>().ok().filter(|value| value.is_finite()).map(|value| format!("{:?}", value)),
+        (_, "f64") => trimmed.parse::<f64>().ok().filter(|value| value.is_finite()).map(|value| format!("{:?}", value)),
+        (_, "char") => {
+            let unquoted = trimmed.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')).unwrap_or(trimmed);
+            let mut chars = unquoted.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(format!("{:?}", c)),
+                _ => None,
+            }
+        }
+        (_, "String") => {
+            let unquoted = literal.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')).unwrap_or(literal);
+            Some(format!("{:?}.to_string()", unquoted))
+        }
+        (_, ty) => bail!(
+            "Invalid field '{}': `{}` fields can't have a default, only bool, integer, float, char, String and enum fields can",
+            spec, ty
+        ),
+    };
+    match expression {
+        Some(expression) => Ok(expression),
+        None => bail!("Invalid field '{}': '{}' is not a valid `{}` default", spec, literal, field.ty),
+    }
+}
+
+/// Parses a list of field specifications for the `type_name` model, rejecting duplicated names
+pub fn parse_fields(specs: &[String], type_name: &str) -> Result<Vec<Field>> {
+    let mut fields: Vec<Field> = Vec::new();
+    for spec in specs {
+        let field = parse_field(spec, type_name)?;
+        if fields.iter().any(|f| f.name == field.name) {
+            bail!("Duplicated field '{}'", field.name);
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// States and allowed transitions of a status field, parsed from `draft->submitted->approved`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusMachine {
+    /// Every state in order of first appearance, the first one being the initial state
+    pub states: Vec<String>,
+    pub transitions: Vec<(String, String)>,
+}
+
+/// Parses comma-separated chains of `->`-separated states, e.g. `draft->submitted->approved, submitted->draft`
+pub fn parse_status_machine(spec: &str) -> Result<StatusMachine> {
+    let mut machine = StatusMachine { states: Vec::new(), transitions: Vec::new() };
+    for chain in spec.split(',') {
+        let states: Vec<&str> = chain.split("->").map(str::trim).collect();
+        if states.len() < 2 {
+            bail!("Invalid status machine '{}': '{}' has no transition, expected `from->to`", spec, chain.trim());
+        }
+        for state in &states {
+            if !is_identifier(state) {
+                bail!("Invalid status machine '{}': '{}' is not a valid state name", spec, state);
+            }
+            if !machine.states.iter().any(|s| s == state) {
+                machine.states.push(state.to_string());
+            }
+        }
+        for pair in states.windows(2) {
+            if pair[0] == pair[1] {
+                bail!("Invalid status machine '{}': '{}' can't transition to itself", spec, pair[0]);
+            }
+            let transition = (pair[0].to_string(), pair[1].to_string());
+            if !machine.transitions.contains(&transition) {
+                machine.transitions.push(transition);
+            }
+        }
+    }
+    Ok(machine)
+}
+
+/// How a resource relates to another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationKind {
+    /// Each item references one item of the other resource through a `{other}_id` field
+    BelongsTo,
+    /// Items of the other resource reference this one through their own `{this}_id` field
+    HasMany,
+}
+
+/// A relation to another resource, parsed from `users:belongs_to` or `comments:has_many`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub resource: String,
+    pub kind: RelationKind,
+}
+
+impl Relation {
+    /// Foreign key field added by a `belongs_to` relation, e.g. `users_id`
+    pub fn foreign_key(&self) -> String {
+        format!("{}_id", self.resource)
+    }
+}
+
+/// Parses a single `resource:belongs_to` or `resource:has_many` relation
+pub fn parse_relation(spec: &str) -> Result<Relation> {
+    let Some((resource, kind)) = spec.split_once(':') else {
+        bail!("Invalid relation '{}': expected `resource:belongs_to` or `resource:has_many`", spec);
+    };
+    let resource = resource.trim();
+    if !is_identifier(resource) {
+        bail!("Invalid relation '{}': '{}' is not a valid resource name", spec, resource);
+    }
+    let kind = match kind.trim() {
+        "belongs_to" => RelationKind::BelongsTo,
+        "has_many" => RelationKind::HasMany,
+        other => bail!("Invalid relation '{}': unknown kind '{}', expected `belongs_to` or `has_many`", spec, other),
+    };
+    Ok(Relation { resource: resource.to_string(), kind })
+}
+
+pub fn parse_relations(specs: &[String]) -> Result<Vec<Relation>> {
+    let mut relations: Vec<Relation> = Vec::new();
+    for spec in specs {
+        let relation = parse_relation(spec)?;
+        if relations.iter().any(|r| r.resource == relation.resource) {
+            bail!("Duplicated relation to '{}'", relation.resource);
+        }
+        relations.push(relation);
+    }
+    Ok(relations)
+}
+
+/// Adds the `String` foreign key of every `belongs_to` relation missing from `fields`,
+/// so `--fields users_id:u64` can still pick another type
+pub fn add_foreign_keys(fields: &mut Vec<Field>, relations: &[Relation]) {
+    for relation in relations.iter().filter(|r| r.kind == RelationKind::BelongsTo) {
+        let name = relation.foreign_key();
+        if !fields.iter().any(|field| field.name == name) {
+            fields.push(Field { name, ty: "String".to_string(), kind: FieldKind::Plain, default: None });
+        }
+    }
+}
+
+/// Picks the fields of a composite primary key, named like `user_id,role_id`. Every one must be among
+/// `fields` and parse from a path segment (String, an integer, bool or char)
+pub fn parse_primary_key(names: &[String], fields: &[Field]) -> Result<Vec<Field>> {
+    if names.len() < 2 {
+        bail!("A composite primary key needs at least two fields, use --id-type for a single id");
+    }
+    let mut key: Vec<Field> = Vec::new();
+    for name in names.iter().map(|name| name.trim()) {
+        let Some(field) = fields.iter().find(|field| field.name == name) else {
+            bail!("Primary key field '{}' is not one of the fields", name);
+        };
+        if key.iter().any(|f| f.name == name) {
+            bail!("Duplicated primary key field '{}'", name);
+        }
+        if !is_map_key(&field.ty) {
+            bail!("Primary key field '{}' is a `{}`, use String, an integer, bool or char", name, field.ty);
+        }
+        key.push(field.clone());
+    }
+    Ok(key)
+}
+
+/// Loosely checks that a string looks like a Rust type (`i32`, `Option<String>`, `chrono::NaiveDate`)
+pub fn is_type(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "_:<>, ".contains(c))
+}
+
+/// A PascalCase type or variant name, like `User` or `Active`
+pub fn is_type_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_uppercase()) && s.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+pub fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Reads the struct name and fields back from a generated model file
+pub fn parse_model(source: &str) -> Option<(String, Vec<Field>)> {
+    let mut lines = source.lines().map(str::trim);
+    let type_name = lines.find_map(|line| {
+        line.strip_prefix("pub struct ")?.strip_suffix('{').map(|name| name.trim().to_string())
+    })?;
+
+    let fields = lines
+        .take_while(|line| *line != "}")
+        .filter_map(|line| {
+            let (name, ty) = line.strip_prefix("pub ")?.split_once(':')?;
+            Some(Field {
+                name: name.trim().to_string(),
+                ty: ty.trim().trim_end_matches(',').trim().to_string(),
+                kind: FieldKind::Plain,
+                default: None,
+            })
+        })
+        .collect();
+
+    Some((type_name, fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_relation_kind() {
+        assert_eq!(
+            parse_relation("users:belongs_to").unwrap(),
+            Relation { resource: "users".to_string(), kind: RelationKind::BelongsTo }
+        );
+        assert_eq!(
+            parse_relation(" comments : has_many ").unwrap(),
+            Relation { resource: "comments".to_string(), kind: RelationKind::HasMany }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_relations() {
+        for (spec, error) in [
+            ("users:has_one", "unknown kind 'has_one', expected `belongs_to` or `has_many`"),
+            ("users", "expected `resource:belongs_to` or `resource:has_many`"),
+            ("2users:belongs_to", "'2users' is not a valid resource name"),
+        ] {
+            let message = parse_relation(spec).unwrap_err().to_string();
+            assert!(message.contains(error), "{}: {}", spec, message);
+        }
+        let specs = ["users:belongs_to".to_string(), "users:has_many".to_string()];
+        assert_eq!(parse_relations(&specs).unwrap_err().to_string(), "Duplicated relation to 'users'");
+    }
+
+    #[test]
+    fn adds_the_foreign_key_of_belongs_to_relations_only() {
+        let relations = parse_relations(&["users:belongs_to".to_string(), "tags:has_many".to_string()]).unwrap();
+        let mut fields = parse_fields(&["title:String".to_string()], "Post").unwrap();
+        add_foreign_keys(&mut fields, &relations);
+        let names: Vec<(&str, &str)> = fields.iter().map(|field| (field.name.as_str(), field.ty.as_str())).collect();
+        assert_eq!(names, [("title", "String"), ("users_id", "String")]);
+
+        // A foreign key given with `--fields` keeps its type
+        let mut fields = parse_fields(&["users_id:u64".to_string()], "Post").unwrap();
+        add_foreign_keys(&mut fields, &relations);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].ty, "u64");
+    }
+
+    #[test]
+    fn parses_each_field_type() {
+        for (spec, ty) in [
+            ("title:String", "String"),
+            ("count:u64", "u64"),
+            ("score:f64", "f64"),
+            ("active:bool", "bool"),
+            ("born:chrono::NaiveDate", "chrono::NaiveDate"),
+            ("nickname:Option<String>", "Option<String>"),
+            ("tags:Vec<String>", "Vec<String>"),
+            ("labels:Map<String,Vec<u32>>", "HashMap<String, Vec<u32>>"),
+        ] {
+            let field = parse_field(spec, "Post").unwrap();
+            assert_eq!((field.ty.as_str(), &field.kind), (ty, &FieldKind::Plain), "{}", spec);
+        }
+
+        let status = parse_field("status:enum(Draft,Published)", "Post").unwrap();
+        assert_eq!(status.ty, "PostStatus");
+        assert_eq!(status.kind, FieldKind::Enum(vec!["Draft".to_string(), "Published".to_string()]));
+
+        let owner = parse_field("owner:ref(User)", "Post").unwrap();
+        assert_eq!((owner.name.as_str(), owner.ty.as_str()), ("user_id", "String"));
+        assert_eq!(owner.kind, FieldKind::Ref { role: "owner".to_string(), target: "User".to_string() });
+    }
+
+    #[test]
+    fn rejects_invalid_field_types() {
+        for (spec, error) in [
+            ("title", "expected `name:Type`"),
+            ("title:Str-ing", "'Str-ing' is not a valid Rust type"),
+            ("tags:Vec<>", "'Vec<>' is not a valid Rust type"),
+            ("tags:Vec<Vec<String>", "unbalanced `<>`"),
+            ("tags:Vec<String,u8>", "`Vec` takes a single type"),
+            ("scores:Map<f64,String>", "`f64` can't be a map key"),
+            ("status:enum(draft)", "'draft' is not a valid enum variant"),
+            ("owner:ref(user)", "'user' is not a valid type name"),
+        ] {
+            let message = parse_field(spec, "Post").unwrap_err().to_string();
+            assert!(message.contains(error), "{}: {}", spec, message);
+        }
+    }
+
+    #[test]
+    fn picks_the_fields_of_a_composite_primary_key() {
+        let fields = parse_fields(&["user_id:String".to_string(), "role_id:u32".to_string(), "tags:Vec<String>".to_string()], "Membership").unwrap();
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        let key = parse_primary_key(&names(&["user_id", " role_id"]), &fields).unwrap();
+        assert_eq!(key.iter().map(|field| field.ty.as_str()).collect::<Vec<_>>(), ["String", "u32"]);
+
+        for (key, error) in [
+            (vec!["user_id"], "needs at least two fields"),
+            (vec!["user_id", "group_id"], "'group_id' is not one of the fields"),
+            (vec!["user_id", "user_id"], "Duplicated primary key field 'user_id'"),
+            (vec!["user_id", "tags"], "'tags' is a `Vec<String>`"),
+        ] {
+            let message = parse_primary_key(&names(&key), &fields).unwrap_err().to_string();
+            assert!(message.contains(error), "{:?}: {}", key, message);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use std::path::Path;
+use std::sync::OnceLock;
+use tokio::process::Command;
+
+/// File `cargo mold new --license-header` copies the header template to, relative to the project root
+pub const LICENSE_HEADER_FILE: &str = "LICENSE_HEADER";
+
+/// Header prepended to every generated `.rs` file, from a template where `{year}` and `{author}`
+/// stand for the current year and the copyright holder
+#[derive(Debug, Clone)]
+pub struct LicenseHeader {
+    /// Lines of the template, commented out
+    template: Vec<String>,
+    author: String,
+}
+
+static HEADER: OnceLock<LicenseHeader> = OnceLock::new();
+
+/// Prepends `header` to the `.rs` files written for the rest of the process, the first call wins
+pub fn set_license_header(header: LicenseHeader) {
+    let _ = HEADER.set(header);
+}
+
+/// `content` of the file at `path` with the license header on top, when one is set, the file is
+/// Rust source and doesn't start with the header already
+pub fn with_license_header(path: &Path, content: &[u8]) -> Option<Vec<u8>> {
+    HEADER.get()?.apply(path, content)
+}
+
+impl LicenseHeader {
+    /// `content` of the file at `path` with the header on top, unless it isn't Rust source or has the header already
+    fn apply(&self, path: &Path, content: &[u8]) -> Option<Vec<u8>> {
+        if path.extension().is_none_or(|extension| extension != "rs") {
+            return None;
+        }
+        let text = std::str::from_utf8(content).ok()?;
+        if self.is_applied(text) {
+            return None;
+        }
+        Some(format!("{}\n{}", self.render(), text).into_bytes())
+    }
+
+    /// Header of the template `text`, whose lines are commented out with `//` unless they are already comments
+    pub fn new(text: &str, author: &str) -> Self {
+        let text = text.trim_end();
+        let commented = text.starts_with("/*") || text.lines().all(|line| line.starts_with("//"));
+        let template = text.lines()
+            .map(|line| match line {
+                _ if commented => line.to_string(),
+                "" => "//".to_string(),
+                _ => format!("// {}", line),
+            })
+            .collect();
+        Self { template, author: author.to_string() }
+    }
+
+    /// Reads the template at `path`, taking the author from `author` or else from `git config user.name`
+    /// when the template mentions one
+    pub async fn load(path: &str, author: Option<&str>) -> Result<Self> {
+        let text = tokio::fs::read_to_string(path).await
+            .map_err(|e| anyhow::anyhow!("❌ Could not read the license header '{}': {}", path, e))?;
+        let author = match author {
+            Some(author) => author.to_string(),
+            None if text.contains("{author}") => git_user_name().await.ok_or_else(|| anyhow::anyhow!(
+                "❌ The license header mentions {{author}}: set `license_author` in .cargo-mold or `git config user.name`"
+            ))?,
+            None => String::new(),
+        };
+        Ok(Self::new(&text, &author))
+    }
+
+    /// The commented template, as it is written into files
+    pub fn template(&self) -> String {
+        self.template.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    /// The header of files written this year
+    pub fn render(&self) -> String {
+        let year = chrono::Local::now().format("%Y").to_string();
+        self.template().replace("{year}", &year).replace("{author}", &self.author)
+    }
+
+    /// Whether `content` starts with the header, whatever year and author it was written with,
+    /// so files generated in earlier years don't get a second one
+    fn is_applied(&self, content: &str) -> bool {
+        let mut lines = content.lines();
+        self.template.iter().all(|template| lines.next().is_some_and(|line| matches_template(line, template)))
+    }
+}
+
+/// Whether `line` is `template` with anything in place of its `{year}` and `{author}`
+fn matches_template(line: &str, template: &str) -> bool {
+    let pattern = template.replace("{author}", "{year}");
+    let mut parts = pattern.split("{year}");
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = line.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// `git config user.name`, when git is installed and it is set
+async fn git_user_name() -> Option<String> {
+    let output = Command::new("git").args(["config", "user.name"]).output().await.ok()?;
+    let name = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> LicenseHeader {
+        LicenseHeader::new("Copyright {year} {author}\n\nLicensed under MIT", "Ada")
+    }
+
+    #[test]
+    fn comments_out_the_template() {
+        assert_eq!(header().template(), "// Copyright {year} {author}\n//\n// Licensed under MIT\n");
+        assert_eq!(LicenseHeader::new("// Already commented\n", "").template(), "// Already commented\n");
+    }
+
+    #[test]
+    fn heads_only_rust_sources() {
+        let headed = header().apply(Path::new("src/main.rs"), b"fn main() {}\n").unwrap();
+        let headed = String::from_utf8(headed).unwrap();
+        assert!(headed.starts_with("// Copyright "), "{}", headed);
+        assert!(headed.contains(" Ada\n//\n// Licensed under MIT\n\nfn main() {}\n"), "{}", headed);
+
+        for path in ["Cargo.toml", "migrations/0001_create_users.sql", ".env", "Makefile"] {
+            assert_eq!(header().apply(Path::new(path), b"content\n"), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn is_not_doubled_on_regeneration() {
+        let path = Path::new("src/lib.rs");
+        let headed = header().apply(path, b"pub mod routes;\n").unwrap();
+        assert_eq!(header().apply(path, &headed), None);
+
+        let last_year = "// Copyright 1999 Someone Else\n//\n// Licensed under MIT\n\npub mod routes;\n";
+        assert_eq!(header().apply(path, last_year.as_bytes()), None);
+    }
+}
@@ -0,0 +1,212 @@
+//! Editing the project's `Cargo.toml` in place, keeping its formatting and comments
+use anyhow::{Context, Result};
+use tokio::fs;
+use toml_edit::{value, ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::utils::project::ProjectContext;
+use crate::utils::utils::write_if_changed;
+
+/// The project's manifest, written back by `save`
+pub struct Manifest {
+    path: String,
+    document: DocumentMut,
+}
+
+impl Manifest {
+    pub async fn load(ctx: &ProjectContext) -> Result<Self> {
+        let path = ctx.crate_path("Cargo.toml");
+        let source = fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("❌ Could not read '{}'", path))?;
+        let document = source.parse::<DocumentMut>().map_err(|e| parse_error(&path, &source, &e))?;
+        Ok(Self { path, document })
+    }
+
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.document.get("dependencies").and_then(|deps| deps.get(name)).is_some()
+    }
+
+    /// Adds `name = {requirement}` to `[dependencies]` unless it is already listed, e.g.
+    /// `add_dependency("clap", r#"{ version = "4", features = ["derive"] }"#)`. Returns whether it was added
+    pub fn add_dependency(&mut self, name: &str, requirement: &str) -> Result<bool> {
+        if self.has_dependency(name) {
+            return Ok(false);
+        }
+        let requirement: toml_edit::Value = requirement
+            .parse()
+            .with_context(|| format!("invalid requirement for {}: {}", name, requirement))?;
+        let dependencies = self.document.entry("dependencies").or_insert_with(|| Item::Table(Table::new()));
+        let Some(dependencies) = dependencies.as_table_like_mut() else {
+            anyhow::bail!("❌ [dependencies] of '{}' is not a table", self.path);
+        };
+        dependencies.insert(name, Item::Value(requirement));
+        Ok(true)
+    }
+
+    /// Declares `name = []` in `[features]` unless it is already. Returns whether it was added
+    pub fn add_feature(&mut self, name: &str) -> Result<bool> {
+        let features = self.document.entry("features").or_insert_with(|| Item::Table(Table::new()));
+        let Some(features) = features.as_table_like_mut() else {
+            anyhow::bail!("❌ [features] of '{}' is not a table", self.path);
+        };
+        if features.contains_key(name) {
+            return Ok(false);
+        }
+        features.insert(name, value(toml_edit::Array::new()));
+        Ok(true)
+    }
+
+    /// Adds a `[[bin]]` target unless one with the same name exists. Returns whether it was added
+    pub fn add_bin(&mut self, name: &str, path: &str) -> Result<bool> {
+        let bins = self.document.entry("bin").or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()));
+        let Some(bins) = bins.as_array_of_tables_mut() else {
+            anyhow::bail!("❌ `bin` of '{}' is not a list of [[bin]] tables", self.path);
+        };
+        if bins.iter().any(|bin| bin.get("name").and_then(Item::as_str) == Some(name)) {
+            return Ok(false);
+        }
+        let mut bin = Table::new();
+        bin.insert("name", value(name));
+        bin.insert("path", value(path));
+        bins.push(bin);
+        Ok(true)
+    }
+
+    /// Adds an `[[example]]` target only built with `feature` unless one with the same name exists.
+    /// Returns whether it was added
+    pub fn add_example(&mut self, name: &str, feature: &str) -> Result<bool> {
+        let examples = self.document.entry("example").or_insert_with(|| Item::ArrayOfTables(ArrayOfTables::new()));
+        let Some(examples) = examples.as_array_of_tables_mut() else {
+            anyhow::bail!("❌ `example` of '{}' is not a list of [[example]] tables", self.path);
+        };
+        if examples.iter().any(|example| example.get("name").and_then(Item::as_str) == Some(name)) {
+            return Ok(false);
+        }
+        let mut example = Table::new();
+        example.insert("name", value(name));
+        example.insert("required-features", value(toml_edit::Array::from_iter([feature])));
+        examples.push(example);
+        Ok(true)
+    }
+
+    /// Name the crate's library is imported with: its `[lib]` name, else the package name with `-` turned into `_`
+    pub fn lib_name(&self) -> Option<String> {
+        let lib_name = self.document.get("lib").and_then(|lib| lib.get("name")).and_then(Item::as_str);
+        let package_name = || self.document.get("package").and_then(|package| package.get("name")).and_then(Item::as_str);
+        lib_name.or_else(package_name).map(|name| name.replace('-', "_"))
+    }
+
+    /// Points `default-run` at the package's own binary when it isn't set, so `cargo run`
+    /// still starts the server once other binaries exist. Returns whether it was set
+    pub fn keep_default_run(&mut self) -> bool {
+        let Some(package) = self.document.get_mut("package").and_then(Item::as_table_like_mut) else {
+            return false;
+        };
+        if package.contains_key("default-run") {
+            return false;
+        }
+        let Some(name) = package.get("name").and_then(Item::as_str).map(str::to_string) else {
+            return false;
+        };
+        package.insert("default-run", value(name));
+        true
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        write_if_changed(&self.path, self.document.to_string()).await?;
+        Ok(())
+    }
+}
+
+/// The manifest at `path` holding `existing`, with whatever `generated` declares that it lacks: missing tables,
+/// keys (dependencies among them) and `[[bin]]`-like targets are added, while the values it sets are kept
+pub fn merge_manifests(path: &str, existing: &str, generated: &str) -> Result<String> {
+    let mut document = existing.parse::<DocumentMut>().map_err(|e| parse_error(path, existing, &e))?;
+    let generated = generated.parse::<DocumentMut>().context("❌ The generated manifest is not valid TOML")?;
+    merge_table(document.as_table_mut(), generated.as_table());
+    Ok(document.to_string())
+}
+
+fn merge_table(into: &mut Table, from: &Table) {
+    for (key, item) in from.iter() {
+        match (into.get_mut(key), item) {
+            (None, _) => {
+                into.insert(key, item.clone());
+            }
+            (Some(Item::Table(existing)), Item::Table(table)) => merge_table(existing, table),
+            (Some(Item::ArrayOfTables(existing)), Item::ArrayOfTables(targets)) => {
+                for target in targets.iter() {
+                    let name = target.get("name").and_then(Item::as_str);
+                    if !existing.iter().any(|table| table.get("name").and_then(Item::as_str) == name) {
+                        existing.push(target.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Why `source` doesn't parse, naming the offending line and how to fix it
+fn parse_error(path: &str, source: &str, error: &toml_edit::TomlError) -> anyhow::Error {
+    let Some(span) = error.span() else {
+        return anyhow::anyhow!("❌ '{}' is not a valid manifest: {}", path, error.message());
+    };
+    let number = source[..span.start].matches('\n').count() + 1;
+    let line = source.lines().nth(number - 1).unwrap_or_default().trim();
+    anyhow::anyhow!(
+        "❌ '{}' is not a valid manifest, line {}: `{}`\n   {}\n   💡 {}",
+        path, number, line, error.message().trim(), suggestion(line)
+    )
+}
+
+/// Fix of a manifest line that doesn't parse, e.g. `cargo-mold = "0.2.1"` for `cargo-mold = 0.2.1`
+fn suggestion(line: &str) -> String {
+    if let Some((key, requirement)) = line.split_once('=') {
+        let requirement = requirement.trim();
+        let bare_version = !requirement.is_empty()
+            && requirement.chars().all(|c| c.is_ascii_digit() || ".*^~<>=, ".contains(c));
+        if bare_version {
+            return format!("Quote the version: {} = \"{}\"", key.trim(), requirement);
+        }
+    }
+    "Fix the TOML syntax of that line, then run the command again".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_of(source: &str) -> String {
+        let error = source.parse::<DocumentMut>().unwrap_err();
+        parse_error("Cargo.toml", source, &error).to_string()
+    }
+
+    #[test]
+    fn names_the_line_and_quotes_a_bare_version() {
+        let message = error_of("[package]\nname = \"app\"\n\n[dependencies]\ncargo-mold = 0.2.1\n");
+        assert!(message.contains("line 5: `cargo-mold = 0.2.1`"), "{}", message);
+        assert!(message.contains(r#"Quote the version: cargo-mold = "0.2.1""#), "{}", message);
+    }
+
+    #[test]
+    fn merges_what_the_existing_manifest_lacks() {
+        let existing = "[package]\nname = \"legacy\"\n\n[dependencies]\n# Pinned for the importer\nserde = \"=1.0.100\"\n";
+        let generated = "[package]\nname = \"app\"\nedition = \"2021\"\n\n[dependencies]\nserde = \"1.0\"\nactix-web = \"4.4\"\n\n\
+                         [[bin]]\nname = \"migrate\"\npath = \"src/bin/migrate.rs\"\n";
+        let merged = merge_manifests("Cargo.toml", existing, generated).unwrap();
+        assert_eq!(
+            merged,
+            "[package]\nname = \"legacy\"\nedition = \"2021\"\n\n[dependencies]\n# Pinned for the importer\nserde = \"=1.0.100\"\n\
+             actix-web = \"4.4\"\n\n[[bin]]\nname = \"migrate\"\npath = \"src/bin/migrate.rs\"\n"
+        );
+        assert_eq!(merge_manifests("Cargo.toml", &merged, generated).unwrap(), merged);
+    }
+
+    #[test]
+    fn suggests_fixing_other_syntax_errors() {
+        let message = error_of("[package\nname = \"app\"\n");
+        assert!(message.contains("line 1: `[package`"), "{}", message);
+        assert!(message.contains("Fix the TOML syntax"), "{}", message);
+    }
+}
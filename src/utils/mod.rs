@@ -1,2 +1,10 @@
 pub mod conversions;
-pub mod utils;
\ No newline at end of file
+pub mod fields;
+pub mod license;
+pub mod manifest;
+pub mod output;
+pub mod plan;
+pub mod project;
+#[allow(clippy::module_inception)]
+pub mod utils;
+pub mod wiring;
\ No newline at end of file
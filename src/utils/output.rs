@@ -0,0 +1,129 @@
+use clap::ValueEnum;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// How the emoji markers of user-facing messages are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputStyle {
+    /// Emoji markers such as ✅ and ❌
+    #[default]
+    Emoji,
+    /// No markers, for terminals and log aggregators that mangle emoji
+    Plain,
+    /// ASCII markers such as `[ok]` and `[error]`
+    Ascii,
+}
+
+static STYLE: OnceLock<OutputStyle> = OnceLock::new();
+
+/// Chooses the style for the rest of the process, the first call wins
+pub fn set_style(style: OutputStyle) {
+    let _ = STYLE.set(style);
+}
+
+/// The chosen style, `Plain` when `NO_EMOJI` is set and none was chosen, `Emoji` otherwise
+pub fn style() -> OutputStyle {
+    *STYLE.get_or_init(|| {
+        if std::env::var_os("NO_EMOJI").is_some_and(|value| !value.is_empty()) {
+            OutputStyle::Plain
+        } else {
+            OutputStyle::Emoji
+        }
+    })
+}
+
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends messages to stderr from now on, keeping stdout for machine-readable output such as `--json`
+pub fn report_to_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Prints a user-facing message in the current style
+pub fn report(message: impl AsRef<str>) {
+    let message = render(message.as_ref(), style());
+    if TO_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Rewrites the emoji of `message` for `style`
+pub fn render(message: &str, style: OutputStyle) -> String {
+    if style == OutputStyle::Emoji {
+        return message.to_string();
+    }
+
+    let mut rendered = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_emoji(c) {
+            rendered.push(c);
+            continue;
+        }
+        // Variation selectors and joined emoji belong to the same marker
+        while chars.next_if(|next| is_emoji(*next)).is_some() {}
+        match style {
+            OutputStyle::Ascii => rendered.push_str(ascii_marker(c)),
+            _ => {
+                chars.next_if_eq(&' ');
+            }
+        }
+    }
+    rendered
+}
+
+fn is_emoji(c: char) -> bool {
+    // Besides the emoji blocks: technical symbols like ⌨ and ⏭, and geometric shapes like ▶
+    matches!(
+        c,
+        '\u{1F000}'..='\u{1FAFF}'
+            | '\u{2300}'..='\u{23FF}'
+            | '\u{25A0}'..='\u{25FF}'
+            | '\u{2600}'..='\u{27BF}'
+            | '\u{2B00}'..='\u{2BFF}'
+            | '\u{FE0F}'
+            | '\u{200D}'
+    )
+}
+
+fn ascii_marker(emoji: char) -> &'static str {
+    match emoji {
+        '✅' => "[ok]",
+        '❌' => "[error]",
+        '⚠' => "[warn]",
+        _ => "*",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_markers_for_each_style() {
+        let message = "✅ Resource 'users' created successfully!";
+        assert_eq!(render(message, OutputStyle::Emoji), message);
+        assert_eq!(render(message, OutputStyle::Plain), "Resource 'users' created successfully!");
+        assert_eq!(render(message, OutputStyle::Ascii), "[ok] Resource 'users' created successfully!");
+    }
+
+    #[test]
+    fn treats_variation_selectors_as_part_of_the_marker() {
+        let message = "⚠️ clippy is not installed";
+        assert_eq!(render(message, OutputStyle::Plain), "clippy is not installed");
+        assert_eq!(render(message, OutputStyle::Ascii), "[warn] clippy is not installed");
+        assert_eq!(render("   - 📦 sqlx", OutputStyle::Ascii), "   - * sqlx");
+    }
+
+    #[test]
+    fn strips_hint_markers_outside_the_emoji_blocks() {
+        assert_eq!(render("▶️ Try the routes with `cargo run --example users`", OutputStyle::Plain), "Try the routes with `cargo run --example users`");
+        assert_eq!(render("▶ Try", OutputStyle::Plain), "Try");
+        assert_eq!(render("⏭️ Skipped", OutputStyle::Ascii), "* Skipped");
+        assert_eq!(render("⌨️ Shell completions", OutputStyle::Plain), "Shell completions");
+        // Arrows are part of the message
+        assert_eq!(render("   - src/models/c.rs → src/models/cc.rs", OutputStyle::Plain), "   - src/models/c.rs → src/models/cc.rs");
+    }
+}
@@ -0,0 +1,278 @@
+//! `--dry-run` support: a generate command runs against a scratch copy of the project, and the
+//! differences with the real one are reported as a `GenerationPlan` instead of being written
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::utils::output::report;
+
+/// Top-level directories never copied into the scratch project, nor compared
+const SKIPPED_DIRS: &[&str] = &["target", ".git"];
+/// Lines of unchanged context around each hunk of a diff
+const CONTEXT_LINES: usize = 3;
+
+/// Every filesystem effect of a generate command, serialized as the `--dry-run --json` manifest
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct GenerationPlan {
+    /// Files the command would create, relative to the project root
+    pub creates: Vec<String>,
+    /// Existing files the command would change
+    pub edits: Vec<FileEdit>,
+    /// Existing files the command would delete, e.g. the old files of a renamed resource
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deletes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct FileEdit {
+    pub path: String,
+    /// Unified diff of the change
+    pub diff: String,
+}
+
+impl GenerationPlan {
+    /// Runs `command` in a scratch copy of the project in the current directory and
+    /// describes what it changed there, leaving the project untouched
+    pub async fn dry_run<F, Fut>(command: F) -> Result<Self>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let project = std::env::current_dir()?;
+        let scratch = std::env::temp_dir().join(format!("cargo-mold-dry-run-{}", std::process::id()));
+        if scratch.exists() {
+            fs::remove_dir_all(&scratch).await?;
+        }
+        copy_tree(&project, &scratch).await.context("❌ Could not copy the project for the dry run")?;
+
+        std::env::set_current_dir(&scratch)?;
+        let result = command().await;
+        std::env::set_current_dir(&project)?;
+
+        let plan = match result {
+            Ok(()) => Self::between(&project, &scratch).await,
+            Err(e) => Err(e),
+        };
+        fs::remove_dir_all(&scratch).await?;
+        plan
+    }
+
+    /// What turns the tree at `before` into the one at `after`
+    pub async fn between(before: &Path, after: &Path) -> Result<Self> {
+        let old = read_tree(before).await?;
+        let new_tree = read_tree(after).await?;
+        let mut plan = Self::default();
+        for (path, new) in &new_tree {
+            let path = path.clone();
+            match old.get(&path) {
+                None => plan.creates.push(path),
+                Some(old) if old != new => {
+                    let diff = unified_diff(&path, &String::from_utf8_lossy(old), &String::from_utf8_lossy(new));
+                    plan.edits.push(FileEdit { path, diff });
+                }
+                Some(_) => {}
+            }
+        }
+        plan.deletes = old.into_keys().filter(|path| !new_tree.contains_key(path)).collect();
+        Ok(plan)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.creates.is_empty() && self.edits.is_empty() && self.deletes.is_empty()
+    }
+
+    /// Prints the plan for people, with the diff of every edit
+    pub fn report(&self) {
+        if self.is_empty() {
+            report("🔍 Dry run: nothing would change");
+            return;
+        }
+        report("🔍 Dry run, nothing was written. The command would:");
+        for path in &self.creates {
+            report(format!("   + create {}", path));
+        }
+        for edit in &self.edits {
+            report(format!("   ~ edit {}", edit.path));
+        }
+        for path in &self.deletes {
+            report(format!("   - delete {}", path));
+        }
+        for edit in &self.edits {
+            report("");
+            report(edit.diff.trim_end());
+        }
+    }
+}
+
+/// Relative paths of the files under `root`, skipping `SKIPPED_DIRS` at the top level
+async fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(dir) = pending.pop() {
+        let mut entries = fs::read_dir(root.join(&dir)).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = dir.join(entry.file_name());
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                let skipped = dir.as_os_str().is_empty() && SKIPPED_DIRS.iter().any(|skipped| entry.file_name() == *skipped);
+                if !skipped {
+                    pending.push(path);
+                }
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+async fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    for file in list_files(from).await? {
+        let target = to.join(&file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::copy(from.join(&file), target).await?;
+    }
+    Ok(())
+}
+
+/// Contents of the files under `root`, keyed by their `/`-separated relative path
+async fn read_tree(root: &Path) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut tree = BTreeMap::new();
+    for file in list_files(root).await? {
+        let key = file.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        tree.insert(key, fs::read(root.join(&file)).await?);
+    }
+    Ok(tree)
+}
+
+/// Unified diff turning `old` into `new`, with `CONTEXT_LINES` lines of context around each hunk
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old, &new);
+
+    let mut diff = format!("--- a/{path}\n+++ b/{path}\n");
+    let changed: Vec<usize> = ops.iter().enumerate().filter(|(_, op)| !matches!(op, Op::Keep(..))).map(|(i, _)| i).collect();
+    let mut i = 0;
+    while i < changed.len() {
+        // A hunk spans the changes whose contexts touch
+        let start = changed[i].saturating_sub(CONTEXT_LINES);
+        let mut end = changed[i];
+        while i < changed.len() && changed[i] <= end + 2 * CONTEXT_LINES {
+            end = changed[i];
+            i += 1;
+        }
+        let end = (end + CONTEXT_LINES + 1).min(ops.len());
+
+        let (old_start, new_start) = ops[..start].iter().fold((0, 0), |(o, n), op| match op {
+            Op::Keep(..) => (o + 1, n + 1),
+            Op::Remove(_) => (o + 1, n),
+            Op::Add(_) => (o, n + 1),
+        });
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|op| !matches!(op, Op::Add(_))).count();
+        let new_len = hunk.iter().filter(|op| !matches!(op, Op::Remove(_))).count();
+        // Empty ranges point at the line before them
+        let position = |start: usize, len: usize| if len == 0 { start } else { start + 1 };
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            position(old_start, old_len), old_len, position(new_start, new_len), new_len
+        ));
+        for op in hunk {
+            match op {
+                Op::Keep(line) => diff.push_str(&format!(" {}\n", line)),
+                Op::Remove(line) => diff.push_str(&format!("-{}\n", line)),
+                Op::Add(line) => diff.push_str(&format!("+{}\n", line)),
+            }
+        }
+    }
+    diff
+}
+
+enum Op<'a> {
+    Keep(&'a str),
+    Remove(&'a str),
+    Add(&'a str),
+}
+
+/// Line edits turning `old` into `new`, from their longest common subsequence.
+/// The common prefix and suffix are matched first, so generated edits stay cheap to diff
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old_mid, new_mid) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // lcs[i][j]: length of the longest common subsequence of old_mid[i..] and new_mid[j..]
+    let mut lcs = vec![vec![0usize; new_mid.len() + 1]; old_mid.len() + 1];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lcs[i][j] = if old_mid[i] == new_mid[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut ops: Vec<Op> = old[..prefix].iter().map(|line| Op::Keep(line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            ops.push(Op::Keep(old_mid[i]));
+            i += 1;
+            j += 1;
+        } else if i < old_mid.len() && (j == new_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(Op::Remove(old_mid[i]));
+            i += 1;
+        } else {
+            ops.push(Op::Add(new_mid[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|line| Op::Keep(line)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn describes_created_edited_and_deleted_files() {
+        let (before, after) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        for (root, files) in [
+            (before.path(), [("src/main.rs", "fn main() {}\n"), ("src/old.rs", ""), ("target/debug/app", "binary")]),
+            (after.path(), [("src/main.rs", "mod new;\nfn main() {}\n"), ("src/new.rs", ""), ("target/debug/app", "rebuilt")]),
+        ] {
+            for (path, content) in files {
+                std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+                std::fs::write(root.join(path), content).unwrap();
+            }
+        }
+
+        let plan = GenerationPlan::between(before.path(), after.path()).await.unwrap();
+        assert_eq!(plan.creates, ["src/new.rs"]);
+        assert_eq!(plan.deletes, ["src/old.rs"]);
+        assert_eq!(plan.edits, [FileEdit {
+            path: "src/main.rs".to_string(),
+            diff: "--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1,1 +1,2 @@\n+mod new;\n fn main() {}\n".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn keeps_three_lines_of_context_around_each_hunk() {
+        let old: String = (1..=12).map(|n| format!("{n}\n")).collect();
+        let new: String = (1..=12).map(|n| match n {
+            2 => "two\n".to_string(),
+            11 => "eleven\n".to_string(),
+            _ => format!("{n}\n"),
+        }).collect();
+        assert_eq!(
+            unified_diff("lines.txt", &old, &new),
+            "--- a/lines.txt\n+++ b/lines.txt\n\
+             @@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -8,5 +8,5 @@\n 8\n 9\n 10\n-11\n+eleven\n 12\n"
+        );
+    }
+}
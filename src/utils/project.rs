@@ -0,0 +1,538 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::utils::license::{set_license_header, LicenseHeader};
+use crate::utils::utils::{register_module, write_if_changed};
+use crate::utils::wiring::inline_module;
+
+/// Marker file written by `cargo mold new` at the root of every generated project
+pub const CONFIG_FILE: &str = ".cargo-mold";
+
+/// Settings read from a project's `.cargo-mold` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Directory holding the crate sources, relative to the project root
+    /// (e.g. `crates/api/src` when the generated crate is a workspace member)
+    pub src_dir: String,
+    /// Whether the project logs through `tracing` (created with `--tracing` or `--otel`),
+    /// in which case generated handlers are instrumented
+    pub tracing: bool,
+    /// Whether the project has a message catalog (created with `--i18n`),
+    /// in which case generated handlers localize their errors
+    pub i18n: bool,
+    /// Whether the project serves an OpenAPI document (created with `--with-openapi`),
+    /// in which case generated handlers are annotated and listed in its `ApiDoc`
+    pub openapi: bool,
+    /// Whether generated resources get a file per module (the default) or inline blocks in their `mod.rs`
+    pub modules_style: ModulesStyle,
+    /// Whether top-level modules are declared in `models/mod.rs` (the default) or `models.rs`
+    pub module_layout: ModuleLayout,
+    /// Naming convention of generated handler functions, unless `g resource --naming` picks another
+    pub handler_naming: HandlerNaming,
+    /// Case of the JSON field names of generated models, unless `g resource --json-case` picks another
+    pub json_case: JsonCase,
+    /// Order the middlewares run in, outermost first, applied to `server.rs` and `routes.rs` by
+    /// `cargo mold g middleware-order`. Middlewares the project doesn't have are ignored
+    pub middleware: Vec<Middleware>,
+    /// Resources whose routes are deprecated, recorded by `g resource --deprecate` so regenerating them keeps it
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub deprecated: BTreeMap<String, Deprecation>,
+    /// Template of the license header put atop every generated `.rs` file, relative to the project root.
+    /// `{year}` stands for the current year and `{author}` for `license_author`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_header: Option<String>,
+    /// Copyright holder of the license header, `git config user.name` when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_author: Option<String>,
+}
+
+/// How the modules of generated resources are laid out, chosen with `cargo mold new --modules-style`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ModulesStyle {
+    /// `models/user.rs`, declared by `pub mod user;` in `models/mod.rs`
+    #[default]
+    Files,
+    /// A `pub mod user { ... }` block in `models/mod.rs`
+    Inline,
+}
+
+/// Where the declarations of a top-level module's files live, chosen with `cargo mold new --module-layout`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleLayout {
+    /// `models/mod.rs` next to the module's files
+    #[default]
+    Legacy,
+    /// `models.rs` next to the `models/` directory, the layout of edition 2018 and later
+    Modern,
+}
+
+/// How generated handler functions are named, e.g. for the `user` resource
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum HandlerNaming {
+    /// `get_user` (list), `find_user`, `create_user`, `update_user`, `delete_user`
+    #[default]
+    Rest,
+    /// `index`, `show`, `store`, `update`, `destroy`
+    Rails,
+    /// `list_user`, `get_user`, `create_user`, `update_user`, `delete_user`
+    #[value(name = "verb_noun")]
+    VerbNoun,
+    /// `user_list`, `user_get`, `user_create`, `user_update`, `user_delete`
+    #[value(name = "noun_verb")]
+    NounVerb,
+}
+
+/// Case of the field names generated models are (de)serialized with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonCase {
+    /// `first_name`, the Rust field names as they are
+    #[default]
+    Snake,
+    /// `firstName`, through `#[serde(rename_all = "camelCase")]`
+    Camel,
+}
+
+impl JsonCase {
+    /// The `#[serde(...)]` attribute of the models, if the case needs one
+    pub fn serde_attribute(self) -> Option<&'static str> {
+        match self {
+            JsonCase::Snake => None,
+            JsonCase::Camel => Some("#[serde(rename_all = \"camelCase\")]"),
+        }
+    }
+
+    /// The JSON name of a snake_case field, converted the way serde does it
+    pub fn field_name(self, field: &str) -> String {
+        match self {
+            JsonCase::Snake => field.to_string(),
+            JsonCase::Camel => {
+                let mut name = String::with_capacity(field.len());
+                let mut capitalize = false;
+                for c in field.chars() {
+                    if c == '_' {
+                        capitalize = !name.is_empty();
+                    } else if capitalize {
+                        name.push(c.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        name.push(c);
+                    }
+                }
+                name
+            }
+        }
+    }
+}
+
+/// A middleware `cargo mold new` can wrap the app or its `/private-api` scope in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Middleware {
+    /// `TracingLogger`, opening the request span and logging every response
+    Tracing,
+    /// actix's `Logger`, writing an access log line per request
+    AccessLog,
+    /// `SecurityHeaders`, setting `Content-Security-Policy` and the other security headers on every response
+    SecurityHeaders,
+    /// `RequestIdMiddleware`, reusing or generating the `X-Request-Id` header
+    RequestId,
+    /// `IdempotencyMiddleware`, replaying the responses of repeated `Idempotency-Key`s
+    Idempotency,
+    /// The JWT middleware of `AuthService`, rejecting unauthenticated requests to `/private-api`
+    Auth,
+    /// `UserContextMiddleware`, tagging the logs of authenticated requests with the token subject
+    UserContext,
+}
+
+impl Middleware {
+    /// Outermost first: the loggers time and span everything, the security headers reach every response,
+    /// requests get their id before anything can log or reject them, and idempotent replays still go
+    /// through everything around them
+    pub const DEFAULT_ORDER: [Middleware; 7] = [
+        Middleware::Tracing,
+        Middleware::AccessLog,
+        Middleware::SecurityHeaders,
+        Middleware::RequestId,
+        Middleware::Idempotency,
+        Middleware::Auth,
+        Middleware::UserContext,
+    ];
+
+    /// Name of the middleware in `.cargo-mold`
+    pub fn name(self) -> &'static str {
+        match self {
+            Middleware::Tracing => "tracing",
+            Middleware::AccessLog => "access_log",
+            Middleware::SecurityHeaders => "security_headers",
+            Middleware::RequestId => "request_id",
+            Middleware::Idempotency => "idempotency",
+            Middleware::Auth => "auth",
+            Middleware::UserContext => "user_context",
+        }
+    }
+
+    /// The call wrapping the app or scope in this middleware, one per line in generated chains
+    pub fn wrap_call(self) -> &'static str {
+        match self {
+            Middleware::Tracing => ".wrap(TracingLogger::default())",
+            Middleware::AccessLog => ".wrap(Logger::new(ACCESS_LOG_FORMAT))",
+            Middleware::SecurityHeaders => ".wrap(security_headers.clone())",
+            Middleware::RequestId => ".wrap(RequestIdMiddleware)",
+            Middleware::Idempotency => ".wrap(idempotency.clone())",
+            Middleware::Auth => ".wrap(auth_service.jwt_middleware())",
+            Middleware::UserContext => ".wrap(UserContextMiddleware)",
+        }
+    }
+
+    /// `enabled` in the order their `.wrap` calls are written for `order` to hold. Actix runs the
+    /// last middleware wrapped first, so this is `order` reversed; ones missing from it go innermost
+    pub fn wrap_sequence(order: &[Middleware], enabled: &[Middleware]) -> Vec<Middleware> {
+        let missing = enabled.iter().filter(|m| !order.contains(m));
+        let ordered = order.iter().rev().filter(|m| enabled.contains(m));
+        missing.chain(ordered).copied().collect()
+    }
+
+    /// Rejects orders listing a middleware twice or running the user context before authentication
+    pub fn check_order(order: &[Middleware]) -> Result<()> {
+        for (i, middleware) in order.iter().enumerate() {
+            if order[..i].contains(middleware) {
+                anyhow::bail!("❌ Middleware '{}' is listed twice in `middleware`", middleware.name());
+            }
+        }
+        let position = |target| order.iter().position(|m| *m == target);
+        if let (Some(auth), Some(user_context)) = (position(Middleware::Auth), position(Middleware::UserContext))
+            && user_context < auth
+        {
+            anyhow::bail!("❌ 'user_context' reads the token claims, it must come after 'auth' in `middleware`");
+        }
+        Ok(())
+    }
+}
+
+/// When a resource's routes were deprecated and when they may stop answering, as `YYYY-MM-DD` dates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Deprecation {
+    pub since: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<String>,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            src_dir: "src".to_string(),
+            tracing: false,
+            i18n: false,
+            openapi: false,
+            modules_style: ModulesStyle::Files,
+            module_layout: ModuleLayout::Legacy,
+            handler_naming: HandlerNaming::Rest,
+            json_case: JsonCase::Snake,
+            middleware: Middleware::DEFAULT_ORDER.to_vec(),
+            deprecated: BTreeMap::new(),
+            license_header: None,
+            license_author: None,
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Renders the config as written into new projects
+    pub fn to_file_content(&self) -> Result<String> {
+        Ok(format!(
+            "# cargo-mold project settings, read by every `cargo mold g` command\n{}",
+            toml::to_string(self)?
+        ))
+    }
+}
+
+/// The cargo-mold project a generate command runs in: where it lives, its settings and layout.
+/// Built once at the start of every command so paths are only worked out here
+#[derive(Debug, Clone)]
+pub struct ProjectContext {
+    root: PathBuf,
+    config: ProjectConfig,
+}
+
+impl ProjectContext {
+    /// Loads the project in the current directory, failing when it isn't a cargo-mold project
+    pub async fn load() -> Result<Self> {
+        Self::load_from(Path::new(".")).await
+    }
+
+    /// Loads the project rooted at `root`
+    pub async fn load_from(root: &Path) -> Result<Self> {
+        let mut ctx = Self {
+            root: root.to_path_buf(),
+            config: ProjectConfig::default(),
+        };
+
+        let config_path = ctx.path(CONFIG_FILE);
+        if !Path::new(&config_path).exists() {
+            anyhow::bail!(
+                "❌ Not a cargo-mold project.\n\
+                 Run this command in a project created with `cargo mold new`"
+            );
+        }
+
+        let content = fs::read_to_string(&config_path).await?;
+        if !is_legacy_placeholder(&content) {
+            ctx.config = toml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("❌ Invalid {}: {}", config_path, e))?;
+        }
+        if let Some(template) = &ctx.config.license_header {
+            let header = LicenseHeader::load(&ctx.path(template), ctx.config.license_author.as_deref()).await?;
+            set_license_header(header);
+        }
+        Ok(ctx)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn config(&self) -> &ProjectConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut ProjectConfig {
+        &mut self.config
+    }
+
+    /// Writes the settings back to `.cargo-mold`
+    pub async fn save_config(&self) -> Result<()> {
+        write_if_changed(self.path(CONFIG_FILE), self.config.to_file_content()?).await?;
+        Ok(())
+    }
+
+    /// Path of `relative` inside the project
+    pub fn path(&self, relative: &str) -> String {
+        if self.root == Path::new(".") {
+            relative.to_string()
+        } else {
+            self.root.join(relative).to_string_lossy().into_owned()
+        }
+    }
+
+    pub fn src_dir(&self) -> String {
+        self.path(&self.config.src_dir)
+    }
+
+    /// Path of `relative` next to the crate manifest, i.e. the parent of the sources directory
+    pub fn crate_path(&self, relative: &str) -> String {
+        match Path::new(&self.config.src_dir).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            Some(crate_dir) => self.path(&crate_dir.join(relative).to_string_lossy()),
+            None => self.path(relative),
+        }
+    }
+
+    pub fn lib_file(&self) -> String {
+        format!("{}/lib.rs", self.src_dir())
+    }
+
+    /// Directory of a top-level module such as `models` or `repositories`
+    pub fn module_dir(&self, module: &str) -> String {
+        format!("{}/{}", self.src_dir(), module)
+    }
+
+    /// Source file of `file` inside a top-level module, e.g. `src/models/user.rs`
+    pub fn module_file(&self, module: &str, file: &str) -> String {
+        format!("{}/{}.rs", self.module_dir(module), file)
+    }
+
+    /// File declaring the files of a top-level module: whichever of `models/mod.rs` and `models.rs`
+    /// exists, or the one of the project's `module_layout` when neither does yet
+    pub fn mod_file(&self, module: &str) -> String {
+        let legacy = self.module_file(module, "mod");
+        let modern = format!("{}.rs", self.module_dir(module));
+        if Path::new(&legacy).exists() {
+            legacy
+        } else if Path::new(&modern).exists() {
+            modern
+        } else {
+            match self.config.module_layout {
+                ModuleLayout::Legacy => legacy,
+                ModuleLayout::Modern => modern,
+            }
+        }
+    }
+
+    pub fn models_dir(&self) -> String {
+        self.module_dir("models")
+    }
+
+    pub fn model_file(&self, name: &str) -> String {
+        self.module_file("models", name)
+    }
+
+    pub fn routes_dir(&self) -> String {
+        self.module_dir("routes")
+    }
+
+    /// The `routes.rs` holding the `/api` and `/private-api` scopes
+    pub fn routes_file(&self) -> String {
+        self.module_file("routes", "routes")
+    }
+
+    pub fn server_file(&self) -> String {
+        self.module_file("server", "server")
+    }
+
+    /// Where `file` of a top-level module lives: its own source file, or the `mod.rs` (or `models.rs`) holding it inline
+    pub fn module_location(&self, module: &str, file: &str) -> String {
+        match self.config.modules_style {
+            ModulesStyle::Files => self.module_file(module, file),
+            ModulesStyle::Inline => self.mod_file(module),
+        }
+    }
+
+    /// Whether `file` of a top-level module exists, as its own source file or inline in `mod.rs`
+    pub fn has_module(&self, module: &str, file: &str) -> bool {
+        Path::new(&self.module_file(module, file)).exists()
+            || std::fs::read_to_string(self.mod_file(module))
+                .is_ok_and(|mod_file| inline_module(&mod_file, file).is_some())
+    }
+
+    /// Source of `file` in a top-level module, read from its own file or from its inline block
+    pub async fn read_module(&self, module: &str, file: &str) -> Result<String> {
+        if let Ok(source) = fs::read_to_string(self.module_file(module, file)).await {
+            return Ok(source);
+        }
+        let mod_file = fs::read_to_string(self.mod_file(module)).await?;
+        let (_, body) = inline_module(&mod_file, file)
+            .ok_or_else(|| anyhow::anyhow!("❌ Module '{}::{}' not found", module, file))?;
+        Ok(mod_file[body].trim_matches('\n').lines()
+            .map(|line| format!("{}\n", line.strip_prefix(INLINE_INDENT).unwrap_or(line)))
+            .collect())
+    }
+
+    /// Writes the source of `file` in a top-level module following the project's `modules_style`.
+    /// Separate files still need declaring in `mod.rs`, while inline blocks replace any previous
+    /// block, declaration or file of the module and get `module` declared in `lib.rs`
+    pub async fn write_module(&self, module: &str, file: &str, source: &str) -> Result<()> {
+        fs::create_dir_all(self.module_dir(module)).await?;
+        if self.config.modules_style == ModulesStyle::Files {
+            write_if_changed(self.module_file(module, file), source).await?;
+            return Ok(());
+        }
+
+        let mod_path = self.mod_file(module);
+        let mut mod_file = fs::read_to_string(&mod_path).await.unwrap_or_default();
+        let body: String = source.lines()
+            .map(|line| if line.is_empty() { "\n".to_string() } else { format!("{}{}\n", INLINE_INDENT, line) })
+            .collect();
+        let block = format!("pub mod {} {{\n{}}}", file, body);
+        let declaration = format!("pub mod {};", file);
+        if let Some((range, _)) = inline_module(&mod_file, file) {
+            mod_file.replace_range(range, &block);
+        } else if let Some(start) = mod_file.find(&declaration) {
+            mod_file.replace_range(start..start + declaration.len(), &block);
+            let _ = fs::remove_file(self.module_file(module, file)).await;
+        } else {
+            let separator = if mod_file.trim().is_empty() { "" } else if mod_file.ends_with('\n') { "\n" } else { "\n\n" };
+            mod_file = format!("{}{}{}\n", mod_file, separator, block);
+        }
+        write_if_changed(&mod_path, mod_file).await?;
+        register_module(&self.lib_file(), module).await?;
+        Ok(())
+    }
+
+    /// Declares `file` in the module's `mod_file` and `module` in `lib.rs`
+    pub async fn register(&self, module: &str, file: &str) -> Result<()> {
+        register_module(&self.mod_file(module), file).await?;
+        register_module(&self.lib_file(), module).await?;
+        Ok(())
+    }
+}
+
+/// Indentation of the body of inline module blocks
+const INLINE_INDENT: &str = "    ";
+
+/// Projects generated before `.cargo-mold` held settings only contain a placeholder note
+fn is_legacy_placeholder(content: &str) -> bool {
+    content.starts_with("Future changes will be done into this file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A project whose `.cargo-mold` holds `config`
+    fn project(config: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(CONFIG_FILE), config).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn fails_outside_a_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let error = ProjectContext::load_from(dir.path()).await.unwrap_err();
+        assert!(error.to_string().contains("Not a cargo-mold project"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn defaults_missing_settings() {
+        for config in ["", "Future changes will be done into this file\n", "tracing = true\n"] {
+            let dir = project(config);
+            let ctx = ProjectContext::load_from(dir.path()).await.unwrap();
+            assert_eq!(ctx.config().tracing, config.contains("tracing"));
+            assert_eq!(ctx.config().src_dir, "src");
+            assert_eq!(ctx.config().modules_style, ModulesStyle::Files);
+            assert_eq!(ctx.config().middleware, Middleware::DEFAULT_ORDER);
+            assert_eq!(ctx.config().license_header, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_the_settings_written_into_new_projects() {
+        let config = ProjectConfig {
+            src_dir: "crates/api/src".to_string(),
+            openapi: true,
+            module_layout: ModuleLayout::Modern,
+            json_case: JsonCase::Camel,
+            middleware: vec![Middleware::RequestId, Middleware::Auth],
+            ..ProjectConfig::default()
+        };
+        let dir = project(&config.to_file_content().unwrap());
+        let ctx = ProjectContext::load_from(dir.path()).await.unwrap();
+
+        assert!(ctx.config().openapi);
+        assert_eq!(ctx.config().json_case, JsonCase::Camel);
+        assert_eq!(ctx.config().middleware, [Middleware::RequestId, Middleware::Auth]);
+        let root = dir.path().display();
+        assert_eq!(ctx.model_file("user"), format!("{}/crates/api/src/models/user.rs", root));
+        assert_eq!(ctx.mod_file("models"), format!("{}/crates/api/src/models.rs", root));
+        assert_eq!(ctx.crate_path("Cargo.toml"), format!("{}/crates/api/Cargo.toml", root));
+    }
+
+    #[tokio::test]
+    async fn declares_modules_in_the_existing_file_over_the_layout() {
+        let dir = project("module_layout = \"modern\"\n");
+        let ctx = ProjectContext::load_from(dir.path()).await.unwrap();
+        let root = dir.path().display();
+        assert_eq!(ctx.mod_file("models"), format!("{}/src/models.rs", root));
+
+        std::fs::create_dir_all(dir.path().join("src/models")).unwrap();
+        std::fs::write(dir.path().join("src/models/mod.rs"), "").unwrap();
+        assert_eq!(ctx.mod_file("models"), format!("{}/src/models/mod.rs", root));
+        assert_eq!(ctx.mod_file("routes"), format!("{}/src/routes.rs", root));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_settings() {
+        let dir = project("src_dir = \"src\"\ntracng = true\n");
+        let error = ProjectContext::load_from(dir.path()).await.unwrap_err();
+        assert!(error.to_string().contains("Invalid"), "{}", error);
+        assert!(error.to_string().contains("tracng"), "{}", error);
+    }
+}
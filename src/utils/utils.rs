@@ -1,3 +1,14 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::process::Command;
+
+use crate::utils::license::with_license_header;
+use crate::utils::project::ProjectContext;
+use crate::utils::output::report;
+use crate::utils::wiring::{inline_module, insert_module_declaration, module_declaration};
+
 pub fn find_matching_parenthesis(content: &str, start_pos: usize) -> Option<usize> {
     let mut count = 1;
     let chars: Vec<char> = content[start_pos..].chars().collect();
@@ -15,4 +26,193 @@ pub fn find_matching_parenthesis(content: &str, start_pos: usize) -> Option<usiz
         }
     }
     None
-}
\ No newline at end of file
+}
+
+/// Declares `pub mod {module};` in a module file, after its other declarations and before any custom
+/// items, creating the file when it doesn't exist yet. Modules already declared, including as an inline
+/// `pub mod {module} { ... }` block, are left alone
+pub async fn register_module(mod_file_path: &str, module: &str) -> Result<()> {
+    let declaration = format!("pub mod {};", module);
+
+    if Path::new(mod_file_path).exists() {
+        let mut mod_file = fs::read_to_string(mod_file_path).await?;
+        if module_declaration(&mod_file, module).is_none() && inline_module(&mod_file, module).is_none() {
+            insert_module_declaration(&mut mod_file, "", &declaration);
+            write_if_changed(mod_file_path, mod_file).await?;
+        }
+    } else {
+        write_if_changed(mod_file_path, declaration).await?;
+    }
+    Ok(())
+}
+
+/// Lists `var` under its own section of the project's `.env-example` when it isn't there yet
+pub async fn document_env(ctx: &ProjectContext, section: &str, var: &str, example: &str) -> Result<()> {
+    let env_path = ctx.path(".env-example");
+    let Ok(mut content) = fs::read_to_string(&env_path).await else {
+        return Ok(());
+    };
+    if content.contains(&format!("{}=", var)) {
+        return Ok(());
+    }
+
+    content.push_str(&format!(
+        "\n# ==========================================\n# {}\n# ==========================================\n{}={}\n",
+        section, var, example
+    ));
+    write_if_changed(env_path, content).await?;
+    Ok(())
+}
+
+/// Writes `content` to `path` unless the file already holds exactly that, reporting it as unchanged:
+/// regenerating identical code then keeps the file's mtime and doesn't set off `cargo watch` rebuilds.
+/// Returns whether the file was written. Rust sources get the project's license header, when it has one
+pub async fn write_if_changed(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<bool> {
+    let path = path.as_ref();
+    let headed = with_license_header(path, content.as_ref());
+    let content = headed.as_deref().unwrap_or(content.as_ref());
+    if fs::read(path).await.is_ok_and(|existing| existing == content) {
+        report(format!("💤 {} unchanged", path.display()));
+        return Ok(false);
+    }
+    fs::write(path, content).await?;
+    Ok(true)
+}
+
+/// Applies `cargo clippy --fix` to freshly generated files.
+/// Clippy can only fix whole targets, so every other source file it can reach (tests, examples,
+/// benches and build scripts included) is restored afterwards
+pub async fn fix_generated(ctx: &ProjectContext, files: &[String]) -> Result<()> {
+    let clippy = Command::new("cargo").args(["clippy", "--version"]).output().await;
+    if !matches!(clippy, Ok(output) if output.status.success()) {
+        report("⚠️ clippy is not installed, skipping --fix (install it with `rustup component add clippy`)");
+        return Ok(());
+    }
+
+    report("🧹 Running cargo clippy --fix on the generated files...");
+    let generated: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+    let mut untouched = HashMap::new();
+    for path in rust_sources(&ctx.root().to_string_lossy()).await? {
+        // Named as `ProjectContext::path` names the generated files
+        let path = if ctx.root() == Path::new(".") { path.strip_prefix(".").unwrap_or(&path).to_path_buf() } else { path };
+        if !generated.contains(&path) {
+            let content = fs::read_to_string(&path).await?;
+            untouched.insert(path, content);
+        }
+    }
+
+    let status = Command::new("cargo")
+        .args(["clippy", "--fix", "--allow-dirty", "--allow-staged", "--allow-no-vcs", "--all-targets", "--quiet"])
+        .current_dir(ctx.root())
+        .status()
+        .await?;
+
+    for (path, content) in untouched {
+        if fs::read_to_string(&path).await.ok().as_ref() != Some(&content) {
+            fs::write(&path, content).await?;
+        }
+    }
+
+    if !status.success() {
+        report("⚠️ cargo clippy --fix failed, the generated files were left as they are");
+    }
+    Ok(())
+}
+
+/// Lists every `.rs` file below `dir`, skipping build outputs (`target/`) and hidden directories
+pub async fn rust_sources(dir: &str) -> Result<Vec<PathBuf>> {
+    let mut sources = Vec::new();
+    let mut pending = vec![PathBuf::from(dir)];
+    while let Some(dir) = pending.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                let name = entry.file_name();
+                if name != "target" && !name.to_string_lossy().starts_with('.') {
+                    pending.push(path);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                sources.push(path);
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Runs `cargo check` on the project, failing with the compiler output when it no longer builds
+pub async fn check_project(ctx: &ProjectContext) -> Result<()> {
+    report("🔍 Running cargo check...");
+    let output = Command::new("cargo")
+        .args(["check", "--all-targets", "--quiet", "--color", "never"])
+        .current_dir(ctx.root())
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("❌ Could not run cargo check: {}", e))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "❌ The project no longer compiles after generation:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+    report("✅ cargo check passed");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn leaves_unchanged_files_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.rs");
+        assert!(write_if_changed(&path, "pub struct User;\n").await.unwrap());
+        let written = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(!write_if_changed(&path, "pub struct User;\n").await.unwrap());
+        assert_eq!(std::fs::metadata(&path).unwrap().modified().unwrap(), written);
+
+        assert!(write_if_changed(&path, "pub struct User {}\n").await.unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "pub struct User {}\n");
+    }
+
+    #[tokio::test]
+    async fn registers_a_module_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let mod_file = dir.path().join("mod.rs");
+        let mod_file = mod_file.to_str().unwrap();
+        register_module(mod_file, "users").await.unwrap();
+        register_module(mod_file, "posts").await.unwrap();
+        register_module(mod_file, "users").await.unwrap();
+        assert_eq!(std::fs::read_to_string(mod_file).unwrap(), "pub mod users;\npub mod posts;");
+    }
+
+    #[tokio::test]
+    async fn fixes_only_the_generated_files() {
+        const NEEDLESS_RETURN: &str = "pub fn answer() -> u32 {\n    return 42;\n}\n";
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join(crate::utils::project::CONFIG_FILE), "").unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n",
+        ).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub mod generated;\npub mod kept;\n").unwrap();
+        std::fs::write(root.join("src/generated.rs"), NEEDLESS_RETURN).unwrap();
+        std::fs::write(root.join("src/kept.rs"), NEEDLESS_RETURN).unwrap();
+        let test = "#[test]\nfn answers() {\n    assert_eq!(twice(), 84);\n}\n\nfn twice() -> u32 {\n    return fixture::generated::answer() * 2;\n}\n";
+        std::fs::write(root.join("tests/answers.rs"), test).unwrap();
+
+        let ctx = ProjectContext::load_from(root).await.unwrap();
+        fix_generated(&ctx, &[format!("{}/generated.rs", ctx.src_dir())]).await.unwrap();
+
+        assert_eq!(std::fs::read_to_string(root.join("src/generated.rs")).unwrap(), "pub fn answer() -> u32 {\n    42\n}\n");
+        assert_eq!(std::fs::read_to_string(root.join("src/kept.rs")).unwrap(), NEEDLESS_RETURN);
+        assert_eq!(std::fs::read_to_string(root.join("tests/answers.rs")).unwrap(), test);
+    }
+}
@@ -0,0 +1,678 @@
+//! Reading and editing the `.configure(...)` wiring of routes files, and the declarations of module
+//! files, without a full Rust parser.
+//! Comments are masked out and string literals skipped, so hand-edited or reformatted files
+//! are matched on their code only
+use std::ops::Range;
+
+/// End (exclusive) of the string, raw string or char literal starting at `start`, if one does
+fn literal_end(bytes: &[u8], start: usize) -> Option<usize> {
+    match bytes[start] {
+        b'"' => {
+            let mut i = start + 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'\\' => i += 2,
+                    b'"' => return Some(i + 1),
+                    _ => i += 1,
+                }
+            }
+            Some(bytes.len())
+        }
+        b'r' if start == 0 || !is_ident_byte(bytes[start - 1]) => {
+            let hashes = bytes[start + 1..].iter().take_while(|&&b| b == b'#').count();
+            if bytes.get(start + 1 + hashes) != Some(&b'"') {
+                return None;
+            }
+            let closing: Vec<u8> = std::iter::once(b'"').chain(std::iter::repeat_n(b'#', hashes)).collect();
+            let body = start + hashes + 2;
+            let end = bytes[body..].windows(closing.len()).position(|window| window == closing.as_slice());
+            Some(end.map_or(bytes.len(), |end| body + end + closing.len()))
+        }
+        // A quote followed by one (possibly escaped) char and a closing quote, otherwise a lifetime
+        b'\'' => match bytes.get(start + 1)? {
+            b'\\' => {
+                let close = bytes.get(start + 3..)?.iter().position(|&b| b == b'\'')?;
+                Some(start + close + 4)
+            }
+            _ => {
+                let len = std::str::from_utf8(&bytes[start + 1..]).ok()?.chars().next()?.len_utf8();
+                (bytes.get(start + 1 + len) == Some(&b'\'')).then_some(start + len + 2)
+            }
+        },
+        _ => None,
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// `source` with its comments replaced by spaces, so every byte offset still points at the same code
+pub fn mask_comments(source: &str) -> String {
+    let bytes = source.as_bytes();
+    let mut masked = bytes.to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = literal_end(bytes, i) {
+            i = end;
+        } else if bytes[i..].starts_with(b"//") {
+            let end = bytes[i..].iter().position(|&b| b == b'\n').map_or(bytes.len(), |end| i + end);
+            masked[i..end].fill(b' ');
+            i = end;
+        } else if bytes[i..].starts_with(b"/*") {
+            // Block comments nest in Rust
+            let mut depth = 0;
+            let mut end = i;
+            while end < bytes.len() {
+                if bytes[end..].starts_with(b"/*") {
+                    depth += 1;
+                    end += 2;
+                } else if bytes[end..].starts_with(b"*/") {
+                    depth -= 1;
+                    end += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    end += 1;
+                }
+            }
+            for byte in &mut masked[i..end] {
+                if *byte != b'\n' {
+                    *byte = b' ';
+                }
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    // Comments are replaced whole, so no multi-byte char is ever cut
+    String::from_utf8(masked).unwrap_or_else(|_| source.to_string())
+}
+
+/// Finds `pattern` in `code`, allowing any whitespace between its characters (e.g. `web::scope("/api")`
+/// also matches `web :: scope(\n "/api"\n)`). Returns the byte range of the match
+pub fn find_code(code: &str, pattern: &str) -> Option<(usize, usize)> {
+    let pattern: Vec<u8> = pattern.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    let bytes = code.as_bytes();
+    let first = *pattern.first()?;
+    'start: for start in (0..bytes.len()).filter(|&i| bytes[i] == first) {
+        // The match must not be the tail of a longer identifier
+        if is_ident_byte(first) && start > 0 && is_ident_byte(bytes[start - 1]) {
+            continue;
+        }
+        let mut i = start;
+        for (k, &expected) in pattern.iter().enumerate() {
+            if k > 0 {
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+            }
+            if bytes.get(i) != Some(&expected) {
+                continue 'start;
+            }
+            i += 1;
+        }
+        if is_ident_byte(pattern[pattern.len() - 1]) && bytes.get(i).is_some_and(|&b| is_ident_byte(b)) {
+            continue;
+        }
+        return Some((start, i));
+    }
+    None
+}
+
+/// Whether `source` contains `pattern` outside of comments, whatever the whitespace in between
+pub fn has_code(source: &str, pattern: &str) -> bool {
+    find_code(&mask_comments(source), pattern).is_some()
+}
+
+/// Offset of the parenthesis closing the call that `from` is inside of, skipping literals.
+/// None when the expression ends first, e.g. a scope built in a `let` statement
+fn enclosing_close(code: &str, from: usize) -> Option<usize> {
+    let bytes = code.as_bytes();
+    let mut depth = 0;
+    let mut i = from;
+    while i < bytes.len() {
+        if let Some(end) = literal_end(bytes, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' if depth == 0 => return Some(i),
+            b')' => depth -= 1,
+            b';' | b'{' | b'}' if depth == 0 => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Byte ranges of the `pub mod {name} { ... }` block in `source` and of its body between the braces
+pub fn inline_module(source: &str, name: &str) -> Option<(Range<usize>, Range<usize>)> {
+    let code = mask_comments(source);
+    let (start, open) = find_code(&code, &format!("pub mod {} {{", name))?;
+    let bytes = code.as_bytes();
+    let mut depth = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        if let Some(end) = literal_end(bytes, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' if depth == 0 => return Some((start..i + 1, open..i)),
+            b'}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Byte ranges of the top-level items of `code` (with its comments masked), each from its first
+/// attribute to its closing `;` or `}`
+fn top_level_items(code: &str) -> Vec<Range<usize>> {
+    let bytes = code.as_bytes();
+    let mut items = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(end) = literal_end(bytes, i) {
+            start.get_or_insert(i);
+            i = end;
+            continue;
+        }
+        let byte = bytes[i];
+        if !byte.is_ascii_whitespace() {
+            start.get_or_insert(i);
+        }
+        match byte {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth = depth.saturating_sub(1);
+                // Outer attributes close their brackets before their item, `static X: T = T { .. };` goes on to its `;`
+                let continues = code[i + 1..].trim_start().starts_with(';');
+                let inner_attribute = byte == b']' && start.is_some_and(|start| code[start..].starts_with("#!"));
+                if depth == 0 && ((byte == b'}' && !continues) || inner_attribute) {
+                    items.extend(start.take().map(|start| start..i + 1));
+                }
+            }
+            b';' if depth == 0 => items.extend(start.take().map(|start| start..i + 1)),
+            _ => {}
+        }
+        i += 1;
+    }
+    items
+}
+
+/// Offset of `item` past its attributes, i.e. where its visibility or keyword starts
+fn after_attributes(code: &str, item: &Range<usize>) -> usize {
+    let bytes = code.as_bytes();
+    let mut i = item.start;
+    while i < item.end {
+        while i < item.end && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == item.end || bytes[i] != b'#' {
+            return i;
+        }
+        let mut depth = 0;
+        while i < item.end {
+            if let Some(end) = literal_end(bytes, i) {
+                i = end;
+                continue;
+            }
+            match bytes[i] {
+                b'[' => depth += 1,
+                b']' if depth == 1 => {
+                    i += 1;
+                    break;
+                }
+                b']' => depth -= 1,
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    i
+}
+
+/// Whether the item declares a module or imports something (e.g. `#[cfg(test)] pub(crate) mod tests;`),
+/// or is an inner attribute such as `#![allow(dead_code)]`, which comes before any of them
+fn is_declaration(code: &str, item: &Range<usize>) -> bool {
+    if code[item.start..].starts_with("#!") {
+        return true;
+    }
+    let rest = code[after_attributes(code, item)..item.end].trim_start();
+    let rest = match rest.strip_prefix("pub") {
+        Some(restricted) if restricted.trim_start().starts_with('(') => {
+            restricted.split_once(')').map_or(restricted, |(_, rest)| rest)
+        }
+        Some(public) => public,
+        None => rest,
+    };
+    let keyword = rest.split_whitespace().next().unwrap_or_default();
+    matches!(keyword, "mod" | "use" | "extern")
+}
+
+/// Offset of the top-level `pub mod {module};` declaration of a module file, past its attributes.
+/// Declarations in comments, or inside other items, don't count
+pub fn module_declaration(source: &str, module: &str) -> Option<usize> {
+    let code = mask_comments(source);
+    let declaration = format!("pubmod{};", module);
+    top_level_items(&code).iter()
+        .map(|item| (after_attributes(&code, item), item.end))
+        .find(|&(start, end)| code[start..end].split_whitespace().collect::<String>() == declaration)
+        .map(|(start, _)| start)
+}
+
+/// Adds `declaration`, preceded by its `attributes` lines, among the declarations of a module file: after
+/// the `mod` and `use` items it starts with, before any other item and its doc comment. Files without
+/// items get it at the end, after their comments
+pub fn insert_module_declaration(source: &mut String, attributes: &str, declaration: &str) {
+    let code = mask_comments(source);
+    let items = top_level_items(&code);
+    let leading = items.iter().take_while(|item| is_declaration(&code, item)).last();
+    if let Some(item) = leading {
+        // After the rest of the line, which may hold a trailing comment
+        let line_end = source[item.end..].find('\n').map_or(source.len(), |end| item.end + end);
+        source.insert_str(line_end, &format!("\n{}{}", attributes, declaration));
+    } else if let Some(item) = items.first() {
+        let mut line_start = source[..item.start].rfind('\n').map_or(0, |end| end + 1);
+        while line_start > 0 {
+            let previous_start = source[..line_start - 1].rfind('\n').map_or(0, |end| end + 1);
+            if !source[previous_start..line_start].trim_start().starts_with("///") {
+                break;
+            }
+            line_start = previous_start;
+        }
+        source.insert_str(line_start, &format!("{}{}\n\n", attributes, declaration));
+    } else if source.trim().is_empty() {
+        *source = format!("{}{}", attributes, declaration);
+    } else {
+        source.push_str(&format!("\n{}{}", attributes, declaration));
+    }
+}
+
+/// Paths passed to `.configure(...)`, with their whitespace removed
+pub fn configure_targets(source: &str) -> Vec<String> {
+    let code = mask_comments(source);
+    let mut targets = Vec::new();
+    let mut offset = 0;
+    while let Some((start, end)) = find_code(&code[offset..], ".configure(") {
+        let open = offset + end;
+        let Some(close) = enclosing_close(&code, open) else {
+            break;
+        };
+        targets.push(code[open..close].split_whitespace().collect());
+        offset += start + 1;
+    }
+    targets
+}
+
+/// Paths brought into scope by `use` declarations, expanding braces (`use a::{b, c::d};` gives `a::b` and `a::c::d`)
+pub fn imported_paths(source: &str) -> Vec<String> {
+    let code = mask_comments(source);
+    let mut paths = Vec::new();
+    for statement in code.split(';') {
+        let statement = format!(" {}", statement.split_whitespace().collect::<Vec<_>>().join(" "));
+        // `use` may follow attributes, a `pub` or a block opening on the same statement
+        if let Some(pos) = statement.rfind(" use ") {
+            expand_use_tree(&statement[pos + 5..], "", &mut paths);
+        }
+    }
+    paths
+}
+
+/// Collects the paths of a whitespace-normalized use tree, dropping `as` renames
+fn expand_use_tree(tree: &str, prefix: &str, paths: &mut Vec<String>) {
+    let tree = tree.trim();
+    let Some(open) = tree.find('{') else {
+        let path = tree.split(" as ").next().unwrap_or(tree);
+        paths.push(format!("{}{}", prefix, path.replace(' ', "")));
+        return;
+    };
+    let prefix = format!("{}{}", prefix, tree[..open].replace(' ', ""));
+    let inner = tree[open + 1..].strip_suffix('}').unwrap_or(&tree[open + 1..]);
+    // Split on the commas of this level only
+    let mut depth = 0;
+    let mut item_start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                expand_use_tree(&inner[item_start..i], &prefix, paths);
+                item_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if item_start < inner.len() {
+        expand_use_tree(&inner[item_start..], &prefix, paths);
+    }
+}
+
+/// Whether `{module}::{function}` is already configured, however it is spelled: through the module,
+/// a longer path ending with it, or the function imported on its own
+pub fn is_configured(source: &str, module: &str, function: &str) -> bool {
+    let imported = || {
+        imported_paths(source).iter().any(|path| {
+            path.ends_with(&format!("{}::{}", module, function)) || path.ends_with(&format!("{}::*", module))
+        })
+    };
+    configure_targets(source).iter().any(|target| {
+        let segments: Vec<&str> = target.split("::").collect();
+        match segments.as_slice() {
+            [.., parent, last] => *last == function && *parent == module,
+            [last] => *last == function && imported(),
+            [] => false,
+        }
+    })
+}
+
+/// Adds `.configure({module}::{function})` at the end of the chain built on `scope`, importing
+/// `crate::{parent}::{module}` when it isn't yet. Nothing changes when the function is already
+/// configured, however it is written. Returns false, leaving the file untouched, when `scope` can't be found
+pub fn insert_configure(routes_file: &mut String, scope: &str, parent: &str, module: &str, function: &str) -> bool {
+    if is_configured(routes_file, module, function) {
+        return true;
+    }
+    let call = |indent: &str| format!("\n{}.configure({}::{})", indent, module, function);
+    insert_chained_call(routes_file, scope, call, &format!("crate::{}::{}", parent, module), "")
+}
+
+/// Like `insert_configure`, for a module only compiled with a Cargo feature. A call chained on the scope
+/// can't carry `#[cfg]`, so the function is called from a closure whose body is gated, like the import
+pub fn insert_gated_configure(routes_file: &mut String, scope: &str, parent: &str, module: &str, function: &str, feature: &str) -> bool {
+    let gated_call = format!("{}::{}(_cfg);", module, function);
+    if is_configured(routes_file, module, function) || has_code(routes_file, &gated_call) {
+        return true;
+    }
+    let gate = format!("#[cfg(feature = \"{}\")]\n", feature);
+    let call = |indent: &str| {
+        format!("\n{indent}.configure(|_cfg| {{\n{indent}    {gate}{indent}    {gated_call}\n{indent}}})")
+    };
+    insert_chained_call(routes_file, scope, call, &format!("crate::{}::{}", parent, module), &gate)
+}
+
+/// Adds `.wrap({module}::{middleware})` at the end of the chain built on `scope`, so it runs before the
+/// middlewares wrapped earlier, importing `crate::{parent}::{module}` when it isn't yet. Nothing changes when
+/// the scope already wraps it. Returns false, leaving the file untouched, when `scope` can't be found
+pub fn insert_wrap(routes_file: &mut String, scope: &str, parent: &str, module: &str, middleware: &str) -> bool {
+    if has_code(routes_file, &format!(".wrap({}::{})", module, middleware)) {
+        return true;
+    }
+    let call = |indent: &str| format!("\n{}.wrap({}::{})", indent, module, middleware);
+    insert_chained_call(routes_file, scope, call, &format!("crate::{}::{}", parent, module), "")
+}
+
+/// Adds the call returned by `call` (given the indentation) after the last call chained on `scope`,
+/// then `use {import};` preceded by `attributes`. Returns false, leaving the file untouched, when `scope` can't be found
+fn insert_chained_call(routes_file: &mut String, scope: &str, call: impl Fn(&str) -> String, import: &str, attributes: &str) -> bool {
+    // 1. Add the route configuration after the last call chained on the scope, before touching
+    // the imports so nothing is left half-wired when the scope can't be found
+    let code = mask_comments(routes_file);
+    let Some((scope_start, scope_end)) = find_code(&code, scope) else {
+        return false;
+    };
+    let Some(close) = enclosing_close(&code, scope_end) else {
+        return false;
+    };
+    let mut insert_pos = code[..close].trim_end().len();
+    // A comment trailing the last call stays on its line
+    if let Some(line_end) = code[insert_pos..close].find('\n') {
+        insert_pos += line_end;
+    }
+
+    // Chained calls keep the indentation of the previous ones
+    let line_start = |pos: usize| code[..pos].rfind('\n').map_or(0, |newline| newline + 1);
+    let indentation = |line: &str| line.len() - line.trim_start().len();
+    let chain = &code[line_start(scope_start)..insert_pos];
+    let indent = match chain.lines().skip(1).filter(|line| line.trim_start().starts_with('.')).last() {
+        Some(line) => indentation(line),
+        None => indentation(&code[line_start(scope_start)..]) + 4,
+    };
+    routes_file.insert_str(insert_pos, &call(&" ".repeat(indent)));
+
+    // 2. Import the module after the last top-level `use`, unless it already is
+    if imported_paths(routes_file).iter().any(|path| path == import) {
+        return true;
+    }
+    let use_statement = format!("{}use {};\n", attributes, import);
+    let code = mask_comments(routes_file);
+    let header_end = find_code(&code, "fn").map_or(code.len(), |(start, _)| start);
+    let last_use = code[..header_end]
+        .match_indices("use ")
+        .filter(|(pos, _)| code[line_start(*pos)..*pos].trim().is_empty() || code[line_start(*pos)..*pos].trim() == "pub")
+        .last();
+    match last_use.and_then(|(pos, _)| code[pos..].find(';').map(|end| pos + end)) {
+        Some(end) => match code[end..].find('\n') {
+            Some(newline) => routes_file.insert_str(end + newline + 1, &use_statement),
+            None => routes_file.push_str(&format!("\n{}", use_statement.trim_end())),
+        },
+        None => routes_file.insert_str(0, &use_statement),
+    }
+    true
+}
+
+/// Adds each of `items` missing from the list opened by `opening` (e.g. `paths(` in an `#[openapi(...)]`
+/// attribute), one per line after the listed ones. Returns false, leaving the file untouched, when the list can't be found
+pub fn insert_list_items(source: &mut String, opening: &str, items: &[String]) -> bool {
+    let code = mask_comments(source);
+    let Some((start, end)) = find_code(&code, opening) else {
+        return false;
+    };
+    let Some(close) = enclosing_close(&code, end) else {
+        return false;
+    };
+    let missing: Vec<&String> = items.iter().filter(|item| find_code(&code[end..close], item).is_none()).collect();
+    if missing.is_empty() {
+        return true;
+    }
+
+    // Items keep the indentation of the listed ones, or go one level deeper than the opening
+    let line_start = code[..start].rfind('\n').map_or(0, |newline| newline + 1);
+    let opening_indent = code[line_start..start].len() - code[line_start..start].trim_start().len();
+    let indent = code[end..close].lines().skip(1)
+        .find(|line| !line.trim().is_empty())
+        .map_or(opening_indent + 4, |line| line.len() - line.trim_start().len());
+    let insert_pos = code[..close].trim_end().len();
+    let separator = if code[..insert_pos].ends_with([',', '(']) { "" } else { "," };
+    let mut addition: String = std::iter::once(separator.to_string())
+        .chain(missing.iter().map(|item| format!("\n{}{},", " ".repeat(indent), item)))
+        .collect();
+    // A list closed on its own line stays so, a one-line list gets its closing parenthesis on a new line
+    if !code[insert_pos..close].contains('\n') {
+        addition.push_str(&format!("\n{}", " ".repeat(opening_indent)));
+    }
+    source.insert_str(insert_pos, &addition);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTES: &str = r#"use actix_web::web;
+
+pub fn public_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api")
+            .configure(users::routes)
+    );
+}
+"#;
+
+    #[test]
+    fn masks_comments_but_not_literals() {
+        // Sources and the comment masked in them, if any
+        let cases = [
+            ("a // pub mod x;\nb", Some("// pub mod x;")),
+            ("a /* pub mod x; */ b", Some("/* pub mod x; */")),
+            ("a /* outer /* inner */ still */ b", Some("/* outer /* inner */ still */")),
+            (r#"s = "// not a comment";"#, None),
+            (r#"s = "escaped \" // still a string";"#, None),
+            (r##"s = r#"/* raw "quoted" */"#;"##, None),
+            ("c = '\"'; // x", Some("// x")),
+            ("fn f<'a>(x: &'a str) {} // y", Some("// y")),
+        ];
+        for (source, comment) in cases {
+            let expected = match comment {
+                Some(comment) => source.replace(comment, &" ".repeat(comment.len())),
+                None => source.to_string(),
+            };
+            assert_eq!(mask_comments(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn finds_code_whatever_its_whitespace() {
+        let cases = [
+            (r#"web::scope("/api")"#, r#"web :: scope(
+    "/api"
+)"#, true),
+            ("use", "reuse x;", false),
+            ("configure", "configured", false),
+            (".configure(", "cfg . configure (x)", true),
+        ];
+        for (pattern, code, found) in cases {
+            assert_eq!(find_code(code, pattern).is_some(), found, "{} in {}", pattern, code);
+        }
+    }
+
+    #[test]
+    fn declarations_in_comments_and_strings_dont_count() {
+        let cases = [
+            ("pub mod users;\n", Some(0)),
+            ("// pub mod users;\n", None),
+            ("/* pub mod users; */\n", None),
+            ("const S: &str = \"pub mod users;\";\n", None),
+            ("const S: &str = r#\"pub mod users;\"#;\n", None),
+            ("#[cfg(test)]\npub mod users;\n", Some(13)),
+            ("pub mod users { pub fn f() {} }\n", None),
+            ("pub mod outer {\n    pub mod users;\n}\n", None),
+            // Declared after custom items
+            ("fn helper() {}\n\npub mod users;\n", Some(16)),
+        ];
+        for (source, offset) in cases {
+            assert_eq!(module_declaration(source, "users"), offset, "{}", source);
+        }
+    }
+
+    #[test]
+    fn finds_inline_module_blocks() {
+        let source = "pub mod users {\n    pub fn f() { let s = \"}\"; }\n}\npub mod posts;\n";
+        let (block, body) = inline_module(source, "users").unwrap();
+        assert_eq!(&source[block], "pub mod users {\n    pub fn f() { let s = \"}\"; }\n}");
+        assert_eq!(&source[body], "\n    pub fn f() { let s = \"}\"; }\n");
+        assert!(inline_module(source, "posts").is_none());
+        assert!(inline_module("// pub mod users { }\n", "users").is_none());
+    }
+
+    #[test]
+    fn inserts_module_declarations() {
+        let cases = [
+            // After the leading declarations, before the first other item and its doc comment
+            ("pub mod a;\nuse x::Y;\n\n/// Docs\npub fn f() {}\n", "pub mod a;\nuse x::Y;\npub mod b;\n\n/// Docs\npub fn f() {}\n"),
+            ("/// Docs\npub fn f() {}\n", "pub mod b;\n\n/// Docs\npub fn f() {}\n"),
+            ("#![allow(dead_code)]\n\nfn f() {}\n", "#![allow(dead_code)]\npub mod b;\n\nfn f() {}\n"),
+            ("pub mod a; // the a module\nfn f() {}\n", "pub mod a; // the a module\npub mod b;\nfn f() {}\n"),
+            // Commented out declarations don't count as leading ones
+            ("// pub mod old;\n", "// pub mod old;\n\npub mod b;"),
+            ("", "pub mod b;"),
+            // Declarations placed after custom items stay there, new ones go before the items
+            ("fn f() {}\n\npub mod a;\n", "pub mod b;\n\nfn f() {}\n\npub mod a;\n"),
+            // Inline blocks are modules too
+            ("pub mod a {\n    pub fn f() {}\n}\n\nfn g() {}\n", "pub mod a {\n    pub fn f() {}\n}\npub mod b;\n\nfn g() {}\n"),
+        ];
+        for (source, expected) in cases {
+            let mut updated = source.to_string();
+            insert_module_declaration(&mut updated, "", "pub mod b;");
+            assert_eq!(updated, expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn inserts_declarations_with_their_attributes() {
+        let mut source = "pub mod a;\n".to_string();
+        insert_module_declaration(&mut source, "#[cfg(feature = \"extra\")]\n", "pub mod b;");
+        assert_eq!(source, "pub mod a;\n#[cfg(feature = \"extra\")]\npub mod b;\n");
+        assert_eq!(module_declaration(&source, "b"), Some(37));
+    }
+
+    #[test]
+    fn reads_configured_targets_and_imports() {
+        let source = "use crate::routes::{posts::post_routes, tags::*};\n\
+                      fn f() { scope.configure(users::routes).configure(post_routes)\n\
+                      // .configure(ghost::routes)\n\
+                      .configure( tags :: routes ) }\n";
+        assert_eq!(configure_targets(source), ["users::routes", "post_routes", "tags::routes"]);
+        assert_eq!(imported_paths(source), ["crate::routes::posts::post_routes", "crate::routes::tags::*"]);
+        assert!(is_configured(source, "users", "routes"));
+        assert!(is_configured(source, "posts", "post_routes"));
+        assert!(!is_configured(source, "ghost", "routes"));
+    }
+
+    #[test]
+    fn inserts_configure_calls_once() {
+        let mut routes = ROUTES.to_string();
+        assert!(insert_configure(&mut routes, "web::scope(\"/api\")", "routes", "posts", "routes"));
+        let expected = ROUTES
+            .replace("use actix_web::web;\n", "use actix_web::web;\nuse crate::routes::posts;\n")
+            .replace(".configure(users::routes)\n", ".configure(users::routes)\n            .configure(posts::routes)\n");
+        assert_eq!(routes, expected);
+
+        // Re-runs leave the file as it is
+        assert!(insert_configure(&mut routes, "web::scope(\"/api\")", "routes", "posts", "routes"));
+        assert_eq!(routes, expected);
+    }
+
+    #[test]
+    fn configure_ignores_scopes_in_comments_and_strings() {
+        let mut routes = "// web::scope(\"/api\").configure(x::routes)\nconst S: &str = r#\"web::scope(\"/api\")\"#;\n".to_string();
+        let before = routes.clone();
+        assert!(!insert_configure(&mut routes, "web::scope(\"/api\")", "routes", "posts", "routes"));
+        assert_eq!(routes, before);
+
+        let mut routes = ROUTES.replace(
+            ".configure(users::routes)",
+            ".configure(users::routes) // .configure(posts::routes)",
+        );
+        assert!(insert_configure(&mut routes, "web::scope(\"/api\")", "routes", "posts", "routes"));
+        assert!(routes.contains(".configure(users::routes) // .configure(posts::routes)\n            .configure(posts::routes)\n"), "{}", routes);
+    }
+
+    #[test]
+    fn inserts_wraps_and_list_items_once() {
+        let mut routes = ROUTES.to_string();
+        assert!(insert_wrap(&mut routes, "web::scope(\"/api\")", "interceptors", "envelope", "EnvelopeInterceptor"));
+        assert!(routes.contains(".configure(users::routes)\n            .wrap(envelope::EnvelopeInterceptor)\n"));
+        let wrapped = routes.clone();
+        assert!(insert_wrap(&mut routes, "web::scope(\"/api\")", "interceptors", "envelope", "EnvelopeInterceptor"));
+        assert_eq!(routes, wrapped);
+
+        let mut openapi = "#[openapi(paths(a::list), components())]".to_string();
+        assert!(insert_list_items(&mut openapi, "paths(", &["a::list".to_string(), "b::list".to_string()]));
+        assert_eq!(openapi, "#[openapi(paths(a::list,\n    b::list,\n), components())]");
+        let listed = openapi.clone();
+        assert!(insert_list_items(&mut openapi, "paths(", &["b::list".to_string()]));
+        assert_eq!(openapi, listed);
+    }
+
+    #[test]
+    fn leaves_reformatted_wiring_alone() {
+        // Already wired, reordered and reformatted by hand
+        let mut routes = ROUTES
+            .replace("use actix_web::web;\n", "use actix_web::web;\nuse crate::routes::{users, posts};\n")
+            .replace(".configure(users::routes)", ".configure(\n                posts :: routes\n            )\n            .configure(users::routes)");
+        let before = routes.clone();
+        assert!(insert_configure(&mut routes, "web::scope(\"/api\")", "routes", "posts", "routes"));
+        assert_eq!(routes, before);
+    }
+}